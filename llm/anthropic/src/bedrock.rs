@@ -0,0 +1,49 @@
+//! Conversions from and to the native request and response bodies of Anthropic's models on
+//! Amazon Bedrock, which are those of the Messages API, for Bedrock's batch inference
+
+use crate::client::MessagesResponse;
+use crate::conversions::{events_to_request, json_output_tool_name, process_response};
+use golem_llm::golem::llm::llm::{Config, Error, ErrorCode, Event, Response};
+use serde_json::{json, Value};
+
+/// Version of the Messages API expected by Bedrock
+const BEDROCK_ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+/// Converts a request to the body of an `InvokeModel` call, also returning the name of the tool
+/// emulating the structured output, if any, to pass to `model_output_to_response`
+pub fn model_input(events: Vec<Event>, config: Config) -> Result<(Value, Option<String>), Error> {
+    let json_output_tool = json_output_tool_name(&config.response_format);
+    let request = events_to_request(events, config)?;
+    let mut input = serde_json::to_value(request).map_err(|err| Error {
+        code: ErrorCode::InternalError,
+        message: format!("Failed to encode request: {err}"),
+        provider_error_json: None,
+        retry_after_ms: None,
+    })?;
+    if let Some(fields) = input.as_object_mut() {
+        // The model is chosen by the Bedrock call, which rejects the fields it does not know
+        fields.remove("model");
+        fields.remove("stream");
+        fields.remove("metadata");
+        fields.insert(
+            "anthropic_version".to_string(),
+            json!(BEDROCK_ANTHROPIC_VERSION),
+        );
+    }
+    Ok((input, json_output_tool))
+}
+
+/// Converts the body of an `InvokeModel` response
+pub fn model_output_to_response(
+    output: Value,
+    json_output_tool: Option<&str>,
+) -> Result<Response, Error> {
+    let response =
+        serde_json::from_value::<MessagesResponse>(output.clone()).map_err(|err| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to decode model output: {err}"),
+            provider_error_json: Some(output.to_string()),
+            retry_after_ms: None,
+        })?;
+    process_response(response, json_output_tool)
+}
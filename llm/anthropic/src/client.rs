@@ -1,6 +1,9 @@
-use golem_llm::error::{error_code_from_status, from_event_source_error, from_reqwest_error};
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
 use golem_llm::event_source::EventSource;
-use golem_llm::golem::llm::llm::Error;
+use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
 use log::trace;
 use reqwest::header::HeaderValue;
 use reqwest::{Client, Method, Response};
@@ -11,6 +14,9 @@ use std::fmt::Debug;
 
 const BASE_URL: &str = "https://api.anthropic.com";
 
+/// Beta feature needed for the code execution tool
+const CODE_EXECUTION_BETA: &str = "code-execution-2025-05-22";
+
 /// The Anthropic API client for creating model responses.
 pub struct MessagesApi {
     api_key: String,
@@ -28,14 +34,19 @@ impl MessagesApi {
     pub fn send_messages(&self, request: MessagesRequest) -> Result<MessagesResponse, Error> {
         trace!("Sending request to Anthropic API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/v1/messages"))
-            .header("anthropic-version", "2023-06-01")
-            .header("x-api-key", &self.api_key)
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            let builder = self
+                .client
+                .request(Method::POST, format!("{BASE_URL}/v1/messages"))
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+                .json(&request);
+            match beta_features(&request.tools) {
+                Some(beta) => builder.header("anthropic-beta", beta),
+                None => builder,
+            }
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         parse_response(response)
     }
@@ -43,24 +54,179 @@ impl MessagesApi {
     pub fn stream_send_messages(&self, request: MessagesRequest) -> Result<EventSource, Error> {
         trace!("Sending request to Anthropic API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/v1/messages"))
-            .header("anthropic-version", "2023-06-01")
-            .header("x-api-key", &self.api_key)
-            .header(
-                reqwest::header::ACCEPT,
-                HeaderValue::from_static("text/event-stream"),
-            )
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            let builder = self
+                .client
+                .request(Method::POST, format!("{BASE_URL}/v1/messages"))
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request);
+            match beta_features(&request.tools) {
+                Some(beta) => builder.header("anthropic-beta", beta),
+                None => builder,
+            }
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         trace!("Initializing SSE stream");
 
         EventSource::new(response)
             .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
     }
+
+    pub fn count_tokens(&self, request: CountTokensRequest) -> Result<CountTokensResponse, Error> {
+        trace!("Sending request to Anthropic API: {request:?}");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            let builder = self
+                .client
+                .request(Method::POST, format!("{BASE_URL}/v1/messages/count_tokens"))
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+                .json(&request);
+            match beta_features(&request.tools) {
+                Some(beta) => builder.header("anthropic-beta", beta),
+                None => builder,
+            }
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    /// Based on https://docs.anthropic.com/en/api/creating-message-batches
+    pub fn create_message_batch(
+        &self,
+        request: CreateMessageBatchRequest,
+    ) -> Result<MessageBatch, Error> {
+        trace!(
+            "Sending message batch with {} requests to Anthropic API",
+            request.requests.len()
+        );
+
+        let beta = request
+            .requests
+            .iter()
+            .find_map(|request| beta_features(&request.params.tools));
+        let response: Response = send_with_retry(|| {
+            let builder = self
+                .client
+                .request(Method::POST, format!("{BASE_URL}/v1/messages/batches"))
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+                .json(&request);
+            match beta {
+                Some(beta) => builder.header("anthropic-beta", beta),
+                None => builder,
+            }
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn retrieve_message_batch(&self, batch_id: &str) -> Result<MessageBatch, Error> {
+        trace!("Retrieving message batch {batch_id} from Anthropic API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(
+                    Method::GET,
+                    format!("{BASE_URL}/v1/messages/batches/{batch_id}"),
+                )
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn cancel_message_batch(&self, batch_id: &str) -> Result<MessageBatch, Error> {
+        trace!("Cancelling message batch {batch_id} with Anthropic API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(
+                    Method::POST,
+                    format!("{BASE_URL}/v1/messages/batches/{batch_id}/cancel"),
+                )
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    /// Downloads the results of an ended message batch from its `results_url`, parsing its
+    /// JSONL lines
+    pub fn message_batch_results(
+        &self,
+        results_url: &str,
+    ) -> Result<Vec<MessageBatchResultLine>, Error> {
+        trace!("Downloading message batch results from {results_url}");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, results_url)
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return parse_response(response);
+        }
+        let body = response
+            .text()
+            .map_err(|err| from_reqwest_error("Failed to receive response body", err))?;
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|err| Error {
+                    code: ErrorCode::InternalError,
+                    message: format!("Failed to decode message batch result: {err}"),
+                    provider_error_json: Some(line.to_string()),
+                    retry_after_ms: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn list_models(&self, after_id: Option<&str>) -> Result<ListModelsResponse, Error> {
+        trace!("Listing models of Anthropic API after {after_id:?}");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            let request = self
+                .client
+                .request(Method::GET, format!("{BASE_URL}/v1/models"))
+                .header("anthropic-version", "2023-06-01")
+                .header("x-api-key", &self.api_key)
+                .query(&[("limit", "1000")]);
+            match after_id {
+                Some(after_id) => request.query(&[("after_id", after_id)]),
+                None => request,
+            }
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+}
+
+/// The beta features used by the tools of a request, sent in the `anthropic-beta` header
+fn beta_features(tools: &Option<Vec<Tool>>) -> Option<&'static str> {
+    tools
+        .iter()
+        .flatten()
+        .any(|tool| matches!(tool, Tool::CodeExecution { .. }))
+        .then_some(CODE_EXECUTION_BETA)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,7 +241,8 @@ pub struct MessagesRequest {
     pub system: Vec<Content>, // can only be Text
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
-    // thinking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<Thinking>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<ToolChoice>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -86,6 +253,32 @@ pub struct MessagesRequest {
     pub top_p: Option<f32>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountTokensRequest {
+    pub messages: Vec<Message>,
+    pub model: String,
+    pub system: Vec<Content>, // can only be Text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<Thinking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+}
+
+impl From<MessagesRequest> for CountTokensRequest {
+    fn from(request: MessagesRequest) -> Self {
+        Self {
+            messages: request.messages,
+            model: request.model,
+            system: request.system,
+            thinking: request.thinking,
+            tool_choice: request.tool_choice,
+            tools: request.tools,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessagesRequestMetadata {
     pub user_id: Option<String>,
@@ -104,7 +297,9 @@ pub enum Content {
     Text {
         text: String,
         #[serde(skip_serializing_if = "Option::is_none")]
-        cache_control: Option<CacheControl>, // citations
+        cache_control: Option<CacheControl>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        citations: Option<Vec<Citation>>,
     },
     #[serde(rename = "image")]
     Image {
@@ -128,15 +323,85 @@ pub enum Content {
         content: Vec<Content>, // can only be Text or Image
         is_error: bool,
     },
-    // Document
-    // Thinking
-    // RedactedThinking
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    #[serde(rename = "web_search_tool_result")]
+    WebSearchToolResult { tool_use_id: String, content: Value },
+    #[serde(rename = "code_execution_tool_result")]
+    CodeExecutionToolResult { tool_use_id: String, content: Value },
+    #[serde(rename = "document")]
+    Document {
+        source: DocumentSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        citations: Option<CitationsConfig>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Thinking {
+    #[serde(rename = "enabled")]
+    Enabled { budget_tokens: u32 },
+    #[serde(rename = "disabled")]
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum CacheControl {
     #[serde(rename = "ephemeral")]
-    Ephemeral,
+    Ephemeral {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ttl: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitationsConfig {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Citation {
+    #[serde(rename = "char_location")]
+    CharLocation {
+        cited_text: String,
+        document_index: u32,
+        document_title: Option<String>,
+    },
+    #[serde(rename = "page_location")]
+    PageLocation {
+        cited_text: String,
+        document_index: u32,
+        document_title: Option<String>,
+    },
+    #[serde(rename = "content_block_location")]
+    ContentBlockLocation {
+        cited_text: String,
+        document_index: u32,
+        document_title: Option<String>,
+    },
+    #[serde(rename = "web_search_result_location")]
+    WebSearchResultLocation {
+        cited_text: String,
+        url: String,
+        title: Option<String>,
+    },
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +413,17 @@ pub enum ImageSource {
     Base64 { data: String, media_type: MediaType },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DocumentSource {
+    #[serde(rename = "url")]
+    Url { url: String },
+    #[serde(rename = "base64")]
+    Base64 { data: String, media_type: String },
+    #[serde(rename = "text")]
+    Text { data: String, media_type: String },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MediaType {
     #[serde(rename = "image/jpeg")]
@@ -195,11 +471,27 @@ pub enum Tool {
         #[serde(skip_serializing_if = "Option::is_none")]
         description: Option<String>,
     },
+    #[serde(rename = "web_search_20250305")]
+    WebSearch {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_uses: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        allowed_domains: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    #[serde(rename = "code_execution_20250522")]
+    CodeExecution {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessagesResponse {
-    pub content: Vec<Content>, // can only be Text or ToolUse (or Thinking / RedactedThinking)
+    pub content: Vec<Content>, // can only be Text, ToolUse, Thinking or RedactedThinking
     pub id: String,
     pub model: String,
     pub role: Role,
@@ -207,6 +499,24 @@ pub struct MessagesResponse {
     pub usage: Usage,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListModelsResponse {
+    pub data: Vec<Model>,
+    pub has_more: bool,
+    pub last_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountTokensResponse {
+    pub input_tokens: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StopReason {
     #[serde(rename = "end_turn")]
@@ -237,6 +547,65 @@ pub enum Role {
     Assistant,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageBatchRequest {
+    pub requests: Vec<MessageBatchRequest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatchRequest {
+    pub custom_id: String,
+    pub params: MessagesRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatch {
+    pub id: String,
+    pub processing_status: ProcessingStatus,
+    pub request_counts: MessageBatchRequestCounts,
+    pub created_at: String,
+    pub cancel_initiated_at: Option<String>,
+    pub results_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ProcessingStatus {
+    #[serde(rename = "in_progress")]
+    InProgress,
+    #[serde(rename = "canceling")]
+    Canceling,
+    #[serde(rename = "ended")]
+    Ended,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatchRequestCounts {
+    pub processing: u32,
+    pub succeeded: u32,
+    pub errored: u32,
+    pub canceled: u32,
+    pub expired: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatchResultLine {
+    pub custom_id: String,
+    pub result: MessageBatchResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MessageBatchResult {
+    #[serde(rename = "succeeded")]
+    Succeeded { message: MessagesResponse },
+    #[serde(rename = "errored")]
+    Errored { error: ErrorResponse },
+    #[serde(rename = "canceled")]
+    Canceled,
+    #[serde(rename = "expired")]
+    Expired,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: ErrorResponseDetails,
@@ -256,6 +625,12 @@ pub enum ContentBlockDelta {
     TextDelta { text: String },
     #[serde(rename = "input_json_delta")]
     InputJsonDelta { partial_json: String },
+    #[serde(rename = "thinking_delta")]
+    ThinkingDelta { thinking: String },
+    #[serde(rename = "signature_delta")]
+    SignatureDelta { signature: String },
+    #[serde(rename = "citations_delta")]
+    CitationsDelta { citation: Citation },
 }
 
 fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T, Error> {
@@ -269,16 +644,19 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
 
         Ok(body)
     } else {
+        let retry_after_ms = retry_after_ms(response.headers());
         let error_body = response
             .json::<ErrorResponse>()
             .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
 
         trace!("Received {status} response from Anthropic API: {error_body:?}");
 
+        let error_json = serde_json::to_string(&error_body).unwrap();
         Err(Error {
-            code: error_code_from_status(status),
+            code: error_code_from_response(status, &error_json),
             message: format!("Request failed with {status}: {}", error_body.error.message),
-            provider_error_json: Some(serde_json::to_string(&error_body).unwrap()),
+            provider_error_json: Some(error_json),
+            retry_after_ms,
         })
     }
 }
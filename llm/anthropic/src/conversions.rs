@@ -1,14 +1,26 @@
 use crate::client::{
-    Content, ImageSource as ClientImageSource, MediaType, MessagesRequest, MessagesRequestMetadata,
-    MessagesResponse, StopReason, Tool, ToolChoice,
+    CacheControl, CitationsConfig, Content, DocumentSource, ImageSource as ClientImageSource,
+    MediaType, MessageBatch, MessageBatchResult, MessageBatchResultLine, MessagesRequest,
+    MessagesRequestMetadata, MessagesResponse, ProcessingStatus, StopReason, Thinking, Tool,
+    ToolChoice as ClientToolChoice,
 };
 use base64::{engine::general_purpose, Engine as _};
+use golem_llm::citations::text_length;
+use golem_llm::error::{error_code_from_response, unsupported};
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, BatchStatus};
 use golem_llm::golem::llm::llm::{
-    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageReference, ImageSource,
-    ImageUrl, Response, ResponseMetadata, Role, ToolCall, ToolDefinition, ToolResult, Usage,
+    BuiltinTool, BuiltinToolCall, BuiltinToolKind, CacheHint, Citation, CitationSource, Config,
+    ContentPart, Error, ErrorCode, Event, FinishReason, ImageReference, ImageSource, ImageUrl,
+    MediaSource, ReasoningConfig, ReasoningContent, ReasoningEffort, Response, ResponseFormat,
+    ResponseMetadata, Role, SearchResult, ToolCall, ToolChoice, ToolDefinition, ToolResult, Usage,
 };
+use reqwest::StatusCode;
+use serde_json::json;
 use std::collections::HashMap;
 
+/// Name of the tool used to force a JSON object output when no schema name is available
+const JSON_OUTPUT_TOOL_NAME: &str = "json_output";
+
 pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<MessagesRequest, Error> {
     let options = config
         .provider_options
@@ -20,10 +32,11 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<MessagesR
         })
         .unwrap_or_default();
 
-    let (user_messages, system_messages) = events_to_messages_and_system_messages(events);
+    let (user_messages, system_messages) = events_to_messages_and_system_messages(events)?;
 
-    let tool_choice = config.tool_choice.map(convert_tool_choice);
-    let tools = config
+    let forced_tool_choice = config.tool_choice.clone();
+    let mut tool_choice = convert_tool_choice(config.tool_choice, config.parallel_tool_calls);
+    let mut tools = config
         .tools
         .and_then(|tools| {
             (!tools.is_empty()).then(|| {
@@ -35,8 +48,34 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<MessagesR
         })
         .transpose()?;
 
+    // Anthropic has no native structured output, so it is emulated by forcing the model
+    // to call a tool whose input schema is the requested response schema.
+    if let Some(json_output_tool) = config
+        .response_format
+        .map(response_format_to_tool)
+        .transpose()?
+        .flatten()
+    {
+        if let Tool::CustomTool { name, .. } = &json_output_tool {
+            check_structured_output_conflicts(name, &forced_tool_choice, &config.reasoning)?;
+            tool_choice = Some(ClientToolChoice::Tool {
+                name: name.clone(),
+                disable_parallel_tool_use: None,
+            });
+        }
+        tools.get_or_insert_with(Vec::new).push(json_output_tool);
+    }
+
+    let thinking = config.reasoning.map(reasoning_config_to_thinking);
+    let max_tokens = match (config.max_tokens, &thinking) {
+        (Some(max_tokens), _) => max_tokens,
+        // max_tokens has to be greater than the thinking budget
+        (None, Some(Thinking::Enabled { budget_tokens })) => budget_tokens + 4096,
+        (None, _) => 4096,
+    };
+
     Ok(MessagesRequest {
-        max_tokens: config.max_tokens.unwrap_or(4096),
+        max_tokens,
         messages: user_messages,
         model: config.model,
         metadata: options
@@ -48,6 +87,7 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<MessagesR
         stream: false,
         system: system_messages,
         temperature: config.temperature,
+        thinking,
         tool_choice,
         tools,
         top_k: options
@@ -61,39 +101,40 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<MessagesR
 
 fn events_to_messages_and_system_messages(
     events: Vec<Event>,
-) -> (Vec<crate::client::Message>, Vec<Content>) {
+) -> Result<(Vec<crate::client::Message>, Vec<Content>), Error> {
     let mut messages: Vec<crate::client::Message> = vec![];
     let mut system_messages: Vec<Content> = vec![];
 
     for event in events {
         match event {
             Event::Message(message) if message.role == Role::System => {
-                system_messages.extend(content_parts_to_content(message.content));
+                let mut content = content_parts_to_content(message.content)?;
+                set_cache_control(&mut content, message.cache_hint);
+                system_messages.extend(content);
+            }
+            Event::Message(message) => {
+                let mut content = content_parts_to_content(message.content)?;
+                set_cache_control(&mut content, message.cache_hint);
+                messages.push(crate::client::Message {
+                    role: match &message.role {
+                        Role::User => crate::client::Role::User,
+                        Role::Assistant => crate::client::Role::Assistant,
+                        Role::Tool => crate::client::Role::User,
+                        Role::System => unreachable!(),
+                    },
+                    content,
+                })
             }
-            Event::Message(message) => messages.push(crate::client::Message {
-                role: match &message.role {
-                    Role::User => crate::client::Role::User,
-                    Role::Assistant => crate::client::Role::Assistant,
-                    Role::Tool => crate::client::Role::User,
-                    Role::System => unreachable!(),
-                },
-                content: content_parts_to_content(message.content),
-            }),
             Event::Response(response) => {
-                if !response.content.is_empty() {
-                    messages.push(crate::client::Message {
-                        role: crate::client::Role::Assistant,
-                        content: content_parts_to_content(response.content),
-                    })
-                }
-                if !response.tool_calls.is_empty() {
+                // Thinking blocks have to be sent back in the same message as the tool calls
+                let content = content_parts_to_content(response.content)?
+                    .into_iter()
+                    .chain(response.tool_calls.into_iter().map(tool_call_to_conent))
+                    .collect::<Vec<_>>();
+                if !content.is_empty() {
                     messages.push(crate::client::Message {
                         role: crate::client::Role::Assistant,
-                        content: response
-                            .tool_calls
-                            .into_iter()
-                            .map(tool_call_to_conent)
-                            .collect(),
+                        content,
                     })
                 }
             }
@@ -103,35 +144,170 @@ fn events_to_messages_and_system_messages(
         }
     }
 
-    (messages, system_messages)
+    Ok((messages, system_messages))
 }
 
-fn convert_tool_choice(tool_name: String) -> ToolChoice {
-    if &tool_name == "auto" {
-        ToolChoice::Auto {
-            disable_parallel_tool_use: None,
-        }
-    } else if &tool_name == "none" {
-        ToolChoice::None {}
-    } else if &tool_name == "any" {
-        ToolChoice::Any {
-            disable_parallel_tool_use: None,
-        }
+fn cache_hint_to_cache_control(cache_hint: CacheHint) -> CacheControl {
+    match cache_hint {
+        CacheHint::Ephemeral => CacheControl::Ephemeral { ttl: None },
+        CacheHint::Extended => CacheControl::Ephemeral {
+            ttl: Some("1h".to_string()),
+        },
+    }
+}
+
+/// Marks the cache breakpoint on the last block of a message which can have one, as the prompt is
+/// cached up to and including the marked block
+fn set_cache_control(content: &mut [Content], cache_hint: Option<CacheHint>) {
+    let Some(cache_hint) = cache_hint else {
+        return;
+    };
+    if let Some(cache_control) = content.iter_mut().rev().find_map(|content| match content {
+        Content::Text { cache_control, .. }
+        | Content::Image { cache_control, .. }
+        | Content::ToolUse { cache_control, .. }
+        | Content::ToolResult { cache_control, .. }
+        | Content::Document { cache_control, .. } => Some(cache_control),
+        Content::Thinking { .. }
+        | Content::RedactedThinking { .. }
+        | Content::ServerToolUse { .. }
+        | Content::WebSearchToolResult { .. }
+        | Content::CodeExecutionToolResult { .. } => None,
+    }) {
+        *cache_control = Some(cache_hint_to_cache_control(cache_hint));
+    }
+}
+
+/// The tool emulating structured output has to be forced, which would override the caller's tool
+/// choice, and Anthropic does not allow forcing a tool with extended thinking
+fn check_structured_output_conflicts(
+    json_output_tool: &str,
+    tool_choice: &Option<ToolChoice>,
+    reasoning: &Option<ReasoningConfig>,
+) -> Result<(), Error> {
+    let conflicting_tool_choice = match tool_choice {
+        Some(ToolChoice::Specific(name)) => name != json_output_tool,
+        Some(_) => true,
+        None => false,
+    };
+    let message = if conflicting_tool_choice {
+        "A response format cannot be combined with a tool choice on Anthropic, as structured \
+         output forces the call of a tool"
+    } else if reasoning.is_some() {
+        "A response format cannot be combined with reasoning on Anthropic, as extended thinking \
+         does not allow forcing the call of a tool"
     } else {
-        ToolChoice::Tool {
-            name: tool_name,
-            disable_parallel_tool_use: None,
+        return Ok(());
+    };
+    Err(Error {
+        code: ErrorCode::InvalidRequest,
+        message: message.to_string(),
+        provider_error_json: None,
+        retry_after_ms: None,
+    })
+}
+
+/// Parallel tool use can only be disabled as part of the tool choice, which defaults to `auto`
+fn convert_tool_choice(
+    tool_choice: Option<ToolChoice>,
+    parallel_tool_calls: Option<bool>,
+) -> Option<ClientToolChoice> {
+    let disable_parallel_tool_use = parallel_tool_calls.map(|parallel| !parallel);
+    match tool_choice {
+        None if disable_parallel_tool_use.is_none() => None,
+        None | Some(ToolChoice::Auto) => Some(ClientToolChoice::Auto {
+            disable_parallel_tool_use,
+        }),
+        Some(ToolChoice::None) => Some(ClientToolChoice::None {}),
+        Some(ToolChoice::Required) => Some(ClientToolChoice::Any {
+            disable_parallel_tool_use,
+        }),
+        Some(ToolChoice::Specific(name)) => Some(ClientToolChoice::Tool {
+            name,
+            disable_parallel_tool_use,
+        }),
+    }
+}
+
+fn reasoning_config_to_thinking(reasoning: ReasoningConfig) -> Thinking {
+    let budget_tokens = reasoning.budget_tokens.unwrap_or(match reasoning.effort {
+        Some(ReasoningEffort::Low) => 1024,
+        Some(ReasoningEffort::Medium) | None => 4096,
+        Some(ReasoningEffort::High) => 16384,
+    });
+    Thinking::Enabled { budget_tokens }
+}
+
+/// Returns the name of the tool used to emulate the given response format, if any
+pub fn json_output_tool_name(response_format: &Option<ResponseFormat>) -> Option<String> {
+    match response_format {
+        Some(ResponseFormat::JsonObject) => Some(JSON_OUTPUT_TOOL_NAME.to_string()),
+        Some(ResponseFormat::JsonSchema(json_schema)) => Some(json_schema.name.clone()),
+        Some(ResponseFormat::Text) | None => None,
+    }
+}
+
+fn response_format_to_tool(response_format: ResponseFormat) -> Result<Option<Tool>, Error> {
+    match response_format {
+        ResponseFormat::Text => Ok(None),
+        ResponseFormat::JsonObject => Ok(Some(Tool::CustomTool {
+            input_schema: json!({ "type": "object" }),
+            name: JSON_OUTPUT_TOOL_NAME.to_string(),
+            cache_control: None,
+            description: Some("Respond with a JSON object".to_string()),
+        })),
+        ResponseFormat::JsonSchema(json_schema) => {
+            match serde_json::from_str(&json_schema.schema) {
+                Ok(value) => Ok(Some(Tool::CustomTool {
+                    input_schema: value,
+                    name: json_schema.name,
+                    cache_control: None,
+                    description: Some(
+                        "Respond with JSON conforming to the input schema".to_string(),
+                    ),
+                })),
+                Err(error) => Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Failed to parse response format schema {}: {error}",
+                        json_schema.name
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                }),
+            }
         }
     }
 }
 
-pub fn process_response(response: MessagesResponse) -> Result<Response, Error> {
+pub fn process_response(
+    response: MessagesResponse,
+    json_output_tool: Option<&str>,
+) -> Result<Response, Error> {
     let mut contents = Vec::new();
     let mut tool_calls = Vec::new();
+    let mut citations = Vec::new();
+    let mut json_output_received = false;
+    let mut server_tool_use = None;
 
     for content in response.content {
         match content {
-            Content::Text { text, .. } => contents.push(ContentPart::Text(text)),
+            Content::Text {
+                text,
+                citations: text_citations,
+                ..
+            } => {
+                // Cited text is returned in separate blocks, each citation covering its block
+                let start_index = text_length(&contents);
+                let end_index = start_index + text.chars().count() as u32;
+                citations.extend(
+                    text_citations
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|citation| convert_citation(citation, start_index, end_index)),
+                );
+                contents.push(ContentPart::Text(text));
+            }
             Content::Image { source, .. } => match source {
                 ClientImageSource::Url { url } => {
                     contents.push(ContentPart::Image(ImageReference::Url(ImageUrl {
@@ -161,11 +337,16 @@ pub fn process_response(response: MessagesResponse) -> Result<Response, Error> {
                                 code: ErrorCode::InvalidRequest,
                                 message: format!("Failed to decode base64 image data: {e}"),
                                 provider_error_json: None,
+                                retry_after_ms: None,
                             });
                         }
                     }
                 }
             },
+            Content::ToolUse { input, name, .. } if Some(name.as_str()) == json_output_tool => {
+                json_output_received = true;
+                contents.push(ContentPart::Text(serde_json::to_string(&input).unwrap()));
+            }
             Content::ToolUse {
                 id, input, name, ..
             } => tool_calls.push(ToolCall {
@@ -173,16 +354,55 @@ pub fn process_response(response: MessagesResponse) -> Result<Response, Error> {
                 name,
                 arguments_json: serde_json::to_string(&input).unwrap(),
             }),
-            Content::ToolResult { .. } => {}
+            Content::ToolResult { .. } | Content::Document { .. } => {}
+            Content::ServerToolUse { .. } => {
+                contents.extend(
+                    server_tool_use
+                        .replace(content)
+                        .and_then(|tool_use| server_tool_call_to_content_part(tool_use, None)),
+                );
+            }
+            Content::WebSearchToolResult { .. } | Content::CodeExecutionToolResult { .. } => {
+                contents.extend(server_tool_use.take().and_then(|tool_use| {
+                    server_tool_call_to_content_part(tool_use, Some(content))
+                }));
+            }
+            Content::Thinking {
+                thinking,
+                signature,
+            } => contents.push(ContentPart::Reasoning(ReasoningContent {
+                text: thinking,
+                signature: Some(signature),
+                redacted_data: None,
+            })),
+            Content::RedactedThinking { data } => {
+                contents.push(ContentPart::Reasoning(ReasoningContent {
+                    text: String::new(),
+                    signature: None,
+                    redacted_data: Some(data),
+                }))
+            }
         }
     }
+    contents.extend(
+        server_tool_use.and_then(|tool_use| server_tool_call_to_content_part(tool_use, None)),
+    );
 
     let metadata = ResponseMetadata {
-        finish_reason: response.stop_reason.map(stop_reason_to_finish_reason),
+        finish_reason: response.stop_reason.map(stop_reason_to_finish_reason).map(
+            |finish_reason| {
+                if json_output_received && finish_reason == FinishReason::ToolCalls {
+                    FinishReason::Stop
+                } else {
+                    finish_reason
+                }
+            },
+        ),
         usage: Some(convert_usage(response.usage)),
         provider_id: None,
         timestamp: None,
         provider_metadata_json: None,
+        cost: None,
     };
 
     Ok(Response {
@@ -190,6 +410,49 @@ pub fn process_response(response: MessagesResponse) -> Result<Response, Error> {
         content: contents,
         tool_calls,
         metadata,
+        citations,
+    })
+}
+
+/// Converts a citation of a text block spanning the given range of the response's text
+pub fn convert_citation(
+    citation: crate::client::Citation,
+    start_index: u32,
+    end_index: u32,
+) -> Option<Citation> {
+    let (source, title, cited_text) = match citation {
+        crate::client::Citation::CharLocation {
+            cited_text,
+            document_index,
+            document_title,
+        }
+        | crate::client::Citation::PageLocation {
+            cited_text,
+            document_index,
+            document_title,
+        }
+        | crate::client::Citation::ContentBlockLocation {
+            cited_text,
+            document_index,
+            document_title,
+        } => (
+            CitationSource::Document(document_index),
+            document_title,
+            cited_text,
+        ),
+        crate::client::Citation::WebSearchResultLocation {
+            cited_text,
+            url,
+            title,
+        } => (CitationSource::Url(url), title, cited_text),
+        crate::client::Citation::Other => return None,
+    };
+    Some(Citation {
+        source,
+        title,
+        cited_text: Some(cited_text),
+        start_index: Some(start_index),
+        end_index: Some(end_index),
     })
 }
 
@@ -211,6 +474,7 @@ pub fn tool_result_to_message(tool_result: ToolResult) -> crate::client::Message
                 content: vec![Content::Text {
                     text: success.result_json,
                     cache_control: None,
+                    citations: None,
                 }],
                 is_error: false,
             },
@@ -220,6 +484,7 @@ pub fn tool_result_to_message(tool_result: ToolResult) -> crate::client::Message
                 content: vec![Content::Text {
                     text: error.error_message,
                     cache_control: None,
+                    citations: None,
                 }],
                 is_error: true,
             },
@@ -242,10 +507,13 @@ pub fn convert_usage(usage: crate::client::Usage) -> Usage {
         input_tokens: Some(usage.input_tokens),
         output_tokens: Some(usage.output_tokens),
         total_tokens: None,
+        reasoning_tokens: None,
+        cache_read_tokens: usage.cache_read_input_tokens,
+        cache_write_tokens: usage.cache_creation_input_tokens,
     }
 }
 
-fn content_parts_to_content(content_parts: Vec<ContentPart>) -> Vec<Content> {
+fn content_parts_to_content(content_parts: Vec<ContentPart>) -> Result<Vec<Content>, Error> {
     let mut result = Vec::new();
 
     for content_part in content_parts {
@@ -253,6 +521,7 @@ fn content_parts_to_content(content_parts: Vec<ContentPart>) -> Vec<Content> {
             ContentPart::Text(text) => result.push(Content::Text {
                 text: text.clone(),
                 cache_control: None,
+                citations: None,
             }),
             ContentPart::Image(image_reference) => match image_reference {
                 ImageReference::Url(image_url) => result.push(Content::Image {
@@ -280,24 +549,385 @@ fn content_parts_to_content(content_parts: Vec<ContentPart>) -> Vec<Content> {
                     });
                 }
             },
+            ContentPart::Reasoning(reasoning) => {
+                if let Some(data) = reasoning.redacted_data {
+                    result.push(Content::RedactedThinking { data });
+                } else {
+                    match result.last_mut() {
+                        // Streamed reasoning arrives in multiple parts, with the signature at the end
+                        Some(Content::Thinking {
+                            thinking,
+                            signature,
+                        }) if signature.is_empty() => {
+                            thinking.push_str(&reasoning.text);
+                            *signature = reasoning.signature.unwrap_or_default();
+                        }
+                        _ => result.push(Content::Thinking {
+                            thinking: reasoning.text,
+                            signature: reasoning.signature.unwrap_or_default(),
+                        }),
+                    }
+                }
+            }
+            ContentPart::Document(document) => result.push(Content::Document {
+                source: document_to_source(document.source, document.mime_type),
+                title: document.title,
+                cache_control: None,
+                citations: Some(CitationsConfig { enabled: true }),
+            }),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            ContentPart::BuiltinToolCall(call) => {
+                // The original server tool blocks are sent back as they were received
+                if let Some(blocks) = call
+                    .provider_data_json
+                    .and_then(|json| serde_json::from_str::<Vec<Content>>(&json).ok())
+                {
+                    result.extend(blocks);
+                }
+            }
         }
     }
 
-    result
+    // Anthropic rejects thinking blocks without a signature
+    result.retain(
+        |content| !matches!(content, Content::Thinking { signature, .. } if signature.is_empty()),
+    );
+
+    Ok(result)
+}
+
+/// Plain text documents are sent as text, anything else (PDFs) as base64 encoded data
+fn document_to_source(source: MediaSource, mime_type: String) -> DocumentSource {
+    match source {
+        MediaSource::Url(url) => DocumentSource::Url { url },
+        MediaSource::Inline(data) if mime_type.starts_with("text/") => DocumentSource::Text {
+            data: String::from_utf8_lossy(&data).into_owned(),
+            media_type: "text/plain".to_string(),
+        },
+        MediaSource::Inline(data) => DocumentSource::Base64 {
+            data: general_purpose::STANDARD.encode(data),
+            media_type: mime_type,
+        },
+    }
 }
 
 fn tool_definition_to_tool(tool: ToolDefinition) -> Result<Tool, Error> {
+    if let Some(builtin) = tool.builtin {
+        return builtin_tool_to_tool(builtin, tool.cache_hint);
+    }
     match serde_json::from_str(&tool.parameters_schema) {
         Ok(value) => Ok(Tool::CustomTool {
             input_schema: value,
             name: tool.name,
-            cache_control: None,
+            cache_control: tool.cache_hint.map(cache_hint_to_cache_control),
             description: tool.description,
         }),
         Err(error) => Err(Error {
             code: ErrorCode::InternalError,
             message: format!("Failed to parse tool parameters for {}: {error}", tool.name),
             provider_error_json: None,
+            retry_after_ms: None,
         }),
     }
 }
+
+fn builtin_tool_to_tool(
+    builtin: BuiltinTool,
+    cache_hint: Option<CacheHint>,
+) -> Result<Tool, Error> {
+    let cache_control = cache_hint.map(cache_hint_to_cache_control);
+    match builtin {
+        BuiltinTool::WebSearch(options) => Ok(Tool::WebSearch {
+            name: "web_search".to_string(),
+            max_uses: options.max_uses,
+            allowed_domains: options.allowed_domains,
+            cache_control,
+        }),
+        BuiltinTool::CodeInterpreter => Ok(Tool::CodeExecution {
+            name: "code_execution".to_string(),
+            cache_control,
+        }),
+        BuiltinTool::FileSearch(_) => Err(unsupported("file search")),
+    }
+}
+
+/// Converts a server tool use block and its result block to a builtin tool call, keeping both
+/// blocks as provider data so they can be sent back in later requests
+pub fn server_tool_call_to_content_part(
+    tool_use: Content,
+    result: Option<Content>,
+) -> Option<ContentPart> {
+    let Content::ServerToolUse { id, name, input } = &tool_use else {
+        return None;
+    };
+    let (tool, input_key) = match name.as_str() {
+        "web_search" => (BuiltinToolKind::WebSearch, "query"),
+        "code_execution" => (BuiltinToolKind::CodeInterpreter, "code"),
+        _ => return None,
+    };
+
+    let mut output = None;
+    let mut results = Vec::new();
+    match &result {
+        Some(Content::WebSearchToolResult { content, .. }) => {
+            results = content
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|result| {
+                    Some(SearchResult {
+                        source: CitationSource::Url(result.get("url")?.as_str()?.to_string()),
+                        title: result
+                            .get("title")
+                            .and_then(|title| title.as_str())
+                            .map(|title| title.to_string()),
+                        text: None,
+                    })
+                })
+                .collect();
+        }
+        Some(Content::CodeExecutionToolResult { content, .. }) => {
+            let streams = ["stdout", "stderr"]
+                .into_iter()
+                .filter_map(|stream| content.get(stream).and_then(|text| text.as_str()))
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>();
+            output = (!streams.is_empty()).then(|| streams.join("\n"));
+        }
+        _ => {}
+    }
+
+    let call = BuiltinToolCall {
+        id: id.clone(),
+        tool,
+        input: input
+            .get(input_key)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string()),
+        output,
+        results,
+        provider_data_json: serde_json::to_string(
+            &std::iter::once(&tool_use)
+                .chain(result.as_ref())
+                .collect::<Vec<_>>(),
+        )
+        .ok(),
+    };
+    Some(ContentPart::BuiltinToolCall(call))
+}
+
+pub fn message_batch_to_batch_info(batch: MessageBatch) -> BatchInfo {
+    let counts = batch.request_counts;
+    let status = match batch.processing_status {
+        ProcessingStatus::InProgress => BatchStatus::Running,
+        ProcessingStatus::Canceling => BatchStatus::Cancelling,
+        ProcessingStatus::Ended if batch.cancel_initiated_at.is_some() => BatchStatus::Cancelled,
+        ProcessingStatus::Ended if counts.expired > 0 => BatchStatus::Expired,
+        ProcessingStatus::Ended => BatchStatus::Completed,
+    };
+    BatchInfo {
+        id: batch.id,
+        status,
+        total_count: counts.processing
+            + counts.succeeded
+            + counts.errored
+            + counts.canceled
+            + counts.expired,
+        succeeded_count: counts.succeeded,
+        failed_count: counts.errored + counts.canceled + counts.expired,
+        created_at: Some(batch.created_at),
+    }
+}
+
+/// Separates the names of the tools emulating structured outputs from the message batch ID
+const BATCH_ID_TOOLS_SEPARATOR: char = ':';
+const BATCH_ID_TOOL_NAMES_SEPARATOR: char = ',';
+
+/// Collects the names of the tools emulating the structured outputs of the items of a batch,
+/// failing if one of them is also the name of a tool of another item, as the batch results would
+/// not tell the two apart, or if it contains the separators of `encode_batch_id`
+pub fn batch_json_output_tools(items: &[BatchItem]) -> Result<Vec<String>, Error> {
+    let mut json_output_tools = items
+        .iter()
+        .filter_map(|item| json_output_tool_name(&item.config.response_format))
+        .collect::<Vec<_>>();
+    json_output_tools.sort();
+    json_output_tools.dedup();
+
+    if let Some(name) = json_output_tools
+        .iter()
+        .find(|name| name.contains([BATCH_ID_TOOLS_SEPARATOR, BATCH_ID_TOOL_NAMES_SEPARATOR]))
+    {
+        return Err(Error {
+            code: ErrorCode::InvalidRequest,
+            message: format!(
+                "Structured output name '{name}' of a batch cannot contain \
+                 '{BATCH_ID_TOOLS_SEPARATOR}' or '{BATCH_ID_TOOL_NAMES_SEPARATOR}'"
+            ),
+            provider_error_json: None,
+            retry_after_ms: None,
+        });
+    }
+
+    for item in items {
+        for tool in item.config.tools.iter().flatten() {
+            if json_output_tools.contains(&tool.name) {
+                return Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Tool '{}' of batch item '{}' has the name of a structured output of the batch",
+                        tool.name, item.custom_id
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                });
+            }
+        }
+    }
+    Ok(json_output_tools)
+}
+
+/// Appends the names of the tools emulating structured outputs to the message batch ID, so the
+/// results can be converted back without keeping any state
+pub fn encode_batch_id(message_batch_id: String, json_output_tools: &[String]) -> String {
+    if json_output_tools.is_empty() {
+        message_batch_id
+    } else {
+        format!(
+            "{message_batch_id}{BATCH_ID_TOOLS_SEPARATOR}{}",
+            json_output_tools.join(&BATCH_ID_TOOL_NAMES_SEPARATOR.to_string())
+        )
+    }
+}
+
+/// Splits a batch ID created by `encode_batch_id` into the message batch ID and the names of the
+/// tools emulating structured outputs
+pub fn decode_batch_id(id: &str) -> (&str, Vec<&str>) {
+    match id.split_once(BATCH_ID_TOOLS_SEPARATOR) {
+        Some((message_batch_id, tools)) => (
+            message_batch_id,
+            tools.split(BATCH_ID_TOOL_NAMES_SEPARATOR).collect(),
+        ),
+        None => (id, Vec::new()),
+    }
+}
+
+/// Converts a line of the results of a message batch. The calls of the tools emulating structured
+/// outputs, named in `json_output_tools`, are returned as text content, like in `send`.
+pub fn message_batch_result_to_batch_result(
+    line: MessageBatchResultLine,
+    json_output_tools: &[&str],
+) -> BatchResult {
+    let result = match line.result {
+        MessageBatchResult::Succeeded { message } => {
+            let json_output_tool = message.content.iter().find_map(|content| match content {
+                Content::ToolUse { name, .. } if json_output_tools.contains(&name.as_str()) => {
+                    Some(name.clone())
+                }
+                _ => None,
+            });
+            process_response(message, json_output_tool.as_deref())
+        }
+        MessageBatchResult::Errored { error } => {
+            let error_json = serde_json::to_string(&error).unwrap();
+            Err(Error {
+                code: error_code_from_response(error_type_to_status(&error.error.typ), &error_json),
+                message: error.error.message,
+                provider_error_json: Some(error_json),
+                retry_after_ms: None,
+            })
+        }
+        MessageBatchResult::Canceled => Err(Error {
+            code: ErrorCode::Unknown,
+            message: "The request was cancelled before being processed".to_string(),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+        MessageBatchResult::Expired => Err(Error {
+            code: ErrorCode::Timeout,
+            message: "The batch expired before the request was processed".to_string(),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+    };
+    BatchResult {
+        custom_id: line.custom_id,
+        result,
+    }
+}
+
+/// Errored batch requests have no HTTP status, only the type of the error they would have had
+fn error_type_to_status(error_type: &str) -> StatusCode {
+    match error_type {
+        "invalid_request_error" => StatusCode::BAD_REQUEST,
+        "authentication_error" => StatusCode::UNAUTHORIZED,
+        "permission_error" => StatusCode::FORBIDDEN,
+        "not_found_error" => StatusCode::NOT_FOUND,
+        "request_too_large" => StatusCode::PAYLOAD_TOO_LARGE,
+        "rate_limit_error" => StatusCode::TOO_MANY_REQUESTS,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use golem_llm::golem::llm::llm::JsonSchemaFormat;
+
+    fn batch_item(custom_id: &str, response_format: Option<ResponseFormat>) -> BatchItem {
+        BatchItem {
+            custom_id: custom_id.to_string(),
+            events: vec![],
+            config: Config {
+                model: "claude-sonnet-4-5".to_string(),
+                temperature: None,
+                max_tokens: None,
+                stop_sequences: None,
+                tools: None,
+                tool_choice: None,
+                parallel_tool_calls: None,
+                response_format,
+                reasoning: None,
+                provider_options: None,
+            },
+        }
+    }
+
+    fn json_schema(name: &str) -> Option<ResponseFormat> {
+        Some(ResponseFormat::JsonSchema(JsonSchemaFormat {
+            name: name.to_string(),
+            schema: r#"{"type": "object"}"#.to_string(),
+            strict: None,
+        }))
+    }
+
+    #[test]
+    fn test_batch_id_round_trip() {
+        let items = [
+            batch_item("1", json_schema("weather")),
+            batch_item("2", Some(ResponseFormat::JsonObject)),
+            batch_item("3", json_schema("weather")),
+            batch_item("4", None),
+        ];
+        let json_output_tools = batch_json_output_tools(&items).unwrap();
+
+        let id = encode_batch_id("msgbatch_01".to_string(), &json_output_tools);
+        assert_eq!(id, "msgbatch_01:json_output,weather");
+        assert_eq!(
+            decode_batch_id(&id),
+            ("msgbatch_01", vec!["json_output", "weather"])
+        );
+
+        let id = encode_batch_id("msgbatch_02".to_string(), &[]);
+        assert_eq!(id, "msgbatch_02");
+        assert_eq!(decode_batch_id(&id), ("msgbatch_02", vec![]));
+    }
+
+    #[test]
+    fn test_batch_json_output_tools_with_separators() {
+        for name in ["weather:v2", "weather,forecast"] {
+            let error = batch_json_output_tools(&[batch_item("1", json_schema(name))]).unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidRequest, "{name}");
+        }
+    }
+}
@@ -1,55 +1,77 @@
+pub mod bedrock;
 mod client;
 mod conversions;
+mod models;
 
 use crate::client::{
-    Content, ContentBlockDelta, ErrorResponse, MessagesApi, MessagesRequest, StopReason, Usage,
+    Citation, Content, ContentBlockDelta, CreateMessageBatchRequest, ErrorResponse,
+    MessageBatchRequest, MessagesApi, MessagesRequest, StopReason, Usage,
 };
 use crate::conversions::{
-    convert_usage, events_to_request, process_response, stop_reason_to_finish_reason,
+    batch_json_output_tools, convert_citation, convert_usage, decode_batch_id, encode_batch_id,
+    events_to_request, json_output_tool_name, message_batch_result_to_batch_result,
+    message_batch_to_batch_info, process_response, server_tool_call_to_content_part,
+    stop_reason_to_finish_reason,
 };
+use crate::models::{KNOWN_MODELS, PRICING};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
 use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
 use golem_llm::config::{get_config_key, with_config_key};
-use golem_llm::durability::{DurableLLM, ExtendedGuest};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::error_code_from_response;
 use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
 use golem_llm::golem::llm::llm::{
-    ChatStream, Config, ContentPart, Error, ErrorCode, Event, Guest, Message, Response,
-    ResponseMetadata, Role, StreamDelta, StreamEvent, ToolCall,
+    ChatStream, Config, ContentPart, Error, ErrorCode, Event, FinishReason, Guest, Message,
+    ModelInfo, ReasoningContent, Response, ResponseMetadata, Role, StreamDelta, StreamEvent,
+    ToolCallDelta,
 };
+use golem_llm::models::known_model_info;
+use golem_llm::pricing::add_cost;
 use golem_rust::wasm_rpc::Pollable;
 use indoc::indoc;
 use log::trace;
+use reqwest::StatusCode;
 use std::cell::{Ref, RefCell, RefMut};
-use std::collections::HashMap;
 
-#[derive(Default)]
-struct JsonFragment {
-    id: String,
-    name: String,
-    json: String,
-}
-
-struct AnthropicChatStream {
+pub struct AnthropicChatStream {
     stream: RefCell<Option<EventSource>>,
     failure: Option<Error>,
     finished: RefCell<bool>,
-    json_fragments: RefCell<HashMap<u64, JsonFragment>>,
+    json_output_tool: Option<String>,
+    json_output_index: RefCell<Option<u64>>,
     response_metadata: RefCell<ResponseMetadata>,
+    text_length: RefCell<u32>,
+    text_block_start: RefCell<u32>,
+    pending_citations: RefCell<Vec<Citation>>,
+    /// Index, block and streamed input of the server tool use waiting for its result
+    server_tool_use: RefCell<Option<(u64, Content, String)>>,
 }
 
 impl AnthropicChatStream {
-    pub fn new(stream: EventSource) -> LlmChatStream<Self> {
+    pub fn new(stream: EventSource, json_output_tool: Option<String>) -> LlmChatStream<Self> {
         LlmChatStream::new(AnthropicChatStream {
             stream: RefCell::new(Some(stream)),
             failure: None,
             finished: RefCell::new(false),
-            json_fragments: RefCell::new(HashMap::new()),
+            json_output_tool,
+            json_output_index: RefCell::new(None),
             response_metadata: RefCell::new(ResponseMetadata {
                 finish_reason: None,
                 usage: None,
                 provider_id: None,
                 timestamp: None,
                 provider_metadata_json: None,
+                cost: None,
             }),
+            text_length: RefCell::new(0),
+            text_block_start: RefCell::new(0),
+            pending_citations: RefCell::new(Vec::new()),
+            server_tool_use: RefCell::new(None),
         })
     }
 
@@ -58,14 +80,32 @@ impl AnthropicChatStream {
             stream: RefCell::new(None),
             failure: Some(error),
             finished: RefCell::new(false),
-            json_fragments: RefCell::new(HashMap::new()),
+            json_output_tool: None,
+            json_output_index: RefCell::new(None),
             response_metadata: RefCell::new(ResponseMetadata {
                 finish_reason: None,
                 usage: None,
                 provider_id: None,
                 timestamp: None,
                 provider_metadata_json: None,
+                cost: None,
             }),
+            text_length: RefCell::new(0),
+            text_block_start: RefCell::new(0),
+            pending_citations: RefCell::new(Vec::new()),
+            server_tool_use: RefCell::new(None),
+        })
+    }
+}
+
+impl AnthropicChatStream {
+    fn text_delta(&self, text: String) -> StreamEvent {
+        *self.text_length.borrow_mut() += text.chars().count() as u32;
+        StreamEvent::Delta(StreamDelta {
+            content: Some(vec![ContentPart::Text(text)]),
+            tool_calls: None,
+            tool_call_deltas: None,
+            citations: None,
         })
     }
 }
@@ -97,6 +137,7 @@ impl LlmChatStreamState for AnthropicChatStream {
                 code: ErrorCode::InternalError,
                 message: message.into(),
                 provider_error_json: None,
+                retry_after_ms: None,
             }
         }
 
@@ -114,7 +155,26 @@ impl LlmChatStreamState for AnthropicChatStream {
                 let error = serde_json::from_value::<ErrorResponse>(json).map_err(|err| {
                     decode_internal_error(format!("Failed to deserialize stream event: {err}"))
                 })?;
-                Err(decode_internal_error(error.error.message))
+                let error_json = serde_json::to_string(&error).unwrap();
+                Err(Error {
+                    code: error_code_from_response(StatusCode::INTERNAL_SERVER_ERROR, &error_json),
+                    message: error.error.message,
+                    provider_error_json: Some(error_json),
+                    retry_after_ms: None,
+                })
+            }
+            Some("message_start") => {
+                // The input and cache token counts are only reported at the start of the message
+                let usage = json
+                    .as_object()
+                    .and_then(|obj| obj.get("message"))
+                    .and_then(|v| v.as_object())
+                    .and_then(|obj| obj.get("usage"))
+                    .and_then(|v| serde_json::from_value::<Usage>(v.clone()).ok());
+                if let Some(usage) = usage {
+                    self.response_metadata.borrow_mut().usage = Some(convert_usage(usage));
+                }
+                Ok(None)
             }
             Some("content_block_start") => {
                 let index = json
@@ -142,18 +202,75 @@ impl LlmChatStreamState for AnthropicChatStream {
                         decode_internal_error(format!("Failed to deserialize stream event: {err}"))
                     })?;
 
-                if let Content::ToolUse { id, name, .. } = content_block {
-                    self.json_fragments.borrow_mut().insert(
-                        index,
-                        JsonFragment {
-                            id,
-                            name,
-                            json: String::new(),
-                        },
-                    );
+                match content_block {
+                    Content::ToolUse { id, name, .. } => {
+                        if self.json_output_tool.as_ref() == Some(&name) {
+                            // The structured output is streamed as text instead of a tool call
+                            *self.json_output_index.borrow_mut() = Some(index);
+                            return Ok(None);
+                        }
+                        Ok(Some(StreamEvent::Delta(StreamDelta {
+                            content: None,
+                            tool_calls: None,
+                            tool_call_deltas: Some(vec![ToolCallDelta {
+                                index: index as u32,
+                                id: Some(id),
+                                name: Some(name),
+                                arguments_fragment: String::new(),
+                            }]),
+                            citations: None,
+                        })))
+                    }
+                    Content::RedactedThinking { data } => {
+                        Ok(Some(StreamEvent::Delta(StreamDelta {
+                            content: Some(vec![ContentPart::Reasoning(ReasoningContent {
+                                text: String::new(),
+                                signature: None,
+                                redacted_data: Some(data),
+                            })]),
+                            tool_calls: None,
+                            tool_call_deltas: None,
+                            citations: None,
+                        })))
+                    }
+                    Content::Text { .. } => {
+                        *self.text_block_start.borrow_mut() = *self.text_length.borrow();
+                        Ok(None)
+                    }
+                    Content::ServerToolUse { .. } => {
+                        *self.server_tool_use.borrow_mut() =
+                            Some((index, content_block, String::new()));
+                        Ok(None)
+                    }
+                    Content::WebSearchToolResult { .. }
+                    | Content::CodeExecutionToolResult { .. } => {
+                        let Some((_, mut tool_use, input_json)) =
+                            self.server_tool_use.borrow_mut().take()
+                        else {
+                            return Ok(None);
+                        };
+                        // The input of the server tool use is streamed like the one of tool calls
+                        if let (Content::ServerToolUse { input, .. }, Ok(streamed_input)) = (
+                            &mut tool_use,
+                            serde_json::from_str::<serde_json::Value>(&input_json),
+                        ) {
+                            *input = streamed_input;
+                        }
+                        Ok(
+                            server_tool_call_to_content_part(tool_use, Some(content_block)).map(
+                                |content_part| {
+                                    StreamEvent::Delta(StreamDelta {
+                                        content: Some(vec![content_part]),
+                                        tool_calls: None,
+                                        tool_call_deltas: None,
+                                        citations: None,
+                                    })
+                                },
+                            ),
+                        )
+                    }
+                    _ => Ok(None),
                 }
-
-                Ok(None)
             }
             Some("content_block_delta") => {
                 let raw_delta = json
@@ -170,12 +287,7 @@ impl LlmChatStreamState for AnthropicChatStream {
                     })?;
 
                 match delta {
-                    ContentBlockDelta::TextDelta { text } => {
-                        Ok(Some(StreamEvent::Delta(StreamDelta {
-                            content: Some(vec![ContentPart::Text(text)]),
-                            tool_calls: None,
-                        })))
-                    }
+                    ContentBlockDelta::TextDelta { text } => Ok(Some(self.text_delta(text))),
                     ContentBlockDelta::InputJsonDelta { partial_json } => {
                         let index = json
                             .as_object()
@@ -187,37 +299,81 @@ impl LlmChatStreamState for AnthropicChatStream {
                                 )
                             })?;
 
-                        let mut json_fragments = self.json_fragments.borrow_mut();
-                        let fragment = json_fragments.entry(index).or_default();
-                        fragment.json.push_str(&partial_json);
+                        if *self.json_output_index.borrow() == Some(index) {
+                            return Ok(Some(self.text_delta(partial_json)));
+                        }
+                        if let Some((server_tool_index, _, input_json)) =
+                            self.server_tool_use.borrow_mut().as_mut()
+                        {
+                            if *server_tool_index == index {
+                                input_json.push_str(&partial_json);
+                                return Ok(None);
+                            }
+                        }
 
+                        Ok(Some(StreamEvent::Delta(StreamDelta {
+                            content: None,
+                            tool_calls: None,
+                            tool_call_deltas: Some(vec![ToolCallDelta {
+                                index: index as u32,
+                                id: None,
+                                name: None,
+                                arguments_fragment: partial_json,
+                            }]),
+                            citations: None,
+                        })))
+                    }
+                    ContentBlockDelta::ThinkingDelta { thinking } => {
+                        Ok(Some(StreamEvent::Delta(StreamDelta {
+                            content: Some(vec![ContentPart::Reasoning(ReasoningContent {
+                                text: thinking,
+                                signature: None,
+                                redacted_data: None,
+                            })]),
+                            tool_calls: None,
+                            tool_call_deltas: None,
+                            citations: None,
+                        })))
+                    }
+                    ContentBlockDelta::SignatureDelta { signature } => {
+                        Ok(Some(StreamEvent::Delta(StreamDelta {
+                            content: Some(vec![ContentPart::Reasoning(ReasoningContent {
+                                text: String::new(),
+                                signature: Some(signature),
+                                redacted_data: None,
+                            })]),
+                            tool_calls: None,
+                            tool_call_deltas: None,
+                            citations: None,
+                        })))
+                    }
+                    ContentBlockDelta::CitationsDelta { citation } => {
+                        // The citations precede the cited text, they are sent once the block ends
+                        self.pending_citations.borrow_mut().push(citation);
                         Ok(None)
                     }
                 }
             }
             Some("content_block_stop") => {
-                let index = json
-                    .as_object()
-                    .and_then(|obj| obj.get("index"))
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        decode_internal_error(
-                            "Unexpected stream event format, does not have 'index' field",
-                        )
-                    })?;
-
-                if let Some(tool_use) = self.json_fragments.borrow_mut().remove(&index) {
-                    Ok(Some(StreamEvent::Delta(StreamDelta {
-                        content: None,
-                        tool_calls: Some(vec![ToolCall {
-                            id: tool_use.id,
-                            name: tool_use.name,
-                            arguments_json: tool_use.json,
-                        }]),
-                    })))
-                } else {
-                    Ok(None)
+                let pending_citations = std::mem::take(&mut *self.pending_citations.borrow_mut());
+                if pending_citations.is_empty() {
+                    return Ok(None);
                 }
+                let start_index = *self.text_block_start.borrow();
+                let end_index = *self.text_length.borrow();
+                Ok(Some(StreamEvent::Delta(StreamDelta {
+                    content: None,
+                    tool_calls: None,
+                    tool_call_deltas: None,
+                    citations: Some(
+                        pending_citations
+                            .into_iter()
+                            .filter_map(|citation| {
+                                convert_citation(citation, start_index, end_index)
+                            })
+                            .collect(),
+                    ),
+                })))
             }
             Some("message_delta") => {
                 let stop_reason = json
@@ -226,17 +382,32 @@ impl LlmChatStreamState for AnthropicChatStream {
                     .and_then(|v| v.as_object())
                     .and_then(|obj| obj.get("stop_reason"))
                     .and_then(|v| serde_json::from_value::<StopReason>(v.clone()).ok());
-                let usage = json
-                    .as_object()
-                    .and_then(|obj| obj.get("usage"))
-                    .and_then(|v| serde_json::from_value::<Usage>(v.clone()).ok());
+                let usage = json.as_object().and_then(|obj| obj.get("usage"));
+                let output_tokens = usage
+                    .and_then(|v| v.as_object())
+                    .and_then(|obj| obj.get("output_tokens"))
+                    .and_then(|v| v.as_u64());
+                let usage = usage.and_then(|v| serde_json::from_value::<Usage>(v.clone()).ok());
 
                 if let Some(stop_reason) = stop_reason {
-                    self.response_metadata.borrow_mut().finish_reason =
-                        Some(stop_reason_to_finish_reason(stop_reason));
+                    let finish_reason = match stop_reason_to_finish_reason(stop_reason) {
+                        FinishReason::ToolCalls if self.json_output_index.borrow().is_some() => {
+                            FinishReason::Stop
+                        }
+                        finish_reason => finish_reason,
+                    };
+                    self.response_metadata.borrow_mut().finish_reason = Some(finish_reason);
                 }
-                if let Some(usage) = usage {
-                    self.response_metadata.borrow_mut().usage = Some(convert_usage(usage));
+                match (usage, output_tokens) {
+                    (Some(usage), _) => {
+                        self.response_metadata.borrow_mut().usage = Some(convert_usage(usage));
+                    }
+                    (None, Some(output_tokens)) => {
+                        if let Some(usage) = &mut self.response_metadata.borrow_mut().usage {
+                            usage.output_tokens = Some(output_tokens as u32);
+                        }
+                    }
+                    (None, None) => {}
                 }
                 Ok(None)
             }
@@ -252,23 +423,34 @@ impl LlmChatStreamState for AnthropicChatStream {
     }
 }
 
-struct AnthropicComponent;
+pub struct AnthropicComponent;
 
 impl AnthropicComponent {
     const ENV_VAR_NAME: &'static str = "ANTHROPIC_API_KEY";
 
-    fn request(client: MessagesApi, request: MessagesRequest) -> Result<Response, Error> {
+    fn request(
+        client: MessagesApi,
+        request: MessagesRequest,
+        json_output_tool: Option<String>,
+    ) -> Result<Response, Error> {
+        let model = request.model.clone();
         let response = client.send_messages(request)?;
-        process_response(response)
+        let mut response = process_response(response, json_output_tool.as_deref())?;
+        add_cost(&mut response.metadata, &model, &PRICING);
+        Ok(response)
     }
 
     fn streaming_request(
         client: MessagesApi,
         mut request: MessagesRequest,
+        json_output_tool: Option<String>,
     ) -> LlmChatStream<AnthropicChatStream> {
         request.stream = true;
+        let model = request.model.clone();
         match client.stream_send_messages(request) {
-            Ok(stream) => AnthropicChatStream::new(stream),
+            Ok(stream) => {
+                AnthropicChatStream::new(stream, json_output_tool).with_pricing(&model, &PRICING)
+            }
             Err(err) => AnthropicChatStream::failed(err),
         }
     }
@@ -276,28 +458,122 @@ impl AnthropicComponent {
 
 impl Guest for AnthropicComponent {
     type ChatStream = LlmChatStream<AnthropicChatStream>;
+    type ChatSession = ChatSession<Self>;
 
     fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
         let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
         let client = MessagesApi::new(anthropic_api_key);
+        let json_output_tool = json_output_tool_name(&config.response_format);
         let request = events_to_request(events, config)?;
-        Self::request(client, request)
+        Self::request(client, request, json_output_tool)
     }
 
     fn stream(events: Vec<Event>, config: Config) -> ChatStream {
         ChatStream::new(Self::unwrapped_stream(events, config))
     }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = MessagesApi::new(anthropic_api_key);
+        let request = events_to_request(events, config)?;
+        let response = client.count_tokens(request.into())?;
+        Ok(response.input_tokens)
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(known_model_info(&model, KNOWN_MODELS))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = MessagesApi::new(anthropic_api_key);
+
+        let mut models = Vec::new();
+        let mut after_id = None;
+        loop {
+            let response = client.list_models(after_id.as_deref())?;
+            models.extend(
+                response
+                    .data
+                    .into_iter()
+                    .map(|model| known_model_info(&model.id, KNOWN_MODELS)),
+            );
+            if !response.has_more || response.last_id.is_none() {
+                break;
+            }
+            after_id = response.last_id;
+        }
+        Ok(models)
+    }
+}
+
+impl BatchGuest for AnthropicComponent {
+    fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+        let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = MessagesApi::new(anthropic_api_key);
+        let json_output_tools = batch_json_output_tools(&items)?;
+        let requests = items
+            .into_iter()
+            .map(|item| {
+                events_to_request(item.events, item.config).map(|params| MessageBatchRequest {
+                    custom_id: item.custom_id,
+                    params,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let batch = client.create_message_batch(CreateMessageBatchRequest { requests })?;
+        Ok(encode_batch_id(batch.id, &json_output_tools))
+    }
+
+    fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+        let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = MessagesApi::new(anthropic_api_key);
+        let (message_batch_id, _) = decode_batch_id(&id);
+        let info = client
+            .retrieve_message_batch(message_batch_id)
+            .map(message_batch_to_batch_info)?;
+        Ok(BatchInfo { id, ..info })
+    }
+
+    /// The results are only available once the batch has ended
+    fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+        let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = MessagesApi::new(anthropic_api_key);
+        let (message_batch_id, json_output_tools) = decode_batch_id(&id);
+        let batch = client.retrieve_message_batch(message_batch_id)?;
+        match batch.results_url {
+            Some(results_url) => Ok(client
+                .message_batch_results(&results_url)?
+                .into_iter()
+                .map(|line| message_batch_result_to_batch_result(line, &json_output_tools))
+                .collect()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+        let anthropic_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = MessagesApi::new(anthropic_api_key);
+        let (message_batch_id, _) = decode_batch_id(&id);
+        let info = client
+            .cancel_message_batch(message_batch_id)
+            .map(message_batch_to_batch_info)?;
+        Ok(BatchInfo { id, ..info })
+    }
 }
 
 impl ExtendedGuest for AnthropicComponent {
+    const PROVIDER_NAME: &'static str = "anthropic";
+
     fn unwrapped_stream(events: Vec<Event>, config: Config) -> LlmChatStream<AnthropicChatStream> {
         with_config_key(
             Self::ENV_VAR_NAME,
             AnthropicChatStream::failed,
             |anthropic_api_key| {
                 let client = MessagesApi::new(anthropic_api_key);
+                let json_output_tool = json_output_tool_name(&config.response_format);
                 match events_to_request(events, config) {
-                    Ok(request) => Self::streaming_request(client, request),
+                    Ok(request) => Self::streaming_request(client, request, json_output_tool),
                     Err(err) => AnthropicChatStream::failed(err),
                 }
             },
@@ -317,6 +593,7 @@ impl ExtendedGuest for AnthropicComponent {
                 Please continue your response from where you left off.
                 Do not include the part of the response that was already seen.
             "}.to_string())],
+            cache_hint: None,
         }));
         extended_events.push(Event::Message(Message {
             role: Role::User,
@@ -324,6 +601,7 @@ impl ExtendedGuest for AnthropicComponent {
             content: vec![ContentPart::Text(
                 "Here is the original question:".to_string(),
             )],
+            cache_hint: None,
         }));
         extended_events.extend(
             original_events
@@ -334,7 +612,13 @@ impl ExtendedGuest for AnthropicComponent {
         let mut partial_result_as_content = Vec::new();
         for delta in partial_result {
             if let Some(contents) = &delta.content {
-                partial_result_as_content.extend_from_slice(contents);
+                // Reasoning is not part of the answer the model has to continue
+                partial_result_as_content.extend(
+                    contents
+                        .iter()
+                        .filter(|content| !matches!(content, ContentPart::Reasoning(_)))
+                        .cloned(),
+                );
             }
             if let Some(tool_calls) = &delta.tool_calls {
                 for tool_call in tool_calls {
@@ -355,6 +639,7 @@ impl ExtendedGuest for AnthropicComponent {
             .into_iter()
             .chain(partial_result_as_content)
             .collect(),
+            cache_hint: None,
         }));
         extended_events
     }
@@ -364,6 +649,8 @@ impl ExtendedGuest for AnthropicComponent {
     }
 }
 
-type DurableAnthropicComponent = DurableLLM<AnthropicComponent>;
+#[cfg(not(feature = "library"))]
+type DurableAnthropicComponent = DurableLLM<CachedLLM<AnthropicComponent>>;
 
+#[cfg(not(feature = "library"))]
 golem_llm::export_llm!(DurableAnthropicComponent with_types_in golem_llm);
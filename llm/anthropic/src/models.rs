@@ -0,0 +1,234 @@
+use golem_llm::golem::llm::llm::ModelCapabilities;
+use golem_llm::models::KnownModel;
+use golem_llm::pricing::{KnownPrice, Price, Pricing};
+
+/// Limits and capabilities of the Anthropic models, as the models API does not report them
+pub const KNOWN_MODELS: &[KnownModel] = &[
+    KnownModel {
+        id: "claude-opus-4-1",
+        context_window: 200_000,
+        max_output_tokens: Some(32_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-opus-4",
+        context_window: 200_000,
+        max_output_tokens: Some(32_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-sonnet-4-5",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-sonnet-4",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-haiku-4-5",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-3-7-sonnet",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-3-5-sonnet",
+        context_window: 200_000,
+        max_output_tokens: Some(8_192),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-3-5-haiku",
+        context_window: 200_000,
+        max_output_tokens: Some(8_192),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-3-opus",
+        context_window: 200_000,
+        max_output_tokens: Some(4_096),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "claude-3-haiku",
+        context_window: 200_000,
+        max_output_tokens: Some(4_096),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+];
+
+/// Prices of the Anthropic models, the input tokens reported by the API exclude the cached ones
+pub const PRICING: Pricing = Pricing {
+    input_includes_cached: false,
+    known_prices: &[
+        KnownPrice {
+            id: "claude-opus-4-1",
+            price: Price {
+                input: 15.0,
+                output: 75.0,
+                cache_read: Some(1.5),
+                cache_write: Some(18.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-opus-4",
+            price: Price {
+                input: 15.0,
+                output: 75.0,
+                cache_read: Some(1.5),
+                cache_write: Some(18.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-sonnet-4-5",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-sonnet-4",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-haiku-4-5",
+            price: Price {
+                input: 1.0,
+                output: 5.0,
+                cache_read: Some(0.1),
+                cache_write: Some(1.25),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-3-7-sonnet",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-3-5-sonnet",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-3-5-haiku",
+            price: Price {
+                input: 0.8,
+                output: 4.0,
+                cache_read: Some(0.08),
+                cache_write: Some(1.0),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-3-opus",
+            price: Price {
+                input: 15.0,
+                output: 75.0,
+                cache_read: Some(1.5),
+                cache_write: Some(18.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "claude-3-haiku",
+            price: Price {
+                input: 0.25,
+                output: 1.25,
+                cache_read: Some(0.03),
+                cache_write: Some(0.3),
+                reasoning: None,
+            },
+        },
+    ],
+};
@@ -0,0 +1,263 @@
+use golem_llm::config::get_config_key_or_none;
+use golem_llm::error::{error_code_from_response, from_reqwest_error};
+use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use golem_llm::retry::send_with_retry;
+use log::trace;
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const API_KEY_ENV_VAR_NAME: &str = "AZURE_OPENAI_API_KEY";
+const AD_TOKEN_ENV_VAR_NAME: &str = "AZURE_OPENAI_AD_TOKEN";
+const TENANT_ID_ENV_VAR_NAME: &str = "AZURE_TENANT_ID";
+const CLIENT_ID_ENV_VAR_NAME: &str = "AZURE_CLIENT_ID";
+const CLIENT_SECRET_ENV_VAR_NAME: &str = "AZURE_CLIENT_SECRET";
+
+/// Scope of the Entra ID access tokens of the Azure OpenAI data plane
+const TOKEN_SCOPE: &str = "https://cognitiveservices.azure.com/.default";
+
+/// Access tokens are refreshed this long before they expire, so they do not expire mid-request
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// How the requests to the Azure OpenAI resource are authenticated
+pub enum Credentials {
+    /// A key of the resource, sent in the `api-key` header
+    ApiKey(String),
+    /// A Microsoft Entra ID access token, sent as a bearer token
+    BearerToken(String),
+}
+
+/// The service principal an access token was requested for. Only a hash of the secret is kept, so
+/// a changed secret requests a new token without the secret itself being cached.
+#[derive(Debug, Clone, PartialEq)]
+struct TokenSource {
+    tenant_id: String,
+    client_id: String,
+    client_secret_hash: u64,
+}
+
+impl TokenSource {
+    fn new(tenant_id: &str, client_id: &str, client_secret: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        client_secret.hash(&mut hasher);
+        Self {
+            tenant_id: tenant_id.to_string(),
+            client_id: client_id.to_string(),
+            client_secret_hash: hasher.finish(),
+        }
+    }
+}
+
+/// An access token of a service principal, and the time it expires at in milliseconds since the
+/// Unix epoch
+struct CachedToken {
+    source: TokenSource,
+    access_token: String,
+    expires_at_ms: u64,
+}
+
+thread_local! {
+    static CACHED_TOKEN: RefCell<Option<CachedToken>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl Credentials {
+    /// Resolves the credentials from the environment, preferring an API key, then a given Entra
+    /// ID access token, and finally requesting an access token for a service principal with the
+    /// client credentials flow. The requested tokens are cached until shortly before they expire.
+    pub fn resolve() -> Result<Self, Error> {
+        if let Some(api_key) = get_config_key_or_none(API_KEY_ENV_VAR_NAME) {
+            return Ok(Credentials::ApiKey(api_key));
+        }
+        if let Some(token) = get_config_key_or_none(AD_TOKEN_ENV_VAR_NAME) {
+            return Ok(Credentials::BearerToken(token));
+        }
+
+        match (
+            get_config_key_or_none(TENANT_ID_ENV_VAR_NAME),
+            get_config_key_or_none(CLIENT_ID_ENV_VAR_NAME),
+            get_config_key_or_none(CLIENT_SECRET_ENV_VAR_NAME),
+        ) {
+            (Some(tenant_id), Some(client_id), Some(client_secret)) => {
+                service_principal_token(&tenant_id, &client_id, &client_secret)
+                    .map(Credentials::BearerToken)
+            }
+            _ => Err(Error {
+                code: ErrorCode::AuthenticationFailed,
+                message: format!(
+                    "Missing credentials: set {API_KEY_ENV_VAR_NAME}, {AD_TOKEN_ENV_VAR_NAME}, or {TENANT_ID_ENV_VAR_NAME}, {CLIENT_ID_ENV_VAR_NAME} and {CLIENT_SECRET_ENV_VAR_NAME}"
+                ),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }),
+        }
+    }
+}
+
+fn service_principal_token(
+    tenant_id: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<String, Error> {
+    validate_tenant_id(tenant_id)?;
+
+    let source = TokenSource::new(tenant_id, client_id, client_secret);
+    if let Some(access_token) = cached_token(&source) {
+        return Ok(access_token);
+    }
+
+    trace!("Requesting Entra ID access token for client {client_id}");
+
+    let client = Client::builder()
+        .build()
+        .expect("Failed to initialize HTTP client");
+    let body = format!(
+        "grant_type=client_credentials&client_id={}&client_secret={}&scope={}",
+        urlencoding::encode(client_id),
+        urlencoding::encode(client_secret),
+        urlencoding::encode(TOKEN_SCOPE)
+    );
+    let response = send_with_retry(|| {
+        client
+            .request(
+                Method::POST,
+                format!("https://login.microsoftonline.com/{tenant_id}/oauth2/v2.0/token"),
+            )
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body.clone())
+    })
+    .map_err(|err| from_reqwest_error("Token request failed", err))?;
+
+    let status = response.status();
+    let raw_body = response
+        .text()
+        .map_err(|err| from_reqwest_error("Failed to receive token response body", err))?;
+    if !status.is_success() {
+        let code = match error_code_from_response(status, &raw_body) {
+            ErrorCode::InvalidRequest => ErrorCode::AuthenticationFailed,
+            code => code,
+        };
+        return Err(Error {
+            code,
+            message: format!("Token request failed with {status}"),
+            provider_error_json: Some(raw_body),
+            retry_after_ms: None,
+        });
+    }
+
+    let token = serde_json::from_str::<TokenResponse>(&raw_body).map_err(|err| Error {
+        code: ErrorCode::InternalError,
+        message: format!("Failed to decode token response body: {err}"),
+        provider_error_json: None,
+        retry_after_ms: None,
+    })?;
+
+    cache_token(
+        source,
+        &token.access_token,
+        now_ms().saturating_add(token.expires_in.saturating_mul(1000)),
+    );
+    Ok(token.access_token)
+}
+
+/// Tenant IDs are GUIDs or domain names, checked before being used as a path segment of the token
+/// endpoint
+fn validate_tenant_id(tenant_id: &str) -> Result<(), Error> {
+    if !tenant_id.is_empty()
+        && tenant_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        Ok(())
+    } else {
+        Err(Error {
+            code: ErrorCode::InvalidRequest,
+            message: format!("Invalid {TENANT_ID_ENV_VAR_NAME}: {tenant_id}"),
+            provider_error_json: None,
+            retry_after_ms: None,
+        })
+    }
+}
+
+fn cached_token(source: &TokenSource) -> Option<String> {
+    CACHED_TOKEN.with_borrow(|cached| {
+        cached
+            .as_ref()
+            .filter(|cached| {
+                cached.source == *source
+                    && cached.expires_at_ms
+                        > now_ms().saturating_add(TOKEN_REFRESH_MARGIN.as_millis() as u64)
+            })
+            .map(|cached| cached.access_token.clone())
+    })
+}
+
+fn cache_token(source: TokenSource, access_token: &str, expires_at_ms: u64) {
+    CACHED_TOKEN.with_borrow_mut(|cached| {
+        *cached = Some(CachedToken {
+            source,
+            access_token: access_token.to_string(),
+            expires_at_ms,
+        })
+    });
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_token_is_keyed_on_the_whole_source() {
+        let source = TokenSource::new("tenant-1", "client", "secret");
+        cache_token(source.clone(), "token", now_ms() + 3_600_000);
+
+        assert_eq!(cached_token(&source), Some("token".to_string()));
+        assert_eq!(
+            cached_token(&TokenSource::new("tenant-2", "client", "secret")),
+            None
+        );
+        assert_eq!(
+            cached_token(&TokenSource::new("tenant-1", "other-client", "secret")),
+            None
+        );
+        assert_eq!(
+            cached_token(&TokenSource::new("tenant-1", "client", "rotated-secret")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cached_token_is_refreshed_before_expiry() {
+        let source = TokenSource::new("tenant-1", "client", "secret");
+        cache_token(source.clone(), "token", now_ms() + 60_000);
+
+        assert_eq!(cached_token(&source), None);
+    }
+
+    #[test]
+    fn test_validate_tenant_id() {
+        assert!(validate_tenant_id("72f988bf-86f1-41af-91ab-2d7cd011db47").is_ok());
+        assert!(validate_tenant_id("contoso.onmicrosoft.com").is_ok());
+        assert!(validate_tenant_id("").is_err());
+        assert!(validate_tenant_id("tenant/../common").is_err());
+        assert!(validate_tenant_id("tenant?x=1").is_err());
+    }
+}
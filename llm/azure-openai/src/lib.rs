@@ -0,0 +1,359 @@
+mod auth;
+mod client;
+mod conversions;
+mod models;
+
+use crate::auth::Credentials;
+use crate::client::{
+    ChatCompletionChunk, CompletionsApi, CompletionsRequest, ErrorResponseBody, StreamOptions,
+    Usage,
+};
+use crate::conversions::{
+    convert_finish_reason, convert_usage, events_to_request, process_response,
+};
+use crate::models::{KNOWN_MODELS, PRICING};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
+use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
+use golem_llm::config::{get_config_key, get_config_key_or_none};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::unsupported;
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{
+    ChatStream, Config, ContentPart, Error, ErrorCode, Event, FinishReason, Guest, ModelInfo,
+    Response, ResponseMetadata, StreamDelta, StreamEvent, ToolCallDelta,
+};
+use golem_llm::models::known_model_info;
+use golem_llm::pricing::add_cost;
+use golem_llm::tokens::estimate_tokens;
+use golem_rust::wasm_rpc::Pollable;
+use log::trace;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::BTreeMap;
+
+pub struct AzureOpenAIChatStream {
+    stream: RefCell<Option<EventSource>>,
+    failure: Option<Error>,
+    finished: RefCell<bool>,
+    finish_reason: RefCell<Option<FinishReason>>,
+    model: String,
+}
+
+impl AzureOpenAIChatStream {
+    pub fn new(stream: EventSource, model: String) -> LlmChatStream<Self> {
+        LlmChatStream::new(AzureOpenAIChatStream {
+            stream: RefCell::new(Some(stream)),
+            failure: None,
+            finished: RefCell::new(false),
+            finish_reason: RefCell::new(None),
+            model,
+        })
+    }
+
+    pub fn failed(error: Error) -> LlmChatStream<Self> {
+        LlmChatStream::new(AzureOpenAIChatStream {
+            stream: RefCell::new(None),
+            failure: Some(error),
+            finished: RefCell::new(false),
+            finish_reason: RefCell::new(None),
+            model: String::new(),
+        })
+    }
+
+    /// Creates the finish event, pricing the stream by the model reported by the deployment
+    fn finish(&self, chunk: &ChatCompletionChunk, usage: &Usage) -> StreamEvent {
+        let mut metadata = ResponseMetadata {
+            finish_reason: *self.finish_reason.borrow(),
+            usage: Some(convert_usage(usage)),
+            provider_id: None,
+            timestamp: chunk.created.map(|created| created.to_string()),
+            provider_metadata_json: None,
+            cost: None,
+        };
+        let model = chunk
+            .model
+            .as_deref()
+            .filter(|model| !model.is_empty())
+            .unwrap_or(&self.model);
+        add_cost(&mut metadata, model, &PRICING);
+        StreamEvent::Finish(metadata)
+    }
+}
+
+impl LlmChatStreamState for AzureOpenAIChatStream {
+    fn failure(&self) -> &Option<Error> {
+        &self.failure
+    }
+
+    fn is_finished(&self) -> bool {
+        *self.finished.borrow()
+    }
+
+    fn set_finished(&self) {
+        *self.finished.borrow_mut() = true;
+    }
+
+    fn stream(&self) -> Ref<'_, Option<EventSource>> {
+        self.stream.borrow()
+    }
+
+    fn stream_mut(&self) -> RefMut<'_, Option<EventSource>> {
+        self.stream.borrow_mut()
+    }
+
+    fn decode_message(&self, raw: &str) -> Result<Option<StreamEvent>, Error> {
+        fn decode_internal_error<S: Into<String>>(message: S) -> Error {
+            Error {
+                code: ErrorCode::InternalError,
+                message: message.into(),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }
+        }
+
+        trace!("Received raw stream event: {raw}");
+        let json: serde_json::Value = serde_json::from_str(raw).map_err(|err| {
+            decode_internal_error(format!("Failed to deserialize stream event: {err}"))
+        })?;
+
+        if json.get("error").is_some_and(|error| !error.is_null()) {
+            let message = serde_json::from_value::<ErrorResponseBody>(json)
+                .map(|error_body| error_body.error.message)
+                .unwrap_or_else(|_| "Received error event from server".to_string());
+            return Err(Error {
+                code: ErrorCode::InternalError,
+                message,
+                provider_error_json: Some(raw.to_string()),
+                retry_after_ms: None,
+            });
+        }
+
+        let chunk: ChatCompletionChunk = serde_json::from_value(json)
+            .map_err(|err| decode_internal_error(format!("Failed to parse stream event: {err}")))?;
+
+        // The first chunk only carries the results of the prompt's content filtering, and the
+        // final chunk only the usage
+        let Some(choice) = chunk.choices.first() else {
+            return Ok(chunk.usage.as_ref().map(|usage| self.finish(&chunk, usage)));
+        };
+
+        if let Some(finish_reason) = &choice.finish_reason {
+            *self.finish_reason.borrow_mut() = Some(convert_finish_reason(finish_reason));
+        }
+
+        let content = choice
+            .delta
+            .content
+            .clone()
+            .filter(|content| !content.is_empty())
+            .map(ContentPart::Text)
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        // The id and name of a tool call are only sent in its first fragment
+        let tool_call_deltas = choice
+            .delta
+            .tool_calls
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tool_call| match tool_call {
+                client::ToolCall::Function {
+                    id,
+                    function,
+                    index,
+                } => ToolCallDelta {
+                    index: index.unwrap_or_default(),
+                    id,
+                    name: function.name,
+                    arguments_fragment: function.arguments,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        if !content.is_empty() || !tool_call_deltas.is_empty() {
+            Ok(Some(StreamEvent::Delta(StreamDelta {
+                content: (!content.is_empty()).then_some(content),
+                tool_calls: None,
+                tool_call_deltas: (!tool_call_deltas.is_empty()).then_some(tool_call_deltas),
+                citations: None,
+            })))
+        } else {
+            Ok(chunk.usage.as_ref().map(|usage| self.finish(&chunk, usage)))
+        }
+    }
+}
+
+pub struct AzureOpenAIComponent;
+
+impl AzureOpenAIComponent {
+    const ENDPOINT_ENV_VAR_NAME: &'static str = "AZURE_OPENAI_ENDPOINT";
+    const API_VERSION_ENV_VAR_NAME: &'static str = "AZURE_OPENAI_API_VERSION";
+    const DEPLOYMENTS_ENV_VAR_NAME: &'static str = "GOLEM_AZURE_OPENAI_DEPLOYMENTS";
+
+    /// The latest generally available version of the data plane API
+    const DEFAULT_API_VERSION: &'static str = "2024-10-21";
+
+    fn create_client() -> Result<CompletionsApi, Error> {
+        let endpoint = get_config_key(Self::ENDPOINT_ENV_VAR_NAME)?;
+        let api_version = get_config_key_or_none(Self::API_VERSION_ENV_VAR_NAME)
+            .unwrap_or_else(|| Self::DEFAULT_API_VERSION.to_string());
+        Ok(CompletionsApi::new(
+            endpoint,
+            api_version,
+            Credentials::resolve()?,
+        ))
+    }
+
+    /// The configured mapping of model names to the names of the deployments serving them
+    fn deployments() -> Result<BTreeMap<String, String>, Error> {
+        match get_config_key_or_none(Self::DEPLOYMENTS_ENV_VAR_NAME) {
+            Some(deployments) => serde_json::from_str(&deployments).map_err(|err| Error {
+                code: ErrorCode::InternalError,
+                message: format!(
+                    "Invalid config key {}, expected a JSON object of model and deployment names: {err}",
+                    Self::DEPLOYMENTS_ENV_VAR_NAME
+                ),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Gets the deployment to send the requests for the given model to. Models without a
+    /// configured deployment are expected to be deployed under their own name.
+    fn deployment(model: &str) -> Result<String, Error> {
+        Ok(Self::deployments()?
+            .remove(model)
+            .unwrap_or_else(|| model.to_string()))
+    }
+
+    fn request(
+        client: CompletionsApi,
+        model: &str,
+        request: CompletionsRequest,
+    ) -> Result<Response, Error> {
+        let deployment = Self::deployment(model)?;
+        let response = client.send_messages(&deployment, request)?;
+        // The deployment reports the model and version it runs, which is priced more accurately
+        let response_model = response
+            .model
+            .clone()
+            .filter(|response_model| !response_model.is_empty())
+            .unwrap_or_else(|| model.to_string());
+        let mut response = process_response(response)?;
+        add_cost(&mut response.metadata, &response_model, &PRICING);
+        Ok(response)
+    }
+
+    fn streaming_request(
+        client: CompletionsApi,
+        model: String,
+        mut request: CompletionsRequest,
+    ) -> LlmChatStream<AzureOpenAIChatStream> {
+        request.stream = Some(true);
+        request.stream_options = Some(StreamOptions {
+            include_usage: true,
+        });
+        let deployment = match Self::deployment(&model) {
+            Ok(deployment) => deployment,
+            Err(err) => return AzureOpenAIChatStream::failed(err),
+        };
+        match client.stream_send_messages(&deployment, request) {
+            Ok(stream) => AzureOpenAIChatStream::new(stream, model),
+            Err(err) => AzureOpenAIChatStream::failed(err),
+        }
+    }
+}
+
+impl Guest for AzureOpenAIComponent {
+    type ChatStream = LlmChatStream<AzureOpenAIChatStream>;
+    type ChatSession = ChatSession<Self>;
+
+    fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+        let client = Self::create_client()?;
+        let model = config.model.clone();
+        let request = events_to_request(events, config)?;
+        Self::request(client, &model, request)
+    }
+
+    fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+        ChatStream::new(Self::unwrapped_stream(events, config))
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(known_model_info(&model, KNOWN_MODELS))
+    }
+
+    /// Lists the models of the configured deployments, as the deployments of a resource can only
+    /// be listed through the Azure management API
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let deployments = Self::deployments()?;
+        if deployments.is_empty() {
+            return Err(unsupported(format!(
+                "listing models without {}",
+                Self::DEPLOYMENTS_ENV_VAR_NAME
+            )));
+        }
+        Ok(deployments
+            .keys()
+            .map(|model| known_model_info(model, KNOWN_MODELS))
+            .collect())
+    }
+}
+
+impl BatchGuest for AzureOpenAIComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+}
+
+impl ExtendedGuest for AzureOpenAIComponent {
+    const PROVIDER_NAME: &'static str = "azure-openai";
+
+    fn unwrapped_stream(
+        events: Vec<Event>,
+        config: Config,
+    ) -> LlmChatStream<AzureOpenAIChatStream> {
+        let client = match Self::create_client() {
+            Ok(client) => client,
+            Err(err) => return AzureOpenAIChatStream::failed(err),
+        };
+        let model = config.model.clone();
+        match events_to_request(events, config) {
+            Ok(request) => Self::streaming_request(client, model, request),
+            Err(err) => AzureOpenAIChatStream::failed(err),
+        }
+    }
+
+    fn subscribe(stream: &Self::ChatStream) -> Pollable {
+        stream.subscribe()
+    }
+}
+
+#[cfg(not(feature = "library"))]
+type DurableAzureOpenAIComponent = DurableLLM<CachedLLM<AzureOpenAIComponent>>;
+
+#[cfg(not(feature = "library"))]
+golem_llm::export_llm!(DurableAzureOpenAIComponent with_types_in golem_llm);
@@ -0,0 +1,307 @@
+use golem_llm::golem::llm::llm::ModelCapabilities;
+use golem_llm::models::KnownModel;
+use golem_llm::pricing::{KnownPrice, Price, Pricing};
+
+/// Limits and capabilities of the OpenAI models, by the names Azure uses for them
+pub const KNOWN_MODELS: &[KnownModel] = &[
+    KnownModel {
+        id: "gpt-5",
+        context_window: 400_000,
+        max_output_tokens: Some(128_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "gpt-4.1",
+        context_window: 1_047_576,
+        max_output_tokens: Some(32_768),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "gpt-4o",
+        context_window: 128_000,
+        max_output_tokens: Some(16_384),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "gpt-4-turbo",
+        context_window: 128_000,
+        max_output_tokens: Some(4_096),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: false,
+        },
+    },
+    KnownModel {
+        id: "gpt-4",
+        context_window: 8_192,
+        max_output_tokens: Some(8_192),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: false,
+        },
+    },
+    KnownModel {
+        id: "gpt-35-turbo",
+        context_window: 16_385,
+        max_output_tokens: Some(4_096),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: false,
+        },
+    },
+    KnownModel {
+        id: "o4-mini",
+        context_window: 200_000,
+        max_output_tokens: Some(100_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "o3",
+        context_window: 200_000,
+        max_output_tokens: Some(100_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "o3-mini",
+        context_window: 200_000,
+        max_output_tokens: Some(100_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "o1",
+        context_window: 200_000,
+        max_output_tokens: Some(100_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "o1-mini",
+        context_window: 128_000,
+        max_output_tokens: Some(65_536),
+        capabilities: ModelCapabilities {
+            tools: false,
+            vision: false,
+            streaming: true,
+            reasoning: true,
+            json_schema: false,
+        },
+    },
+];
+
+/// Prices of the OpenAI models on Azure (global standard deployments), the input tokens reported
+/// by the API include the cached ones
+pub const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[
+        KnownPrice {
+            id: "gpt-5",
+            price: Price {
+                input: 1.25,
+                output: 10.0,
+                cache_read: Some(0.125),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-5-mini",
+            price: Price {
+                input: 0.25,
+                output: 2.0,
+                cache_read: Some(0.025),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-5-nano",
+            price: Price {
+                input: 0.05,
+                output: 0.4,
+                cache_read: Some(0.005),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4.1",
+            price: Price {
+                input: 2.0,
+                output: 8.0,
+                cache_read: Some(0.5),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4.1-mini",
+            price: Price {
+                input: 0.4,
+                output: 1.6,
+                cache_read: Some(0.1),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4.1-nano",
+            price: Price {
+                input: 0.1,
+                output: 0.4,
+                cache_read: Some(0.025),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4o",
+            price: Price {
+                input: 2.5,
+                output: 10.0,
+                cache_read: Some(1.25),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4o-mini",
+            price: Price {
+                input: 0.15,
+                output: 0.6,
+                cache_read: Some(0.075),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4-turbo",
+            price: Price {
+                input: 10.0,
+                output: 30.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-4",
+            price: Price {
+                input: 30.0,
+                output: 60.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gpt-35-turbo",
+            price: Price {
+                input: 0.5,
+                output: 1.5,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "o4-mini",
+            price: Price {
+                input: 1.1,
+                output: 4.4,
+                cache_read: Some(0.275),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "o3",
+            price: Price {
+                input: 2.0,
+                output: 8.0,
+                cache_read: Some(0.5),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "o3-mini",
+            price: Price {
+                input: 1.1,
+                output: 4.4,
+                cache_read: Some(0.55),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "o1",
+            price: Price {
+                input: 15.0,
+                output: 60.0,
+                cache_read: Some(7.5),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "o1-mini",
+            price: Price {
+                input: 1.1,
+                output: 4.4,
+                cache_read: Some(0.55),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+    ],
+};
@@ -0,0 +1,281 @@
+//! Bedrock's batch inference reads the requests of a batch from a JSON lines file in S3, with each
+//! request in the model's native format, and writes the responses next to it. The files of a batch
+//! are kept in a directory named after its job, in the S3 location given by
+//! `GOLEM_BEDROCK_BATCH_S3_URI`:
+//!
+//! - `records.jsonl`: the requests, one record per batch item
+//! - `items.json`: the batch items the records were created from
+//! - `output/<job id>/records.jsonl.out`: the responses, written by Bedrock
+//! - `output/<job id>/manifest.json.out`: the record counts, written by Bedrock
+
+use crate::conversions::custom_error;
+use aws_sdk_bedrock::types::ModelInvocationJobStatus;
+use golem_llm::config::get_config_key;
+use golem_llm::error::error_code_from_response;
+use golem_llm::golem::llm::batch::{BatchResult, BatchStatus};
+use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub const RECORDS_FILE_NAME: &str = "records.jsonl";
+pub const ITEMS_FILE_NAME: &str = "items.json";
+pub const OUTPUT_FILE_NAME: &str = "records.jsonl.out";
+pub const MANIFEST_FILE_NAME: &str = "manifest.json.out";
+
+#[derive(Debug)]
+pub struct BatchEnvironment {
+    /// The S3 location the directories of the batches are created in
+    pub s3_uri: String,
+    /// The service role Bedrock assumes to read and write the files of the batches
+    pub role_arn: String,
+}
+
+impl BatchEnvironment {
+    pub fn load_from_env() -> Result<Self, Error> {
+        Ok(Self {
+            s3_uri: get_config_key("GOLEM_BEDROCK_BATCH_S3_URI")?,
+            role_arn: get_config_key("GOLEM_BEDROCK_BATCH_ROLE_ARN")?,
+        })
+    }
+}
+
+/// Where the files of a batch are stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchLocation {
+    pub bucket: String,
+    pub directory: String,
+}
+
+impl BatchLocation {
+    /// The directory of a new batch, in the S3 location given by `s3_uri`
+    pub fn new(s3_uri: &str, job_name: &str) -> Result<Self, Error> {
+        let (bucket, prefix) = parse_s3_uri(s3_uri)?;
+        let directory = if prefix.is_empty() {
+            job_name.to_string()
+        } else {
+            format!("{prefix}/{job_name}")
+        };
+        Ok(Self {
+            bucket: bucket.to_string(),
+            directory,
+        })
+    }
+
+    /// The directory of a submitted batch, from the S3 URI of its records file
+    pub fn from_records_uri(records_uri: &str) -> Result<Self, Error> {
+        let (bucket, key) = parse_s3_uri(records_uri)?;
+        let directory = key
+            .strip_suffix(RECORDS_FILE_NAME)
+            .and_then(|directory| directory.strip_suffix('/'))
+            .ok_or_else(|| {
+                custom_error(
+                    ErrorCode::InvalidRequest,
+                    format!("The batch reading {records_uri} was not submitted by this component"),
+                )
+            })?;
+        Ok(Self {
+            bucket: bucket.to_string(),
+            directory: directory.to_string(),
+        })
+    }
+
+    pub fn key(&self, file_name: &str) -> String {
+        format!("{}/{file_name}", self.directory)
+    }
+
+    pub fn uri(&self, file_name: &str) -> String {
+        format!("s3://{}/{}", self.bucket, self.key(file_name))
+    }
+
+    pub fn output_uri(&self) -> String {
+        format!("s3://{}/{}/output/", self.bucket, self.directory)
+    }
+
+    /// The key of a file written by Bedrock, in the directory named after the ID of the job
+    pub fn output_key(&self, job_arn: &str, file_name: &str) -> String {
+        let job_id = job_arn.rsplit('/').next().unwrap_or(job_arn);
+        format!("{}/output/{job_id}/{file_name}", self.directory)
+    }
+}
+
+/// Splits an `s3://bucket/prefix` URI into the bucket and the prefix, without its trailing slash
+pub fn parse_s3_uri(uri: &str) -> Result<(&str, &str), Error> {
+    let path = uri
+        .strip_prefix("s3://")
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| custom_error(ErrorCode::InvalidRequest, format!("Invalid S3 URI: {uri}")))?;
+    let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+    Ok((bucket, prefix.trim_end_matches('/')))
+}
+
+/// Bedrock requires record IDs of 11 alphanumeric characters
+pub fn record_id(index: usize) -> String {
+    format!("{index:011}")
+}
+
+/// A request of the records file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Record {
+    pub record_id: String,
+    pub model_input: Value,
+}
+
+/// The batch item a record was created from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordItem {
+    pub record_id: String,
+    pub custom_id: String,
+    /// Name of the tool emulating the structured output requested by the item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_output_tool: Option<String>,
+}
+
+/// A line of the output file, with either the response to a record or the reason it failed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordOutput {
+    pub record_id: String,
+    pub model_output: Option<Value>,
+    pub error: Option<RecordError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordError {
+    pub error_code: u16,
+    pub error_message: String,
+}
+
+/// The record counts of a processed batch
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub total_record_count: u32,
+    pub success_record_count: u32,
+    pub error_record_count: u32,
+}
+
+pub fn job_status_to_batch_status(status: Option<&ModelInvocationJobStatus>) -> BatchStatus {
+    match status {
+        Some(ModelInvocationJobStatus::Submitted)
+        | Some(ModelInvocationJobStatus::Validating)
+        | Some(ModelInvocationJobStatus::Scheduled)
+        | None => BatchStatus::Pending,
+        Some(ModelInvocationJobStatus::Completed)
+        | Some(ModelInvocationJobStatus::PartiallyCompleted) => BatchStatus::Completed,
+        Some(ModelInvocationJobStatus::Failed) => BatchStatus::Failed,
+        Some(ModelInvocationJobStatus::Stopping) => BatchStatus::Cancelling,
+        Some(ModelInvocationJobStatus::Stopped) => BatchStatus::Cancelled,
+        Some(ModelInvocationJobStatus::Expired) => BatchStatus::Expired,
+        Some(_) => BatchStatus::Running,
+    }
+}
+
+/// Converts a line of the output file, using the items of the batch to get the custom ID of the
+/// record and the tool emulating its structured output
+pub fn record_output_to_batch_result(
+    output: RecordOutput,
+    items: &HashMap<String, RecordItem>,
+) -> BatchResult {
+    let item = items.get(&output.record_id);
+    let result = match (output.model_output, output.error) {
+        (_, Some(error)) => {
+            let error_json = serde_json::to_string(&error).unwrap();
+            let status =
+                StatusCode::from_u16(error.error_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            Err(Error {
+                code: error_code_from_response(status, &error_json),
+                message: error.error_message,
+                provider_error_json: Some(error_json),
+                retry_after_ms: None,
+            })
+        }
+        (Some(model_output), None) => golem_llm_anthropic::bedrock::model_output_to_response(
+            model_output,
+            item.and_then(|item| item.json_output_tool.as_deref()),
+        ),
+        (None, None) => Err(custom_error(
+            ErrorCode::InternalError,
+            "Batch record has neither a model output nor an error".to_string(),
+        )),
+    };
+    BatchResult {
+        custom_id: item.map_or(output.record_id, |item| item.custom_id.clone()),
+        result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_s3_uri() {
+        assert_eq!(
+            parse_s3_uri("s3://bucket/batches/").unwrap(),
+            ("bucket", "batches")
+        );
+        assert_eq!(parse_s3_uri("s3://bucket").unwrap(), ("bucket", ""));
+        assert!(parse_s3_uri("https://bucket.s3.amazonaws.com/batches").is_err());
+        assert!(parse_s3_uri("s3://").is_err());
+    }
+
+    #[test]
+    fn test_batch_location() {
+        let location = BatchLocation::new("s3://bucket/batches", "golem-llm-1").unwrap();
+        assert_eq!(
+            location.uri(RECORDS_FILE_NAME),
+            "s3://bucket/batches/golem-llm-1/records.jsonl"
+        );
+        assert_eq!(
+            location.output_key(
+                "arn:aws:bedrock:us-east-1:123456789012:model-invocation-job/abcdef123456",
+                OUTPUT_FILE_NAME
+            ),
+            "batches/golem-llm-1/output/abcdef123456/records.jsonl.out"
+        );
+        assert_eq!(
+            BatchLocation::from_records_uri(&location.uri(RECORDS_FILE_NAME)).unwrap(),
+            location
+        );
+        assert_eq!(
+            BatchLocation::new("s3://bucket/", "golem-llm-1")
+                .unwrap()
+                .key(ITEMS_FILE_NAME),
+            "golem-llm-1/items.json"
+        );
+        assert!(BatchLocation::from_records_uri("s3://bucket/input.jsonl").is_err());
+    }
+
+    #[test]
+    fn test_record_id() {
+        assert_eq!(record_id(0), "00000000000");
+        assert_eq!(record_id(12345), "00000012345");
+    }
+
+    #[test]
+    fn test_record_output_to_batch_result() {
+        let items = HashMap::from([(
+            record_id(0),
+            RecordItem {
+                record_id: record_id(0),
+                custom_id: "question-1".to_string(),
+                json_output_tool: None,
+            },
+        )]);
+        let output = serde_json::from_str::<RecordOutput>(
+            r#"{"recordId":"00000000000","modelInput":{},"error":{"errorCode":400,"errorMessage":"Malformed input request"}}"#,
+        )
+        .unwrap();
+
+        let result = record_output_to_batch_result(output, &items);
+        assert_eq!(result.custom_id, "question-1");
+        let error = result.result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidRequest);
+        assert_eq!(error.message, "Malformed input request");
+    }
+}
@@ -1,55 +1,104 @@
 use crate::async_utils::UnsafeFuture;
+use crate::batch::{
+    self, job_status_to_batch_status, record_output_to_batch_result, BatchEnvironment,
+    BatchLocation, Manifest, Record, RecordItem, RecordOutput, ITEMS_FILE_NAME, MANIFEST_FILE_NAME,
+    OUTPUT_FILE_NAME, RECORDS_FILE_NAME,
+};
 use crate::conversions::converse_output_to_complete_response;
 use crate::conversions::{
-    self, from_converse_sdk_error, from_converse_stream_sdk_error, BedrockInput,
+    self, custom_error, from_batch_sdk_error, from_converse_sdk_error,
+    from_converse_stream_sdk_error, from_list_foundation_models_sdk_error, BedrockInput,
+};
+use crate::credentials::CredentialSource;
+use crate::models::{
+    base_model, has_inference_profile, inference_profile_id, region_inference_profile_prefix,
+    PRICING,
 };
 use crate::stream::BedrockChatStream;
 use crate::wasi_client::WasiClient;
 use aws_config::BehaviorVersion;
+use aws_sdk_bedrock::types::{
+    FoundationModelSummary, ModelInvocationJobInputDataConfig, ModelInvocationJobOutputDataConfig,
+    ModelInvocationJobS3InputDataConfig, ModelInvocationJobS3OutputDataConfig, ModelModality,
+    S3InputFormat,
+};
 use aws_sdk_bedrockruntime as bedrock;
 use aws_sdk_bedrockruntime::config::{AsyncSleep, Sleep};
 use aws_sdk_bedrockruntime::operation::converse::builders::ConverseFluentBuilder;
 use aws_sdk_bedrockruntime::operation::converse_stream::builders::ConverseStreamFluentBuilder;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_smithy_types::date_time::Format as DateTimeFormat;
 use aws_types::region;
 use golem_llm::config::{get_config_key, get_config_key_or_none};
-use golem_llm::golem::llm::llm::{Config, Error, Event, Response};
+use golem_llm::error::unsupported;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult};
+use golem_llm::golem::llm::llm::{Config, Error, ErrorCode, Event, Response};
+use golem_llm::pricing::add_cost;
 use log::trace;
-use wasi::clocks::monotonic_clock;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use wasi::clocks::{monotonic_clock, wall_clock};
 use wstd::runtime::Reactor;
 
 #[derive(Debug)]
 pub struct Bedrock {
     client: bedrock::Client,
+    models_client: aws_sdk_bedrock::Client,
+    s3_client: aws_sdk_s3::Client,
+    cross_region_inference: Option<CrossRegionInference>,
+}
+
+/// The cross-region inference profiles the requests of the foundation models are sent to
+#[derive(Debug, Clone, PartialEq)]
+enum CrossRegionInference {
+    /// The profiles with the given prefix, for every foundation model
+    Prefix(String),
+    /// The profiles of the region's geography, only for the models known to have one
+    Auto(&'static str),
 }
 
 impl Bedrock {
     pub async fn new() -> Result<Self, Error> {
         let environment = BedrockEnvironment::load_from_env()?;
+        let region = environment.aws_region();
+        let credentials = environment.credentials.credentials(&region).await?;
 
         let sdk_config = aws_config::defaults(BehaviorVersion::latest())
-            .region(environment.aws_region())
+            .region(region)
             .http_client(WasiClient::new())
-            .credentials_provider(environment.aws_credentials())
+            .credentials_provider(credentials)
             .sleep_impl(WasiSleep::new())
             .load()
             .await;
         let client = bedrock::Client::new(&sdk_config);
-        Ok(Self { client })
+        let models_client = aws_sdk_bedrock::Client::new(&sdk_config);
+        let s3_client = aws_sdk_s3::Client::new(&sdk_config);
+        Ok(Self {
+            client,
+            models_client,
+            s3_client,
+            cross_region_inference: environment.cross_region_inference()?,
+        })
     }
 
     pub async fn converse(&self, events: Vec<Event>, config: Config) -> Result<Response, Error> {
-        let input = BedrockInput::from_events(config, events).await?;
+        let mut input = BedrockInput::from_events(config, events).await?;
+        input.model_id = self.model_id(input.model_id);
 
         trace!("Sending request to AWS Bedrock: {input:?}");
 
         let model_id = input.model_id.clone();
+        let json_output_tool = input.json_output_tool.clone();
         let response = self
             .init_converse(input)
             .send()
             .await
-            .map_err(|e| from_converse_sdk_error(model_id, e))?;
+            .map_err(|e| from_converse_sdk_error(model_id.clone(), e))?;
 
-        converse_output_to_complete_response(response)
+        let mut response =
+            converse_output_to_complete_response(response, json_output_tool.as_deref())?;
+        add_cost(&mut response.metadata, base_model(&model_id), &PRICING);
+        Ok(response)
     }
 
     pub async fn converse_stream(&self, events: Vec<Event>, config: Config) -> BedrockChatStream {
@@ -57,24 +106,304 @@ impl Bedrock {
 
         match bedrock_input {
             Err(err) => BedrockChatStream::failed(err),
-            Ok(input) => {
+            Ok(mut input) => {
+                input.model_id = self.model_id(input.model_id);
                 trace!("Sending request to AWS Bedrock: {input:?}");
                 let model_id = input.model_id.clone();
+                let json_output_tool = input.json_output_tool.clone();
                 let response = self
                     .init_converse_stream(input)
                     .send()
                     .await
-                    .map_err(|e| from_converse_stream_sdk_error(model_id, e));
+                    .map_err(|e| from_converse_stream_sdk_error(model_id.clone(), e));
 
                 trace!("Creating AWS Bedrock event stream");
                 match response {
-                    Ok(response) => BedrockChatStream::new(response.stream),
+                    Ok(response) => BedrockChatStream::new(
+                        response.stream,
+                        json_output_tool,
+                        PRICING.model_pricing(base_model(&model_id)),
+                    ),
                     Err(error) => BedrockChatStream::failed(error),
                 }
             }
         }
     }
 
+    pub async fn list_foundation_models(&self) -> Result<Vec<FoundationModelSummary>, Error> {
+        trace!("Listing AWS Bedrock foundation models");
+
+        let response = self
+            .models_client
+            .list_foundation_models()
+            .by_output_modality(ModelModality::Text)
+            .send()
+            .await
+            .map_err(from_list_foundation_models_sdk_error)?;
+
+        Ok(response.model_summaries.unwrap_or_default())
+    }
+
+    /// Uploads the records of the batch to S3 and creates a batch inference job reading them,
+    /// returning the ARN of the job. Only Anthropic's models are supported, using the conversions
+    /// of the Anthropic component to their native format.
+    pub async fn create_batch(&self, items: Vec<BatchItem>) -> Result<String, Error> {
+        let environment = BatchEnvironment::load_from_env()?;
+        let model = match items.first() {
+            Some(item) => item.config.model.clone(),
+            None => {
+                return Err(custom_error(
+                    ErrorCode::InvalidRequest,
+                    "A batch needs at least one item".to_string(),
+                ))
+            }
+        };
+        if items.iter().any(|item| item.config.model != model) {
+            return Err(custom_error(
+                ErrorCode::InvalidRequest,
+                "Every item of a Bedrock batch must use the same model".to_string(),
+            ));
+        }
+        if !base_model(&model).starts_with("anthropic.") {
+            return Err(unsupported(format!(
+                "batches of model {model}, only Anthropic models are supported"
+            )));
+        }
+
+        let mut records = String::new();
+        let mut record_items = Vec::new();
+        for (index, item) in items.into_iter().enumerate() {
+            let record_id = batch::record_id(index);
+            let (model_input, json_output_tool) =
+                golem_llm_anthropic::bedrock::model_input(item.events, item.config)?;
+            let record = Record {
+                record_id: record_id.clone(),
+                model_input,
+            };
+            records.push_str(&serde_json::to_string(&record).unwrap());
+            records.push('\n');
+            record_items.push(RecordItem {
+                record_id,
+                custom_id: item.custom_id,
+                json_output_tool,
+            });
+        }
+
+        let now = wall_clock::now();
+        let job_name = format!("golem-llm-{}-{}", now.seconds, now.nanoseconds);
+        let location = BatchLocation::new(&environment.s3_uri, &job_name)?;
+        self.put_object(
+            &location,
+            ITEMS_FILE_NAME,
+            serde_json::to_vec(&record_items).unwrap(),
+        )
+        .await?;
+        self.put_object(&location, RECORDS_FILE_NAME, records.into_bytes())
+            .await?;
+
+        let input_data_config = ModelInvocationJobS3InputDataConfig::builder()
+            .s3_input_format(S3InputFormat::Jsonl)
+            .s3_uri(location.uri(RECORDS_FILE_NAME))
+            .build()
+            .map_err(|err| custom_error(ErrorCode::InternalError, err.to_string()))?;
+        let output_data_config = ModelInvocationJobS3OutputDataConfig::builder()
+            .s3_uri(location.output_uri())
+            .build()
+            .map_err(|err| custom_error(ErrorCode::InternalError, err.to_string()))?;
+
+        trace!("Creating AWS Bedrock batch inference job {job_name}");
+
+        let response = self
+            .models_client
+            .create_model_invocation_job()
+            .job_name(job_name)
+            .role_arn(environment.role_arn)
+            .model_id(self.model_id(model))
+            .input_data_config(ModelInvocationJobInputDataConfig::S3InputDataConfig(
+                input_data_config,
+            ))
+            .output_data_config(ModelInvocationJobOutputDataConfig::S3OutputDataConfig(
+                output_data_config,
+            ))
+            .send()
+            .await
+            .map_err(|err| from_batch_sdk_error("creating batch inference job", err))?;
+        Ok(response.job_arn)
+    }
+
+    /// Gets the progress of a batch inference job, counting its records once Bedrock has written
+    /// its manifest
+    pub async fn get_batch(&self, job_arn: &str) -> Result<BatchInfo, Error> {
+        let job = self
+            .models_client
+            .get_model_invocation_job()
+            .job_identifier(job_arn)
+            .send()
+            .await
+            .map_err(|err| from_batch_sdk_error("getting batch inference job", err))?;
+        let location = batch_location(job.input_data_config.as_ref())?;
+
+        let manifest = self
+            .get_object(
+                &location.bucket,
+                &location.output_key(job_arn, MANIFEST_FILE_NAME),
+            )
+            .await?;
+        let (total_count, succeeded_count, failed_count) = match manifest {
+            Some(manifest) => {
+                let manifest = decode_json::<Manifest>(&manifest)?;
+                (
+                    manifest.total_record_count,
+                    manifest.success_record_count,
+                    manifest.error_record_count,
+                )
+            }
+            None => (self.get_record_items(&location).await?.len() as u32, 0, 0),
+        };
+
+        Ok(BatchInfo {
+            id: job.job_arn,
+            status: job_status_to_batch_status(job.status.as_ref()),
+            total_count,
+            succeeded_count,
+            failed_count,
+            created_at: job.submit_time.fmt(DateTimeFormat::DateTime).ok(),
+        })
+    }
+
+    /// Gets the responses of a batch inference job, which are only available once it has ended
+    pub async fn get_batch_results(&self, job_arn: &str) -> Result<Vec<BatchResult>, Error> {
+        let job = self
+            .models_client
+            .get_model_invocation_job()
+            .job_identifier(job_arn)
+            .send()
+            .await
+            .map_err(|err| from_batch_sdk_error("getting batch inference job", err))?;
+        let location = batch_location(job.input_data_config.as_ref())?;
+
+        let Some(output) = self
+            .get_object(
+                &location.bucket,
+                &location.output_key(job_arn, OUTPUT_FILE_NAME),
+            )
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let items = self
+            .get_record_items(&location)
+            .await?
+            .into_iter()
+            .map(|item| (item.record_id.clone(), item))
+            .collect::<HashMap<_, _>>();
+
+        output
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                decode_json::<RecordOutput>(line)
+                    .map(|output| record_output_to_batch_result(output, &items))
+            })
+            .collect()
+    }
+
+    pub async fn stop_batch(&self, job_arn: &str) -> Result<BatchInfo, Error> {
+        self.models_client
+            .stop_model_invocation_job()
+            .job_identifier(job_arn)
+            .send()
+            .await
+            .map_err(|err| from_batch_sdk_error("stopping batch inference job", err))?;
+        self.get_batch(job_arn).await
+    }
+
+    async fn get_record_items(&self, location: &BatchLocation) -> Result<Vec<RecordItem>, Error> {
+        match self
+            .get_object(&location.bucket, &location.key(ITEMS_FILE_NAME))
+            .await?
+        {
+            Some(items) => decode_json(&items),
+            None => Err(custom_error(
+                ErrorCode::InternalError,
+                format!(
+                    "The batch items are missing from {}",
+                    location.uri(ITEMS_FILE_NAME)
+                ),
+            )),
+        }
+    }
+
+    async fn put_object(
+        &self,
+        location: &BatchLocation,
+        file_name: &str,
+        body: Vec<u8>,
+    ) -> Result<(), Error> {
+        trace!("Uploading {}", location.uri(file_name));
+
+        self.s3_client
+            .put_object()
+            .bucket(&location.bucket)
+            .key(location.key(file_name))
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|err| {
+                from_batch_sdk_error(&format!("uploading {}", location.uri(file_name)), err)
+            })?;
+        Ok(())
+    }
+
+    /// Downloads a file, returning `None` if it does not exist (yet)
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Option<String>, Error> {
+        trace!("Downloading s3://{bucket}/{key}");
+
+        match self
+            .s3_client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(response) => {
+                let body = response.body.collect().await.map_err(|err| {
+                    custom_error(
+                        ErrorCode::InternalError,
+                        format!("Failed to download s3://{bucket}/{key}: {err}"),
+                    )
+                })?;
+                Ok(Some(
+                    String::from_utf8_lossy(&body.into_bytes()).into_owned(),
+                ))
+            }
+            Err(err)
+                if err
+                    .as_service_error()
+                    .is_some_and(|err| err.is_no_such_key()) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(from_batch_sdk_error(
+                &format!("downloading s3://{bucket}/{key}"),
+                err,
+            )),
+        }
+    }
+
+    /// Routes the requests of the base models through the configured cross-region inference
+    /// profile
+    fn model_id(&self, model_id: String) -> String {
+        match &self.cross_region_inference {
+            Some(CrossRegionInference::Prefix(prefix)) => inference_profile_id(prefix, &model_id),
+            Some(CrossRegionInference::Auto(prefix)) if has_inference_profile(&model_id) => {
+                inference_profile_id(prefix, &model_id)
+            }
+            _ => model_id,
+        }
+    }
+
     fn init_converse(&self, input: conversions::BedrockInput) -> ConverseFluentBuilder {
         self.client
             .converse()
@@ -101,21 +430,42 @@ impl Bedrock {
     }
 }
 
+fn batch_location(
+    input_data_config: Option<&ModelInvocationJobInputDataConfig>,
+) -> Result<BatchLocation, Error> {
+    match input_data_config {
+        Some(ModelInvocationJobInputDataConfig::S3InputDataConfig(config)) => {
+            BatchLocation::from_records_uri(&config.s3_uri)
+        }
+        _ => Err(custom_error(
+            ErrorCode::InvalidRequest,
+            "The batch was not submitted by this component".to_string(),
+        )),
+    }
+}
+
+fn decode_json<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    serde_json::from_str(json).map_err(|err| Error {
+        code: ErrorCode::InternalError,
+        message: format!("Failed to decode batch file: {err}"),
+        provider_error_json: Some(json.to_string()),
+        retry_after_ms: None,
+    })
+}
+
 #[derive(Debug)]
 pub struct BedrockEnvironment {
-    access_key_id: String,
     region: String,
-    secret_access_key: String,
-    session_token: Option<String>,
+    credentials: CredentialSource,
+    cross_region_inference: Option<String>,
 }
 
 impl BedrockEnvironment {
     pub fn load_from_env() -> Result<Self, Error> {
         Ok(Self {
-            access_key_id: get_config_key("AWS_ACCESS_KEY_ID")?,
             region: get_config_key("AWS_REGION")?,
-            secret_access_key: get_config_key("AWS_SECRET_ACCESS_KEY")?,
-            session_token: get_config_key_or_none("AWS_SESSION_TOKEN"),
+            credentials: CredentialSource::load_from_env()?,
+            cross_region_inference: get_config_key_or_none("GOLEM_BEDROCK_CROSS_REGION_INFERENCE"),
         })
     }
 
@@ -123,22 +473,32 @@ impl BedrockEnvironment {
         region::Region::new(self.region.clone())
     }
 
-    fn aws_credentials(&self) -> bedrock::config::Credentials {
-        bedrock::config::Credentials::new(
-            self.access_key_id.clone(),
-            self.secret_access_key.clone(),
-            self.session_token.clone(),
-            None,
-            "llm-bedrock",
-        )
+    /// The cross-region inference profiles to use, either with the given prefix (for example `eu`
+    /// or `global`) or `auto` for the geography of the region
+    fn cross_region_inference(&self) -> Result<Option<CrossRegionInference>, Error> {
+        match self.cross_region_inference.as_deref() {
+            None => Ok(None),
+            Some("auto") => region_inference_profile_prefix(&self.region)
+                .map(|prefix| Some(CrossRegionInference::Auto(prefix)))
+                .ok_or_else(|| {
+                    custom_error(
+                        ErrorCode::InvalidRequest,
+                        format!(
+                            "No cross-region inference profiles are known for region {}",
+                            self.region
+                        ),
+                    )
+                }),
+            Some(prefix) => Ok(Some(CrossRegionInference::Prefix(prefix.to_string()))),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
-struct WasiSleep;
+pub struct WasiSleep;
 
 impl WasiSleep {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self
     }
 }
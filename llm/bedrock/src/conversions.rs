@@ -1,16 +1,25 @@
+use aws_sdk_bedrock::operation::list_foundation_models::ListFoundationModelsError;
 use aws_sdk_bedrockruntime as bedrock;
-use aws_sdk_bedrockruntime::error::SdkError;
+use aws_sdk_bedrockruntime::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_bedrockruntime::operation::{converse, converse_stream};
 use aws_sdk_bedrockruntime::types::{
-    ContentBlockDeltaEvent, ContentBlockStartEvent, ConversationRole, ConverseStreamMetadataEvent,
-    ConverseStreamOutput, ImageBlock, ImageFormat, InferenceConfiguration, MessageStopEvent,
-    SystemContentBlock, Tool, ToolConfiguration, ToolInputSchema, ToolSpecification, ToolUseBlock,
+    AnyToolChoice, AutoToolChoice, CachePointBlock, CachePointType, ContentBlockDeltaEvent,
+    ContentBlockStartEvent, ConversationRole, ConverseStreamMetadataEvent, ConverseStreamOutput,
+    DocumentBlock, DocumentFormat, DocumentSource, ImageBlock, ImageFormat, InferenceConfiguration,
+    MessageStopEvent, ReasoningContentBlock, ReasoningContentBlockDelta, ReasoningTextBlock,
+    SpecificToolChoice, SystemContentBlock, Tool, ToolChoice, ToolConfiguration, ToolInputSchema,
+    ToolSpecification, ToolUseBlock,
 };
-use aws_smithy_types::{Document, Number};
+use aws_smithy_types::{Blob, Document, Number};
+use base64::{engine::general_purpose, Engine as _};
 use golem_llm::golem::llm::llm;
 use std::collections::HashMap;
+use std::fmt::Debug;
 use wstd::http;
 
+/// Name of the tool used to force a JSON object output when no schema name is available
+const JSON_OUTPUT_TOOL_NAME: &str = "json_output";
+
 #[derive(Debug)]
 pub struct BedrockInput {
     pub model_id: String,
@@ -19,6 +28,8 @@ pub struct BedrockInput {
     pub inference_configuration: InferenceConfiguration,
     pub tools: Option<ToolConfiguration>,
     pub additional_fields: aws_smithy_types::Document,
+    /// Name of the tool used to emulate the requested response format, if any
+    pub json_output_tool: Option<String>,
 }
 
 impl BedrockInput {
@@ -28,7 +39,7 @@ impl BedrockInput {
     ) -> Result<Self, llm::Error> {
         let (user_messages, system_instructions) = events_to_bedrock_message_groups(events).await?;
 
-        let options = config
+        let mut options = config
             .provider_options
             .map(|options| {
                 options
@@ -38,10 +49,48 @@ impl BedrockInput {
             })
             .unwrap_or_default();
 
+        let json_output_tool = config
+            .response_format
+            .map(response_format_to_tool_spec)
+            .transpose()?
+            .flatten();
+        if let Some(spec) = &json_output_tool {
+            check_structured_output_conflicts(&spec.name, &config.tool_choice, &config.reasoning)?;
+        }
+
+        let thinking_budget = config.reasoning.map(reasoning_config_to_budget_tokens);
+        if let Some(budget_tokens) = thinking_budget {
+            options.insert(
+                "thinking".to_string(),
+                Document::Object(HashMap::from([
+                    ("type".to_string(), Document::String("enabled".to_string())),
+                    (
+                        "budget_tokens".to_string(),
+                        Document::Number(Number::PosInt(budget_tokens as u64)),
+                    ),
+                ])),
+            );
+        }
+        // max_tokens has to be greater than the thinking budget
+        let max_tokens = config
+            .max_tokens
+            .or(thinking_budget.map(|budget_tokens| budget_tokens + 4096));
+
+        let json_output_tool_name = json_output_tool.as_ref().map(|spec| spec.name.clone());
+        let has_tool_blocks = user_messages.iter().any(|message| {
+            message.content().iter().any(|block| {
+                matches!(
+                    block,
+                    bedrock::types::ContentBlock::ToolUse(_)
+                        | bedrock::types::ContentBlock::ToolResult(_)
+                )
+            })
+        });
+
         Ok(BedrockInput {
             model_id: config.model.clone(),
             inference_configuration: InferenceConfiguration::builder()
-                .set_max_tokens(config.max_tokens.map(|x| x as i32))
+                .set_max_tokens(max_tokens.map(|x| x as i32))
                 .set_temperature(config.temperature)
                 .set_stop_sequences(config.stop_sequences.clone())
                 .set_top_p(options.get("top_p").and_then(|v| match v {
@@ -51,22 +100,119 @@ impl BedrockInput {
                 .build(),
             messages: user_messages,
             system_instructions,
-            tools: tool_defs_to_bedrock_tool_config(config.tools.unwrap_or_default())?,
+            tools: tool_defs_to_bedrock_tool_config(
+                config.tools.unwrap_or_default(),
+                config.tool_choice,
+                json_output_tool,
+                has_tool_blocks,
+            )?,
             additional_fields: Document::Object(options),
+            json_output_tool: json_output_tool_name,
         })
     }
 }
 
+/// Reasoning is enabled through the model specific request fields, as supported by Anthropic models
+fn reasoning_config_to_budget_tokens(reasoning: llm::ReasoningConfig) -> u32 {
+    reasoning.budget_tokens.unwrap_or(match reasoning.effort {
+        Some(llm::ReasoningEffort::Low) => 1024,
+        Some(llm::ReasoningEffort::Medium) | None => 4096,
+        Some(llm::ReasoningEffort::High) => 16384,
+    })
+}
+
+/// The tool emulating structured output has to be forced, which would override the caller's tool
+/// choice, and Claude on Bedrock does not allow forcing a tool with extended thinking
+fn check_structured_output_conflicts(
+    json_output_tool: &str,
+    tool_choice: &Option<llm::ToolChoice>,
+    reasoning: &Option<llm::ReasoningConfig>,
+) -> Result<(), llm::Error> {
+    let conflicting_tool_choice = match tool_choice {
+        Some(llm::ToolChoice::Specific(name)) => name != json_output_tool,
+        Some(_) => true,
+        None => false,
+    };
+    let message = if conflicting_tool_choice {
+        "A response format cannot be combined with a tool choice on Bedrock, as structured \
+         output forces the call of a tool"
+    } else if reasoning.is_some() {
+        "A response format cannot be combined with reasoning on Bedrock, as extended thinking \
+         does not allow forcing the call of a tool"
+    } else {
+        return Ok(());
+    };
+    Err(custom_error(
+        llm::ErrorCode::InvalidRequest,
+        message.to_string(),
+    ))
+}
+
+/// Structured output is emulated by forcing the model to call a tool
+/// whose input schema is the requested response schema.
+fn response_format_to_tool_spec(
+    response_format: llm::ResponseFormat,
+) -> Result<Option<ToolSpecification>, llm::Error> {
+    let (name, description, schema) = match response_format {
+        llm::ResponseFormat::Text => return Ok(None),
+        llm::ResponseFormat::JsonObject => (
+            JSON_OUTPUT_TOOL_NAME.to_string(),
+            "Respond with a JSON object",
+            Document::Object(HashMap::from([(
+                "type".to_string(),
+                Document::String("object".to_string()),
+            )])),
+        ),
+        llm::ResponseFormat::JsonSchema(json_schema) => (
+            json_schema.name,
+            "Respond with JSON conforming to the input schema",
+            json_str_to_smithy_document(&json_schema.schema)?,
+        ),
+    };
+
+    Ok(Some(
+        ToolSpecification::builder()
+            .name(name)
+            .description(description)
+            .input_schema(ToolInputSchema::Json(schema))
+            .build()
+            .unwrap(),
+    ))
+}
+
 fn tool_defs_to_bedrock_tool_config(
     tools: Vec<llm::ToolDefinition>,
+    tool_choice: Option<llm::ToolChoice>,
+    json_output_tool: Option<ToolSpecification>,
+    has_tool_blocks: bool,
 ) -> Result<Option<ToolConfiguration>, llm::Error> {
-    if tools.is_empty() {
+    if tools.is_empty() && json_output_tool.is_none() {
         return Ok(None);
     }
 
+    // The Converse API has no `none` tool choice, so the tools are not sent instead, which it only
+    // accepts if the messages contain no tool calls or results
+    if json_output_tool.is_none() && tool_choice == Some(llm::ToolChoice::None) {
+        return if has_tool_blocks {
+            Err(custom_error(
+                llm::ErrorCode::Unsupported,
+                "Unsupported: the none tool choice with tool calls or results in the events"
+                    .to_string(),
+            ))
+        } else {
+            Ok(None)
+        };
+    }
+
     let mut specs: Vec<Tool> = vec![];
 
     for def in tools {
+        if def.builtin.is_some() {
+            return Err(custom_error(
+                llm::ErrorCode::Unsupported,
+                "Unsupported: builtin tools".to_string(),
+            ));
+        }
         let schema = json_str_to_smithy_document(&def.parameters_schema)?;
 
         specs.push(Tool::ToolSpec(
@@ -77,16 +223,53 @@ fn tool_defs_to_bedrock_tool_config(
                 .build()
                 .unwrap(),
         ));
+        if def.cache_hint.is_some() {
+            specs.push(Tool::CachePoint(cache_point()));
+        }
     }
 
+    // The structured output emulation forces its tool, the conflicting tool choices are rejected by
+    // `check_structured_output_conflicts`
+    let tool_choice = match json_output_tool {
+        Some(spec) => {
+            let tool_choice = ToolChoice::Tool(
+                SpecificToolChoice::builder()
+                    .name(spec.name.clone())
+                    .build()
+                    .unwrap(),
+            );
+            specs.push(Tool::ToolSpec(spec));
+            Some(tool_choice)
+        }
+        None => tool_choice.map(convert_tool_choice).transpose()?,
+    };
+
     Ok(Some(
         ToolConfiguration::builder()
             .set_tools(Some(specs))
+            .set_tool_choice(tool_choice)
             .build()
             .unwrap(),
     ))
 }
 
+/// The Converse API cannot forbid calling the tools, which is done by not sending them before
+/// converting the tool choice, and it has no option to limit the number of tool calls per
+/// response, so the parallel tool calls flag is ignored
+fn convert_tool_choice(tool_choice: llm::ToolChoice) -> Result<ToolChoice, llm::Error> {
+    match tool_choice {
+        llm::ToolChoice::Auto => Ok(ToolChoice::Auto(AutoToolChoice::builder().build())),
+        llm::ToolChoice::None => Err(custom_error(
+            llm::ErrorCode::Unsupported,
+            "Unsupported: the none tool choice".to_string(),
+        )),
+        llm::ToolChoice::Required => Ok(ToolChoice::Any(AnyToolChoice::builder().build())),
+        llm::ToolChoice::Specific(name) => Ok(ToolChoice::Tool(
+            SpecificToolChoice::builder().name(name).build().unwrap(),
+        )),
+    }
+}
+
 async fn events_to_bedrock_message_groups(
     events: Vec<llm::Event>,
 ) -> Result<(Vec<bedrock::types::Message>, Vec<SystemContentBlock>), llm::Error> {
@@ -102,7 +285,15 @@ async fn events_to_bedrock_message_groups(
                             system_instructions.push(SystemContentBlock::Text(text));
                         }
                     }
+                    if message.cache_hint.is_some() {
+                        system_instructions.push(SystemContentBlock::CachePoint(cache_point()));
+                    }
                 } else {
+                    let mut content =
+                        content_parts_to_bedrock_content_blocks(message.content).await?;
+                    if message.cache_hint.is_some() {
+                        content.push(bedrock::types::ContentBlock::CachePoint(cache_point()));
+                    }
                     user_messages.push(
                         bedrock::types::Message::builder()
                             .role(if message.role == llm::Role::User {
@@ -110,33 +301,21 @@ async fn events_to_bedrock_message_groups(
                             } else {
                                 ConversationRole::Assistant
                             })
-                            .set_content(Some(
-                                content_parts_to_bedrock_content_blocks(message.content).await?,
-                            ))
+                            .set_content(Some(content))
                             .build()
                             .unwrap(),
                     );
                 }
             }
             llm::Event::Response(response) => {
-                if !response.content.is_empty() {
+                // Reasoning blocks have to be sent back in the same message as the tool calls
+                let mut content = content_parts_to_bedrock_content_blocks(response.content).await?;
+                content.extend(tool_calls_to_bedrock_content_blocks(response.tool_calls).await?);
+                if !content.is_empty() {
                     user_messages.push(
                         bedrock::types::Message::builder()
                             .role(ConversationRole::Assistant)
-                            .set_content(Some(
-                                content_parts_to_bedrock_content_blocks(response.content).await?,
-                            ))
-                            .build()
-                            .unwrap(),
-                    );
-                }
-                if !response.tool_calls.is_empty() {
-                    user_messages.push(
-                        bedrock::types::Message::builder()
-                            .role(ConversationRole::Assistant)
-                            .set_content(Some(
-                                tool_calls_to_bedrock_content_blocks(response.tool_calls).await?,
-                            ))
+                            .set_content(Some(content))
                             .build()
                             .unwrap(),
                     );
@@ -155,6 +334,15 @@ async fn events_to_bedrock_message_groups(
     Ok((user_messages, system_instructions))
 }
 
+/// Bedrock has a single cache lifetime, so both cache hints become the same cache point, placed
+/// after the content to be cached
+fn cache_point() -> CachePointBlock {
+    CachePointBlock::builder()
+        .r#type(CachePointType::Default)
+        .build()
+        .unwrap()
+}
+
 async fn content_parts_to_bedrock_content_blocks(
     content_parts: Vec<llm::ContentPart>,
 ) -> Result<Vec<bedrock::types::ContentBlock>, llm::Error> {
@@ -167,12 +355,99 @@ async fn content_parts_to_bedrock_content_blocks(
             llm::ContentPart::Image(image) => {
                 bedrock_content_blocks.push(image_ref_to_bedrock_image_content_block(image).await?);
             }
+            llm::ContentPart::Reasoning(reasoning) => {
+                push_reasoning_content_block(&mut bedrock_content_blocks, reasoning)?;
+            }
+            llm::ContentPart::Document(document) => {
+                bedrock_content_blocks.push(document_to_bedrock_content_block(document).await?);
+            }
+            llm::ContentPart::Audio(_) => {
+                return Err(custom_error(
+                    llm::ErrorCode::Unsupported,
+                    "Unsupported: audio input".to_string(),
+                ));
+            }
+            // Calls of provider-hosted tools are only understood by the provider making them
+            llm::ContentPart::BuiltinToolCall(_) => {}
         }
     }
 
+    // Bedrock rejects reasoning blocks without a signature
+    bedrock_content_blocks.retain(|block| {
+        !matches!(
+            block,
+            bedrock::types::ContentBlock::ReasoningContent(ReasoningContentBlock::ReasoningText(text))
+                if text.signature.is_none()
+        )
+    });
+
     Ok(bedrock_content_blocks)
 }
 
+/// Streamed reasoning arrives in multiple parts with the signature at the end, these are merged
+/// into a single reasoning block
+fn push_reasoning_content_block(
+    bedrock_content_blocks: &mut Vec<bedrock::types::ContentBlock>,
+    reasoning: llm::ReasoningContent,
+) -> Result<(), llm::Error> {
+    if let Some(redacted_data) = reasoning.redacted_data {
+        let data = general_purpose::STANDARD
+            .decode(redacted_data)
+            .map_err(|err| {
+                custom_error(
+                    llm::ErrorCode::InvalidRequest,
+                    format!("Failed to decode redacted reasoning data: {err}"),
+                )
+            })?;
+        bedrock_content_blocks.push(bedrock::types::ContentBlock::ReasoningContent(
+            ReasoningContentBlock::RedactedContent(Blob::new(data)),
+        ));
+        return Ok(());
+    }
+
+    let (text, signature) = match bedrock_content_blocks.last() {
+        Some(bedrock::types::ContentBlock::ReasoningContent(
+            ReasoningContentBlock::ReasoningText(previous),
+        )) if previous.signature.is_none() => {
+            let text = format!("{}{}", previous.text, reasoning.text);
+            bedrock_content_blocks.pop();
+            (text, reasoning.signature)
+        }
+        _ => (reasoning.text, reasoning.signature),
+    };
+
+    bedrock_content_blocks.push(bedrock::types::ContentBlock::ReasoningContent(
+        ReasoningContentBlock::ReasoningText(
+            ReasoningTextBlock::builder()
+                .text(text)
+                .set_signature(signature)
+                .build()
+                .unwrap(),
+        ),
+    ));
+    Ok(())
+}
+
+fn bedrock_reasoning_to_llm_content_part(block: ReasoningContentBlock) -> Option<llm::ContentPart> {
+    match block {
+        ReasoningContentBlock::ReasoningText(text) => {
+            Some(llm::ContentPart::Reasoning(llm::ReasoningContent {
+                text: text.text,
+                signature: text.signature,
+                redacted_data: None,
+            }))
+        }
+        ReasoningContentBlock::RedactedContent(blob) => {
+            Some(llm::ContentPart::Reasoning(llm::ReasoningContent {
+                text: String::new(),
+                signature: None,
+                redacted_data: Some(general_purpose::STANDARD.encode(blob.into_inner())),
+            }))
+        }
+        _ => None,
+    }
+}
+
 async fn tool_calls_to_bedrock_content_blocks(
     tool_calls: Vec<llm::ToolCall>,
 ) -> Result<Vec<bedrock::types::ContentBlock>, llm::Error> {
@@ -258,6 +533,71 @@ async fn get_image_content_block_from_url(
     ))
 }
 
+/// Converse only accepts inline documents (or S3 locations), so documents referenced by URL are
+/// downloaded like images are
+async fn document_to_bedrock_content_block(
+    document: llm::Document,
+) -> Result<bedrock::types::ContentBlock, llm::Error> {
+    let bytes = match document.source {
+        llm::MediaSource::Inline(data) => data,
+        llm::MediaSource::Url(url) => get_bytes_from_url(&url).await?,
+    };
+    Ok(bedrock::types::ContentBlock::Document(
+        DocumentBlock::builder()
+            .format(str_to_bedrock_document_format(&document.mime_type)?)
+            .name(document_name(document.title))
+            .source(DocumentSource::Bytes(bytes.into()))
+            .build()
+            .unwrap(),
+    ))
+}
+
+/// Document names may only contain alphanumeric characters, single spaces, hyphens, parentheses
+/// and square brackets
+fn document_name(title: Option<String>) -> String {
+    let name = title
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-()[]".contains(c) {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if name.is_empty() {
+        "document".to_string()
+    } else {
+        name
+    }
+}
+
+fn str_to_bedrock_document_format(mime_type: &str) -> Result<DocumentFormat, llm::Error> {
+    match mime_type {
+        "application/pdf" => Ok(DocumentFormat::Pdf),
+        "text/plain" => Ok(DocumentFormat::Txt),
+        "text/markdown" => Ok(DocumentFormat::Md),
+        "text/html" => Ok(DocumentFormat::Html),
+        "text/csv" => Ok(DocumentFormat::Csv),
+        "application/msword" => Ok(DocumentFormat::Doc),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+            Ok(DocumentFormat::Docx)
+        }
+        "application/vnd.ms-excel" => Ok(DocumentFormat::Xls),
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => {
+            Ok(DocumentFormat::Xlsx)
+        }
+        other => Err(custom_error(
+            llm::ErrorCode::Unsupported,
+            format!("Unsupported document type: {other}"),
+        )),
+    }
+}
+
 async fn get_bytes_from_url(url: &str) -> Result<Vec<u8>, llm::Error> {
     let client = http::Client::new();
 
@@ -268,14 +608,14 @@ async fn get_bytes_from_url(url: &str) -> Result<Vec<u8>, llm::Error> {
     let response = client.send(request).await.map_err(|err| {
         custom_error(
             llm::ErrorCode::InvalidRequest,
-            format!("Could not read image bytes from url: {url}, cause: {err}"),
+            format!("Could not read bytes from url: {url}, cause: {err}"),
         )
     })?;
     if !response.status().is_success() {
         return Err(custom_error(
             llm::ErrorCode::InvalidRequest,
             format!(
-                "Could not read image bytes from url: {url}, cause: request failed with status: {}",
+                "Could not read bytes from url: {url}, cause: request failed with status: {}",
                 response.status()
             ),
         ));
@@ -284,7 +624,7 @@ async fn get_bytes_from_url(url: &str) -> Result<Vec<u8>, llm::Error> {
     let bytes = response.into_body().bytes().await.map_err(|err| {
         custom_error(
             llm::ErrorCode::InvalidRequest,
-            format!("Could not read image bytes from url: {url}, cause: {err}"),
+            format!("Could not read bytes from url: {url}, cause: {err}"),
         )
     })?;
 
@@ -301,12 +641,14 @@ fn str_to_bedrock_mime_type(mime_type: &str) -> Result<ImageFormat, llm::Error>
             code: llm::ErrorCode::Unsupported,
             message: format!("Unsupported image type: {other}"),
             provider_error_json: None,
+            retry_after_ms: None,
         }),
     }
 }
 
 pub fn converse_output_to_complete_response(
     response: converse::ConverseOutput,
+    json_output_tool: Option<&str>,
 ) -> Result<llm::Response, llm::Error> {
     let output = response.output().ok_or(custom_error(
         llm::ErrorCode::InternalError,
@@ -323,6 +665,7 @@ pub fn converse_output_to_complete_response(
         Ok(message) => {
             let mut content_parts: Vec<llm::ContentPart> = vec![];
             let mut tool_calls: Vec<llm::ToolCall> = vec![];
+            let mut json_output_received = false;
             for block in message.content.clone() {
                 match block {
                     bedrock::types::ContentBlock::Text(text) => {
@@ -331,13 +674,28 @@ pub fn converse_output_to_complete_response(
                     bedrock::types::ContentBlock::Image(image) => {
                         content_parts.push(bedrock_image_to_llm_content_part(image));
                     }
+                    bedrock::types::ContentBlock::ReasoningContent(reasoning) => {
+                        content_parts.extend(bedrock_reasoning_to_llm_content_part(reasoning));
+                    }
+                    bedrock::types::ContentBlock::ToolUse(tool)
+                        if Some(tool.name.as_str()) == json_output_tool =>
+                    {
+                        json_output_received = true;
+                        content_parts.push(llm::ContentPart::Text(
+                            bedrock_tool_use_to_llm_tool_call(tool)?.arguments_json,
+                        ));
+                    }
                     bedrock::types::ContentBlock::ToolUse(tool) => {
                         tool_calls.push(bedrock_tool_use_to_llm_tool_call(tool)?);
                     }
                     _ => {}
                 }
             }
-            let metadata = converse_output_to_response_metadata(&response);
+            let mut metadata = converse_output_to_response_metadata(&response);
+            if json_output_received && metadata.finish_reason == Some(llm::FinishReason::ToolCalls)
+            {
+                metadata.finish_reason = Some(llm::FinishReason::Stop);
+            }
             Ok(llm::Response {
                 // bedrock does not return an id as part of the response struct.
                 // there may be one present in `additional_model_response_fields`
@@ -347,6 +705,7 @@ pub fn converse_output_to_complete_response(
                 content: content_parts,
                 tool_calls,
                 metadata,
+                citations: Vec::new(),
             })
         }
     }
@@ -379,6 +738,7 @@ fn converse_output_to_response_metadata(
             .clone()
             .and_then(smithy_document_to_metadata_json),
         timestamp: None,
+        cost: None,
     }
 }
 
@@ -391,6 +751,9 @@ fn bedrock_usage_to_llm_usage(usage: &bedrock::types::TokenUsage) -> llm::Usage
         input_tokens: Some(usage.input_tokens() as u32),
         output_tokens: Some(usage.output_tokens() as u32),
         total_tokens: Some(usage.total_tokens() as u32),
+        reasoning_tokens: None,
+        cache_read_tokens: usage.cache_read_input_tokens().map(|tokens| tokens as u32),
+        cache_write_tokens: usage.cache_write_input_tokens().map(|tokens| tokens as u32),
     }
 }
 
@@ -451,11 +814,14 @@ fn process_content_block_start_event(block: ContentBlockStartEvent) -> Option<ll
         if let Ok(tool_use) = start_info.as_tool_use() {
             return Some(llm::StreamEvent::Delta(llm::StreamDelta {
                 content: None,
-                tool_calls: Some(vec![llm::ToolCall {
-                    id: tool_use.tool_use_id.clone(),
-                    name: tool_use.name.clone(),
-                    arguments_json: "".to_owned(),
+                tool_calls: None,
+                tool_call_deltas: Some(vec![llm::ToolCallDelta {
+                    index: block.content_block_index as u32,
+                    id: Some(tool_use.tool_use_id.clone()),
+                    name: Some(tool_use.name.clone()),
+                    arguments_fragment: "".to_owned(),
                 }]),
+                citations: None,
             }));
         }
     }
@@ -467,16 +833,46 @@ fn process_content_block_delta_event(block: ContentBlockDeltaEvent) -> Option<ll
         if let Ok(tool_use) = block_info.as_tool_use() {
             return Some(llm::StreamEvent::Delta(llm::StreamDelta {
                 content: None,
-                tool_calls: Some(vec![llm::ToolCall {
-                    id: "".to_owned(),
-                    name: "".to_owned(),
-                    arguments_json: tool_use.input.clone(),
+                tool_calls: None,
+                tool_call_deltas: Some(vec![llm::ToolCallDelta {
+                    index: block.content_block_index as u32,
+                    id: None,
+                    name: None,
+                    arguments_fragment: tool_use.input.clone(),
                 }]),
+                citations: None,
             }));
         } else if let Ok(text) = block_info.as_text() {
             return Some(llm::StreamEvent::Delta(llm::StreamDelta {
                 content: Some(vec![llm::ContentPart::Text(text.clone())]),
                 tool_calls: None,
+                tool_call_deltas: None,
+                citations: None,
+            }));
+        } else if let Ok(reasoning) = block_info.as_reasoning_content() {
+            let reasoning = match reasoning {
+                ReasoningContentBlockDelta::Text(text) => llm::ReasoningContent {
+                    text: text.clone(),
+                    signature: None,
+                    redacted_data: None,
+                },
+                ReasoningContentBlockDelta::Signature(signature) => llm::ReasoningContent {
+                    text: String::new(),
+                    signature: Some(signature.clone()),
+                    redacted_data: None,
+                },
+                ReasoningContentBlockDelta::RedactedContent(blob) => llm::ReasoningContent {
+                    text: String::new(),
+                    signature: None,
+                    redacted_data: Some(general_purpose::STANDARD.encode(blob.as_ref())),
+                },
+                _ => return None,
+            };
+            return Some(llm::StreamEvent::Delta(llm::StreamDelta {
+                content: Some(vec![llm::ContentPart::Reasoning(reasoning)]),
+                tool_calls: None,
+                tool_call_deltas: None,
+                citations: None,
             }));
         }
     }
@@ -490,6 +886,7 @@ fn process_metadata_event(metadata: ConverseStreamMetadataEvent) -> Option<llm::
         usage: metadata.usage().map(bedrock_usage_to_llm_usage),
         provider_id: Some("bedrock".to_owned()),
         provider_metadata_json: None,
+        cost: None,
     }))
 }
 
@@ -503,6 +900,7 @@ fn process_message_stop_event(event: MessageStopEvent) -> Option<llm::StreamEven
             .additional_model_response_fields
             .clone()
             .and_then(smithy_document_to_metadata_json),
+        cost: None,
     }))
 }
 
@@ -511,6 +909,7 @@ fn json_str_to_smithy_document(value: &str) -> Result<Document, llm::Error> {
         code: llm::ErrorCode::InvalidRequest,
         message: format!("Invalid tool schema: {err}"),
         provider_error_json: None,
+        retry_after_ms: None,
     })?;
     Ok(serde_json_to_smithy_document(json_value))
 }
@@ -576,14 +975,48 @@ fn serde_json_to_smithy_document(value: serde_json::Value) -> Document {
     }
 }
 
+/// Gets the error code of an AWS SDK error, based on the exception names of the Bedrock and STS
+/// APIs
+fn error_code_from_sdk_error<E: ProvideErrorMetadata, R>(
+    sdk_error: &SdkError<E, R>,
+) -> llm::ErrorCode {
+    if let SdkError::TimeoutError(_) = sdk_error {
+        return llm::ErrorCode::Timeout;
+    }
+    let message = sdk_error.message().unwrap_or_default().to_lowercase();
+    match sdk_error.code() {
+        Some("ThrottlingException") => llm::ErrorCode::RateLimitExceeded,
+        Some("ServiceUnavailableException") | Some("ModelNotReadyException") => {
+            llm::ErrorCode::Overloaded
+        }
+        Some("ModelTimeoutException") => llm::ErrorCode::Timeout,
+        Some("ResourceNotFoundException") => llm::ErrorCode::ModelNotFound,
+        Some("ServiceQuotaExceededException") => llm::ErrorCode::QuotaExceeded,
+        Some("AccessDeniedException")
+        | Some("UnrecognizedClientException")
+        | Some("AccessDenied")
+        | Some("ExpiredTokenException")
+        | Some("InvalidClientTokenId")
+        | Some("InvalidIdentityToken") => llm::ErrorCode::AuthenticationFailed,
+        Some("ValidationException")
+            if message.contains("too long") || message.contains("input length") =>
+        {
+            llm::ErrorCode::ContextLengthExceeded
+        }
+        Some("ValidationException") => llm::ErrorCode::InvalidRequest,
+        _ => llm::ErrorCode::InternalError,
+    }
+}
+
 pub fn from_converse_sdk_error(
     model_id: String,
     sdk_error: SdkError<converse::ConverseError>,
 ) -> llm::Error {
     llm::Error {
-        code: llm::ErrorCode::InternalError,
+        code: error_code_from_sdk_error(&sdk_error),
         message: format!("Error calling Bedrock model {model_id}: {sdk_error:?}",),
         provider_error_json: None,
+        retry_after_ms: None,
     }
 }
 
@@ -592,9 +1025,51 @@ pub fn from_converse_stream_sdk_error(
     sdk_error: SdkError<converse_stream::ConverseStreamError>,
 ) -> llm::Error {
     llm::Error {
-        code: llm::ErrorCode::InternalError,
+        code: error_code_from_sdk_error(&sdk_error),
         message: format!("Error calling Bedrock model {model_id}: {sdk_error:?}",),
         provider_error_json: None,
+        retry_after_ms: None,
+    }
+}
+
+pub fn from_list_foundation_models_sdk_error(
+    sdk_error: aws_sdk_bedrock::error::SdkError<ListFoundationModelsError>,
+) -> llm::Error {
+    llm::Error {
+        code: error_code_from_sdk_error(&sdk_error),
+        message: format!("Error listing Bedrock foundation models: {sdk_error:?}"),
+        provider_error_json: None,
+        retry_after_ms: None,
+    }
+}
+
+pub fn from_sts_sdk_error<E: ProvideErrorMetadata + Debug, R: Debug>(
+    role_arn: &str,
+    sdk_error: SdkError<E, R>,
+) -> llm::Error {
+    llm::Error {
+        code: error_code_from_sdk_error(&sdk_error),
+        message: format!("Error assuming role {role_arn}: {sdk_error:?}"),
+        provider_error_json: None,
+        retry_after_ms: None,
+    }
+}
+
+/// Converts an error of the batch inference or S3 APIs. The missing resources are jobs or files,
+/// not models.
+pub fn from_batch_sdk_error<E: ProvideErrorMetadata + Debug, R: Debug>(
+    action: &str,
+    sdk_error: SdkError<E, R>,
+) -> llm::Error {
+    let code = match error_code_from_sdk_error(&sdk_error) {
+        llm::ErrorCode::ModelNotFound => llm::ErrorCode::InvalidRequest,
+        code => code,
+    };
+    llm::Error {
+        code,
+        message: format!("Error {action}: {sdk_error:?}"),
+        provider_error_json: None,
+        retry_after_ms: None,
     }
 }
 
@@ -603,6 +1078,7 @@ pub fn custom_error(code: llm::ErrorCode, message: String) -> llm::Error {
         code,
         message,
         provider_error_json: None,
+        retry_after_ms: None,
     }
 }
 
@@ -617,6 +1093,45 @@ pub fn merge_metadata(
     metadata1.provider_metadata_json = metadata1
         .provider_metadata_json
         .or(metadata2.provider_metadata_json);
+    metadata1.cost = metadata1.cost.or(metadata2.cost);
 
     metadata1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_structured_output_conflicts() {
+        let reasoning = Some(llm::ReasoningConfig {
+            effort: Some(llm::ReasoningEffort::Low),
+            budget_tokens: None,
+        });
+
+        assert!(check_structured_output_conflicts("answer", &None, &None).is_ok());
+        assert!(check_structured_output_conflicts(
+            "answer",
+            &Some(llm::ToolChoice::Specific("answer".to_string())),
+            &None
+        )
+        .is_ok());
+        for tool_choice in [
+            llm::ToolChoice::Auto,
+            llm::ToolChoice::None,
+            llm::ToolChoice::Required,
+            llm::ToolChoice::Specific("get-weather".to_string()),
+        ] {
+            let error =
+                check_structured_output_conflicts("answer", &Some(tool_choice.clone()), &None)
+                    .unwrap_err();
+            assert_eq!(
+                error.code,
+                llm::ErrorCode::InvalidRequest,
+                "{tool_choice:?}"
+            );
+        }
+        let error = check_structured_output_conflicts("answer", &None, &reasoning).unwrap_err();
+        assert_eq!(error.code, llm::ErrorCode::InvalidRequest);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::client::WasiSleep;
+use crate::conversions::{custom_error, from_sts_sdk_error};
+use crate::wasi_client::WasiClient;
+use aws_config::BehaviorVersion;
+use aws_sdk_bedrockruntime::config::Credentials;
+use aws_sdk_sts as sts;
+use aws_types::region;
+use golem_llm::config::{get_config_key, get_config_key_or_none};
+use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use log::trace;
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::time::{Duration, SystemTime};
+
+const PROVIDER_NAME: &str = "llm-bedrock";
+
+/// Session name of the assumed roles, unless `AWS_ROLE_SESSION_NAME` is set
+const DEFAULT_SESSION_NAME: &str = "golem-llm-bedrock";
+
+/// Temporary credentials are refreshed this long before they expire, so they do not expire
+/// mid-request
+const REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// Where the credentials signing the requests come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CredentialSource {
+    /// Credentials given in the environment, either long-lived keys or a session
+    Static(Credentials),
+    /// Temporary credentials of an assumed role
+    Role {
+        role_arn: String,
+        session_name: String,
+        authentication: RoleAuthentication,
+    },
+}
+
+/// How the request assuming a role is authenticated
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoleAuthentication {
+    /// Signed with the credentials given in the environment, passing the external ID required by
+    /// the role's trust policy, if any
+    SourceCredentials {
+        source: Credentials,
+        external_id: Option<String>,
+    },
+    /// With an OpenID Connect token of a web identity provider
+    WebIdentity(WebIdentityToken),
+}
+
+/// The web identity token, given directly or in a file which may be rotated by its provider
+#[derive(Clone, PartialEq)]
+pub enum WebIdentityToken {
+    Value(String),
+    File(String),
+}
+
+impl Debug for WebIdentityToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebIdentityToken::Value(_) => write!(f, "Value(** redacted **)"),
+            WebIdentityToken::File(path) => write!(f, "File({path:?})"),
+        }
+    }
+}
+
+/// The temporary credentials of an assumed role, and the source they were obtained from, so they
+/// are not reused when any part of the source, like the external ID or the token, changes
+struct CachedCredentials {
+    source: CredentialSource,
+    credentials: Credentials,
+}
+
+thread_local! {
+    static CACHED_CREDENTIALS: RefCell<Option<CachedCredentials>> = const { RefCell::new(None) };
+}
+
+impl CredentialSource {
+    /// Loads the credential source from the environment. If `AWS_ROLE_ARN` is set, the role is
+    /// assumed with the web identity token of `AWS_WEB_IDENTITY_TOKEN` or
+    /// `AWS_WEB_IDENTITY_TOKEN_FILE`, or otherwise with the static credentials.
+    pub fn load_from_env() -> Result<Self, Error> {
+        let Some(role_arn) = get_config_key_or_none("AWS_ROLE_ARN") else {
+            return Ok(CredentialSource::Static(static_credentials()?));
+        };
+        let session_name = get_config_key_or_none("AWS_ROLE_SESSION_NAME")
+            .unwrap_or_else(|| DEFAULT_SESSION_NAME.to_string());
+
+        let token = get_config_key_or_none("AWS_WEB_IDENTITY_TOKEN")
+            .map(WebIdentityToken::Value)
+            .or_else(|| {
+                get_config_key_or_none("AWS_WEB_IDENTITY_TOKEN_FILE").map(WebIdentityToken::File)
+            });
+        let authentication = match token {
+            Some(token) => RoleAuthentication::WebIdentity(token),
+            None => RoleAuthentication::SourceCredentials {
+                source: static_credentials()?,
+                external_id: get_config_key_or_none("AWS_ROLE_EXTERNAL_ID"),
+            },
+        };
+        Ok(CredentialSource::Role {
+            role_arn,
+            session_name,
+            authentication,
+        })
+    }
+
+    /// Gets the credentials to sign the requests with, assuming the role if there is one. The
+    /// temporary credentials of the role are reused until shortly before they expire.
+    pub async fn credentials(&self, region: &region::Region) -> Result<Credentials, Error> {
+        let (role_arn, session_name, authentication) = match self {
+            CredentialSource::Static(credentials) => return Ok(credentials.clone()),
+            CredentialSource::Role {
+                role_arn,
+                session_name,
+                authentication,
+            } => (role_arn, session_name, authentication),
+        };
+
+        if let Some(credentials) = cached_credentials(self) {
+            return Ok(credentials);
+        }
+
+        trace!("Assuming role {role_arn} with session {session_name}");
+
+        let credentials = match authentication {
+            RoleAuthentication::SourceCredentials {
+                source,
+                external_id,
+            } => {
+                let output = sts_client(region, Some(source.clone()))
+                    .assume_role()
+                    .role_arn(role_arn)
+                    .role_session_name(session_name)
+                    .set_external_id(external_id.clone())
+                    .send()
+                    .await
+                    .map_err(|err| from_sts_sdk_error(role_arn, err))?;
+                sts_credentials_to_credentials(output.credentials(), role_arn)?
+            }
+            RoleAuthentication::WebIdentity(token) => {
+                let token = match token {
+                    WebIdentityToken::Value(token) => token.clone(),
+                    WebIdentityToken::File(path) => std::fs::read_to_string(path)
+                        .map(|token| token.trim().to_string())
+                        .map_err(|err| {
+                            custom_error(
+                                ErrorCode::AuthenticationFailed,
+                                format!("Failed to read web identity token file {path}: {err}"),
+                            )
+                        })?,
+                };
+                // The web identity token authenticates the request, so it is not signed
+                let output = sts_client(region, None)
+                    .assume_role_with_web_identity()
+                    .role_arn(role_arn)
+                    .role_session_name(session_name)
+                    .web_identity_token(token)
+                    .send()
+                    .await
+                    .map_err(|err| from_sts_sdk_error(role_arn, err))?;
+                sts_credentials_to_credentials(output.credentials(), role_arn)?
+            }
+        };
+
+        cache_credentials(self, &credentials);
+        Ok(credentials)
+    }
+}
+
+fn static_credentials() -> Result<Credentials, Error> {
+    Ok(Credentials::new(
+        get_config_key("AWS_ACCESS_KEY_ID")?,
+        get_config_key("AWS_SECRET_ACCESS_KEY")?,
+        get_config_key_or_none("AWS_SESSION_TOKEN"),
+        None,
+        PROVIDER_NAME,
+    ))
+}
+
+fn cached_credentials(source: &CredentialSource) -> Option<Credentials> {
+    CACHED_CREDENTIALS.with_borrow(|cached| {
+        cached
+            .as_ref()
+            .filter(|cached| {
+                cached.source == *source
+                    && cached
+                        .credentials
+                        .expiry()
+                        .is_some_and(|expiry| expiry > SystemTime::now() + REFRESH_MARGIN)
+            })
+            .map(|cached| cached.credentials.clone())
+    })
+}
+
+fn cache_credentials(source: &CredentialSource, credentials: &Credentials) {
+    CACHED_CREDENTIALS.with_borrow_mut(|cached| {
+        *cached = Some(CachedCredentials {
+            source: source.clone(),
+            credentials: credentials.clone(),
+        })
+    });
+}
+
+fn sts_client(region: &region::Region, credentials: Option<Credentials>) -> sts::Client {
+    let mut config = sts::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(region.clone())
+        .http_client(WasiClient::new())
+        .sleep_impl(WasiSleep::new());
+    if let Some(credentials) = credentials {
+        config = config.credentials_provider(credentials);
+    }
+    sts::Client::from_conf(config.build())
+}
+
+fn sts_credentials_to_credentials(
+    credentials: Option<&sts::types::Credentials>,
+    role_arn: &str,
+) -> Result<Credentials, Error> {
+    let credentials = credentials.ok_or_else(|| {
+        custom_error(
+            ErrorCode::AuthenticationFailed,
+            format!("No credentials returned for role {role_arn}"),
+        )
+    })?;
+    Ok(Credentials::new(
+        credentials.access_key_id(),
+        credentials.secret_access_key(),
+        Some(credentials.session_token().to_string()),
+        SystemTime::try_from(*credentials.expiration()).ok(),
+        PROVIDER_NAME,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(external_id: Option<&str>) -> CredentialSource {
+        CredentialSource::Role {
+            role_arn: "arn:aws:iam::123456789012:role/bedrock".to_string(),
+            session_name: DEFAULT_SESSION_NAME.to_string(),
+            authentication: RoleAuthentication::SourceCredentials {
+                source: Credentials::new("AKID", "SECRET", None, None, PROVIDER_NAME),
+                external_id: external_id.map(|id| id.to_string()),
+            },
+        }
+    }
+
+    fn temporary_credentials(expires_in: Duration) -> Credentials {
+        Credentials::new(
+            "ASIA",
+            "SECRET",
+            Some("TOKEN".to_string()),
+            Some(SystemTime::now() + expires_in),
+            PROVIDER_NAME,
+        )
+    }
+
+    #[test]
+    fn test_cached_credentials_match_the_whole_source() {
+        let credentials = temporary_credentials(Duration::from_secs(3600));
+        cache_credentials(&role(Some("first")), &credentials);
+
+        assert_eq!(cached_credentials(&role(Some("first"))), Some(credentials));
+        assert_eq!(cached_credentials(&role(Some("second"))), None);
+        assert_eq!(cached_credentials(&role(None)), None);
+    }
+
+    #[test]
+    fn test_cached_credentials_are_refreshed_before_expiring() {
+        cache_credentials(&role(None), &temporary_credentials(REFRESH_MARGIN / 2));
+
+        assert_eq!(cached_credentials(&role(None)), None);
+    }
+}
@@ -1,16 +1,23 @@
 use async_utils::get_async_runtime;
 use client::Bedrock;
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
 use golem_llm::durability::{DurableLLM, ExtendedGuest};
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
 use golem_llm::golem::llm::llm::{
-    self, ChatStream, Config, Error, Event, Guest, Message, Response,
+    self, ChatStream, Config, Error, Event, Guest, Message, ModelInfo, Response,
 };
+use golem_llm::tokens::estimate_tokens;
 use golem_rust::bindings::wasi::clocks::monotonic_clock;
 use indoc::indoc;
 use stream::BedrockChatStream;
 
 mod async_utils;
+mod batch;
 mod client;
 mod conversions;
+mod credentials;
+mod models;
 mod stream;
 mod wasi_client;
 
@@ -18,6 +25,7 @@ struct BedrockComponent;
 
 impl Guest for BedrockComponent {
     type ChatStream = BedrockChatStream;
+    type ChatSession = ChatSession<Self>;
 
     fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
         let runtime = get_async_runtime();
@@ -31,9 +39,72 @@ impl Guest for BedrockComponent {
     fn stream(events: Vec<Event>, config: Config) -> ChatStream {
         ChatStream::new(Self::unwrapped_stream(events, config))
     }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(models::model_info(model))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let runtime = get_async_runtime();
+
+        runtime.block_on(async {
+            let client = get_bedrock_client().await?;
+            let models = client.list_foundation_models().await?;
+            Ok(models
+                .into_iter()
+                .map(models::foundation_model_to_model_info)
+                .collect())
+        })
+    }
+}
+
+/// Batches are batch inference jobs, identified by their ARN, reading the requests from and
+/// writing the responses to S3
+impl BatchGuest for BedrockComponent {
+    fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+        let runtime = get_async_runtime();
+
+        runtime.block_on(async {
+            let client = get_bedrock_client().await?;
+            client.create_batch(items).await
+        })
+    }
+
+    fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+        let runtime = get_async_runtime();
+
+        runtime.block_on(async {
+            let client = get_bedrock_client().await?;
+            client.get_batch(&id).await
+        })
+    }
+
+    fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+        let runtime = get_async_runtime();
+
+        runtime.block_on(async {
+            let client = get_bedrock_client().await?;
+            client.get_batch_results(&id).await
+        })
+    }
+
+    fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+        let runtime = get_async_runtime();
+
+        runtime.block_on(async {
+            let client = get_bedrock_client().await?;
+            client.stop_batch(&id).await
+        })
+    }
 }
 
 impl ExtendedGuest for BedrockComponent {
+    const PROVIDER_NAME: &'static str = "bedrock";
+
     fn unwrapped_stream(messages: Vec<Event>, config: Config) -> Self::ChatStream {
         let runtime = get_async_runtime();
 
@@ -61,6 +132,7 @@ impl ExtendedGuest for BedrockComponent {
                 Do not include the part of the response that was already seen.
                 If the response starts with a new word and no punctuation then add a space to the beginning."
             }.to_string())],
+            cache_hint: None,
         }));
         extended_events.push(Event::Message(Message {
             role: llm::Role::User,
@@ -68,6 +140,7 @@ impl ExtendedGuest for BedrockComponent {
             content: vec![llm::ContentPart::Text(
                 "Here is the original question:".to_string(),
             )],
+            cache_hint: None,
         }));
         extended_events.extend(
             original_events
@@ -78,7 +151,13 @@ impl ExtendedGuest for BedrockComponent {
         let mut partial_result_as_content = Vec::new();
         for delta in partial_result {
             if let Some(contents) = &delta.content {
-                partial_result_as_content.extend_from_slice(contents);
+                // Reasoning is not part of the answer the model has to continue
+                partial_result_as_content.extend(
+                    contents
+                        .iter()
+                        .filter(|content| !matches!(content, llm::ContentPart::Reasoning(_)))
+                        .cloned(),
+                );
             }
             if let Some(tool_calls) = &delta.tool_calls {
                 for tool_call in tool_calls {
@@ -99,6 +178,7 @@ impl ExtendedGuest for BedrockComponent {
             .into_iter()
             .chain(partial_result_as_content)
             .collect(),
+            cache_hint: None,
         }));
         extended_events
     }
@@ -113,6 +193,6 @@ async fn get_bedrock_client() -> Result<Bedrock, llm::Error> {
     Bedrock::new().await
 }
 
-type DurableBedrockComponent = DurableLLM<BedrockComponent>;
+type DurableBedrockComponent = DurableLLM<CachedLLM<BedrockComponent>>;
 
 golem_llm::export_llm!(DurableBedrockComponent with_types_in golem_llm);
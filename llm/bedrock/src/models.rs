@@ -0,0 +1,614 @@
+use aws_sdk_bedrock::types::{FoundationModelSummary, ModelModality};
+use golem_llm::golem::llm::llm::{ModelCapabilities, ModelInfo};
+use golem_llm::models::{known_model_info, KnownModel};
+use golem_llm::pricing::{KnownPrice, Price, Pricing};
+
+/// Prefixes of the cross-region inference profiles wrapping the base models
+const INFERENCE_PROFILE_PREFIXES: &[&str] = &["us", "eu", "apac", "au", "jp", "us-gov", "global"];
+
+/// Prefixes of the base models wrapped by cross-region inference profiles, in at least some
+/// geographies
+const INFERENCE_PROFILE_MODELS: &[&str] = &[
+    "anthropic.claude-3",
+    "anthropic.claude-haiku-4",
+    "anthropic.claude-opus-4",
+    "anthropic.claude-sonnet-4",
+    "amazon.nova-",
+    "deepseek.r1",
+    "meta.llama3-1",
+    "meta.llama3-2",
+    "meta.llama3-3",
+    "meta.llama4",
+    "mistral.pixtral-large",
+    "writer.palmyra",
+];
+
+/// Limits and capabilities of the models available through the Converse API
+pub const KNOWN_MODELS: &[KnownModel] = &[
+    KnownModel {
+        id: "anthropic.claude-opus-4-1",
+        context_window: 200_000,
+        max_output_tokens: Some(32_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-opus-4",
+        context_window: 200_000,
+        max_output_tokens: Some(32_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-sonnet-4-5",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-sonnet-4",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-haiku-4-5",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-3-7-sonnet",
+        context_window: 200_000,
+        max_output_tokens: Some(64_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-3-5-sonnet",
+        context_window: 200_000,
+        max_output_tokens: Some(8_192),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-3-5-haiku",
+        context_window: 200_000,
+        max_output_tokens: Some(8_192),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-3-opus",
+        context_window: 200_000,
+        max_output_tokens: Some(4_096),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "anthropic.claude-3-haiku",
+        context_window: 200_000,
+        max_output_tokens: Some(4_096),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "amazon.nova-premier",
+        context_window: 1_000_000,
+        max_output_tokens: Some(10_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "amazon.nova-pro",
+        context_window: 300_000,
+        max_output_tokens: Some(10_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "amazon.nova-lite",
+        context_window: 300_000,
+        max_output_tokens: Some(10_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "amazon.nova-micro",
+        context_window: 128_000,
+        max_output_tokens: Some(10_000),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "meta.llama3-1",
+        context_window: 128_000,
+        max_output_tokens: Some(2_048),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "meta.llama3-2",
+        context_window: 128_000,
+        max_output_tokens: Some(2_048),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "meta.llama3-3",
+        context_window: 128_000,
+        max_output_tokens: Some(2_048),
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+];
+
+/// The identifier of the model wrapped by a cross-region inference profile, which may also be given
+/// by the ARN of the profile or of the foundation model
+pub fn base_model(model: &str) -> &str {
+    let model = match model.strip_prefix("arn:") {
+        Some(arn) => arn.rsplit_once('/').map_or(model, |(_, id)| id),
+        None => model,
+    };
+    model
+        .split_once('.')
+        .filter(|(prefix, _)| INFERENCE_PROFILE_PREFIXES.contains(prefix))
+        .map_or(model, |(_, base_model)| base_model)
+}
+
+/// The prefix of the cross-region inference profiles of the geography the region is in
+pub fn region_inference_profile_prefix(region: &str) -> Option<&'static str> {
+    if region.starts_with("us-gov-") {
+        Some("us-gov")
+    } else if region.starts_with("us-") {
+        Some("us")
+    } else if region.starts_with("eu-") {
+        Some("eu")
+    } else if region.starts_with("ap-") {
+        Some("apac")
+    } else {
+        None
+    }
+}
+
+/// Whether the model is a foundation model known to be wrapped by cross-region inference profiles
+pub fn has_inference_profile(model: &str) -> bool {
+    INFERENCE_PROFILE_MODELS
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
+/// The identifier of the cross-region inference profile with the given prefix wrapping the model,
+/// keeping the inference profiles and ARNs as they are
+pub fn inference_profile_id(prefix: &str, model: &str) -> String {
+    if model.starts_with("arn:") || base_model(model) != model {
+        model.to_string()
+    } else {
+        format!("{prefix}.{model}")
+    }
+}
+
+pub fn model_info(model: String) -> ModelInfo {
+    ModelInfo {
+        id: model.clone(),
+        ..known_model_info(base_model(&model), KNOWN_MODELS)
+    }
+}
+
+pub fn foundation_model_to_model_info(model: FoundationModelSummary) -> ModelInfo {
+    let mut model_info = model_info(model.model_id);
+    // The reported modalities and streaming support are more accurate than the table
+    if let Some(capabilities) = &mut model_info.capabilities {
+        capabilities.vision = model
+            .input_modalities
+            .unwrap_or_default()
+            .contains(&ModelModality::Image);
+        capabilities.streaming = model.response_streaming_supported.unwrap_or(true);
+    }
+    model_info
+}
+
+/// On-demand prices of the models available through the Converse API, the input tokens reported by
+/// the API exclude the cached ones
+pub const PRICING: Pricing = Pricing {
+    input_includes_cached: false,
+    known_prices: &[
+        KnownPrice {
+            id: "anthropic.claude-opus-4-1",
+            price: Price {
+                input: 15.0,
+                output: 75.0,
+                cache_read: Some(1.5),
+                cache_write: Some(18.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-opus-4",
+            price: Price {
+                input: 15.0,
+                output: 75.0,
+                cache_read: Some(1.5),
+                cache_write: Some(18.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-sonnet-4-5",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-sonnet-4",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-haiku-4-5",
+            price: Price {
+                input: 1.0,
+                output: 5.0,
+                cache_read: Some(0.1),
+                cache_write: Some(1.25),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-3-7-sonnet",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-3-5-sonnet",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: Some(3.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-3-5-haiku",
+            price: Price {
+                input: 0.8,
+                output: 4.0,
+                cache_read: Some(0.08),
+                cache_write: Some(1.0),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-3-opus",
+            price: Price {
+                input: 15.0,
+                output: 75.0,
+                cache_read: Some(1.5),
+                cache_write: Some(18.75),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "anthropic.claude-3-haiku",
+            price: Price {
+                input: 0.25,
+                output: 1.25,
+                cache_read: Some(0.03),
+                cache_write: Some(0.3),
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "amazon.nova-premier",
+            price: Price {
+                input: 2.5,
+                output: 12.5,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "amazon.nova-pro",
+            price: Price {
+                input: 0.8,
+                output: 3.2,
+                cache_read: Some(0.2),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "amazon.nova-lite",
+            price: Price {
+                input: 0.06,
+                output: 0.24,
+                cache_read: Some(0.015),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "amazon.nova-micro",
+            price: Price {
+                input: 0.035,
+                output: 0.14,
+                cache_read: Some(0.00875),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-1-8b",
+            price: Price {
+                input: 0.22,
+                output: 0.22,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-1-70b",
+            price: Price {
+                input: 0.72,
+                output: 0.72,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-1-405b",
+            price: Price {
+                input: 2.4,
+                output: 2.4,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-2-1b",
+            price: Price {
+                input: 0.1,
+                output: 0.1,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-2-3b",
+            price: Price {
+                input: 0.15,
+                output: 0.15,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-2-11b",
+            price: Price {
+                input: 0.16,
+                output: 0.16,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-2-90b",
+            price: Price {
+                input: 0.72,
+                output: 0.72,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "meta.llama3-3-70b",
+            price: Price {
+                input: 0.72,
+                output: 0.72,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_model() {
+        assert_eq!(
+            base_model("anthropic.claude-3-5-sonnet-20240620-v1:0"),
+            "anthropic.claude-3-5-sonnet-20240620-v1:0"
+        );
+        assert_eq!(
+            base_model("us.anthropic.claude-3-5-sonnet-20240620-v1:0"),
+            "anthropic.claude-3-5-sonnet-20240620-v1:0"
+        );
+        assert_eq!(
+            base_model("us-gov.anthropic.claude-3-5-sonnet-20240620-v1:0"),
+            "anthropic.claude-3-5-sonnet-20240620-v1:0"
+        );
+        assert_eq!(
+            base_model("global.anthropic.claude-sonnet-4-20250514-v1:0"),
+            "anthropic.claude-sonnet-4-20250514-v1:0"
+        );
+        assert_eq!(
+            base_model(
+                "arn:aws:bedrock:eu-west-1:123456789012:inference-profile/eu.amazon.nova-pro-v1:0"
+            ),
+            "amazon.nova-pro-v1:0"
+        );
+        assert_eq!(
+            base_model(
+                "arn:aws:bedrock:us-east-1::foundation-model/meta.llama3-3-70b-instruct-v1:0"
+            ),
+            "meta.llama3-3-70b-instruct-v1:0"
+        );
+        assert_eq!(
+            base_model("mistral.mistral-large-2402-v1:0"),
+            "mistral.mistral-large-2402-v1:0"
+        );
+    }
+
+    #[test]
+    fn test_inference_profile_id() {
+        assert_eq!(
+            inference_profile_id("eu", "anthropic.claude-3-7-sonnet-20250219-v1:0"),
+            "eu.anthropic.claude-3-7-sonnet-20250219-v1:0"
+        );
+        assert_eq!(
+            inference_profile_id("eu", "us.anthropic.claude-3-7-sonnet-20250219-v1:0"),
+            "us.anthropic.claude-3-7-sonnet-20250219-v1:0"
+        );
+        let arn = "arn:aws:bedrock:us-east-1::foundation-model/amazon.nova-lite-v1:0";
+        assert_eq!(inference_profile_id("us", arn), arn);
+    }
+
+    #[test]
+    fn test_region_inference_profile_prefix() {
+        assert_eq!(region_inference_profile_prefix("us-east-1"), Some("us"));
+        assert_eq!(
+            region_inference_profile_prefix("us-gov-west-1"),
+            Some("us-gov")
+        );
+        assert_eq!(region_inference_profile_prefix("eu-central-1"), Some("eu"));
+        assert_eq!(
+            region_inference_profile_prefix("ap-northeast-1"),
+            Some("apac")
+        );
+        assert_eq!(region_inference_profile_prefix("ca-central-1"), None);
+        assert_eq!(region_inference_profile_prefix("sa-east-1"), None);
+    }
+
+    #[test]
+    fn test_has_inference_profile() {
+        assert!(has_inference_profile(
+            "anthropic.claude-3-5-haiku-20241022-v1:0"
+        ));
+        assert!(has_inference_profile(
+            "anthropic.claude-sonnet-4-5-20250929-v1:0"
+        ));
+        assert!(has_inference_profile("amazon.nova-micro-v1:0"));
+        assert!(has_inference_profile("meta.llama3-2-11b-instruct-v1:0"));
+        assert!(!has_inference_profile("anthropic.claude-v2:1"));
+        assert!(!has_inference_profile("amazon.titan-text-express-v1"));
+        assert!(!has_inference_profile("cohere.command-r-plus-v1:0"));
+        assert!(!has_inference_profile(
+            "us.anthropic.claude-3-5-haiku-20241022-v1:0"
+        ));
+    }
+}
@@ -3,10 +3,13 @@ use crate::{
     conversions::{converse_stream_output_to_stream_event, custom_error, merge_metadata},
 };
 use aws_sdk_bedrockruntime::{
-    self as bedrock, primitives::event_stream::EventReceiver,
-    types::error::ConverseStreamOutputError,
+    self as bedrock,
+    primitives::event_stream::EventReceiver,
+    types::{error::ConverseStreamOutputError, ConverseStreamOutput},
 };
+use golem_llm::chat_stream::ToolCallAssembler;
 use golem_llm::golem::llm::llm;
+use golem_llm::pricing::ModelPricing;
 use std::cell::{RefCell, RefMut};
 
 type BedrockEventSource =
@@ -16,14 +19,26 @@ pub struct BedrockChatStream {
     stream: RefCell<Option<BedrockEventSource>>,
     failure: Option<llm::Error>,
     finished: RefCell<bool>,
+    json_output_tool: Option<String>,
+    json_output_index: RefCell<Option<i32>>,
+    tool_calls: ToolCallAssembler,
+    pricing: Option<ModelPricing>,
 }
 
 impl BedrockChatStream {
-    pub fn new(stream: BedrockEventSource) -> BedrockChatStream {
+    pub fn new(
+        stream: BedrockEventSource,
+        json_output_tool: Option<String>,
+        pricing: Option<ModelPricing>,
+    ) -> BedrockChatStream {
         BedrockChatStream {
             stream: RefCell::new(Some(stream)),
             failure: None,
             finished: RefCell::new(false),
+            json_output_tool,
+            json_output_index: RefCell::new(None),
+            tool_calls: ToolCallAssembler::new(),
+            pricing,
         }
     }
 
@@ -32,6 +47,10 @@ impl BedrockChatStream {
             stream: RefCell::new(None),
             failure: Some(error),
             finished: RefCell::new(true),
+            json_output_tool: None,
+            json_output_index: RefCell::new(None),
+            tool_calls: ToolCallAssembler::new(),
+            pricing: None,
         }
     }
 
@@ -50,6 +69,73 @@ impl BedrockChatStream {
     fn set_finished(&self) {
         *self.finished.borrow_mut() = true;
     }
+
+    /// Converts a stream event, turning the structured output tool call into text content
+    fn convert_stream_output(&self, output: ConverseStreamOutput) -> Option<llm::StreamEvent> {
+        if let Some(json_output_tool) = &self.json_output_tool {
+            match &output {
+                ConverseStreamOutput::ContentBlockStart(block)
+                    if block
+                        .start
+                        .as_ref()
+                        .and_then(|start| start.as_tool_use().ok())
+                        .is_some_and(|tool_use| &tool_use.name == json_output_tool) =>
+                {
+                    *self.json_output_index.borrow_mut() = Some(block.content_block_index);
+                    return None;
+                }
+                ConverseStreamOutput::ContentBlockDelta(block)
+                    if *self.json_output_index.borrow() == Some(block.content_block_index) =>
+                {
+                    return block
+                        .delta
+                        .as_ref()
+                        .and_then(|delta| delta.as_tool_use().ok())
+                        .map(|tool_use| {
+                            llm::StreamEvent::Delta(llm::StreamDelta {
+                                content: Some(vec![llm::ContentPart::Text(tool_use.input.clone())]),
+                                tool_calls: None,
+                                tool_call_deltas: None,
+                                citations: None,
+                            })
+                        });
+                }
+                _ => {}
+            }
+        }
+
+        match converse_stream_output_to_stream_event(output) {
+            Some(llm::StreamEvent::Finish(mut metadata))
+                if self.json_output_index.borrow().is_some()
+                    && metadata.finish_reason == Some(llm::FinishReason::ToolCalls) =>
+            {
+                metadata.finish_reason = Some(llm::FinishReason::Stop);
+                Some(llm::StreamEvent::Finish(metadata))
+            }
+            event => event,
+        }
+    }
+
+    /// Merges the finish event with the metadata event following it
+    fn process_event(
+        &self,
+        event: Result<llm::StreamEvent, llm::Error>,
+    ) -> Vec<Result<llm::StreamEvent, llm::Error>> {
+        if let Ok(llm::StreamEvent::Finish(metadata)) = &event {
+            if let Some(Ok(llm::StreamEvent::Finish(final_metadata))) = self.get_single_event() {
+                return self.tool_calls.process(vec![Ok(llm::StreamEvent::Finish(
+                    merge_metadata(metadata.clone(), final_metadata),
+                ))]);
+            }
+        }
+        // Errors end the stream, so the tool calls received before them are emitted first
+        if event.is_err() {
+            let mut events = self.tool_calls.complete();
+            events.push(event);
+            return events;
+        }
+        self.tool_calls.process(vec![event])
+    }
     fn get_single_event(&self) -> Option<Result<llm::StreamEvent, llm::Error>> {
         if let Some(stream) = self.stream_mut().as_mut() {
             let runtime = async_utils::get_async_runtime();
@@ -61,7 +147,7 @@ impl BedrockChatStream {
                 match token {
                     Ok(Some(output)) => {
                         log::trace!("Processing bedrock stream event: {output:?}");
-                        converse_stream_output_to_stream_event(output).map(Ok)
+                        self.convert_stream_output(output).map(Ok)
                     }
                     Ok(None) => {
                         log::trace!("running set_finished on stream due to None event received");
@@ -92,18 +178,19 @@ impl llm::GuestChatStream for BedrockChatStream {
         if self.is_finished() {
             return Some(vec![]);
         }
-        self.get_single_event().map(|event| {
-            if let Ok(llm::StreamEvent::Finish(metadata)) = &event {
-                if let Some(Ok(llm::StreamEvent::Finish(final_metadata))) = self.get_single_event()
-                {
-                    return vec![Ok(llm::StreamEvent::Finish(merge_metadata(
-                        metadata.clone(),
-                        final_metadata,
-                    )))];
-                }
+        match self.get_single_event() {
+            Some(event) => {
+                let events = self.process_event(event);
+                // The usage is only known once the metadata of both finish events is merged
+                Some(match &self.pricing {
+                    Some(pricing) => pricing.add_stream_costs(events),
+                    None => events,
+                })
             }
-            vec![event]
-        })
+            // The tool calls of a stream ending without a finish event are still emitted
+            None if self.is_finished() => Some(self.tool_calls.complete()),
+            None => None,
+        }
     }
 
     fn get_next(&self) -> Vec<Result<llm::StreamEvent, llm::Error>> {
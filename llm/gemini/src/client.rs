@@ -0,0 +1,488 @@
+use base64::{engine::general_purpose, Engine as _};
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
+use log::trace;
+use reqwest::header::HeaderValue;
+use reqwest::{Client, Method, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+const BASE_URL: &str = "https://generativelanguage.googleapis.com";
+
+/// The Gemini API client for generating content.
+///
+/// Based on https://ai.google.dev/api/generate-content
+pub struct GenerateContentApi {
+    api_key: String,
+    client: Client,
+}
+
+impl GenerateContentApi {
+    pub fn new(api_key: String) -> Self {
+        let client = Client::builder()
+            .build()
+            .expect("Failed to initialize HTTP client");
+        Self { api_key, client }
+    }
+
+    pub fn generate_content(
+        &self,
+        model: &str,
+        request: GenerateContentRequest,
+    ) -> Result<GenerateContentResponse, Error> {
+        trace!("Sending request to Gemini API: {request:?}");
+
+        let model = model.trim_start_matches("models/");
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(
+                    Method::POST,
+                    format!("{BASE_URL}/v1beta/models/{model}:generateContent"),
+                )
+                .header("x-goog-api-key", &self.api_key)
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn stream_generate_content(
+        &self,
+        model: &str,
+        request: GenerateContentRequest,
+    ) -> Result<EventSource, Error> {
+        trace!("Sending request to Gemini API: {request:?}");
+
+        let model = model.trim_start_matches("models/");
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(
+                    Method::POST,
+                    format!("{BASE_URL}/v1beta/models/{model}:streamGenerateContent?alt=sse"),
+                )
+                .header("x-goog-api-key", &self.api_key)
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        trace!("Initializing SSE stream");
+
+        EventSource::new(response)
+            .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
+    }
+
+    pub fn count_tokens(
+        &self,
+        model: &str,
+        request: GenerateContentRequest,
+    ) -> Result<CountTokensResponse, Error> {
+        let model = model.trim_start_matches("models/");
+        let request = CountTokensRequest {
+            generate_content_request: ModelGenerateContentRequest {
+                model: format!("models/{model}"),
+                request,
+            },
+        };
+
+        trace!("Sending request to Gemini API: {request:?}");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(
+                    Method::POST,
+                    format!("{BASE_URL}/v1beta/models/{model}:countTokens"),
+                )
+                .header("x-goog-api-key", &self.api_key)
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn get_model(&self, model: &str) -> Result<Model, Error> {
+        trace!("Getting model {model} from Gemini API");
+
+        let model = model.trim_start_matches("models/");
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, format!("{BASE_URL}/v1beta/models/{model}"))
+                .header("x-goog-api-key", &self.api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn list_models(&self, page_token: Option<&str>) -> Result<ListModelsResponse, Error> {
+        trace!("Listing models of Gemini API from page {page_token:?}");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            let request = self
+                .client
+                .request(Method::GET, format!("{BASE_URL}/v1beta/models"))
+                .header("x-goog-api-key", &self.api_key)
+                .query(&[("pageSize", "1000")]);
+            match page_token {
+                Some(page_token) => request.query(&[("pageToken", page_token)]),
+                None => request,
+            }
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateContentRequest {
+    pub contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<Content>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_config: Option<ToolConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GenerationConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountTokensRequest {
+    pub generate_content_request: ModelGenerateContentRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelGenerateContentRequest {
+    pub model: String,
+    #[serde(flatten)]
+    pub request: GenerateContentRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Content {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+    #[serde(default)]
+    pub parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    #[serde(rename = "user")]
+    User,
+    #[serde(rename = "model")]
+    Model,
+}
+
+/// A single part of a content. Exactly one of the data fields (`text`, `inline_data`,
+/// `file_data`, `function_call` or `function_response`) is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Part {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_data: Option<FileData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_response: Option<FunctionResponse>,
+    /// Whether the part is a summary of the model's thoughts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thought: Option<bool>,
+    /// Opaque signature of the model's thoughts, has to be sent back in multi-turn conversations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thought_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blob {
+    pub mime_type: String,
+    /// Base64 encoded data
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub file_uri: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub response: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+    pub function_declarations: Vec<FunctionDeclaration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDeclaration {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters_json_schema: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolConfig {
+    pub function_calling_config: FunctionCallingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionCallingConfig {
+    pub mode: FunctionCallingMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_function_names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FunctionCallingMode {
+    #[serde(rename = "AUTO")]
+    Auto,
+    #[serde(rename = "ANY")]
+    Any,
+    #[serde(rename = "NONE")]
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_json_schema: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_config: Option<ThinkingConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_thoughts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountTokensResponse {
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_token_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_token_limit: Option<u32>,
+    #[serde(default)]
+    pub supported_generation_methods: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListModelsResponse {
+    #[serde(default)]
+    pub models: Vec<Model>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateContentResponse {
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+    pub prompt_feedback: Option<PromptFeedback>,
+    pub usage_metadata: Option<UsageMetadata>,
+    pub model_version: Option<String>,
+    pub response_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+    pub content: Option<Content>,
+    pub finish_reason: Option<FinishReason>,
+    pub index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FinishReason {
+    Stop,
+    MaxTokens,
+    Safety,
+    Recitation,
+    Language,
+    Blocklist,
+    ProhibitedContent,
+    Spii,
+    MalformedFunctionCall,
+    ImageSafety,
+    UnexpectedToolCall,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptFeedback {
+    pub block_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    pub prompt_token_count: Option<u32>,
+    pub candidates_token_count: Option<u32>,
+    pub thoughts_token_count: Option<u32>,
+    pub cached_content_token_count: Option<u32>,
+    pub total_token_count: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub code: u16,
+    pub message: String,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponseBody {
+    pub error: ErrorResponse,
+}
+
+/// Downloads a file so it can be sent inline, as Gemini only accepts URIs of uploaded files
+pub fn url_to_blob(url: &str) -> Result<Blob, Error> {
+    let response = Client::new()
+        .get(url)
+        .send()
+        .map_err(|err| from_reqwest_error(format!("Failed to download {url}"), err))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(Error {
+            code: ErrorCode::InvalidRequest,
+            message: format!("Failed to download {url}: {status}"),
+            provider_error_json: None,
+            retry_after_ms: None,
+        });
+    }
+
+    let mime_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.to_string())
+        .unwrap_or_else(|| {
+            mime_guess::from_path(url)
+                .first_or_octet_stream()
+                .to_string()
+        });
+    let bytes = response
+        .bytes()
+        .map_err(|err| from_reqwest_error(format!("Failed to download {url}"), err))?;
+
+    Ok(Blob {
+        mime_type,
+        data: general_purpose::STANDARD.encode(&bytes),
+    })
+}
+
+fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T, Error> {
+    let status = response.status();
+    if status.is_success() {
+        let raw_body = response
+            .text()
+            .map_err(|err| from_reqwest_error("Failed to receive response body", err))?;
+
+        let body = serde_json::from_str::<T>(&raw_body).map_err(|err| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to decode response body: {err}"),
+            provider_error_json: Some(raw_body),
+            retry_after_ms: None,
+        })?;
+
+        trace!("Received response from Gemini API: {body:?}");
+
+        Ok(body)
+    } else {
+        let retry_after_ms = retry_after_ms(response.headers());
+        let raw_error_body = response
+            .text()
+            .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
+
+        trace!("Received {status} response from Gemini API: {raw_error_body:?}");
+
+        let message = serde_json::from_str::<ErrorResponseBody>(&raw_error_body)
+            .map(|error_body| error_body.error.message)
+            .unwrap_or_else(|_| format!("Request failed with {status}"));
+
+        Err(Error {
+            code: error_code_from_response(status, &raw_error_body),
+            message,
+            provider_error_json: Some(raw_error_body),
+            retry_after_ms,
+        })
+    }
+}
@@ -0,0 +1,515 @@
+use crate::client::{
+    url_to_blob, Blob, Content, FunctionCall, FunctionCallingConfig, FunctionCallingMode,
+    FunctionDeclaration, FunctionResponse, GenerateContentRequest, GenerateContentResponse,
+    GenerationConfig, Model, Part, ThinkingConfig, Tool, ToolConfig, UsageMetadata,
+};
+use base64::{engine::general_purpose, Engine as _};
+use golem_llm::error::unsupported;
+use golem_llm::golem::llm::llm::{
+    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageReference, ImageSource,
+    MediaSource, Message, ModelCapabilities, ModelInfo, ReasoningContent, ReasoningEffort,
+    Response, ResponseFormat, ResponseMetadata, Role, ToolCall, ToolChoice, ToolDefinition,
+    ToolResult, Usage,
+};
+use std::collections::HashMap;
+
+pub fn events_to_request(
+    events: Vec<Event>,
+    config: Config,
+) -> Result<GenerateContentRequest, Error> {
+    let options = config
+        .provider_options
+        .map(|options| {
+            options
+                .into_iter()
+                .map(|kv| (kv.key, kv.value))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut system_parts = Vec::new();
+    let mut contents = Vec::new();
+    for event in events {
+        match event {
+            Event::Message(message) => match message.role {
+                Role::System => system_parts.extend(message_to_parts(message)?),
+                Role::Assistant => contents.push(Content {
+                    role: Some(crate::client::Role::Model),
+                    parts: message_to_parts(message)?,
+                }),
+                Role::User | Role::Tool => contents.push(Content {
+                    role: Some(crate::client::Role::User),
+                    parts: message_to_parts(message)?,
+                }),
+            },
+            Event::Response(response) => contents.push(response_to_content(response)?),
+            Event::ToolResults(tool_results) => contents.push(Content {
+                role: Some(crate::client::Role::User),
+                parts: tool_results.into_iter().map(tool_result_to_part).collect(),
+            }),
+        }
+    }
+
+    let mut function_declarations = Vec::new();
+    for tool in config.tools.unwrap_or_default() {
+        function_declarations.push(tool_definition_to_function_declaration(tool)?);
+    }
+    let tools = if function_declarations.is_empty() {
+        Vec::new()
+    } else {
+        vec![Tool {
+            function_declarations,
+        }]
+    };
+
+    let (response_mime_type, response_json_schema) = match config.response_format {
+        Some(response_format) => convert_response_format(response_format)?,
+        None => (None, None),
+    };
+
+    let generation_config = GenerationConfig {
+        stop_sequences: config.stop_sequences,
+        response_mime_type,
+        response_json_schema,
+        max_output_tokens: config.max_tokens,
+        temperature: config.temperature,
+        top_p: options
+            .get("top_p")
+            .and_then(|top_p_s| top_p_s.parse::<f32>().ok()),
+        top_k: options
+            .get("top_k")
+            .and_then(|top_k_s| top_k_s.parse::<u32>().ok()),
+        seed: options
+            .get("seed")
+            .and_then(|seed_s| seed_s.parse::<i32>().ok()),
+        presence_penalty: options
+            .get("presence_penalty")
+            .and_then(|pp_s| pp_s.parse::<f32>().ok()),
+        frequency_penalty: options
+            .get("frequency_penalty")
+            .and_then(|fp_s| fp_s.parse::<f32>().ok()),
+        thinking_config: config.reasoning.map(|reasoning| ThinkingConfig {
+            include_thoughts: Some(true),
+            thinking_budget: reasoning
+                .budget_tokens
+                .or(reasoning.effort.map(effort_to_thinking_budget)),
+        }),
+    };
+
+    Ok(GenerateContentRequest {
+        contents,
+        system_instruction: (!system_parts.is_empty()).then_some(Content {
+            role: None,
+            parts: system_parts,
+        }),
+        tools,
+        tool_config: config.tool_choice.map(convert_tool_choice),
+        generation_config: Some(generation_config),
+    })
+}
+
+pub fn process_response(response: GenerateContentResponse) -> Result<Response, Error> {
+    let usage = response.usage_metadata.as_ref().map(convert_usage);
+    let id = response.response_id.clone().unwrap_or_default();
+
+    match response.candidates.into_iter().next() {
+        Some(candidate) => {
+            let parts = candidate
+                .content
+                .map(|content| content.parts)
+                .unwrap_or_default();
+            let (content, tool_calls) = parts_to_content(parts, 0);
+
+            let finish_reason = candidate
+                .finish_reason
+                .as_ref()
+                .map(|finish_reason| convert_finish_reason(finish_reason, !tool_calls.is_empty()));
+
+            Ok(Response {
+                id,
+                content,
+                tool_calls,
+                metadata: ResponseMetadata {
+                    finish_reason,
+                    usage,
+                    provider_id: None,
+                    timestamp: None,
+                    provider_metadata_json: None,
+                    cost: None,
+                },
+                citations: Vec::new(),
+            })
+        }
+        None => match response
+            .prompt_feedback
+            .and_then(|feedback| feedback.block_reason)
+        {
+            Some(block_reason) => Ok(Response {
+                id,
+                content: vec![],
+                tool_calls: vec![],
+                metadata: ResponseMetadata {
+                    finish_reason: Some(FinishReason::ContentFilter),
+                    usage,
+                    provider_id: None,
+                    timestamp: None,
+                    provider_metadata_json: Some(format!(r#"{{"blockReason":"{block_reason}"}}"#)),
+                    cost: None,
+                },
+                citations: Vec::new(),
+            }),
+            None => Err(Error {
+                code: ErrorCode::InternalError,
+                message: "No candidates in response".to_string(),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }),
+        },
+    }
+}
+
+/// Converts the parts of a candidate to content parts and tool calls. Tool calls without an id
+/// get one generated from their name and position, starting at `tool_call_offset`.
+pub fn parts_to_content(
+    parts: Vec<Part>,
+    tool_call_offset: usize,
+) -> (Vec<ContentPart>, Vec<ToolCall>) {
+    let mut content = Vec::new();
+    let mut tool_calls = Vec::new();
+
+    for part in parts {
+        if part.thought == Some(true) {
+            let text = part.text.unwrap_or_default();
+            if !text.is_empty() || part.thought_signature.is_some() {
+                content.push(ContentPart::Reasoning(ReasoningContent {
+                    text,
+                    signature: part.thought_signature,
+                    redacted_data: None,
+                }));
+            }
+            continue;
+        }
+
+        if let Some(signature) = part.thought_signature {
+            // Signature attached to a regular part, kept as an empty reasoning part so it can be
+            // sent back with the same part in the next turn
+            content.push(ContentPart::Reasoning(ReasoningContent {
+                text: String::new(),
+                signature: Some(signature),
+                redacted_data: None,
+            }));
+        }
+
+        if let Some(text) = part.text.filter(|text| !text.is_empty()) {
+            content.push(ContentPart::Text(text));
+        }
+        if let Some(inline_data) = part.inline_data {
+            if let Ok(data) = general_purpose::STANDARD.decode(&inline_data.data) {
+                content.push(ContentPart::Image(ImageReference::Inline(ImageSource {
+                    data,
+                    mime_type: inline_data.mime_type,
+                    detail: None,
+                })));
+            }
+        }
+        if let Some(function_call) = part.function_call {
+            let position = tool_call_offset + tool_calls.len();
+            tool_calls.push(function_call_to_tool_call(function_call, position));
+        }
+    }
+
+    (content, tool_calls)
+}
+
+fn function_call_to_tool_call(function_call: FunctionCall, position: usize) -> ToolCall {
+    let arguments_json = if function_call.args.is_null() {
+        "{}".to_string()
+    } else {
+        function_call.args.to_string()
+    };
+    ToolCall {
+        id: function_call
+            .id
+            .unwrap_or_else(|| format!("{}-{position}", function_call.name)),
+        name: function_call.name,
+        arguments_json,
+    }
+}
+
+fn message_to_parts(message: Message) -> Result<Vec<Part>, Error> {
+    let mut parts = Vec::new();
+    for content_part in message.content {
+        match content_part {
+            ContentPart::Text(text) => parts.push(Part {
+                text: Some(text),
+                ..Default::default()
+            }),
+            ContentPart::Image(image_reference) => parts.push(Part {
+                inline_data: Some(image_reference_to_blob(image_reference)?),
+                ..Default::default()
+            }),
+            // Reasoning is only sent back as part of the model's responses
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(document) => parts.push(Part {
+                inline_data: Some(media_source_to_blob(document.source, document.mime_type)?),
+                ..Default::default()
+            }),
+            ContentPart::Audio(audio) => parts.push(Part {
+                inline_data: Some(media_source_to_blob(audio.source, audio.mime_type)?),
+                ..Default::default()
+            }),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
+        }
+    }
+    Ok(parts)
+}
+
+fn response_to_content(response: Response) -> Result<Content, Error> {
+    let mut parts = Vec::new();
+    let mut pending_signature = None;
+
+    for content_part in response.content {
+        match content_part {
+            ContentPart::Text(text) => parts.push(Part {
+                text: Some(text),
+                thought_signature: pending_signature.take(),
+                ..Default::default()
+            }),
+            ContentPart::Image(image_reference) => parts.push(Part {
+                inline_data: Some(image_reference_to_blob(image_reference)?),
+                ..Default::default()
+            }),
+            ContentPart::Reasoning(reasoning) => {
+                if reasoning.text.is_empty() {
+                    pending_signature = reasoning.signature;
+                } else {
+                    parts.push(Part {
+                        text: Some(reasoning.text),
+                        thought: Some(true),
+                        thought_signature: reasoning.signature,
+                        ..Default::default()
+                    });
+                }
+            }
+            ContentPart::Document(document) => parts.push(Part {
+                inline_data: Some(media_source_to_blob(document.source, document.mime_type)?),
+                ..Default::default()
+            }),
+            ContentPart::Audio(audio) => parts.push(Part {
+                inline_data: Some(media_source_to_blob(audio.source, audio.mime_type)?),
+                ..Default::default()
+            }),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
+        }
+    }
+
+    for tool_call in response.tool_calls {
+        parts.push(Part {
+            function_call: Some(FunctionCall {
+                id: Some(tool_call.id),
+                name: tool_call.name,
+                args: serde_json::from_str(&tool_call.arguments_json)
+                    .unwrap_or(serde_json::Value::Object(Default::default())),
+            }),
+            thought_signature: pending_signature.take(),
+            ..Default::default()
+        });
+    }
+
+    Ok(Content {
+        role: Some(crate::client::Role::Model),
+        parts,
+    })
+}
+
+fn tool_result_to_part(tool_result: ToolResult) -> Part {
+    let function_response = match tool_result {
+        ToolResult::Success(success) => {
+            let result = serde_json::from_str(&success.result_json)
+                .unwrap_or(serde_json::Value::String(success.result_json));
+            FunctionResponse {
+                id: Some(success.id),
+                name: success.name,
+                // The response has to be a JSON object
+                response: if result.is_object() {
+                    result
+                } else {
+                    serde_json::json!({ "result": result })
+                },
+            }
+        }
+        ToolResult::Error(error) => FunctionResponse {
+            id: Some(error.id),
+            name: error.name,
+            response: serde_json::json!({
+                "error": {
+                    "code": error.error_code,
+                    "message": error.error_message,
+                }
+            }),
+        },
+    };
+
+    Part {
+        function_response: Some(function_response),
+        ..Default::default()
+    }
+}
+
+fn image_reference_to_blob(image_reference: ImageReference) -> Result<Blob, Error> {
+    match image_reference {
+        ImageReference::Url(image_url) => url_to_blob(&image_url.url),
+        ImageReference::Inline(image_source) => Ok(Blob {
+            mime_type: image_source.mime_type,
+            data: general_purpose::STANDARD.encode(&image_source.data),
+        }),
+    }
+}
+
+fn media_source_to_blob(source: MediaSource, mime_type: String) -> Result<Blob, Error> {
+    match source {
+        MediaSource::Url(url) => Ok(Blob {
+            mime_type,
+            ..url_to_blob(&url)?
+        }),
+        MediaSource::Inline(data) => Ok(Blob {
+            mime_type,
+            data: general_purpose::STANDARD.encode(&data),
+        }),
+    }
+}
+
+fn tool_definition_to_function_declaration(
+    tool: ToolDefinition,
+) -> Result<FunctionDeclaration, Error> {
+    if tool.builtin.is_some() {
+        return Err(unsupported("builtin tools"));
+    }
+    match serde_json::from_str(&tool.parameters_schema) {
+        Ok(value) => Ok(FunctionDeclaration {
+            name: tool.name,
+            description: tool.description,
+            parameters_json_schema: value,
+        }),
+        Err(error) => Err(Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to parse tool parameters for {}: {error}", tool.name),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+    }
+}
+
+/// Gemini has no option to limit the number of function calls per response, so the parallel tool
+/// calls flag is ignored
+fn convert_tool_choice(tool_choice: ToolChoice) -> ToolConfig {
+    let (mode, allowed_function_names) = match tool_choice {
+        ToolChoice::Auto => (FunctionCallingMode::Auto, None),
+        ToolChoice::None => (FunctionCallingMode::None, None),
+        ToolChoice::Required => (FunctionCallingMode::Any, None),
+        ToolChoice::Specific(name) => (FunctionCallingMode::Any, Some(vec![name])),
+    };
+    ToolConfig {
+        function_calling_config: FunctionCallingConfig {
+            mode,
+            allowed_function_names,
+        },
+    }
+}
+
+fn convert_response_format(
+    response_format: ResponseFormat,
+) -> Result<(Option<String>, Option<serde_json::Value>), Error> {
+    match response_format {
+        ResponseFormat::Text => Ok((Some("text/plain".to_string()), None)),
+        ResponseFormat::JsonObject => Ok((Some("application/json".to_string()), None)),
+        ResponseFormat::JsonSchema(json_schema) => {
+            match serde_json::from_str(&json_schema.schema) {
+                Ok(schema) => Ok((Some("application/json".to_string()), Some(schema))),
+                Err(error) => Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Failed to parse response format schema {}: {error}",
+                        json_schema.name
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                }),
+            }
+        }
+    }
+}
+
+fn effort_to_thinking_budget(effort: ReasoningEffort) -> u32 {
+    match effort {
+        ReasoningEffort::Low => 1024,
+        ReasoningEffort::Medium => 8192,
+        ReasoningEffort::High => 24576,
+    }
+}
+
+pub fn convert_finish_reason(
+    value: &crate::client::FinishReason,
+    has_tool_calls: bool,
+) -> FinishReason {
+    match value {
+        // Gemini reports function calls as a regular stop
+        crate::client::FinishReason::Stop if has_tool_calls => FinishReason::ToolCalls,
+        crate::client::FinishReason::Stop => FinishReason::Stop,
+        crate::client::FinishReason::MaxTokens => FinishReason::Length,
+        crate::client::FinishReason::Safety
+        | crate::client::FinishReason::Recitation
+        | crate::client::FinishReason::Blocklist
+        | crate::client::FinishReason::ProhibitedContent
+        | crate::client::FinishReason::Spii
+        | crate::client::FinishReason::ImageSafety => FinishReason::ContentFilter,
+        crate::client::FinishReason::MalformedFunctionCall
+        | crate::client::FinishReason::UnexpectedToolCall => FinishReason::Error,
+        crate::client::FinishReason::Language | crate::client::FinishReason::Other => {
+            FinishReason::Other
+        }
+    }
+}
+
+pub fn convert_usage(value: &UsageMetadata) -> Usage {
+    let output_tokens = match (value.candidates_token_count, value.thoughts_token_count) {
+        (Some(candidates), Some(thoughts)) => Some(candidates + thoughts),
+        (candidates, None) => candidates,
+        (None, thoughts) => thoughts,
+    };
+    Usage {
+        input_tokens: value.prompt_token_count,
+        output_tokens,
+        total_tokens: value.total_token_count,
+        reasoning_tokens: value.thoughts_token_count,
+        cache_read_tokens: value.cached_content_token_count,
+        cache_write_tokens: None,
+    }
+}
+
+/// Whether the model can be used for chat, as the models endpoint also lists embedding models
+pub fn supports_generate_content(model: &Model) -> bool {
+    model
+        .supported_generation_methods
+        .iter()
+        .any(|method| method == "generateContent")
+}
+
+pub fn model_to_model_info(model: Model) -> ModelInfo {
+    let capabilities = supports_generate_content(&model).then(|| ModelCapabilities {
+        tools: true,
+        vision: true,
+        streaming: true,
+        reasoning: model.thinking.unwrap_or(false),
+        json_schema: true,
+    });
+
+    ModelInfo {
+        id: model.name.trim_start_matches("models/").to_string(),
+        context_window: model.input_token_limit,
+        max_output_tokens: model.output_token_limit,
+        capabilities,
+    }
+}
@@ -0,0 +1,311 @@
+mod client;
+mod conversions;
+mod models;
+
+use crate::client::{GenerateContentApi, GenerateContentRequest, GenerateContentResponse};
+use crate::conversions::{
+    convert_finish_reason, convert_usage, events_to_request, model_to_model_info, parts_to_content,
+    process_response, supports_generate_content,
+};
+use crate::models::PRICING;
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
+use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
+use golem_llm::config::{get_config_key, with_config_key};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::unsupported;
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{
+    ChatStream, Config, Error, ErrorCode, Event, FinishReason, Guest, ModelInfo, Response,
+    ResponseMetadata, StreamDelta, StreamEvent, ToolCallDelta,
+};
+use golem_llm::pricing::add_cost;
+use golem_rust::wasm_rpc::Pollable;
+use log::trace;
+use std::cell::{Ref, RefCell, RefMut};
+
+pub struct GeminiChatStream {
+    stream: RefCell<Option<EventSource>>,
+    failure: Option<Error>,
+    finished: RefCell<bool>,
+    tool_call_count: RefCell<usize>,
+    pending_finish: RefCell<Option<ResponseMetadata>>,
+}
+
+impl GeminiChatStream {
+    pub fn new(stream: EventSource) -> LlmChatStream<Self> {
+        LlmChatStream::new(GeminiChatStream {
+            stream: RefCell::new(Some(stream)),
+            failure: None,
+            finished: RefCell::new(false),
+            tool_call_count: RefCell::new(0),
+            pending_finish: RefCell::new(None),
+        })
+    }
+
+    pub fn failed(error: Error) -> LlmChatStream<Self> {
+        LlmChatStream::new(GeminiChatStream {
+            stream: RefCell::new(None),
+            failure: Some(error),
+            finished: RefCell::new(false),
+            tool_call_count: RefCell::new(0),
+            pending_finish: RefCell::new(None),
+        })
+    }
+}
+
+impl LlmChatStreamState for GeminiChatStream {
+    fn failure(&self) -> &Option<Error> {
+        &self.failure
+    }
+
+    fn is_finished(&self) -> bool {
+        *self.finished.borrow()
+    }
+
+    fn set_finished(&self) {
+        *self.finished.borrow_mut() = true;
+    }
+
+    fn stream(&self) -> Ref<'_, Option<EventSource>> {
+        self.stream.borrow()
+    }
+
+    fn stream_mut(&self) -> RefMut<'_, Option<EventSource>> {
+        self.stream.borrow_mut()
+    }
+
+    fn decode_message(&self, raw: &str) -> Result<Option<StreamEvent>, Error> {
+        trace!("Received raw stream event: {raw}");
+        let chunk: GenerateContentResponse = serde_json::from_str(raw).map_err(|err| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to deserialize stream event: {err}"),
+            provider_error_json: Some(raw.to_string()),
+            retry_after_ms: None,
+        })?;
+
+        let usage = chunk.usage_metadata.as_ref().map(convert_usage);
+
+        match chunk.candidates.into_iter().next() {
+            Some(candidate) => {
+                let parts = candidate
+                    .content
+                    .map(|content| content.parts)
+                    .unwrap_or_default();
+                let mut tool_call_count = self.tool_call_count.borrow_mut();
+                let (content, tool_calls) = parts_to_content(parts, *tool_call_count);
+                // Function calls always arrive complete, each is sent as a single fragment
+                let tool_call_deltas = tool_calls
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, tool_call)| ToolCallDelta {
+                        index: (*tool_call_count + i) as u32,
+                        id: Some(tool_call.id),
+                        name: Some(tool_call.name),
+                        arguments_fragment: tool_call.arguments_json,
+                    })
+                    .collect::<Vec<_>>();
+                *tool_call_count += tool_call_deltas.len();
+
+                if let Some(finish_reason) = &candidate.finish_reason {
+                    // Each chunk is a complete response, the last one carries both the last
+                    // piece of content and the finish metadata
+                    *self.pending_finish.borrow_mut() = Some(ResponseMetadata {
+                        finish_reason: Some(convert_finish_reason(
+                            finish_reason,
+                            *tool_call_count > 0,
+                        )),
+                        usage,
+                        provider_id: None,
+                        timestamp: None,
+                        provider_metadata_json: None,
+                        cost: None,
+                    });
+                }
+
+                if content.is_empty() && tool_call_deltas.is_empty() {
+                    Ok(None)
+                } else {
+                    Ok(Some(StreamEvent::Delta(StreamDelta {
+                        content: (!content.is_empty()).then_some(content),
+                        tool_calls: None,
+                        tool_call_deltas: (!tool_call_deltas.is_empty())
+                            .then_some(tool_call_deltas),
+                        citations: None,
+                    })))
+                }
+            }
+            None => {
+                if chunk
+                    .prompt_feedback
+                    .and_then(|feedback| feedback.block_reason)
+                    .is_some()
+                {
+                    *self.pending_finish.borrow_mut() = Some(ResponseMetadata {
+                        finish_reason: Some(FinishReason::ContentFilter),
+                        usage,
+                        provider_id: None,
+                        timestamp: None,
+                        provider_metadata_json: None,
+                        cost: None,
+                    });
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn decode_messages(&self, raw: &str) -> Result<Vec<StreamEvent>, Error> {
+        let mut stream_events = self.decode_message(raw)?.into_iter().collect::<Vec<_>>();
+        if let Some(metadata) = self.pending_finish.borrow_mut().take() {
+            stream_events.push(StreamEvent::Finish(metadata));
+        }
+        Ok(stream_events)
+    }
+}
+
+pub struct GeminiComponent;
+
+impl GeminiComponent {
+    const ENV_VAR_NAME: &'static str = "GEMINI_API_KEY";
+
+    fn request(
+        client: GenerateContentApi,
+        model: &str,
+        request: GenerateContentRequest,
+    ) -> Result<Response, Error> {
+        let response = client.generate_content(model, request)?;
+        let mut response = process_response(response)?;
+        add_cost(
+            &mut response.metadata,
+            model.trim_start_matches("models/"),
+            &PRICING,
+        );
+        Ok(response)
+    }
+
+    fn streaming_request(
+        client: GenerateContentApi,
+        model: &str,
+        request: GenerateContentRequest,
+    ) -> LlmChatStream<GeminiChatStream> {
+        match client.stream_generate_content(model, request) {
+            Ok(stream) => GeminiChatStream::new(stream)
+                .with_pricing(model.trim_start_matches("models/"), &PRICING),
+            Err(err) => GeminiChatStream::failed(err),
+        }
+    }
+}
+
+impl Guest for GeminiComponent {
+    type ChatStream = LlmChatStream<GeminiChatStream>;
+    type ChatSession = ChatSession<Self>;
+
+    fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+        let gemini_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = GenerateContentApi::new(gemini_api_key);
+        let model = config.model.clone();
+        let request = events_to_request(events, config)?;
+        Self::request(client, &model, request)
+    }
+
+    fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+        ChatStream::new(Self::unwrapped_stream(events, config))
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        let gemini_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = GenerateContentApi::new(gemini_api_key);
+        let model = config.model.clone();
+        let request = events_to_request(events, config)?;
+        let response = client.count_tokens(&model, request)?;
+        Ok(response.total_tokens)
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        let gemini_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = GenerateContentApi::new(gemini_api_key);
+        let response = client.get_model(&model)?;
+        Ok(ModelInfo {
+            id: model,
+            ..model_to_model_info(response)
+        })
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let gemini_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = GenerateContentApi::new(gemini_api_key);
+
+        let mut models = Vec::new();
+        let mut page_token = None;
+        loop {
+            let response = client.list_models(page_token.as_deref())?;
+            models.extend(
+                response
+                    .models
+                    .into_iter()
+                    .filter(supports_generate_content)
+                    .map(model_to_model_info),
+            );
+            match response.next_page_token {
+                Some(next_page_token) if !next_page_token.is_empty() => {
+                    page_token = Some(next_page_token)
+                }
+                _ => break,
+            }
+        }
+        Ok(models)
+    }
+}
+
+impl BatchGuest for GeminiComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+}
+
+impl ExtendedGuest for GeminiComponent {
+    const PROVIDER_NAME: &'static str = "gemini";
+
+    fn unwrapped_stream(events: Vec<Event>, config: Config) -> LlmChatStream<GeminiChatStream> {
+        with_config_key(
+            Self::ENV_VAR_NAME,
+            GeminiChatStream::failed,
+            |gemini_api_key| {
+                let client = GenerateContentApi::new(gemini_api_key);
+                let model = config.model.clone();
+
+                match events_to_request(events, config) {
+                    Ok(request) => Self::streaming_request(client, &model, request),
+                    Err(err) => GeminiChatStream::failed(err),
+                }
+            },
+        )
+    }
+
+    fn subscribe(stream: &Self::ChatStream) -> Pollable {
+        stream.subscribe()
+    }
+}
+
+#[cfg(not(feature = "library"))]
+type DurableGeminiComponent = DurableLLM<CachedLLM<GeminiComponent>>;
+
+#[cfg(not(feature = "library"))]
+golem_llm::export_llm!(DurableGeminiComponent with_types_in golem_llm);
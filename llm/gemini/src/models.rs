@@ -0,0 +1,79 @@
+use golem_llm::pricing::{KnownPrice, Price, Pricing};
+
+/// Prices of the Gemini models for prompts up to 200k tokens, the input tokens reported by the API
+/// include the cached ones
+pub const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[
+        KnownPrice {
+            id: "gemini-2.5-pro",
+            price: Price {
+                input: 1.25,
+                output: 10.0,
+                cache_read: Some(0.31),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gemini-2.5-flash",
+            price: Price {
+                input: 0.3,
+                output: 2.5,
+                cache_read: Some(0.075),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gemini-2.5-flash-lite",
+            price: Price {
+                input: 0.1,
+                output: 0.4,
+                cache_read: Some(0.025),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gemini-2.0-flash",
+            price: Price {
+                input: 0.1,
+                output: 0.4,
+                cache_read: Some(0.025),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gemini-2.0-flash-lite",
+            price: Price {
+                input: 0.075,
+                output: 0.3,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gemini-1.5-pro",
+            price: Price {
+                input: 1.25,
+                output: 5.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "gemini-1.5-flash",
+            price: Price {
+                input: 0.075,
+                output: 0.3,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+    ],
+};
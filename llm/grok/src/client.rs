@@ -1,6 +1,9 @@
-use golem_llm::error::{error_code_from_status, from_event_source_error, from_reqwest_error};
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
 use golem_llm::event_source::EventSource;
 use golem_llm::golem::llm::llm::Error;
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
 use log::trace;
 use reqwest::header::HeaderValue;
 use reqwest::{Client, Method, Response};
@@ -28,13 +31,13 @@ impl CompletionsApi {
     pub fn send_messages(&self, request: CompletionsRequest) -> Result<CompletionsResponse, Error> {
         trace!("Sending request to xAI API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/v1/chat/completions"))
-            .bearer_auth(self.api_key.clone())
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/chat/completions"))
+                .bearer_auth(self.api_key.clone())
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         parse_response(response)
     }
@@ -42,23 +45,48 @@ impl CompletionsApi {
     pub fn stream_send_messages(&self, request: CompletionsRequest) -> Result<EventSource, Error> {
         trace!("Sending request to xAI API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/v1/chat/completions"))
-            .bearer_auth(self.api_key.clone())
-            .header(
-                reqwest::header::ACCEPT,
-                HeaderValue::from_static("text/event-stream"),
-            )
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/chat/completions"))
+                .bearer_auth(self.api_key.clone())
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         trace!("Initializing SSE stream");
 
         EventSource::new(response)
             .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
     }
+
+    pub fn list_language_models(&self) -> Result<LanguageModelsResponse, Error> {
+        trace!("Listing language models of xAI API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, format!("{BASE_URL}/v1/language-models"))
+                .bearer_auth(self.api_key.clone())
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageModelsResponse {
+    pub models: Vec<LanguageModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageModel {
+    pub id: String,
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,10 +100,16 @@ pub struct CompletionsRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub n: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<Effort>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_parameters: Option<SearchParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub seed: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
@@ -86,7 +120,7 @@ pub struct CompletionsRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_choice: Option<String>,
+    pub tool_choice: Option<ToolChoice>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tools: Vec<Tool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -97,6 +131,55 @@ pub struct CompletionsRequest {
     pub user: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchParameters {
+    pub mode: SearchMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_search_results: Option<u32>,
+    pub return_citations: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Vec<SearchSource>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SearchMode {
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "on")]
+    On,
+    #[serde(rename = "off")]
+    Off,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SearchSource {
+    #[serde(rename = "web")]
+    Web {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        allowed_websites: Option<Vec<String>>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseFormat {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "json_object")]
+    JsonObject,
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchema },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamOptions {
     pub include_usage: bool,
@@ -118,6 +201,25 @@ pub struct Function {
     pub parameters: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    String(String), // none, auto or required
+    Function(ToolChoiceFunction),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolChoiceFunction {
+    #[serde(rename = "function")]
+    Function { function: FunctionName },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionName {
+    pub name: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "role")]
 pub enum Message {
@@ -232,6 +334,7 @@ pub struct CompletionsResponse {
     pub model: String,
     pub system_fingerprint: Option<String>,
     pub usage: Option<Usage>,
+    pub citations: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -294,6 +397,7 @@ pub struct ChatCompletionChunk {
     pub choices: Vec<ChoiceChunk>,
     pub usage: Option<Usage>,
     pub system_fingerprint: String,
+    pub citations: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -306,6 +410,7 @@ pub struct ChoiceChunk {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChoiceDelta {
     pub content: Option<String>,
+    pub reasoning_content: Option<String>,
     pub tool_calls: Option<Vec<ToolCall>>,
     pub role: String,
 }
@@ -321,6 +426,7 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
 
         Ok(body)
     } else {
+        let retry_after_ms = retry_after_ms(response.headers());
         let error_body = response
             .text()
             .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
@@ -328,9 +434,10 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
         trace!("Received {status} response from xAI API: {error_body:?}");
 
         Err(Error {
-            code: error_code_from_status(status),
+            code: error_code_from_response(status, &error_body),
             message: format!("Request failed with {status}"),
             provider_error_json: Some(serde_json::to_string(&error_body).unwrap()),
+            retry_after_ms,
         })
     }
 }
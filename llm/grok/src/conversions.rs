@@ -1,8 +1,14 @@
-use crate::client::{CompletionsRequest, CompletionsResponse, Detail, Effort};
+use crate::client::{
+    CompletionsRequest, CompletionsResponse, Detail, Effort, FunctionName, SearchMode,
+    SearchParameters, SearchSource, ToolChoiceFunction,
+};
 use base64::{engine::general_purpose, Engine as _};
+use golem_llm::error::unsupported;
 use golem_llm::golem::llm::llm::{
-    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageDetail, ImageReference,
-    Response, ResponseMetadata, Role, ToolCall, ToolDefinition, ToolResult, Usage,
+    BuiltinTool, BuiltinToolCall, BuiltinToolKind, CitationSource, Config, ContentPart, Error,
+    ErrorCode, Event, FinishReason, ImageDetail, ImageReference, ReasoningContent, ReasoningEffort,
+    Response, ResponseFormat, ResponseMetadata, Role, SearchResult, ToolCall, ToolCallDelta,
+    ToolChoice, ToolDefinition, ToolResult, Usage,
 };
 use std::collections::HashMap;
 
@@ -23,20 +29,20 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
             Event::Message(message) => match message.role {
                 Role::User => completion_messages.push(crate::client::Message::User {
                     name: message.name,
-                    content: convert_content_parts_to_client_content(message.content),
+                    content: convert_content_parts_to_client_content(message.content)?,
                 }),
                 Role::Assistant => completion_messages.push(crate::client::Message::Assistant {
                     name: message.name,
-                    content: Some(convert_content_parts_to_client_content(message.content)),
+                    content: Some(convert_content_parts_to_client_content(message.content)?),
                     tool_calls: None,
                 }),
                 Role::System => completion_messages.push(crate::client::Message::System {
                     name: message.name,
-                    content: convert_content_parts_to_client_content(message.content),
+                    content: convert_content_parts_to_client_content(message.content)?,
                 }),
                 Role::Tool => completion_messages.push(crate::client::Message::Tool {
                     name: message.name,
-                    content: convert_content_parts_to_client_content(message.content),
+                    content: convert_content_parts_to_client_content(message.content)?,
                     tool_call_id: None,
                 }),
             },
@@ -49,7 +55,8 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
                 if !response.content.is_empty() || !response.tool_calls.is_empty() {
                     completion_messages.push(crate::client::Message::Assistant {
                         content: (!response.content.is_empty())
-                            .then(|| convert_content_parts_to_client_content(response.content)),
+                            .then(|| convert_content_parts_to_client_content(response.content))
+                            .transpose()?,
                         name: None,
                         tool_calls: (!response.tool_calls.is_empty()).then(|| {
                             response
@@ -65,10 +72,37 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
     }
 
     let mut tools = Vec::new();
+    let mut search_parameters = None;
     for tool in config.tools.unwrap_or_default() {
-        tools.push(tool_definition_to_tool(tool)?)
+        match tool.builtin {
+            // Web search is enabled by the live search parameters instead of a tool
+            Some(BuiltinTool::WebSearch(options)) => {
+                search_parameters = Some(SearchParameters {
+                    mode: SearchMode::Auto,
+                    max_search_results: options.max_uses,
+                    return_citations: true,
+                    sources: options.allowed_domains.map(|allowed_websites| {
+                        vec![SearchSource::Web {
+                            allowed_websites: Some(allowed_websites),
+                        }]
+                    }),
+                })
+            }
+            Some(BuiltinTool::CodeInterpreter) => {
+                return Err(unsupported("code interpreter builtin tool"))
+            }
+            Some(BuiltinTool::FileSearch(_)) => {
+                return Err(unsupported("file search builtin tool"))
+            }
+            None => tools.push(tool_definition_to_tool(tool)?),
+        }
     }
 
+    let response_format = config
+        .response_format
+        .map(convert_response_format)
+        .transpose()?;
+
     Ok(CompletionsRequest {
         messages: completion_messages,
         model: config.model,
@@ -77,12 +111,21 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
             .and_then(|fp_s| fp_s.parse::<f32>().ok()),
         max_completion_tokens: config.max_tokens,
         n: options.get("n").and_then(|n_s| n_s.parse::<u32>().ok()),
+        parallel_tool_calls: config.parallel_tool_calls,
         presence_penalty: options
             .get("presence_penalty")
             .and_then(|pp_s| pp_s.parse::<f32>().ok()),
-        reasoning_effort: options
-            .get("reasoning_effort")
-            .and_then(|effort_s| effort_s.parse::<Effort>().ok()),
+        reasoning_effort: config
+            .reasoning
+            .and_then(|reasoning| reasoning.effort)
+            .map(convert_reasoning_effort)
+            .or_else(|| {
+                options
+                    .get("reasoning_effort")
+                    .and_then(|effort_s| effort_s.parse::<Effort>().ok())
+            }),
+        response_format,
+        search_parameters,
         seed: options
             .get("seed")
             .and_then(|seed_s| seed_s.parse::<u32>().ok()),
@@ -90,7 +133,7 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
         stream: Some(false),
         stream_options: None,
         temperature: config.temperature,
-        tool_choice: config.tool_choice,
+        tool_choice: config.tool_choice.map(convert_tool_choice),
         tools,
         top_logprobs: options
             .get("top_logprobs")
@@ -106,11 +149,18 @@ pub fn process_response(mut response: CompletionsResponse) -> Result<Response, E
     let choice = response.choices.pop();
     match choice {
         Some(choice) => {
-            let content = choice
-                .message
-                .content
+            let content = response
+                .citations
+                .map(|citations| live_search_to_content_part(&response.id, citations))
                 .into_iter()
-                .map(ContentPart::Text)
+                .chain(
+                    choice
+                        .message
+                        .reasoning_content
+                        .into_iter()
+                        .map(reasoning_to_content_part),
+                )
+                .chain(choice.message.content.into_iter().map(ContentPart::Text))
                 .collect();
 
             let tool_calls = choice
@@ -130,6 +180,7 @@ pub fn process_response(mut response: CompletionsResponse) -> Result<Response, E
                 provider_id: None,
                 timestamp: Some(response.created.to_string()),
                 provider_metadata_json: None,
+                cost: None,
             };
 
             Ok(Response {
@@ -137,16 +188,37 @@ pub fn process_response(mut response: CompletionsResponse) -> Result<Response, E
                 content,
                 tool_calls,
                 metadata,
+                citations: Vec::new(),
             })
         }
         None => Err(Error {
             code: ErrorCode::InternalError,
             message: "No choices in response".to_string(),
             provider_error_json: None,
+            retry_after_ms: None,
         }),
     }
 }
 
+/// Converts the sources used by live search to a web search call
+pub fn live_search_to_content_part(response_id: &str, citations: Vec<String>) -> ContentPart {
+    ContentPart::BuiltinToolCall(BuiltinToolCall {
+        id: format!("{response_id}-live-search"),
+        tool: BuiltinToolKind::WebSearch,
+        input: None,
+        output: None,
+        results: citations
+            .into_iter()
+            .map(|url| SearchResult {
+                source: CitationSource::Url(url),
+                title: None,
+                text: None,
+            })
+            .collect(),
+        provider_data_json: None,
+    })
+}
+
 pub fn tool_result_to_message(tool_result: ToolResult) -> crate::client::Message {
     match tool_result {
         ToolResult::Success(success) => crate::client::Message::Tool {
@@ -176,6 +248,20 @@ pub fn convert_client_tool_call_to_tool_call(tool_call: crate::client::ToolCall)
     }
 }
 
+pub fn convert_client_tool_call_to_tool_call_delta(
+    tool_call: crate::client::ToolCall,
+    index: u32,
+) -> ToolCallDelta {
+    match tool_call {
+        crate::client::ToolCall::Function { function, id, .. } => ToolCallDelta {
+            index,
+            id: Some(id),
+            name: Some(function.name),
+            arguments_fragment: function.arguments,
+        },
+    }
+}
+
 pub fn convert_tool_call_to_client_tool_call(tool_call: ToolCall) -> crate::client::ToolCall {
     crate::client::ToolCall::Function {
         id: tool_call.id,
@@ -187,7 +273,9 @@ pub fn convert_tool_call_to_client_tool_call(tool_call: ToolCall) -> crate::clie
     }
 }
 
-fn convert_content_parts_to_client_content(contents: Vec<ContentPart>) -> crate::client::Content {
+fn convert_content_parts_to_client_content(
+    contents: Vec<ContentPart>,
+) -> Result<crate::client::Content, Error> {
     let mut result = Vec::new();
     for content in contents {
         match content {
@@ -212,9 +300,44 @@ fn convert_content_parts_to_client_content(contents: Vec<ContentPart>) -> crate:
                     });
                 }
             },
+            // xAI does not accept reasoning content in requests
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // The sources of live search are not sent back
+            ContentPart::BuiltinToolCall(_) => {}
         }
     }
-    crate::client::Content::List(result)
+    Ok(crate::client::Content::List(result))
+}
+
+pub fn reasoning_to_content_part(text: String) -> ContentPart {
+    ContentPart::Reasoning(ReasoningContent {
+        text,
+        signature: None,
+        redacted_data: None,
+    })
+}
+
+fn convert_tool_choice(tool_choice: ToolChoice) -> crate::client::ToolChoice {
+    match tool_choice {
+        ToolChoice::Auto => crate::client::ToolChoice::String("auto".to_string()),
+        ToolChoice::None => crate::client::ToolChoice::String("none".to_string()),
+        ToolChoice::Required => crate::client::ToolChoice::String("required".to_string()),
+        ToolChoice::Specific(name) => {
+            crate::client::ToolChoice::Function(ToolChoiceFunction::Function {
+                function: FunctionName { name },
+            })
+        }
+    }
+}
+
+fn convert_reasoning_effort(effort: ReasoningEffort) -> Effort {
+    // Grok only supports low and high reasoning effort
+    match effort {
+        ReasoningEffort::Low | ReasoningEffort::Medium => Effort::Low,
+        ReasoningEffort::High => Effort::High,
+    }
 }
 
 impl From<ImageDetail> for Detail {
@@ -241,6 +364,9 @@ pub fn convert_usage(value: &crate::client::Usage) -> Usage {
         input_tokens: Some(value.prompt_tokens),
         output_tokens: Some(value.completion_tokens),
         total_tokens: Some(value.total_tokens),
+        reasoning_tokens: Some(value.completion_tokens_details.reasoning_tokens),
+        cache_read_tokens: Some(value.prompt_tokens_details.cached_tokens),
+        cache_write_tokens: None,
     }
 }
 
@@ -257,6 +383,36 @@ fn tool_definition_to_tool(tool: ToolDefinition) -> Result<crate::client::Tool,
             code: ErrorCode::InternalError,
             message: format!("Failed to parse tool parameters for {}: {error}", tool.name),
             provider_error_json: None,
+            retry_after_ms: None,
         }),
     }
 }
+
+fn convert_response_format(
+    response_format: ResponseFormat,
+) -> Result<crate::client::ResponseFormat, Error> {
+    match response_format {
+        ResponseFormat::Text => Ok(crate::client::ResponseFormat::Text),
+        ResponseFormat::JsonObject => Ok(crate::client::ResponseFormat::JsonObject),
+        ResponseFormat::JsonSchema(json_schema) => {
+            match serde_json::from_str(&json_schema.schema) {
+                Ok(schema) => Ok(crate::client::ResponseFormat::JsonSchema {
+                    json_schema: crate::client::JsonSchema {
+                        name: json_schema.name,
+                        schema,
+                        strict: json_schema.strict,
+                    },
+                }),
+                Err(error) => Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Failed to parse response format schema {}: {error}",
+                        json_schema.name
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                }),
+            }
+        }
+    }
+}
@@ -1,28 +1,42 @@
 mod client;
 mod conversions;
+mod models;
 
 use crate::client::{ChatCompletionChunk, CompletionsApi, CompletionsRequest, StreamOptions};
 use crate::conversions::{
-    convert_client_tool_call_to_tool_call, convert_finish_reason, convert_usage, events_to_request,
-    process_response,
+    convert_client_tool_call_to_tool_call_delta, convert_finish_reason, convert_usage,
+    events_to_request, live_search_to_content_part, process_response, reasoning_to_content_part,
 };
+use crate::models::{KNOWN_MODELS, PRICING};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
 use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
 use golem_llm::config::{get_config_key, with_config_key};
-use golem_llm::durability::{DurableLLM, ExtendedGuest};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::unsupported;
 use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
 use golem_llm::golem::llm::llm::{
-    ChatStream, Config, ContentPart, Error, ErrorCode, Event, FinishReason, Guest, Response,
-    ResponseMetadata, StreamDelta, StreamEvent,
+    ChatStream, Config, ContentPart, Error, ErrorCode, Event, FinishReason, Guest, ModelInfo,
+    Response, ResponseMetadata, StreamDelta, StreamEvent,
 };
+use golem_llm::models::known_model_info;
+use golem_llm::pricing::add_cost;
+use golem_llm::tokens::estimate_tokens;
 use golem_rust::wasm_rpc::Pollable;
 use log::trace;
 use std::cell::{Ref, RefCell, RefMut};
 
-struct GrokChatStream {
+pub struct GrokChatStream {
     stream: RefCell<Option<EventSource>>,
     failure: Option<Error>,
     finished: RefCell<bool>,
     finish_reason: RefCell<Option<FinishReason>>,
+    next_tool_call: RefCell<u32>,
+    live_search: RefCell<Option<ContentPart>>,
 }
 
 impl GrokChatStream {
@@ -32,6 +46,8 @@ impl GrokChatStream {
             failure: None,
             finished: RefCell::new(false),
             finish_reason: RefCell::new(None),
+            next_tool_call: RefCell::new(0),
+            live_search: RefCell::new(None),
         })
     }
 
@@ -41,6 +57,8 @@ impl GrokChatStream {
             failure: Some(error),
             finished: RefCell::new(false),
             finish_reason: RefCell::new(None),
+            next_tool_call: RefCell::new(0),
+            live_search: RefCell::new(None),
         })
     }
 }
@@ -72,6 +90,7 @@ impl LlmChatStreamState for GrokChatStream {
                 code: ErrorCode::InternalError,
                 message: message.into(),
                 provider_error_json: None,
+                retry_after_ms: None,
             }
         }
 
@@ -89,22 +108,38 @@ impl LlmChatStreamState for GrokChatStream {
                 let message: ChatCompletionChunk = serde_json::from_value(json).map_err(|err| {
                     decode_internal_error(format!("Failed to parse stream event: {err}"))
                 })?;
+                if let Some(citations) = message.citations {
+                    *self.live_search.borrow_mut() =
+                        Some(live_search_to_content_part(&message.id, citations));
+                }
                 if let Some(choice) = message.choices.into_iter().next() {
                     if let Some(finish_reason) = choice.finish_reason {
                         *self.finish_reason.borrow_mut() =
                             Some(convert_finish_reason(&finish_reason));
                     }
+                    let content = choice
+                        .delta
+                        .reasoning_content
+                        .into_iter()
+                        .map(reasoning_to_content_part)
+                        .chain(choice.delta.content.into_iter().map(ContentPart::Text))
+                        .collect::<Vec<_>>();
                     Ok(Some(StreamEvent::Delta(StreamDelta {
-                        content: choice
-                            .delta
-                            .content
-                            .map(|text| vec![ContentPart::Text(text)]),
-                        tool_calls: choice.delta.tool_calls.map(|calls| {
+                        content: (!content.is_empty()).then_some(content),
+                        tool_calls: None,
+                        tool_call_deltas: choice.delta.tool_calls.map(|calls| {
+                            // Grok sends every tool call complete, in a single fragment
+                            let mut next_tool_call = self.next_tool_call.borrow_mut();
                             calls
                                 .into_iter()
-                                .map(convert_client_tool_call_to_tool_call)
+                                .map(|call| {
+                                    let index = *next_tool_call;
+                                    *next_tool_call += 1;
+                                    convert_client_tool_call_to_tool_call_delta(call, index)
+                                })
                                 .collect()
                         }),
+                        citations: None,
                     })))
                 } else if let Some(usage) = message.usage {
                     let finish_reason = self.finish_reason.borrow();
@@ -114,6 +149,7 @@ impl LlmChatStreamState for GrokChatStream {
                         provider_id: None,
                         timestamp: Some(message.created.to_string()),
                         provider_metadata_json: None,
+                        cost: None,
                     })))
                 } else {
                     Ok(None)
@@ -125,16 +161,33 @@ impl LlmChatStreamState for GrokChatStream {
             )),
         }
     }
+
+    fn decode_messages(&self, raw: &str) -> Result<Vec<StreamEvent>, Error> {
+        let stream_event = self.decode_message(raw)?;
+        // The sources of live search are sent with the last chunk, before its event
+        let live_search = self.live_search.borrow_mut().take().map(|content_part| {
+            StreamEvent::Delta(StreamDelta {
+                content: Some(vec![content_part]),
+                tool_calls: None,
+                tool_call_deltas: None,
+                citations: None,
+            })
+        });
+        Ok(live_search.into_iter().chain(stream_event).collect())
+    }
 }
 
-struct GrokComponent;
+pub struct GrokComponent;
 
 impl GrokComponent {
     const ENV_VAR_NAME: &'static str = "XAI_API_KEY";
 
     fn request(client: CompletionsApi, request: CompletionsRequest) -> Result<Response, Error> {
+        let model = request.model.clone();
         let response = client.send_messages(request)?;
-        process_response(response)
+        let mut response = process_response(response)?;
+        add_cost(&mut response.metadata, &model, &PRICING);
+        Ok(response)
     }
 
     fn streaming_request(
@@ -145,8 +198,9 @@ impl GrokComponent {
         request.stream_options = Some(StreamOptions {
             include_usage: true,
         });
+        let model = request.model.clone();
         match client.stream_send_messages(request) {
-            Ok(stream) => GrokChatStream::new(stream),
+            Ok(stream) => GrokChatStream::new(stream).with_pricing(&model, &PRICING),
             Err(err) => GrokChatStream::failed(err),
         }
     }
@@ -154,6 +208,7 @@ impl GrokComponent {
 
 impl Guest for GrokComponent {
     type ChatStream = LlmChatStream<GrokChatStream>;
+    type ChatSession = ChatSession<Self>;
 
     fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
         let xai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
@@ -165,9 +220,58 @@ impl Guest for GrokComponent {
     fn stream(messages: Vec<Event>, config: Config) -> ChatStream {
         ChatStream::new(Self::unwrapped_stream(messages, config))
     }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(known_model_info(&model, KNOWN_MODELS))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let xai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = CompletionsApi::new(xai_api_key);
+        let response = client.list_language_models()?;
+        Ok(response
+            .models
+            .into_iter()
+            .map(|model| {
+                let mut model_info = known_model_info(&model.id, KNOWN_MODELS);
+                // The API reports the input modalities, which is more accurate than the table
+                if let Some(capabilities) = &mut model_info.capabilities {
+                    capabilities.vision = model
+                        .input_modalities
+                        .iter()
+                        .any(|modality| modality == "image");
+                }
+                model_info
+            })
+            .collect())
+    }
+}
+
+impl BatchGuest for GrokComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
 }
 
 impl ExtendedGuest for GrokComponent {
+    const PROVIDER_NAME: &'static str = "grok";
+
     fn unwrapped_stream(messages: Vec<Event>, config: Config) -> LlmChatStream<GrokChatStream> {
         with_config_key(Self::ENV_VAR_NAME, GrokChatStream::failed, |xai_api_key| {
             let client = CompletionsApi::new(xai_api_key);
@@ -184,6 +288,8 @@ impl ExtendedGuest for GrokComponent {
     }
 }
 
-type DurableGrokComponent = DurableLLM<GrokComponent>;
+#[cfg(not(feature = "library"))]
+type DurableGrokComponent = DurableLLM<CachedLLM<GrokComponent>>;
 
+#[cfg(not(feature = "library"))]
 golem_llm::export_llm!(DurableGrokComponent with_types_in golem_llm);
@@ -0,0 +1,169 @@
+use golem_llm::golem::llm::llm::ModelCapabilities;
+use golem_llm::models::KnownModel;
+use golem_llm::pricing::{KnownPrice, Price, Pricing};
+
+/// Context windows and capabilities of the xAI models, the API does not report them and there
+/// is no separate limit on the output
+pub const KNOWN_MODELS: &[KnownModel] = &[
+    KnownModel {
+        id: "grok-4",
+        context_window: 256_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "grok-4-fast",
+        context_window: 2_000_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "grok-code-fast-1",
+        context_window: 256_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "grok-3",
+        context_window: 131_072,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "grok-3-mini",
+        context_window: 131_072,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "grok-2",
+        context_window: 131_072,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "grok-2-vision",
+        context_window: 32_768,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+];
+
+/// Prices of the xAI models, the input tokens reported by the API include the cached ones
+pub const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[
+        KnownPrice {
+            id: "grok-4",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.75),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "grok-4-fast",
+            price: Price {
+                input: 0.2,
+                output: 0.5,
+                cache_read: Some(0.05),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "grok-code-fast-1",
+            price: Price {
+                input: 0.2,
+                output: 1.5,
+                cache_read: Some(0.02),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "grok-3",
+            price: Price {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.75),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "grok-3-mini",
+            price: Price {
+                input: 0.3,
+                output: 0.5,
+                cache_read: Some(0.075),
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "grok-2",
+            price: Price {
+                input: 2.0,
+                output: 10.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "grok-2-vision",
+            price: Price {
+                input: 2.0,
+                output: 10.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+    ],
+};
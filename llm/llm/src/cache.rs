@@ -0,0 +1,515 @@
+use crate::chat_session::ChatSession;
+use crate::config::get_config_key_or_none;
+use crate::durability::ExtendedGuest;
+use crate::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use crate::golem::llm::llm::{
+    ChatStream, Config, Error, Event, Guest, ModelInfo, Response, StreamDelta,
+};
+use bindings::wasi::keyvalue::eventual;
+use bindings::wasi::keyvalue::types::{Bucket, OutgoingValue};
+use bindings::wasi::keyvalue::wasi_keyvalue_error::Error as KeyValueError;
+use golem_rust::wasm_rpc::Pollable;
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Config key of the number of seconds responses are cached for. Caching is disabled if it is not set.
+const CACHE_TTL_ENV_VAR_NAME: &str = "GOLEM_LLM_CACHE_TTL_SECONDS";
+
+/// Config key selecting the storage of `ConfiguredCacheStorage`: `memory` (the default) or `keyvalue`
+const CACHE_STORAGE_ENV_VAR_NAME: &str = "GOLEM_LLM_CACHE_STORAGE";
+
+/// Config key of the `wasi:keyvalue` bucket of `KeyValueCacheStorage`
+const CACHE_BUCKET_ENV_VAR_NAME: &str = "GOLEM_LLM_CACHE_BUCKET";
+
+const DEFAULT_CACHE_BUCKET: &str = "golem-llm-cache";
+
+mod bindings {
+    wit_bindgen::generate!({
+        path: "../wit",
+        world: "cache-storage",
+        generate_all,
+    });
+}
+
+/// Provider option skipping the cache lookup of a request. The response is still stored in the cache,
+/// refreshing the cached one. The option is not passed to the provider.
+pub const CACHE_BYPASS_OPTION: &str = "golem_llm_cache_bypass";
+
+/// Wraps an LLM implementation with a cache of the responses of `send`, returning the stored response
+/// for requests with the same events and config within the TTL configured by `GOLEM_LLM_CACHE_TTL_SECONDS`.
+///
+/// Streams, token counting, model information and batches are passed through to the wrapped
+/// implementation. When used together with `DurableLLM`, it has to be wrapped by it
+/// (`DurableLLM<CachedLLM<Impl>>`), so cache hits are persisted like any other response.
+pub struct CachedLLM<Impl, Storage = ConfiguredCacheStorage> {
+    phantom: PhantomData<(Impl, Storage)>,
+}
+
+/// A cached response, and the time it expires at in milliseconds since the Unix epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub response: Response,
+    pub expires_at_ms: u64,
+}
+
+/// Key-value storage of the cached responses, keyed by the hash of the requests
+pub trait CacheStorage: 'static {
+    fn get(key: &str) -> Option<CachedResponse>;
+    fn put(key: String, cached: CachedResponse);
+}
+
+/// Stores the cached responses in the memory of the worker, so they are lost when the worker restarts
+pub struct InMemoryCacheStorage;
+
+thread_local! {
+    static IN_MEMORY_CACHE: RefCell<HashMap<String, CachedResponse>> = RefCell::new(HashMap::new());
+}
+
+impl CacheStorage for InMemoryCacheStorage {
+    fn get(key: &str) -> Option<CachedResponse> {
+        IN_MEMORY_CACHE.with_borrow(|cache| cache.get(key).cloned())
+    }
+
+    fn put(key: String, cached: CachedResponse) {
+        IN_MEMORY_CACHE.with_borrow_mut(|cache| {
+            let now = now_ms();
+            cache.retain(|_, cached| cached.expires_at_ms > now);
+            cache.insert(key, cached);
+        })
+    }
+}
+
+/// Stores the cached responses as JSON in the `wasi:keyvalue` bucket named by `GOLEM_LLM_CACHE_BUCKET`
+/// (`golem-llm-cache` by default), so they survive worker restarts and are shared by the workers using
+/// the bucket. Expired responses are deleted when they are looked up. Storage failures are logged and
+/// treated as cache misses, so they never fail a request.
+pub struct KeyValueCacheStorage;
+
+impl KeyValueCacheStorage {
+    fn bucket() -> Result<Bucket, KeyValueError> {
+        let name = get_config_key_or_none(CACHE_BUCKET_ENV_VAR_NAME)
+            .unwrap_or_else(|| DEFAULT_CACHE_BUCKET.to_string());
+        Bucket::open_bucket(&name)
+    }
+
+    fn try_get(key: &str) -> Result<Option<CachedResponse>, String> {
+        let bucket = Self::bucket().map_err(|err| err.trace())?;
+        let Some(value) = eventual::get(&bucket, key).map_err(|err| err.trace())? else {
+            return Ok(None);
+        };
+        let body = value
+            .incoming_value_consume_sync()
+            .map_err(|err| err.trace())?;
+        let cached =
+            serde_json::from_slice::<CachedResponse>(&body).map_err(|err| err.to_string())?;
+        if cached.expires_at_ms > now_ms() {
+            Ok(Some(cached))
+        } else {
+            eventual::delete(&bucket, key).map_err(|err| err.trace())?;
+            Ok(None)
+        }
+    }
+
+    fn try_put(key: &str, cached: &CachedResponse) -> Result<(), String> {
+        let body = serde_json::to_vec(cached).map_err(|err| err.to_string())?;
+        let bucket = Self::bucket().map_err(|err| err.trace())?;
+        let value = OutgoingValue::new_outgoing_value();
+        value
+            .outgoing_value_write_body_sync(&body)
+            .map_err(|err| err.trace())?;
+        eventual::set(&bucket, key, &value).map_err(|err| err.trace())
+    }
+}
+
+impl CacheStorage for KeyValueCacheStorage {
+    fn get(key: &str) -> Option<CachedResponse> {
+        Self::try_get(key).unwrap_or_else(|err| {
+            warn!("Failed to read cached response {key}: {err}");
+            None
+        })
+    }
+
+    fn put(key: String, cached: CachedResponse) {
+        if let Err(err) = Self::try_put(&key, &cached) {
+            warn!("Failed to store cached response {key}: {err}");
+        }
+    }
+}
+
+/// Stores the cached responses in the storage selected by `GOLEM_LLM_CACHE_STORAGE`, either the worker's
+/// memory (`memory`, the default) or a `wasi:keyvalue` bucket (`keyvalue`)
+pub struct ConfiguredCacheStorage;
+
+impl ConfiguredCacheStorage {
+    fn uses_key_value() -> bool {
+        uses_key_value(get_config_key_or_none(CACHE_STORAGE_ENV_VAR_NAME).as_deref())
+    }
+}
+
+impl CacheStorage for ConfiguredCacheStorage {
+    fn get(key: &str) -> Option<CachedResponse> {
+        if Self::uses_key_value() {
+            KeyValueCacheStorage::get(key)
+        } else {
+            InMemoryCacheStorage::get(key)
+        }
+    }
+
+    fn put(key: String, cached: CachedResponse) {
+        if Self::uses_key_value() {
+            KeyValueCacheStorage::put(key, cached)
+        } else {
+            InMemoryCacheStorage::put(key, cached)
+        }
+    }
+}
+
+fn uses_key_value(storage: Option<&str>) -> bool {
+    match storage {
+        None | Some("memory") => false,
+        Some("keyvalue") => true,
+        Some(other) => {
+            warn!("Unknown {CACHE_STORAGE_ENV_VAR_NAME} value {other}, caching in memory");
+            false
+        }
+    }
+}
+
+impl<Impl: ExtendedGuest, Storage: CacheStorage> CachedLLM<Impl, Storage> {
+    fn ttl() -> Option<Duration> {
+        get_config_key_or_none(CACHE_TTL_ENV_VAR_NAME)
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+}
+
+impl<Impl: ExtendedGuest, Storage: CacheStorage> Guest for CachedLLM<Impl, Storage> {
+    type ChatStream = Impl::ChatStream;
+    type ChatSession = ChatSession<Self>;
+
+    fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+        send_cached::<Storage>(
+            Impl::PROVIDER_NAME,
+            events,
+            config,
+            Self::ttl(),
+            now_ms(),
+            Impl::send,
+        )
+    }
+
+    fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+        Impl::stream(events, take_bypass_option(config).0)
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Impl::count_tokens(events, take_bypass_option(config).0)
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Impl::get_model_info(model)
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        Impl::list_models()
+    }
+}
+
+impl<Impl: ExtendedGuest, Storage: CacheStorage> BatchGuest for CachedLLM<Impl, Storage> {
+    fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+        Impl::submit_batch(items)
+    }
+
+    fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+        Impl::poll_batch(id)
+    }
+
+    fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+        Impl::get_batch_results(id)
+    }
+
+    fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+        Impl::cancel_batch(id)
+    }
+}
+
+impl<Impl: ExtendedGuest, Storage: CacheStorage> ExtendedGuest for CachedLLM<Impl, Storage> {
+    const PROVIDER_NAME: &'static str = Impl::PROVIDER_NAME;
+
+    fn unwrapped_stream(events: Vec<Event>, config: Config) -> Self::ChatStream {
+        Impl::unwrapped_stream(events, take_bypass_option(config).0)
+    }
+
+    fn retry_prompt(
+        original_events: &[Result<Event, Error>],
+        partial_result: &[StreamDelta],
+    ) -> Vec<Event> {
+        Impl::retry_prompt(original_events, partial_result)
+    }
+
+    fn subscribe(stream: &Self::ChatStream) -> Pollable {
+        Impl::subscribe(stream)
+    }
+}
+
+/// Returns the cached response of the request if it is still valid at `now_ms` and the cache is not
+/// bypassed, and otherwise the response of `send`, which is cached for `ttl`
+fn send_cached<Storage: CacheStorage>(
+    provider: &str,
+    events: Vec<Event>,
+    config: Config,
+    ttl: Option<Duration>,
+    now_ms: u64,
+    send: impl FnOnce(Vec<Event>, Config) -> Result<Response, Error>,
+) -> Result<Response, Error> {
+    let (config, bypass) = take_bypass_option(config);
+    let Some(ttl) = ttl else {
+        return send(events, config);
+    };
+
+    let key = cache_key(provider, &events, &config);
+    if !bypass {
+        if let Some(cached) = Storage::get(&key).filter(|cached| cached.expires_at_ms > now_ms) {
+            trace!("Returning cached response {key}");
+            return Ok(cached.response);
+        }
+    }
+
+    let response = send(events, config)?;
+    Storage::put(
+        key,
+        CachedResponse {
+            response: response.clone(),
+            expires_at_ms: now_ms.saturating_add(ttl.as_millis() as u64),
+        },
+    );
+    Ok(response)
+}
+
+/// Removes the cache bypass option from the config, returning whether it was set
+fn take_bypass_option(mut config: Config) -> (Config, bool) {
+    let mut bypass = false;
+    if let Some(options) = config.provider_options.as_mut() {
+        options.retain(|kv| {
+            if kv.key == CACHE_BYPASS_OPTION {
+                bypass = kv.value == "true";
+                false
+            } else {
+                true
+            }
+        });
+    }
+    (config, bypass)
+}
+
+/// Hashes the request together with the provider answering it, as the key-value storage can be shared
+/// by several providers. The JSON encoding is used, as it contains every field and does not change
+/// between versions like the debug representation may.
+fn cache_key(provider: &str, events: &[Event], config: &Config) -> String {
+    let request =
+        serde_json::to_vec(&(provider, events, config)).expect("Failed to encode the request");
+    let hash = Sha256::digest(request);
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golem::llm::llm::{ContentPart, Kv, Message, ResponseMetadata, Role};
+    use std::cell::Cell;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn events(question: &str) -> Vec<Event> {
+        vec![Event::Message(Message {
+            role: Role::User,
+            name: None,
+            content: vec![ContentPart::Text(question.to_string())],
+            cache_hint: None,
+        })]
+    }
+
+    fn config(bypass: bool) -> Config {
+        Config {
+            model: "model".to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: bypass.then(|| {
+                vec![Kv {
+                    key: CACHE_BYPASS_OPTION.to_string(),
+                    value: "true".to_string(),
+                }]
+            }),
+        }
+    }
+
+    fn response(id: &str) -> Response {
+        Response {
+            id: id.to_string(),
+            content: vec![ContentPart::Text("answer".to_string())],
+            tool_calls: vec![],
+            citations: vec![],
+            metadata: ResponseMetadata {
+                finish_reason: None,
+                usage: None,
+                provider_id: None,
+                timestamp: None,
+                provider_metadata_json: None,
+                cost: None,
+            },
+        }
+    }
+
+    /// Sends the request through the in-memory cache at `now_ms`, which has to be close to the current
+    /// time, as the in-memory storage evicts the entries that have expired, answering with a response
+    /// numbered by the number of calls reaching the provider
+    fn send(
+        question: &str,
+        bypass: bool,
+        ttl: Option<Duration>,
+        now_ms: u64,
+        calls: &Cell<u32>,
+    ) -> Response {
+        send_cached::<InMemoryCacheStorage>(
+            "test",
+            events(question),
+            config(bypass),
+            ttl,
+            now_ms,
+            |_, config| {
+                assert_eq!(config.provider_options, None);
+                calls.set(calls.get() + 1);
+                Ok(response(&format!("response-{}", calls.get())))
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cached_until_ttl_expires() {
+        let start = now_ms();
+        let calls = Cell::new(0);
+        assert_eq!(
+            send("ttl", false, Some(TTL), start, &calls).id,
+            "response-1"
+        );
+        assert_eq!(
+            send("ttl", false, Some(TTL), start + 59_999, &calls).id,
+            "response-1"
+        );
+        assert_eq!(
+            send("ttl", false, Some(TTL), start + 60_000, &calls).id,
+            "response-2"
+        );
+        assert_eq!(
+            send("ttl", false, Some(TTL), start + 61_000, &calls).id,
+            "response-2"
+        );
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_different_requests_are_cached_separately() {
+        let start = now_ms();
+        let calls = Cell::new(0);
+        assert_eq!(
+            send("first", false, Some(TTL), start, &calls).id,
+            "response-1"
+        );
+        assert_eq!(
+            send("second", false, Some(TTL), start, &calls).id,
+            "response-2"
+        );
+        assert_eq!(
+            send("first", false, Some(TTL), start + 1_000, &calls).id,
+            "response-1"
+        );
+    }
+
+    #[test]
+    fn test_bypass_refreshes_cached_response() {
+        let start = now_ms();
+        let calls = Cell::new(0);
+        assert_eq!(
+            send("bypass", false, Some(TTL), start, &calls).id,
+            "response-1"
+        );
+        assert_eq!(
+            send("bypass", true, Some(TTL), start + 1_000, &calls).id,
+            "response-2"
+        );
+        // The bypass option is not part of the key, so the refreshed response replaces the cached one
+        assert_eq!(
+            send("bypass", false, Some(TTL), start + 2_000, &calls).id,
+            "response-2"
+        );
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_disabled_without_ttl() {
+        let start = now_ms();
+        let calls = Cell::new(0);
+        assert_eq!(
+            send("disabled", false, None, start, &calls).id,
+            "response-1"
+        );
+        assert_eq!(
+            send("disabled", false, None, start, &calls).id,
+            "response-2"
+        );
+        assert_eq!(send("disabled", true, None, start, &calls).id, "response-3");
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let key = cache_key("openai", &events("key"), &config(false));
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, cache_key("openai", &events("key"), &config(false)));
+        assert_ne!(key, cache_key("anthropic", &events("key"), &config(false)));
+        assert_ne!(
+            key,
+            cache_key("openai", &events("other key"), &config(false))
+        );
+    }
+
+    #[test]
+    fn test_uses_key_value() {
+        assert!(!uses_key_value(None));
+        assert!(!uses_key_value(Some("memory")));
+        assert!(uses_key_value(Some("keyvalue")));
+        assert!(!uses_key_value(Some("redis")));
+    }
+
+    #[test]
+    fn test_cached_response_json_round_trip() {
+        let cached = CachedResponse {
+            response: response("response-1"),
+            expires_at_ms: 60_000,
+        };
+        let json = serde_json::to_vec(&cached).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<CachedResponse>(&json).unwrap(),
+            cached
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use crate::golem::llm::llm::{
+    ChatSession as ChatSessionResource, ChatStream, Config, Error, Event, Guest, GuestChatSession,
+    Message, Response, ToolResult,
+};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+/// Provider independent implementation of the `chat-session` resource on top of an LLM `Guest`.
+///
+/// The session only keeps the chat events in memory. When used as `ChatSession<DurableLLM<Impl>>`
+/// every `send` goes through the durable implementation, so after a worker restart Golem's replay
+/// rebuilds the same history from the persisted responses without calling the provider again.
+pub struct ChatSession<Impl> {
+    config: Config,
+    events: RefCell<Vec<Event>>,
+    phantom: PhantomData<Impl>,
+}
+
+impl<Impl> ChatSession<Impl> {
+    fn push(&self, event: Event) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl<Impl: Guest + 'static> GuestChatSession for ChatSession<Impl> {
+    fn new(config: Config) -> Self {
+        Self {
+            config,
+            events: RefCell::new(Vec::new()),
+            phantom: PhantomData,
+        }
+    }
+
+    fn add_message(&self, message: Message) {
+        self.push(Event::Message(message));
+    }
+
+    fn add_tool_results(&self, results: Vec<ToolResult>) {
+        self.push(Event::ToolResults(results));
+    }
+
+    fn add_response(&self, response: Response) {
+        self.push(Event::Response(response));
+    }
+
+    fn send(&self) -> Result<Response, Error> {
+        let events = self.history();
+        let response = Impl::send(events, self.config.clone())?;
+        self.push(Event::Response(response.clone()));
+        Ok(response)
+    }
+
+    fn stream(&self) -> ChatStream {
+        Impl::stream(self.history(), self.config.clone())
+    }
+
+    fn history(&self) -> Vec<Event> {
+        self.events.borrow().clone()
+    }
+
+    fn fork(&self) -> ChatSessionResource {
+        ChatSessionResource::new(Self {
+            config: self.config.clone(),
+            events: RefCell::new(self.history()),
+            phantom: PhantomData,
+        })
+    }
+}
@@ -1,7 +1,11 @@
 use crate::event_source::{Event, EventSource, MessageEvent};
-use crate::golem::llm::llm::{Error, ErrorCode, GuestChatStream, StreamEvent};
+use crate::golem::llm::llm::{
+    Error, ErrorCode, GuestChatStream, StreamDelta, StreamEvent, ToolCall, ToolCallDelta,
+};
+use crate::pricing::{ModelPricing, Pricing};
 use golem_rust::wasm_rpc::Pollable;
-use std::cell::{Ref, RefMut};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::BTreeMap;
 use std::task::Poll;
 
 pub trait LlmChatStreamState: 'static {
@@ -11,15 +15,144 @@ pub trait LlmChatStreamState: 'static {
     fn stream(&self) -> Ref<'_, Option<EventSource>>;
     fn stream_mut(&self) -> RefMut<'_, Option<EventSource>>;
     fn decode_message(&self, raw: &str) -> Result<Option<StreamEvent>, Error>;
+
+    /// Decodes a raw message that may carry more than one stream event, for providers
+    /// sending the content and the finish metadata in the same message.
+    fn decode_messages(&self, raw: &str) -> Result<Vec<StreamEvent>, Error> {
+        self.decode_message(raw)
+            .map(|stream_event| stream_event.into_iter().collect())
+    }
+}
+
+/// Assembles the tool call fragments of a stream, and emits the completed tool calls right before
+/// the stream's finish event, or when the stream ends without one, so every provider streams tool
+/// calls the same way
+#[derive(Default)]
+pub struct ToolCallAssembler {
+    tool_calls: RefCell<BTreeMap<u32, ToolCall>>,
+}
+
+impl ToolCallAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the tool call fragments of the given events, inserting the completed tool calls
+    /// before the finish event
+    pub fn process(
+        &self,
+        events: Vec<Result<StreamEvent, Error>>,
+    ) -> Vec<Result<StreamEvent, Error>> {
+        let mut result = Vec::with_capacity(events.len());
+        for event in events {
+            match &event {
+                Ok(StreamEvent::Delta(delta)) => {
+                    for tool_call_delta in delta.tool_call_deltas.iter().flatten() {
+                        self.add(tool_call_delta);
+                    }
+                }
+                Ok(StreamEvent::Finish(_)) => result.extend(self.complete()),
+                Err(_) => {}
+            }
+            result.push(event);
+        }
+        result
+    }
+
+    fn add(&self, delta: &ToolCallDelta) {
+        let mut tool_calls = self.tool_calls.borrow_mut();
+        let tool_call = tool_calls.entry(delta.index).or_insert_with(|| ToolCall {
+            id: String::new(),
+            name: String::new(),
+            arguments_json: String::new(),
+        });
+        if let Some(id) = &delta.id {
+            tool_call.id.clone_from(id);
+        }
+        if let Some(name) = &delta.name {
+            tool_call.name.clone_from(name);
+        }
+        tool_call.arguments_json.push_str(&delta.arguments_fragment);
+    }
+
+    /// Takes the assembled tool calls, emitting the valid ones in a single delta, followed by an
+    /// error for each call without a name or with invalid arguments
+    pub fn complete(&self) -> Vec<Result<StreamEvent, Error>> {
+        let tool_calls = std::mem::take(&mut *self.tool_calls.borrow_mut());
+
+        let mut completed = Vec::with_capacity(tool_calls.len());
+        let mut errors = Vec::new();
+        for (index, mut tool_call) in tool_calls {
+            if tool_call.name.is_empty() {
+                errors.push(Err(invalid_tool_call(format!(
+                    "Tool call {index} has no name"
+                ))));
+                continue;
+            }
+            if tool_call.id.is_empty() {
+                tool_call.id = format!("{}-{index}", tool_call.name);
+            }
+            if tool_call.arguments_json.trim().is_empty() {
+                tool_call.arguments_json = "{}".to_string();
+            } else if let Err(err) =
+                serde_json::from_str::<serde_json::Value>(&tool_call.arguments_json)
+            {
+                errors.push(Err(invalid_tool_call(format!(
+                    "Tool call {} has invalid arguments: {err}",
+                    tool_call.name
+                ))));
+                continue;
+            }
+            completed.push(tool_call);
+        }
+
+        let mut result = Vec::with_capacity(errors.len() + 1);
+        if !completed.is_empty() {
+            result.push(Ok(StreamEvent::Delta(StreamDelta {
+                content: None,
+                tool_calls: Some(completed),
+                tool_call_deltas: None,
+                citations: None,
+            })));
+        }
+        result.extend(errors);
+        result
+    }
+}
+
+fn invalid_tool_call(message: String) -> Error {
+    Error {
+        code: ErrorCode::InternalError,
+        message,
+        provider_error_json: None,
+        retry_after_ms: None,
+    }
 }
 
 pub struct LlmChatStream<T> {
     implementation: T,
+    tool_calls: ToolCallAssembler,
+    pricing: Option<ModelPricing>,
 }
 
 impl<T: LlmChatStreamState> LlmChatStream<T> {
     pub fn new(implementation: T) -> Self {
-        Self { implementation }
+        Self {
+            implementation,
+            tool_calls: ToolCallAssembler::new(),
+            pricing: None,
+        }
+    }
+
+    /// Sets the cost of the stream's finish event from its usage, if the price of the model is known
+    pub fn with_pricing(mut self, model: &str, pricing: &Pricing) -> Self {
+        self.pricing = pricing.model_pricing(model);
+        self
+    }
+
+    /// The error the stream failed with before receiving any events, if any
+    pub fn failure(&self) -> &Option<Error> {
+        self.implementation.failure()
     }
 
     pub fn subscribe(&self) -> Pollable {
@@ -40,21 +173,25 @@ impl<T: LlmChatStreamState> GuestChatStream for LlmChatStream<T> {
         let mut stream = self.implementation.stream_mut();
         if let Some(stream) = stream.as_mut() {
             match stream.poll_next() {
+                // The tool calls of a stream ending without a finish event are still emitted
                 Poll::Ready(None) => {
                     self.implementation.set_finished();
-                    Some(vec![])
+                    Some(self.tool_calls.complete())
                 }
                 Poll::Ready(Some(Err(crate::event_source::error::Error::StreamEnded))) => {
                     self.implementation.set_finished();
-                    Some(vec![])
+                    Some(self.tool_calls.complete())
                 }
                 Poll::Ready(Some(Err(error))) => {
                     self.implementation.set_finished();
-                    Some(vec![Err(Error {
+                    let mut events = self.tool_calls.complete();
+                    events.push(Err(Error {
                         code: ErrorCode::InternalError,
                         message: error.to_string(),
                         provider_error_json: None,
-                    })])
+                        retry_after_ms: None,
+                    }));
+                    Some(events)
                 }
                 Poll::Ready(Some(Ok(event))) => {
                     let mut events = vec![];
@@ -63,15 +200,15 @@ impl<T: LlmChatStreamState> GuestChatStream for LlmChatStream<T> {
                         Event::Open => {}
                         Event::Message(MessageEvent { data, .. }) => {
                             if data != "[DONE]" {
-                                match self.implementation.decode_message(&data) {
-                                    Ok(Some(stream_event)) => {
-                                        if matches!(stream_event, StreamEvent::Finish(_)) {
-                                            self.implementation.set_finished();
+                                match self.implementation.decode_messages(&data) {
+                                    Ok(stream_events) => {
+                                        // Ignored events result in an empty list
+                                        for stream_event in stream_events {
+                                            if matches!(stream_event, StreamEvent::Finish(_)) {
+                                                self.implementation.set_finished();
+                                            }
+                                            events.push(Ok(stream_event));
                                         }
-                                        events.push(Ok(stream_event));
-                                    }
-                                    Ok(None) => {
-                                        // Ignored event
                                     }
                                     Err(err) => events.push(Err(err)),
                                 }
@@ -82,7 +219,11 @@ impl<T: LlmChatStreamState> GuestChatStream for LlmChatStream<T> {
                     if events.is_empty() {
                         None
                     } else {
-                        Some(events)
+                        let events = self.tool_calls.process(events);
+                        match &self.pricing {
+                            Some(pricing) => Some(pricing.add_stream_costs(events)),
+                            None => Some(events),
+                        }
                     }
                 }
                 Poll::Pending => None,
@@ -105,3 +246,98 @@ impl<T: LlmChatStreamState> GuestChatStream for LlmChatStream<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golem::llm::llm::{FinishReason, ResponseMetadata};
+
+    fn delta(
+        index: u32,
+        name: Option<&str>,
+        arguments_fragment: &str,
+    ) -> Result<StreamEvent, Error> {
+        Ok(StreamEvent::Delta(StreamDelta {
+            content: None,
+            tool_calls: None,
+            tool_call_deltas: Some(vec![ToolCallDelta {
+                index,
+                id: name.map(|name| format!("call-{name}")),
+                name: name.map(|name| name.to_string()),
+                arguments_fragment: arguments_fragment.to_string(),
+            }]),
+            citations: None,
+        }))
+    }
+
+    fn finish() -> Result<StreamEvent, Error> {
+        Ok(StreamEvent::Finish(ResponseMetadata {
+            finish_reason: Some(FinishReason::ToolCalls),
+            usage: None,
+            provider_id: None,
+            timestamp: None,
+            provider_metadata_json: None,
+            cost: None,
+        }))
+    }
+
+    fn completed_tool_calls(event: &Result<StreamEvent, Error>) -> Vec<ToolCall> {
+        match event {
+            Ok(StreamEvent::Delta(StreamDelta {
+                tool_calls: Some(tool_calls),
+                ..
+            })) => tool_calls.clone(),
+            other => panic!("Expected completed tool calls, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_tool_calls_are_emitted_before_finish() {
+        let assembler = ToolCallAssembler::new();
+        let events = assembler.process(vec![
+            delta(0, Some("weather"), "{\"city\":"),
+            delta(0, None, "\"Paris\"}"),
+        ]);
+        assert_eq!(events.len(), 2);
+
+        let events = assembler.process(vec![finish()]);
+        assert_eq!(events.len(), 2);
+        let tool_calls = completed_tool_calls(&events[0]);
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].id, "call-weather");
+        assert_eq!(tool_calls[0].name, "weather");
+        assert_eq!(tool_calls[0].arguments_json, "{\"city\":\"Paris\"}");
+        assert!(matches!(events[1], Ok(StreamEvent::Finish(_))));
+    }
+
+    #[test]
+    fn test_invalid_tool_call_does_not_fail_the_others() {
+        let assembler = ToolCallAssembler::new();
+        assembler.process(vec![
+            delta(0, Some("weather"), "{\"city\":\"Paris\"}"),
+            delta(1, Some("time"), "{\"zone\":"),
+            delta(2, None, "{}"),
+        ]);
+
+        let events = assembler.process(vec![finish()]);
+        assert_eq!(events.len(), 4);
+        let tool_calls = completed_tool_calls(&events[0]);
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].name, "weather");
+        assert!(matches!(&events[1], Err(error) if error.message.contains("time")));
+        assert!(matches!(&events[2], Err(error) if error.message.contains("Tool call 2")));
+        assert!(matches!(events[3], Ok(StreamEvent::Finish(_))));
+    }
+
+    #[test]
+    fn test_pending_tool_calls_are_flushed_without_finish() {
+        let assembler = ToolCallAssembler::new();
+        assembler.process(vec![delta(0, Some("weather"), "")]);
+
+        let events = assembler.complete();
+        assert_eq!(events.len(), 1);
+        let tool_calls = completed_tool_calls(&events[0]);
+        assert_eq!(tool_calls[0].arguments_json, "{}");
+        assert!(assembler.complete().is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+use crate::golem::llm::llm::ContentPart;
+
+/// Number of characters in the text parts of the given content. Citation offsets are counted in
+/// the response's text parts only, so consumers can map them back without considering other parts.
+pub fn text_length(content: &[ContentPart]) -> u32 {
+    content
+        .iter()
+        .map(|part| match part {
+            ContentPart::Text(text) => text.chars().count() as u32,
+            _ => 0,
+        })
+        .sum()
+}
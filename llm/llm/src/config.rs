@@ -16,6 +16,7 @@ pub fn with_config_key<R>(
                 code: ErrorCode::InternalError,
                 message: format!("Missing config key: {key_str}"),
                 provider_error_json: None,
+                retry_after_ms: None,
             };
             fail(error)
         }
@@ -28,6 +29,7 @@ pub fn get_config_key(key: impl AsRef<OsStr>) -> Result<String, Error> {
         code: ErrorCode::InternalError,
         message: format!("Missing config key: {key_str}"),
         provider_error_json: None,
+        retry_after_ms: None,
     })
 }
 
@@ -1,3 +1,4 @@
+use crate::golem::llm::batch::Guest as BatchGuest;
 use crate::golem::llm::llm::{
     Config, ContentPart, Error, Event, Guest, Message, Role, StreamDelta,
 };
@@ -10,8 +11,12 @@ pub struct DurableLLM<Impl> {
     phantom: PhantomData<Impl>,
 }
 
-/// Trait to be implemented in addition to the LLM `Guest` trait when wrapping it with `DurableLLM`.
-pub trait ExtendedGuest: Guest + 'static {
+/// Trait to be implemented in addition to the LLM `Guest` traits when wrapping it with `DurableLLM`.
+pub trait ExtendedGuest: Guest + BatchGuest + 'static {
+    /// Name of the provider, distinguishing its cached responses from the ones of other providers
+    /// sharing the same cache storage
+    const PROVIDER_NAME: &'static str;
+
     /// Creates an instance of the LLM specific `ChatStream` without wrapping it in a `Resource`
     fn unwrapped_stream(events: Vec<Event>, config: Config) -> Self::ChatStream;
 
@@ -35,6 +40,7 @@ pub trait ExtendedGuest: Guest + 'static {
                 }.to_string()),
                 ContentPart::Text("Here is the original question:".to_string()),
             ],
+            cache_hint: None,
         }));
         extended_events.extend(
             original_events
@@ -45,7 +51,13 @@ pub trait ExtendedGuest: Guest + 'static {
         let mut partial_result_as_content = Vec::new();
         for delta in partial_result {
             if let Some(contents) = &delta.content {
-                partial_result_as_content.extend_from_slice(contents);
+                // Reasoning is not part of the answer the model has to continue
+                partial_result_as_content.extend(
+                    contents
+                        .iter()
+                        .filter(|content| !matches!(content, ContentPart::Reasoning(_)))
+                        .cloned(),
+                );
             }
             if let Some(tool_calls) = &delta.tool_calls {
                 for tool_call in tool_calls {
@@ -66,6 +78,7 @@ pub trait ExtendedGuest: Guest + 'static {
             .into_iter()
             .chain(partial_result_as_content)
             .collect(),
+            cache_hint: None,
         }));
         extended_events
     }
@@ -76,14 +89,17 @@ pub trait ExtendedGuest: Guest + 'static {
 /// When the durability feature flag is off, wrapping with `DurableLLM` is just a passthrough
 #[cfg(not(feature = "durability"))]
 mod passthrough_impl {
+    use crate::chat_session::ChatSession;
     use crate::durability::{DurableLLM, ExtendedGuest};
+    use crate::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
     use crate::golem::llm::llm::{
-        ChatStream, Config, Error, Event, Guest, Message, Response, ToolCall, ToolResult,
+        ChatStream, Config, Error, Event, Guest, Message, ModelInfo, Response, ToolCall, ToolResult,
     };
     use crate::init_logging;
 
     impl<Impl: ExtendedGuest> Guest for DurableLLM<Impl> {
         type ChatStream = Impl::ChatStream;
+        type ChatSession = ChatSession<Self>;
 
         fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
             init_logging();
@@ -94,9 +110,49 @@ mod passthrough_impl {
             init_logging();
             Impl::stream(events, config)
         }
+
+        fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+            init_logging();
+            Impl::count_tokens(events, config)
+        }
+
+        fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+            init_logging();
+            Impl::get_model_info(model)
+        }
+
+        fn list_models() -> Result<Vec<ModelInfo>, Error> {
+            init_logging();
+            Impl::list_models()
+        }
+    }
+
+    impl<Impl: ExtendedGuest> BatchGuest for DurableLLM<Impl> {
+        fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+            init_logging();
+            Impl::submit_batch(items)
+        }
+
+        fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+            Impl::poll_batch(id)
+        }
+
+        fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+            init_logging();
+            Impl::get_batch_results(id)
+        }
+
+        fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+            Impl::cancel_batch(id)
+        }
     }
 }
 
+#[cfg(feature = "durability")]
+pub use durable_impl::DurableChatStream;
+
 /// When the durability feature flag is on, wrapping with `DurableLLM` adds custom durability
 /// on top of the provider-specific LLM implementation using Golem's special host functions and
 /// the `golem-rust` helper library.
@@ -107,9 +163,11 @@ mod passthrough_impl {
 /// which is implemented using the type classes and builder in the `golem-rust` library.
 #[cfg(feature = "durability")]
 mod durable_impl {
+    use crate::chat_session::ChatSession;
     use crate::durability::{DurableLLM, ExtendedGuest};
+    use crate::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
     use crate::golem::llm::llm::{
-        ChatStream, Config, Error, Event, Guest, GuestChatStream, Response, StreamDelta,
+        ChatStream, Config, Error, Event, Guest, GuestChatStream, ModelInfo, Response, StreamDelta,
         StreamEvent,
     };
     use crate::init_logging;
@@ -124,6 +182,7 @@ mod durable_impl {
 
     impl<Impl: ExtendedGuest> Guest for DurableLLM<Impl> {
         type ChatStream = DurableChatStream<Impl>;
+        type ChatSession = ChatSession<Self>;
 
         fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
             init_logging();
@@ -169,6 +228,144 @@ mod durable_impl {
                 ))
             }
         }
+
+        fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+            init_logging();
+
+            let durability = Durability::<u32, Error>::new(
+                "golem_llm",
+                "count_tokens",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::count_tokens(events.clone(), config.clone())
+                });
+                durability.persist_serializable(SendInput { events, config }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+            init_logging();
+
+            let durability = Durability::<ModelInfo, Error>::new(
+                "golem_llm",
+                "get_model_info",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::get_model_info(model.clone())
+                });
+                durability.persist_serializable(ModelInfoInput { model }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn list_models() -> Result<Vec<ModelInfo>, Error> {
+            init_logging();
+
+            let durability = Durability::<Vec<ModelInfo>, Error>::new(
+                "golem_llm",
+                "list_models",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::list_models()
+                });
+                durability.persist_serializable(NoInput, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+    }
+
+    /// The batch jobs are tracked like any other remote call, the submission and cancellation
+    /// being persisted as writes, and the polled progress and results as reads, so a replayed
+    /// worker sees the same batch states as the original one did
+    impl<Impl: ExtendedGuest> BatchGuest for DurableLLM<Impl> {
+        fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+            init_logging();
+
+            let durability = Durability::<String, Error>::new(
+                "golem_llm",
+                "submit_batch",
+                DurableFunctionType::WriteRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::submit_batch(items.clone())
+                });
+                durability.persist_serializable(SubmitBatchInput { items }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+
+            let durability = Durability::<BatchInfo, Error>::new(
+                "golem_llm",
+                "poll_batch",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::poll_batch(id.clone())
+                });
+                durability.persist_serializable(BatchIdInput { id }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+            init_logging();
+
+            let durability = Durability::<Vec<BatchResult>, Error>::new(
+                "golem_llm",
+                "get_batch_results",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::get_batch_results(id.clone())
+                });
+                durability.persist_serializable(BatchIdInput { id }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+
+            let durability = Durability::<BatchInfo, Error>::new(
+                "golem_llm",
+                "cancel_batch",
+                DurableFunctionType::WriteRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    Impl::cancel_batch(id.clone())
+                });
+                durability.persist_serializable(BatchIdInput { id }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
     }
 
     /// Represents the durable chat stream's state
@@ -208,7 +405,9 @@ mod durable_impl {
     }
 
     impl<Impl: ExtendedGuest> DurableChatStream<Impl> {
-        fn live(stream: Impl::ChatStream) -> Self {
+        /// Wraps a live stream created by `Impl::unwrapped_stream`, for implementations
+        /// choosing the underlying implementation themselves, like the router
+        pub fn live(stream: Impl::ChatStream) -> Self {
             Self {
                 state: RefCell::new(Some(DurableChatStreamState::Live {
                     stream,
@@ -219,7 +418,8 @@ mod durable_impl {
             }
         }
 
-        fn replay(original_events: Vec<Result<Event, Error>>, config: Config) -> Self {
+        /// Creates a stream replaying the events persisted by a previous `live` stream
+        pub fn replay(original_events: Vec<Result<Event, Error>>, config: Config) -> Self {
             Self {
                 state: RefCell::new(Some(DurableChatStreamState::Replay {
                     original_events,
@@ -417,6 +617,21 @@ mod durable_impl {
         config: Config,
     }
 
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct ModelInfoInput {
+        model: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct SubmitBatchInput {
+        items: Vec<BatchItem>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct BatchIdInput {
+        id: String,
+    }
+
     #[derive(Debug, IntoValue)]
     struct NoInput;
 
@@ -1,5 +1,7 @@
 use crate::event_source;
 use crate::golem::llm::llm::{Error, ErrorCode};
+use crate::retry::retry_after;
+use reqwest::header::HeaderMap;
 use reqwest::StatusCode;
 
 /// Creates an `Error` value representing that something is unsuported
@@ -8,6 +10,7 @@ pub fn unsupported(what: impl AsRef<str>) -> Error {
         code: ErrorCode::Unsupported,
         message: format!("Unsupported: {}", what.as_ref()),
         provider_error_json: None,
+        retry_after_ms: None,
     }
 }
 
@@ -16,6 +19,7 @@ pub fn from_reqwest_error(details: impl AsRef<str>, err: reqwest::Error) -> Erro
         code: ErrorCode::InternalError,
         message: format!("{}: {err}", details.as_ref()),
         provider_error_json: None,
+        retry_after_ms: None,
     }
 }
 
@@ -24,20 +28,260 @@ pub fn from_event_source_error(details: impl AsRef<str>, err: event_source::erro
         code: ErrorCode::InternalError,
         message: format!("{}: {err}", details.as_ref()),
         provider_error_json: None,
+        retry_after_ms: None,
     }
 }
 
+/// Gets the error code of a failed response from its status code. A `404` may be about any
+/// resource (a batch, a file, a stored response or a wrong endpoint), so it is an invalid request
+/// unless the body tells that the model is missing, see `error_code_from_response`.
 pub fn error_code_from_status(status: StatusCode) -> ErrorCode {
-    if status == StatusCode::TOO_MANY_REQUESTS {
-        ErrorCode::RateLimitExceeded
-    } else if status == StatusCode::UNAUTHORIZED
-        || status == StatusCode::FORBIDDEN
-        || status == StatusCode::PAYMENT_REQUIRED
+    match status.as_u16() {
+        401 | 403 => ErrorCode::AuthenticationFailed,
+        402 => ErrorCode::QuotaExceeded,
+        408 | 504 => ErrorCode::Timeout,
+        413 => ErrorCode::ContextLengthExceeded,
+        429 => ErrorCode::RateLimitExceeded,
+        // 529 is used by Anthropic for `overloaded_error`
+        503 | 529 => ErrorCode::Overloaded,
+        _ if status.is_client_error() => ErrorCode::InvalidRequest,
+        _ => ErrorCode::InternalError,
+    }
+}
+
+/// Gets the error code of a failed response, using the error types and messages providers put in
+/// their error bodies for the cases the status code alone does not tell
+pub fn error_code_from_response(status: StatusCode, body: &str) -> ErrorCode {
+    let body = body.to_lowercase();
+    let contains_any = |patterns: &[&str]| patterns.iter().any(|pattern| body.contains(pattern));
+
+    if contains_any(&[
+        "context_length_exceeded",
+        "maximum context length",
+        "context window",
+        "prompt is too long",
+        "input is too long",
+        "too many input tokens",
+        "exceeds the maximum number of tokens",
+        "maximum prompt length",
+    ]) {
+        ErrorCode::ContextLengthExceeded
+    } else if contains_any(&[
+        "insufficient_quota",
+        "credit balance is too low",
+        "insufficient credits",
+        "billing",
+    ]) {
+        ErrorCode::QuotaExceeded
+    } else if contains_any(&["overloaded_error", "overloaded"]) {
+        ErrorCode::Overloaded
+    } else if contains_any(&[
+        "content_filter",
+        "content_policy_violation",
+        "content management policy",
+        "flagged by moderation",
+    ]) {
+        ErrorCode::ContentFiltered
+    } else if contains_any(&["model_not_found", "invalid_model", "not a valid model id"])
+        || (body.contains("model") && contains_any(&["not found", "does not exist"]))
+        // Anthropic names the missing model in the message of a `not_found_error`
+        || (body.contains("not_found_error") && body.contains("model:"))
     {
-        ErrorCode::AuthenticationFailed
-    } else if status.is_client_error() {
-        ErrorCode::InvalidRequest
+        ErrorCode::ModelNotFound
     } else {
-        ErrorCode::InternalError
+        error_code_from_status(status)
+    }
+}
+
+/// Gets the time to wait before retrying, if the response tells it in its headers
+pub fn retry_after_ms(headers: &HeaderMap) -> Option<u32> {
+    retry_after(headers).map(|delay| delay.as_millis().min(u32::MAX as u128) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(code: u16) -> StatusCode {
+        StatusCode::from_u16(code).unwrap()
+    }
+
+    #[test]
+    fn test_error_code_from_status() {
+        let cases = [
+            (400, ErrorCode::InvalidRequest),
+            (401, ErrorCode::AuthenticationFailed),
+            (402, ErrorCode::QuotaExceeded),
+            (403, ErrorCode::AuthenticationFailed),
+            (404, ErrorCode::InvalidRequest),
+            (408, ErrorCode::Timeout),
+            (413, ErrorCode::ContextLengthExceeded),
+            (422, ErrorCode::InvalidRequest),
+            (429, ErrorCode::RateLimitExceeded),
+            (500, ErrorCode::InternalError),
+            (503, ErrorCode::Overloaded),
+            (504, ErrorCode::Timeout),
+            (529, ErrorCode::Overloaded),
+        ];
+        for (code, expected) in cases {
+            assert_eq!(error_code_from_status(status(code)), expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_error_code_from_response() {
+        let cases = [
+            (
+                "OpenAI context length",
+                400,
+                r#"{"error":{"message":"This model's maximum context length is 128000 tokens. However, your messages resulted in 130000 tokens. Please reduce the length of the messages.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+                ErrorCode::ContextLengthExceeded,
+            ),
+            (
+                "Anthropic prompt too long",
+                400,
+                r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#,
+                ErrorCode::ContextLengthExceeded,
+            ),
+            (
+                "Gemini input token count",
+                400,
+                r#"{"error":{"code":400,"message":"The input token count (1200000) exceeds the maximum number of tokens allowed (1048576).","status":"INVALID_ARGUMENT"}}"#,
+                ErrorCode::ContextLengthExceeded,
+            ),
+            (
+                "Mistral context length",
+                400,
+                r#"{"object":"error","message":"Prompt contains 40000 tokens and 0 draft tokens, too large for model with 32768 maximum context length","type":"invalid_request_message_error","param":null,"code":"3051"}"#,
+                ErrorCode::ContextLengthExceeded,
+            ),
+            (
+                "Grok prompt length",
+                400,
+                r#"{"code":"Client specified an invalid argument","error":"This model's maximum prompt length is 131072 but the request contains 200000 tokens."}"#,
+                ErrorCode::ContextLengthExceeded,
+            ),
+            (
+                "OpenAI quota",
+                429,
+                r#"{"error":{"message":"You exceeded your current quota, please check your plan and billing details.","type":"insufficient_quota","param":null,"code":"insufficient_quota"}}"#,
+                ErrorCode::QuotaExceeded,
+            ),
+            (
+                "Anthropic credit balance",
+                400,
+                r#"{"type":"error","error":{"type":"invalid_request_error","message":"Your credit balance is too low to access the Anthropic API. Please go to Plans & Billing to upgrade or purchase credits."}}"#,
+                ErrorCode::QuotaExceeded,
+            ),
+            (
+                "OpenRouter credits",
+                402,
+                r#"{"error":{"message":"Insufficient credits. Add more using https://openrouter.ai/settings/credits","code":402}}"#,
+                ErrorCode::QuotaExceeded,
+            ),
+            (
+                "Anthropic overloaded",
+                529,
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+                ErrorCode::Overloaded,
+            ),
+            (
+                "Gemini overloaded model",
+                503,
+                r#"{"error":{"code":503,"message":"The model is overloaded. Please try again later.","status":"UNAVAILABLE"}}"#,
+                ErrorCode::Overloaded,
+            ),
+            (
+                "Azure OpenAI content filter",
+                400,
+                r#"{"error":{"message":"The response was filtered due to the prompt triggering Azure OpenAI's content management policy.","type":null,"param":"prompt","code":"content_filter","status":400}}"#,
+                ErrorCode::ContentFiltered,
+            ),
+            (
+                "OpenAI missing model",
+                404,
+                r#"{"error":{"message":"The model `gpt-9` does not exist or you do not have access to it.","type":"invalid_request_error","param":null,"code":"model_not_found"}}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                "Anthropic missing model",
+                404,
+                r#"{"type":"error","error":{"type":"not_found_error","message":"model: claude-9"}}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                "Gemini missing model",
+                404,
+                r#"{"error":{"code":404,"message":"models/gemini-9 is not found for API version v1beta, or is not supported for generateContent.","status":"NOT_FOUND"}}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                "Ollama missing model",
+                404,
+                r#"{"error":"model \"llama9\" not found, try pulling it first"}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                "Mistral invalid model",
+                400,
+                r#"{"object":"error","message":"Invalid model: mistral-9","type":"invalid_model","param":null,"code":"1500"}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                "OpenRouter invalid model",
+                400,
+                r#"{"error":{"message":"openai/gpt-9 is not a valid model ID","code":400}}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                "OpenAI missing batch",
+                404,
+                r#"{"error":{"message":"No batch found with id 'batch_123'.","type":"invalid_request_error","param":null,"code":null}}"#,
+                ErrorCode::InvalidRequest,
+            ),
+            (
+                "OpenAI missing previous response",
+                404,
+                r#"{"error":{"message":"Previous response with id 'resp_123' not found.","type":"invalid_request_error","param":"previous_response_id","code":"previous_response_not_found"}}"#,
+                ErrorCode::InvalidRequest,
+            ),
+            (
+                "Azure OpenAI missing deployment",
+                404,
+                r#"{"error":{"code":"DeploymentNotFound","message":"The API deployment for this resource does not exist. If you created the deployment within the last 5 minutes, please wait a moment and try again."}}"#,
+                ErrorCode::InvalidRequest,
+            ),
+            (
+                "Wrong endpoint",
+                404,
+                "404 page not found",
+                ErrorCode::InvalidRequest,
+            ),
+            (
+                "Anthropic rate limit",
+                429,
+                r#"{"type":"error","error":{"type":"rate_limit_error","message":"Number of request tokens has exceeded your per-minute rate limit"}}"#,
+                ErrorCode::RateLimitExceeded,
+            ),
+        ];
+        for (name, code, body, expected) in cases {
+            assert_eq!(
+                error_code_from_response(status(code), body),
+                expected,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_retry_after_ms() {
+        let headers = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("retry-after", value.parse().unwrap());
+            headers
+        };
+        assert_eq!(retry_after_ms(&headers("2")), Some(2000));
+        assert_eq!(retry_after_ms(&headers("1e30")), Some(u32::MAX));
+        assert_eq!(retry_after_ms(&headers("-1")), None);
     }
 }
@@ -1,7 +1,14 @@
+pub mod cache;
+pub mod chat_session;
 pub mod chat_stream;
+pub mod citations;
 pub mod config;
 pub mod durability;
 pub mod error;
+pub mod models;
+pub mod pricing;
+pub mod retry;
+pub mod tokens;
 
 #[allow(dead_code)]
 pub mod event_source;
@@ -11,7 +18,13 @@ wit_bindgen::generate!({
     world: "llm-library",
     generate_all,
     generate_unused_types: true,
-    additional_derives: [PartialEq, golem_rust::FromValueAndType, golem_rust::IntoValue],
+    additional_derives: [
+        PartialEq,
+        golem_rust::FromValueAndType,
+        golem_rust::IntoValue,
+        serde::Serialize,
+        serde::Deserialize,
+    ],
     pub_export_macro: true,
 });
 
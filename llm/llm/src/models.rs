@@ -0,0 +1,28 @@
+use crate::golem::llm::llm::{ModelCapabilities, ModelInfo};
+
+/// Limits and capabilities of a model known by a provider implementation
+pub struct KnownModel {
+    /// Identifier of the model, also matching versioned identifiers starting with it
+    pub id: &'static str,
+    pub context_window: u32,
+    pub max_output_tokens: Option<u32>,
+    pub capabilities: ModelCapabilities,
+}
+
+/// Looks up a model in a provider's table of known models.
+///
+/// Versioned model identifiers (like `gpt-4o-2024-08-06`) match the longest known identifier
+/// they start with. Models not in the table get a `model-info` without limits and capabilities.
+pub fn known_model_info(model: &str, known_models: &[KnownModel]) -> ModelInfo {
+    let known_model = known_models
+        .iter()
+        .filter(|known_model| model.starts_with(known_model.id))
+        .max_by_key(|known_model| known_model.id.len());
+
+    ModelInfo {
+        id: model.to_string(),
+        context_window: known_model.map(|known_model| known_model.context_window),
+        max_output_tokens: known_model.and_then(|known_model| known_model.max_output_tokens),
+        capabilities: known_model.map(|known_model| known_model.capabilities),
+    }
+}
@@ -0,0 +1,301 @@
+use crate::config::get_config_key_or_none;
+use crate::golem::llm::llm::{Cost, Error, ResponseMetadata, StreamEvent, Usage};
+use log::warn;
+
+/// Config key of a JSON object of model prices overriding and extending the built-in tables, for
+/// example `{"gpt-4o": {"input": 2.5, "output": 10, "cache-read": 1.25}}`
+const PRICES_ENV_VAR_NAME: &str = "GOLEM_LLM_PRICES";
+
+/// Prices of a model in US dollars per million tokens. The cache and reasoning prices default to
+/// the input and output prices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: Option<f64>,
+    pub cache_write: Option<f64>,
+    pub reasoning: Option<f64>,
+}
+
+/// Price of a model known by a provider implementation
+pub struct KnownPrice {
+    /// Identifier of the model, also matching versioned identifiers starting with it
+    pub id: &'static str,
+    pub price: Price,
+}
+
+/// The prices of a provider's models, and how the provider reports the usage
+pub struct Pricing {
+    /// Whether the input tokens reported by the provider include the tokens read from and written
+    /// to the prompt cache
+    pub input_includes_cached: bool,
+    pub known_prices: &'static [KnownPrice],
+}
+
+/// The price of a single model, calculating the cost of the requests sent to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    price: Price,
+    input_includes_cached: bool,
+}
+
+impl Pricing {
+    /// Looks up the price of a model, preferring the ones configured in `GOLEM_LLM_PRICES` to the
+    /// built-in table. Like with the known models, versioned model identifiers match the longest
+    /// identifier they start with.
+    pub fn model_pricing(&self, model: &str) -> Option<ModelPricing> {
+        self.model_pricing_with(&configured_prices(), model)
+    }
+
+    fn model_pricing_with(
+        &self,
+        configured_prices: &[(String, Price)],
+        model: &str,
+    ) -> Option<ModelPricing> {
+        let overridden = configured_prices
+            .iter()
+            .filter(|(id, _)| model.starts_with(id.as_str()))
+            .max_by_key(|(id, _)| id.len())
+            .map(|(_, price)| *price);
+        let price = overridden.or_else(|| {
+            self.known_prices
+                .iter()
+                .filter(|known_price| model.starts_with(known_price.id))
+                .max_by_key(|known_price| known_price.id.len())
+                .map(|known_price| known_price.price)
+        })?;
+
+        Some(ModelPricing {
+            price,
+            input_includes_cached: self.input_includes_cached,
+        })
+    }
+}
+
+impl ModelPricing {
+    pub fn cost(&self, usage: &Usage) -> Cost {
+        let cache_read_tokens = usage.cache_read_tokens.unwrap_or_default();
+        let cache_write_tokens = usage.cache_write_tokens.unwrap_or_default();
+        let mut input_tokens = usage.input_tokens.unwrap_or_default();
+        if self.input_includes_cached {
+            input_tokens =
+                input_tokens.saturating_sub(cache_read_tokens.saturating_add(cache_write_tokens));
+        }
+        let reasoning_tokens = usage.reasoning_tokens.unwrap_or_default();
+        let output_tokens = usage
+            .output_tokens
+            .unwrap_or_default()
+            .saturating_sub(reasoning_tokens);
+
+        let input = micro_usd(input_tokens, self.price.input);
+        let output = micro_usd(output_tokens, self.price.output);
+        let cached = micro_usd(
+            cache_read_tokens,
+            self.price.cache_read.unwrap_or(self.price.input),
+        ) + micro_usd(
+            cache_write_tokens,
+            self.price.cache_write.unwrap_or(self.price.input),
+        );
+        let reasoning = micro_usd(
+            reasoning_tokens,
+            self.price.reasoning.unwrap_or(self.price.output),
+        );
+
+        Cost {
+            input: Some(input),
+            output: Some(output),
+            cached: Some(cached),
+            reasoning: Some(reasoning),
+            total: input + output + cached + reasoning,
+        }
+    }
+
+    /// Sets the cost of the response from its usage, unless the provider already reported it
+    pub fn add_cost(&self, metadata: &mut ResponseMetadata) {
+        if metadata.cost.is_none() {
+            metadata.cost = metadata.usage.as_ref().map(|usage| self.cost(usage));
+        }
+    }
+
+    /// Sets the cost of the finish events of a stream
+    pub fn add_stream_costs(
+        &self,
+        mut events: Vec<Result<StreamEvent, Error>>,
+    ) -> Vec<Result<StreamEvent, Error>> {
+        for event in &mut events {
+            if let Ok(StreamEvent::Finish(metadata)) = event {
+                self.add_cost(metadata);
+            }
+        }
+        events
+    }
+}
+
+/// Sets the cost of a response to the given model from its usage, if the price of the model is known
+pub fn add_cost(metadata: &mut ResponseMetadata, model: &str, pricing: &Pricing) {
+    if let Some(model_pricing) = pricing.model_pricing(model) {
+        model_pricing.add_cost(metadata);
+    }
+}
+
+/// The cost in millionths of US dollars, as the prices are per million tokens
+fn micro_usd(tokens: u32, price: f64) -> u64 {
+    (tokens as f64 * price).round() as u64
+}
+
+fn configured_prices() -> Vec<(String, Price)> {
+    get_config_key_or_none(PRICES_ENV_VAR_NAME)
+        .map(|prices| parse_prices(&prices))
+        .unwrap_or_default()
+}
+
+fn parse_prices(prices: &str) -> Vec<(String, Price)> {
+    match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(prices) {
+        Ok(prices) => prices
+            .into_iter()
+            .filter_map(|(model, price)| match parse_price(&price) {
+                Some(price) => Some((model, price)),
+                None => {
+                    warn!("Ignoring the invalid price of {model} in {PRICES_ENV_VAR_NAME}");
+                    None
+                }
+            })
+            .collect(),
+        Err(err) => {
+            warn!("Ignoring {PRICES_ENV_VAR_NAME}, it is not a JSON object: {err}");
+            Vec::new()
+        }
+    }
+}
+
+fn parse_price(price: &serde_json::Value) -> Option<Price> {
+    let field = |name: &str| price.get(name).and_then(|value| value.as_f64());
+    Some(Price {
+        input: field("input")?,
+        output: field("output")?,
+        cache_read: field("cache-read"),
+        cache_write: field("cache-write"),
+        reasoning: field("reasoning"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICE: Price = Price {
+        input: 2.0,
+        output: 8.0,
+        cache_read: Some(0.5),
+        cache_write: None,
+        reasoning: None,
+    };
+
+    const PRICING: Pricing = Pricing {
+        input_includes_cached: true,
+        known_prices: &[
+            KnownPrice {
+                id: "gpt-4.1",
+                price: PRICE,
+            },
+            KnownPrice {
+                id: "gpt-4.1-mini",
+                price: Price {
+                    input: 0.4,
+                    output: 1.6,
+                    cache_read: None,
+                    cache_write: None,
+                    reasoning: None,
+                },
+            },
+        ],
+    };
+
+    fn usage(input: u32, output: u32, cache_read: u32, cache_write: u32) -> Usage {
+        Usage {
+            input_tokens: Some(input),
+            output_tokens: Some(output),
+            total_tokens: None,
+            reasoning_tokens: None,
+            cache_read_tokens: Some(cache_read),
+            cache_write_tokens: Some(cache_write),
+        }
+    }
+
+    #[test]
+    fn test_cost_without_cache_tokens() {
+        let pricing = PRICING.model_pricing_with(&[], "gpt-4.1").unwrap();
+        let cost = pricing.cost(&usage(1000, 500, 0, 0));
+        assert_eq!(cost.input, Some(2000));
+        assert_eq!(cost.output, Some(4000));
+        assert_eq!(cost.cached, Some(0));
+        assert_eq!(cost.reasoning, Some(0));
+        assert_eq!(cost.total, 6000);
+    }
+
+    #[test]
+    fn test_cost_with_cache_tokens() {
+        let pricing = PRICING.model_pricing_with(&[], "gpt-4.1").unwrap();
+        let cost = pricing.cost(&usage(1000, 0, 400, 100));
+        // The cached tokens are included in the input tokens, and cache writes cost as much as
+        // the input
+        assert_eq!(cost.input, Some(1000));
+        assert_eq!(cost.cached, Some(400));
+        assert_eq!(cost.total, 1400);
+    }
+
+    #[test]
+    fn test_cost_with_huge_cache_token_counts() {
+        let pricing = PRICING.model_pricing_with(&[], "gpt-4.1").unwrap();
+        let cost = pricing.cost(&usage(1000, 0, u32::MAX, u32::MAX));
+        assert_eq!(cost.input, Some(0));
+    }
+
+    #[test]
+    fn test_model_prefix_lookup() {
+        let price = |model: &str| {
+            PRICING
+                .model_pricing_with(&[], model)
+                .map(|pricing| pricing.price.input)
+        };
+        assert_eq!(price("gpt-4.1"), Some(2.0));
+        assert_eq!(price("gpt-4.1-2025-04-14"), Some(2.0));
+        assert_eq!(price("gpt-4.1-mini-2025-04-14"), Some(0.4));
+        assert_eq!(price("gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_parse_prices() {
+        let prices = parse_prices(
+            r#"{"gpt-4.1": {"input": 1, "output": 4, "cache-read": 0.25}, "my-model": {"input": 3}}"#,
+        );
+        assert_eq!(
+            prices,
+            vec![(
+                "gpt-4.1".to_string(),
+                Price {
+                    input: 1.0,
+                    output: 4.0,
+                    cache_read: Some(0.25),
+                    cache_write: None,
+                    reasoning: None,
+                }
+            )]
+        );
+        assert_eq!(parse_prices("not json"), vec![]);
+        assert_eq!(parse_prices(r#"["gpt-4.1"]"#), vec![]);
+    }
+
+    #[test]
+    fn test_configured_prices_override_known_prices() {
+        let configured = parse_prices(r#"{"gpt-4.1-2025": {"input": 1, "output": 4}}"#);
+        let price = |model: &str| {
+            PRICING
+                .model_pricing_with(&configured, model)
+                .map(|pricing| pricing.price.input)
+        };
+        assert_eq!(price("gpt-4.1-2025-04-14"), Some(1.0));
+        assert_eq!(price("gpt-4.1"), Some(2.0));
+        assert_eq!(price("gpt-4.1-mini"), Some(0.4));
+    }
+}
@@ -0,0 +1,362 @@
+use crate::config::get_config_key_or_none;
+use golem_rust::bindings::wasi::clocks::monotonic_clock::subscribe_duration;
+use log::warn;
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct RetryConfig {
+    pub max_attempts: usize,
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryConfig {
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 4,
+            min_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// Reads the retry configuration from the `GOLEM_LLM_MAX_RETRIES`, `GOLEM_LLM_RETRY_MIN_DELAY_MS`
+    /// and `GOLEM_LLM_RETRY_MAX_DELAY_MS` config keys, using the defaults for the missing ones
+    pub fn from_env() -> Self {
+        Self::from_lookup(get_config_key_or_none)
+    }
+
+    fn from_lookup(lookup: impl Fn(&'static str) -> Option<String>) -> Self {
+        let mut config = Self::new();
+        if let Some(max_retries) =
+            lookup("GOLEM_LLM_MAX_RETRIES").and_then(|value| value.parse::<usize>().ok())
+        {
+            config = config.with_max_attempts(max_retries + 1);
+        }
+        if let Some(min_delay) =
+            lookup("GOLEM_LLM_RETRY_MIN_DELAY_MS").and_then(|value| value.parse::<u64>().ok())
+        {
+            config = config.with_min_delay(Duration::from_millis(min_delay));
+        }
+        if let Some(max_delay) =
+            lookup("GOLEM_LLM_RETRY_MAX_DELAY_MS").and_then(|value| value.parse::<u64>().ok())
+        {
+            config = config.with_max_delay(Duration::from_millis(max_delay));
+        }
+        config
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    fn backoff(&self, attempts: usize) -> Duration {
+        let exponent = u32::try_from(attempts.saturating_sub(1)).unwrap_or(u32::MAX);
+        let delay = self
+            .min_delay
+            .checked_mul(2_u32.saturating_pow(exponent))
+            .unwrap_or(self.max_delay);
+        std::cmp::min(delay, self.max_delay)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Which failures of a request are retried
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryPolicy {
+    /// Only the failures telling that the server did not process the request: rate limiting
+    /// (`429`), Anthropic's `overloaded_error` (`529`) and `503` responses telling when to retry.
+    /// Connection errors and other server errors are not retried, as the request may have been
+    /// processed, so this is safe for requests which must not be repeated, like billed completions,
+    /// uploads or creating batches.
+    Unprocessed,
+    /// Every transient failure, including connection errors, timeouts and server errors, for
+    /// requests which can be repeated without side effects, like reads or cancellations
+    Idempotent,
+}
+
+impl RetryPolicy {
+    fn retries_status(self, status: StatusCode, headers: &HeaderMap) -> bool {
+        match self {
+            RetryPolicy::Unprocessed => match status.as_u16() {
+                429 | 529 => true,
+                503 => retry_after(headers).is_some(),
+                _ => false,
+            },
+            RetryPolicy::Idempotent => matches!(status.as_u16(), 408 | 429 | 500..=599),
+        }
+    }
+
+    fn retries_connection_errors(self) -> bool {
+        self == RetryPolicy::Idempotent
+    }
+}
+
+/// Sends the request created by `request`, retrying the failures where the request was not
+/// processed by the server with exponential backoff, configured by `RetryConfig::from_env`. Safe
+/// for every request, see `RetryPolicy::Unprocessed`.
+///
+/// The request is rebuilt for every attempt, so `request` must not consume anything it captures.
+pub fn send_with_retry(request: impl Fn() -> RequestBuilder) -> Result<Response, reqwest::Error> {
+    send_with_retry_config(&RetryConfig::from_env(), RetryPolicy::Unprocessed, request)
+}
+
+/// Sends the request created by `request` like `send_with_retry`, but also retrying connection
+/// errors, timeouts and server errors. Only for requests which can be repeated without side
+/// effects, see `RetryPolicy::Idempotent`.
+pub fn send_idempotent_with_retry(
+    request: impl Fn() -> RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    send_with_retry_config(&RetryConfig::from_env(), RetryPolicy::Idempotent, request)
+}
+
+/// Sends the request created by `request` with the given retry configuration and policy.
+///
+/// Responses with a `Retry-After` (or `retry-after-ms`) header are retried after the requested
+/// delay, unless it is longer than the maximum delay, in which case the response is returned as is.
+pub fn send_with_retry_config(
+    config: &RetryConfig,
+    policy: RetryPolicy,
+    request: impl Fn() -> RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let mut attempts = 1;
+    loop {
+        let result = request().send();
+        if attempts >= config.max_attempts {
+            return result;
+        }
+
+        let delay = match &result {
+            Ok(response) if policy.retries_status(response.status(), response.headers()) => {
+                match retry_after(response.headers()) {
+                    Some(delay) if delay > config.max_delay => return result,
+                    Some(delay) => delay,
+                    None => config.backoff(attempts),
+                }
+            }
+            Ok(_) => return result,
+            Err(_) if policy.retries_connection_errors() => config.backoff(attempts),
+            Err(_) => return result,
+        };
+
+        match &result {
+            Ok(response) => warn!(
+                "Request failed with status {}, retrying in {delay:?}",
+                response.status()
+            ),
+            Err(err) => warn!("Request failed with {err}, retrying in {delay:?}"),
+        }
+        drop(result);
+
+        subscribe_duration(delay.as_nanos() as u64).block();
+        attempts += 1;
+    }
+}
+
+/// Gets the delay requested by the server, either in the non-standard `retry-after-ms` header or in
+/// seconds in `Retry-After`. HTTP dates in `Retry-After` are not supported and fall back to backoff.
+///
+/// Delays too long to be represented are clamped to `Duration::MAX`, so they are still longer than
+/// any maximum delay and the response is returned instead of being retried.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| value.is_finite() && *value >= 0.0)
+    };
+    let delay = |secs: f64| Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX);
+    header("retry-after-ms")
+        .map(|millis| delay(millis / 1000.0))
+        .or_else(|| header("retry-after").map(delay))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::collections::HashMap;
+
+    fn headers(entries: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn status(code: u16) -> StatusCode {
+        StatusCode::from_u16(code).unwrap()
+    }
+
+    #[test]
+    fn test_backoff_doubles_from_min_delay() {
+        let config = RetryConfig::new()
+            .with_min_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(10));
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(200));
+        assert_eq!(config.backoff(3), Duration::from_millis(400));
+        assert_eq!(config.backoff(4), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_backoff_is_capped_at_max_delay() {
+        let config = RetryConfig::new()
+            .with_min_delay(Duration::from_millis(500))
+            .with_max_delay(Duration::from_secs(2));
+        assert_eq!(config.backoff(3), Duration::from_secs(2));
+        assert_eq!(config.backoff(64), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_backoff_does_not_overflow() {
+        let config = RetryConfig::new()
+            .with_min_delay(Duration::from_millis(u64::MAX))
+            .with_max_delay(Duration::from_secs(30));
+        assert_eq!(config.backoff(0), Duration::from_secs(30));
+        assert_eq!(config.backoff(2), Duration::from_secs(30));
+        assert_eq!(config.backoff(usize::MAX), Duration::from_secs(30));
+
+        let config = RetryConfig::new()
+            .with_min_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(10));
+        assert_eq!(config.backoff(0), Duration::from_millis(100));
+        assert_eq!(config.backoff(usize::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "3")])),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", " 1.5 ")])),
+            Some(Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_retry_after_ms_takes_precedence() {
+        assert_eq!(
+            retry_after(&headers(&[("retry-after-ms", "250"), ("retry-after", "3")])),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            retry_after(&headers(&[
+                ("retry-after-ms", "later"),
+                ("retry-after", "3")
+            ])),
+            Some(Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    fn test_retry_after_huge_values() {
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "1e30")])),
+            Some(Duration::MAX)
+        );
+        assert_eq!(
+            retry_after(&headers(&[("retry-after-ms", "1e30")])),
+            Some(Duration::MAX)
+        );
+    }
+
+    #[test]
+    fn test_retry_after_invalid_values() {
+        assert_eq!(retry_after(&headers(&[])), None);
+        assert_eq!(
+            retry_after(&headers(&[(
+                "retry-after",
+                "Wed, 21 Oct 2015 07:28:00 GMT"
+            )])),
+            None
+        );
+        assert_eq!(retry_after(&headers(&[("retry-after", "-1")])), None);
+        assert_eq!(retry_after(&headers(&[("retry-after-ms", "-250")])), None);
+        assert_eq!(retry_after(&headers(&[("retry-after", "soon")])), None);
+        assert_eq!(retry_after(&headers(&[("retry-after", "NaN")])), None);
+    }
+
+    #[test]
+    fn test_unprocessed_policy_statuses() {
+        let policy = RetryPolicy::Unprocessed;
+        assert!(policy.retries_status(status(429), &headers(&[])));
+        assert!(policy.retries_status(status(529), &headers(&[])));
+        assert!(policy.retries_status(status(503), &headers(&[("retry-after", "1")])));
+        assert!(!policy.retries_status(status(503), &headers(&[])));
+        assert!(!policy.retries_status(status(500), &headers(&[])));
+        assert!(!policy.retries_status(status(502), &headers(&[])));
+        assert!(!policy.retries_status(status(504), &headers(&[])));
+        assert!(!policy.retries_status(status(408), &headers(&[])));
+        assert!(!policy.retries_status(status(400), &headers(&[])));
+        assert!(!policy.retries_connection_errors());
+    }
+
+    #[test]
+    fn test_idempotent_policy_statuses() {
+        let policy = RetryPolicy::Idempotent;
+        for code in [408, 429, 500, 502, 503, 504, 529] {
+            assert!(policy.retries_status(status(code), &headers(&[])), "{code}");
+        }
+        for code in [200, 400, 401, 404, 422] {
+            assert!(
+                !policy.retries_status(status(code), &headers(&[])),
+                "{code}"
+            );
+        }
+        assert!(policy.retries_connection_errors());
+    }
+
+    #[test]
+    fn test_config_defaults_without_keys() {
+        let config = RetryConfig::from_lookup(|_| None);
+        assert_eq!(config.max_attempts, 4);
+        assert_eq!(config.min_delay, Duration::from_millis(500));
+        assert_eq!(config.max_delay, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_config_from_keys() {
+        let values = HashMap::from([
+            ("GOLEM_LLM_MAX_RETRIES", "0"),
+            ("GOLEM_LLM_RETRY_MIN_DELAY_MS", "100"),
+            ("GOLEM_LLM_RETRY_MAX_DELAY_MS", "5000"),
+        ]);
+        let config = RetryConfig::from_lookup(|key| values.get(key).map(|value| value.to_string()));
+        assert_eq!(config.max_attempts, 1);
+        assert_eq!(config.min_delay, Duration::from_millis(100));
+        assert_eq!(config.max_delay, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_config_ignores_invalid_values() {
+        let values = HashMap::from([
+            ("GOLEM_LLM_MAX_RETRIES", "many"),
+            ("GOLEM_LLM_RETRY_MIN_DELAY_MS", "-1"),
+        ]);
+        let config = RetryConfig::from_lookup(|key| values.get(key).map(|value| value.to_string()));
+        assert_eq!(config.max_attempts, 4);
+        assert_eq!(config.min_delay, Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::golem::llm::llm::{
+    Config, ContentPart, Document, Event, ImageDetail, ImageReference, MediaSource,
+    ResponseFormat, ToolResult,
+};
+
+/// Average number of characters per token of the common BPE tokenizers for English text
+const CHARS_PER_TOKEN: usize = 4;
+/// Tokens used by the role and the separators of each message
+const TOKENS_PER_MESSAGE: u32 = 4;
+/// Tokens used to prime the model's response
+const TOKENS_PER_REPLY: u32 = 3;
+/// Tokens of a low detail image, and of a typical 1024x1024 image, as counted by OpenAI
+const TOKENS_PER_LOW_DETAIL_IMAGE: u32 = 85;
+const TOKENS_PER_IMAGE: u32 = 765;
+/// Rough tokens of a document or audio whose contents cannot be measured as text, such as a single
+/// PDF page or a minute of audio
+const TOKENS_PER_MEDIA: u32 = 1500;
+
+/// Estimates the number of input tokens the given events and configuration would use.
+///
+/// Used by providers not having a token counting endpoint. The estimate is based on the average
+/// characters per token ratio of the common tokenizers, so it is only accurate within about 10-20%
+/// for English text, and tends to underestimate for other languages.
+pub fn estimate_tokens(events: &[Event], config: &Config) -> u32 {
+    let mut tokens = TOKENS_PER_REPLY;
+
+    for event in events {
+        tokens += TOKENS_PER_MESSAGE;
+        match event {
+            Event::Message(message) => {
+                tokens += content_tokens(&message.content);
+            }
+            Event::Response(response) => {
+                tokens += content_tokens(&response.content);
+                for tool_call in &response.tool_calls {
+                    tokens += text_tokens(&tool_call.name) + text_tokens(&tool_call.arguments_json);
+                }
+            }
+            Event::ToolResults(tool_results) => {
+                for tool_result in tool_results {
+                    tokens += match tool_result {
+                        ToolResult::Success(success) => {
+                            text_tokens(&success.name) + text_tokens(&success.result_json)
+                        }
+                        ToolResult::Error(failure) => {
+                            text_tokens(&failure.name) + text_tokens(&failure.error_message)
+                        }
+                    };
+                }
+            }
+        }
+    }
+
+    for tool in config.tools.iter().flatten() {
+        tokens += text_tokens(&tool.name)
+            + text_tokens(tool.description.as_deref().unwrap_or_default())
+            + text_tokens(&tool.parameters_schema);
+    }
+
+    if let Some(ResponseFormat::JsonSchema(format)) = &config.response_format {
+        tokens += text_tokens(&format.schema);
+    }
+
+    tokens
+}
+
+fn content_tokens(content: &[ContentPart]) -> u32 {
+    content
+        .iter()
+        .map(|part| match part {
+            ContentPart::Text(text) => text_tokens(text),
+            ContentPart::Image(image) => image_tokens(image),
+            ContentPart::Reasoning(reasoning) => text_tokens(&reasoning.text),
+            ContentPart::Document(document) => document_tokens(document),
+            ContentPart::Audio(_) => TOKENS_PER_MEDIA,
+            ContentPart::BuiltinToolCall(call) => {
+                text_tokens(call.input.as_deref().unwrap_or_default())
+                    + text_tokens(call.output.as_deref().unwrap_or_default())
+                    + call
+                        .results
+                        .iter()
+                        .map(|result| text_tokens(result.text.as_deref().unwrap_or_default()))
+                        .sum::<u32>()
+            }
+        })
+        .sum()
+}
+
+fn text_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u32
+}
+
+fn document_tokens(document: &Document) -> u32 {
+    match &document.source {
+        MediaSource::Inline(data) if document.mime_type.starts_with("text/") => {
+            text_tokens(&String::from_utf8_lossy(data))
+        }
+        _ => TOKENS_PER_MEDIA,
+    }
+}
+
+fn image_tokens(image: &ImageReference) -> u32 {
+    let detail = match image {
+        ImageReference::Url(url) => url.detail,
+        ImageReference::Inline(source) => source.detail,
+    };
+    match detail {
+        Some(ImageDetail::Low) => TOKENS_PER_LOW_DETAIL_IMAGE,
+        _ => TOKENS_PER_IMAGE,
+    }
+}
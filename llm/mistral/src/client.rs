@@ -0,0 +1,371 @@
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::llm::Error;
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
+use log::trace;
+use reqwest::header::HeaderValue;
+use reqwest::{Client, Method, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+const BASE_URL: &str = "https://api.mistral.ai";
+
+/// The Chat Completions API client for creating model responses.
+pub struct ChatCompletionsApi {
+    api_key: String,
+    client: Client,
+}
+
+impl ChatCompletionsApi {
+    pub fn new(api_key: String) -> Self {
+        let client = Client::builder()
+            .build()
+            .expect("Failed to initialize HTTP client");
+        Self { api_key, client }
+    }
+
+    pub fn send_messages(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        trace!("Sending request to Mistral API: {request:?}");
+
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/chat/completions"))
+                .bearer_auth(self.api_key.clone())
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn stream_send_messages(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<EventSource, Error> {
+        trace!("Sending request to Mistral API: {request:?}");
+
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/chat/completions"))
+                .bearer_auth(self.api_key.clone())
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        trace!("Initializing SSE stream");
+
+        EventSource::new(response)
+            .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
+    }
+
+    pub fn list_models(&self) -> Result<ModelsResponse, Error> {
+        trace!("Listing models of Mistral API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, format!("{BASE_URL}/v1/models"))
+                .bearer_auth(self.api_key.clone())
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelsResponse {
+    pub data: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub capabilities: Option<ModelCapabilities>,
+    pub max_context_length: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    #[serde(default)]
+    pub completion_chat: bool,
+    #[serde(default)]
+    pub function_calling: bool,
+    #[serde(default)]
+    pub vision: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    pub messages: Vec<Message>,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safe_prompt: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseFormat {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "json_object")]
+    JsonObject,
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchema },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Tool {
+    #[serde(rename = "function")]
+    Function { function: Function },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Function {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    String(String), // none, auto, any or required
+    Function(ToolChoiceFunction),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolChoiceFunction {
+    #[serde(rename = "function")]
+    Function { function: FunctionName },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionName {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "role")]
+pub enum Message {
+    #[serde(rename = "system")]
+    System { content: Content },
+    #[serde(rename = "user")]
+    User { content: Content },
+    #[serde(rename = "assistant")]
+    Assistant {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        content: Option<Content>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tool_calls: Option<Vec<ToolCall>>,
+    },
+    #[serde(rename = "tool")]
+    Tool {
+        content: Content,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tool_call_id: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text(String),
+    List(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ContentPart {
+    #[serde(rename = "text")]
+    Text { text: String },
+    /// An image by its URL, or inlined as a data URL
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: String },
+}
+
+/// A tool call, the type of which is not always present in the responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", default)]
+    pub tool_type: ToolType,
+    pub function: FunctionCall,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub enum ToolType {
+    #[serde(rename = "function")]
+    #[default]
+    Function,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub arguments: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    pub choices: Vec<Choice>,
+    pub created: u64,
+    pub id: String,
+    pub model: String,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Choice {
+    pub finish_reason: Option<FinishReason>,
+    pub index: u32,
+    pub message: ResponseMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FinishReason {
+    #[serde(rename = "stop")]
+    Stop,
+    #[serde(rename = "length")]
+    Length,
+    #[serde(rename = "model_length")]
+    ModelLength,
+    #[serde(rename = "error")]
+    Error,
+    #[serde(rename = "tool_calls")]
+    ToolCalls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseMessage {
+    pub content: Option<ResponseContent>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+}
+
+/// Content of a response. Reasoning models return a list of chunks, separating the thinking from
+/// the answer, the others a single string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseContent {
+    Text(String),
+    Chunks(Vec<ResponseChunk>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseChunk {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "thinking")]
+    Thinking { thinking: Vec<ResponseChunk> },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub completion_tokens: u32,
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub created: Option<u64>,
+    pub model: String,
+    pub choices: Vec<ChoiceChunk>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceChunk {
+    pub index: u32,
+    pub delta: ChoiceDelta,
+    pub finish_reason: Option<FinishReason>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceDelta {
+    pub content: Option<ResponseContent>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub role: Option<String>,
+}
+
+fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T, Error> {
+    let status = response.status();
+    if status.is_success() {
+        let body = response
+            .json::<T>()
+            .map_err(|err| from_reqwest_error("Failed to decode response body", err))?;
+
+        trace!("Received response from Mistral API: {body:?}");
+
+        Ok(body)
+    } else {
+        let retry_after_ms = retry_after_ms(response.headers());
+        let error_body = response
+            .text()
+            .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
+
+        trace!("Received {status} response from Mistral API: {error_body:?}");
+
+        Err(Error {
+            code: error_code_from_response(status, &error_body),
+            message: format!("Request failed with {status}"),
+            provider_error_json: Some(serde_json::to_string(&error_body).unwrap()),
+            retry_after_ms,
+        })
+    }
+}
@@ -0,0 +1,363 @@
+use crate::client::{
+    ChatCompletionRequest, ChatCompletionResponse, FunctionName, ResponseChunk, ResponseContent,
+    ToolChoiceFunction, ToolType,
+};
+use base64::{engine::general_purpose, Engine as _};
+use golem_llm::error::unsupported;
+use golem_llm::golem::llm::llm::{
+    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageReference, ReasoningContent,
+    Response, ResponseFormat, ResponseMetadata, Role, ToolCall, ToolCallDelta, ToolChoice,
+    ToolDefinition, ToolResult, Usage,
+};
+use std::collections::HashMap;
+
+pub fn events_to_request(
+    events: Vec<Event>,
+    config: Config,
+) -> Result<ChatCompletionRequest, Error> {
+    let options = config
+        .provider_options
+        .map(|options| {
+            options
+                .into_iter()
+                .map(|kv| (kv.key, kv.value))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut completion_messages = Vec::new();
+    for event in events {
+        match event {
+            Event::Message(message) => match message.role {
+                Role::User => completion_messages.push(crate::client::Message::User {
+                    content: convert_content_parts_to_client_content(message.content)?,
+                }),
+                Role::Assistant => completion_messages.push(crate::client::Message::Assistant {
+                    content: Some(convert_content_parts_to_client_content(message.content)?),
+                    tool_calls: None,
+                }),
+                Role::System => completion_messages.push(crate::client::Message::System {
+                    content: convert_content_parts_to_client_content(message.content)?,
+                }),
+                Role::Tool => completion_messages.push(crate::client::Message::Tool {
+                    name: message.name,
+                    content: convert_content_parts_to_client_content(message.content)?,
+                    tool_call_id: None,
+                }),
+            },
+            Event::ToolResults(tool_results) => {
+                completion_messages.extend(tool_results.into_iter().map(tool_result_to_message))
+            }
+            Event::Response(response) => {
+                if !response.content.is_empty() || !response.tool_calls.is_empty() {
+                    completion_messages.push(crate::client::Message::Assistant {
+                        content: (!response.content.is_empty())
+                            .then(|| convert_content_parts_to_client_content(response.content))
+                            .transpose()?,
+                        tool_calls: (!response.tool_calls.is_empty()).then(|| {
+                            response
+                                .tool_calls
+                                .into_iter()
+                                .map(convert_tool_call_to_client_tool_call)
+                                .collect::<Vec<_>>()
+                        }),
+                    })
+                }
+            }
+        }
+    }
+
+    let mut tools = Vec::new();
+    for tool in config.tools.unwrap_or_default() {
+        tools.push(tool_definition_to_tool(tool)?)
+    }
+
+    let response_format = config
+        .response_format
+        .map(convert_response_format)
+        .transpose()?;
+
+    // Magistral models always reason, and the API has no reasoning options to map the config to
+    Ok(ChatCompletionRequest {
+        messages: completion_messages,
+        model: config.model,
+        frequency_penalty: options
+            .get("frequency_penalty")
+            .and_then(|fp_s| fp_s.parse::<f32>().ok()),
+        max_tokens: config.max_tokens,
+        n: options.get("n").and_then(|n_s| n_s.parse::<u32>().ok()),
+        parallel_tool_calls: config.parallel_tool_calls,
+        presence_penalty: options
+            .get("presence_penalty")
+            .and_then(|pp_s| pp_s.parse::<f32>().ok()),
+        random_seed: options
+            .get("random_seed")
+            .and_then(|seed_s| seed_s.parse::<u32>().ok()),
+        response_format,
+        safe_prompt: options
+            .get("safe_prompt")
+            .and_then(|safe_prompt_s| safe_prompt_s.parse::<bool>().ok()),
+        stop: config.stop_sequences,
+        stream: Some(false),
+        temperature: config.temperature,
+        tool_choice: config.tool_choice.map(convert_tool_choice),
+        tools,
+        top_p: options
+            .get("top_p")
+            .and_then(|top_p_s| top_p_s.parse::<f32>().ok()),
+    })
+}
+
+pub fn process_response(mut response: ChatCompletionResponse) -> Result<Response, Error> {
+    let choice = response.choices.pop();
+    match choice {
+        Some(choice) => {
+            let content = choice
+                .message
+                .content
+                .map(response_content_to_content_parts)
+                .unwrap_or_default();
+
+            let tool_calls = choice
+                .message
+                .tool_calls
+                .map(|tool_calls| {
+                    tool_calls
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, tool_call)| {
+                            convert_client_tool_call_to_tool_call(tool_call, index as u32)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let metadata = ResponseMetadata {
+                finish_reason: choice.finish_reason.as_ref().map(convert_finish_reason),
+                usage: response.usage.as_ref().map(convert_usage),
+                provider_id: Some(response.id.clone()),
+                timestamp: Some(response.created.to_string()),
+                provider_metadata_json: None,
+                cost: None,
+            };
+
+            Ok(Response {
+                id: response.id,
+                content,
+                tool_calls,
+                metadata,
+                citations: Vec::new(),
+            })
+        }
+        None => Err(Error {
+            code: ErrorCode::InternalError,
+            message: "No choices in response".to_string(),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+    }
+}
+
+/// Converts the content of a response or of a stream delta, separating the thinking of reasoning
+/// models from their answer
+pub fn response_content_to_content_parts(content: ResponseContent) -> Vec<ContentPart> {
+    match content {
+        ResponseContent::Text(text) if text.is_empty() => Vec::new(),
+        ResponseContent::Text(text) => vec![ContentPart::Text(text)],
+        ResponseContent::Chunks(chunks) => chunks
+            .into_iter()
+            .filter_map(|chunk| match chunk {
+                ResponseChunk::Text { text } => Some(ContentPart::Text(text)),
+                ResponseChunk::Thinking { thinking } => {
+                    Some(ContentPart::Reasoning(ReasoningContent {
+                        text: chunks_to_text(thinking),
+                        signature: None,
+                        redacted_data: None,
+                    }))
+                }
+                ResponseChunk::Other => None,
+            })
+            .collect(),
+    }
+}
+
+fn chunks_to_text(chunks: Vec<ResponseChunk>) -> String {
+    chunks
+        .into_iter()
+        .filter_map(|chunk| match chunk {
+            ResponseChunk::Text { text } => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn tool_result_to_message(tool_result: ToolResult) -> crate::client::Message {
+    match tool_result {
+        ToolResult::Success(success) => crate::client::Message::Tool {
+            name: Some(success.name),
+            content: crate::client::Content::Text(success.result_json),
+            tool_call_id: Some(success.id),
+        },
+        ToolResult::Error(failure) => crate::client::Message::Tool {
+            name: Some(failure.name),
+            content: crate::client::Content::Text(failure.error_message),
+            tool_call_id: Some(failure.id),
+        },
+    }
+}
+
+/// Converts a tool call of a response, naming the calls without an identifier by their index
+pub fn convert_client_tool_call_to_tool_call(
+    tool_call: crate::client::ToolCall,
+    index: u32,
+) -> ToolCall {
+    ToolCall {
+        id: tool_call
+            .id
+            .unwrap_or_else(|| format!("{}-{index}", tool_call.function.name)),
+        name: tool_call.function.name,
+        arguments_json: tool_call.function.arguments,
+    }
+}
+
+pub fn convert_client_tool_call_to_tool_call_delta(
+    tool_call: crate::client::ToolCall,
+    index: u32,
+) -> ToolCallDelta {
+    ToolCallDelta {
+        index,
+        id: tool_call.id,
+        name: Some(tool_call.function.name),
+        arguments_fragment: tool_call.function.arguments,
+    }
+}
+
+pub fn convert_tool_call_to_client_tool_call(tool_call: ToolCall) -> crate::client::ToolCall {
+    crate::client::ToolCall {
+        id: Some(tool_call.id),
+        tool_type: ToolType::Function,
+        function: crate::client::FunctionCall {
+            name: tool_call.name,
+            arguments: tool_call.arguments_json,
+        },
+        index: None,
+    }
+}
+
+fn convert_content_parts_to_client_content(
+    contents: Vec<ContentPart>,
+) -> Result<crate::client::Content, Error> {
+    let mut result = Vec::new();
+    for content in contents {
+        match content {
+            ContentPart::Text(text) => result.push(crate::client::ContentPart::Text { text }),
+            // Mistral has no image detail levels
+            ContentPart::Image(image_reference) => match image_reference {
+                ImageReference::Url(image_url) => {
+                    result.push(crate::client::ContentPart::ImageUrl {
+                        image_url: image_url.url,
+                    })
+                }
+                ImageReference::Inline(image_source) => {
+                    let base64_data = general_purpose::STANDARD.encode(&image_source.data);
+                    let media_type = &image_source.mime_type;
+                    result.push(crate::client::ContentPart::ImageUrl {
+                        image_url: format!("data:{media_type};base64,{base64_data}"),
+                    });
+                }
+            },
+            // The thinking of reasoning models is not sent back
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            ContentPart::BuiltinToolCall(_) => {}
+        }
+    }
+    Ok(crate::client::Content::List(result))
+}
+
+fn convert_tool_choice(tool_choice: ToolChoice) -> crate::client::ToolChoice {
+    match tool_choice {
+        ToolChoice::Auto => crate::client::ToolChoice::String("auto".to_string()),
+        ToolChoice::None => crate::client::ToolChoice::String("none".to_string()),
+        ToolChoice::Required => crate::client::ToolChoice::String("required".to_string()),
+        ToolChoice::Specific(name) => {
+            crate::client::ToolChoice::Function(ToolChoiceFunction::Function {
+                function: FunctionName { name },
+            })
+        }
+    }
+}
+
+pub fn convert_finish_reason(value: &crate::client::FinishReason) -> FinishReason {
+    match value {
+        crate::client::FinishReason::Stop => FinishReason::Stop,
+        crate::client::FinishReason::Length | crate::client::FinishReason::ModelLength => {
+            FinishReason::Length
+        }
+        crate::client::FinishReason::Error => FinishReason::Error,
+        crate::client::FinishReason::ToolCalls => FinishReason::ToolCalls,
+    }
+}
+
+pub fn convert_usage(value: &crate::client::Usage) -> Usage {
+    Usage {
+        input_tokens: Some(value.prompt_tokens),
+        output_tokens: Some(value.completion_tokens),
+        total_tokens: Some(value.total_tokens),
+        reasoning_tokens: None,
+        cache_read_tokens: None,
+        cache_write_tokens: None,
+    }
+}
+
+fn tool_definition_to_tool(tool: ToolDefinition) -> Result<crate::client::Tool, Error> {
+    if tool.builtin.is_some() {
+        return Err(unsupported("builtin tools"));
+    }
+
+    match serde_json::from_str(&tool.parameters_schema) {
+        Ok(value) => Ok(crate::client::Tool::Function {
+            function: crate::client::Function {
+                name: tool.name,
+                description: tool.description,
+                parameters: value,
+            },
+        }),
+        Err(error) => Err(Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to parse tool parameters for {}: {error}", tool.name),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+    }
+}
+
+fn convert_response_format(
+    response_format: ResponseFormat,
+) -> Result<crate::client::ResponseFormat, Error> {
+    match response_format {
+        ResponseFormat::Text => Ok(crate::client::ResponseFormat::Text),
+        ResponseFormat::JsonObject => Ok(crate::client::ResponseFormat::JsonObject),
+        ResponseFormat::JsonSchema(json_schema) => {
+            match serde_json::from_str(&json_schema.schema) {
+                Ok(schema) => Ok(crate::client::ResponseFormat::JsonSchema {
+                    json_schema: crate::client::JsonSchema {
+                        name: json_schema.name,
+                        schema,
+                        strict: json_schema.strict,
+                    },
+                }),
+                Err(error) => Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Failed to parse response format schema {}: {error}",
+                        json_schema.name
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                }),
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+mod client;
+mod conversions;
+mod models;
+
+use crate::client::{ChatCompletionChunk, ChatCompletionRequest, ChatCompletionsApi};
+use crate::conversions::{
+    convert_client_tool_call_to_tool_call_delta, convert_finish_reason, convert_usage,
+    events_to_request, process_response, response_content_to_content_parts,
+};
+use crate::models::{KNOWN_MODELS, PRICING};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
+use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
+use golem_llm::config::{get_config_key, with_config_key};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::unsupported;
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{
+    ChatStream, Config, Error, ErrorCode, Event, FinishReason, Guest, ModelInfo, Response,
+    ResponseMetadata, StreamDelta, StreamEvent,
+};
+use golem_llm::models::known_model_info;
+use golem_llm::pricing::add_cost;
+use golem_llm::tokens::estimate_tokens;
+use golem_rust::wasm_rpc::Pollable;
+use log::trace;
+use std::cell::{Ref, RefCell, RefMut};
+
+pub struct MistralChatStream {
+    stream: RefCell<Option<EventSource>>,
+    failure: Option<Error>,
+    finished: RefCell<bool>,
+    finish_reason: RefCell<Option<FinishReason>>,
+    next_tool_call: RefCell<u32>,
+    pending_finish: RefCell<Option<ResponseMetadata>>,
+}
+
+impl MistralChatStream {
+    pub fn new(stream: EventSource) -> LlmChatStream<Self> {
+        LlmChatStream::new(MistralChatStream {
+            stream: RefCell::new(Some(stream)),
+            failure: None,
+            finished: RefCell::new(false),
+            finish_reason: RefCell::new(None),
+            next_tool_call: RefCell::new(0),
+            pending_finish: RefCell::new(None),
+        })
+    }
+
+    pub fn failed(error: Error) -> LlmChatStream<Self> {
+        LlmChatStream::new(MistralChatStream {
+            stream: RefCell::new(None),
+            failure: Some(error),
+            finished: RefCell::new(false),
+            finish_reason: RefCell::new(None),
+            next_tool_call: RefCell::new(0),
+            pending_finish: RefCell::new(None),
+        })
+    }
+}
+
+impl LlmChatStreamState for MistralChatStream {
+    fn failure(&self) -> &Option<Error> {
+        &self.failure
+    }
+
+    fn is_finished(&self) -> bool {
+        *self.finished.borrow()
+    }
+
+    fn set_finished(&self) {
+        *self.finished.borrow_mut() = true;
+    }
+
+    fn stream(&self) -> Ref<'_, Option<EventSource>> {
+        self.stream.borrow()
+    }
+
+    fn stream_mut(&self) -> RefMut<'_, Option<EventSource>> {
+        self.stream.borrow_mut()
+    }
+
+    fn decode_message(&self, raw: &str) -> Result<Option<StreamEvent>, Error> {
+        fn decode_internal_error<S: Into<String>>(message: S) -> Error {
+            Error {
+                code: ErrorCode::InternalError,
+                message: message.into(),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }
+        }
+
+        trace!("Received raw stream event: {raw}");
+        let chunk: ChatCompletionChunk = serde_json::from_str(raw)
+            .map_err(|err| decode_internal_error(format!("Failed to parse stream event: {err}")))?;
+
+        let mut stream_event = None;
+        if let Some(choice) = chunk.choices.into_iter().next() {
+            if let Some(finish_reason) = choice.finish_reason {
+                *self.finish_reason.borrow_mut() = Some(convert_finish_reason(&finish_reason));
+            }
+            let content = choice
+                .delta
+                .content
+                .map(response_content_to_content_parts)
+                .unwrap_or_default();
+            let tool_call_deltas = choice.delta.tool_calls.map(|calls| {
+                // Mistral sends every tool call complete, in a single fragment
+                let mut next_tool_call = self.next_tool_call.borrow_mut();
+                calls
+                    .into_iter()
+                    .map(|call| {
+                        let index = *next_tool_call;
+                        *next_tool_call += 1;
+                        convert_client_tool_call_to_tool_call_delta(call, index)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            if !content.is_empty() || tool_call_deltas.is_some() {
+                stream_event = Some(StreamEvent::Delta(StreamDelta {
+                    content: (!content.is_empty()).then_some(content),
+                    tool_calls: None,
+                    tool_call_deltas,
+                    citations: None,
+                }));
+            }
+        }
+
+        // The usage is sent with the last chunk, which may also carry content
+        if let Some(usage) = chunk.usage {
+            *self.pending_finish.borrow_mut() = Some(ResponseMetadata {
+                finish_reason: *self.finish_reason.borrow(),
+                usage: Some(convert_usage(&usage)),
+                provider_id: Some(chunk.id),
+                timestamp: chunk.created.map(|created| created.to_string()),
+                provider_metadata_json: None,
+                cost: None,
+            });
+        }
+
+        Ok(stream_event)
+    }
+
+    fn decode_messages(&self, raw: &str) -> Result<Vec<StreamEvent>, Error> {
+        let mut stream_events = self.decode_message(raw)?.into_iter().collect::<Vec<_>>();
+        if let Some(metadata) = self.pending_finish.borrow_mut().take() {
+            stream_events.push(StreamEvent::Finish(metadata));
+        }
+        Ok(stream_events)
+    }
+}
+
+pub struct MistralComponent;
+
+impl MistralComponent {
+    const ENV_VAR_NAME: &'static str = "MISTRAL_API_KEY";
+
+    fn request(
+        client: ChatCompletionsApi,
+        request: ChatCompletionRequest,
+    ) -> Result<Response, Error> {
+        let model = request.model.clone();
+        let response = client.send_messages(request)?;
+        let mut response = process_response(response)?;
+        add_cost(&mut response.metadata, &model, &PRICING);
+        Ok(response)
+    }
+
+    fn streaming_request(
+        client: ChatCompletionsApi,
+        mut request: ChatCompletionRequest,
+    ) -> LlmChatStream<MistralChatStream> {
+        request.stream = Some(true);
+        let model = request.model.clone();
+        match client.stream_send_messages(request) {
+            Ok(stream) => MistralChatStream::new(stream).with_pricing(&model, &PRICING),
+            Err(err) => MistralChatStream::failed(err),
+        }
+    }
+}
+
+impl Guest for MistralComponent {
+    type ChatStream = LlmChatStream<MistralChatStream>;
+    type ChatSession = ChatSession<Self>;
+
+    fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+        let mistral_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ChatCompletionsApi::new(mistral_api_key);
+        let request = events_to_request(events, config)?;
+        Self::request(client, request)
+    }
+
+    fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+        ChatStream::new(Self::unwrapped_stream(events, config))
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(known_model_info(&model, KNOWN_MODELS))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let mistral_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ChatCompletionsApi::new(mistral_api_key);
+        let response = client.list_models()?;
+        Ok(response
+            .data
+            .into_iter()
+            .filter(|model| {
+                model
+                    .capabilities
+                    .as_ref()
+                    .is_none_or(|capabilities| capabilities.completion_chat)
+            })
+            .map(|model| {
+                let mut model_info = known_model_info(&model.id, KNOWN_MODELS);
+                // The API reports the context window and the capabilities, which is more
+                // accurate than the table
+                if model.max_context_length.is_some() {
+                    model_info.context_window = model.max_context_length;
+                }
+                if let (Some(capabilities), Some(reported)) =
+                    (&mut model_info.capabilities, &model.capabilities)
+                {
+                    capabilities.tools = reported.function_calling;
+                    capabilities.vision = reported.vision;
+                }
+                model_info
+            })
+            .collect())
+    }
+}
+
+impl BatchGuest for MistralComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+}
+
+impl ExtendedGuest for MistralComponent {
+    const PROVIDER_NAME: &'static str = "mistral";
+
+    fn unwrapped_stream(events: Vec<Event>, config: Config) -> LlmChatStream<MistralChatStream> {
+        with_config_key(
+            Self::ENV_VAR_NAME,
+            MistralChatStream::failed,
+            |mistral_api_key| {
+                let client = ChatCompletionsApi::new(mistral_api_key);
+
+                match events_to_request(events, config) {
+                    Ok(request) => Self::streaming_request(client, request),
+                    Err(err) => MistralChatStream::failed(err),
+                }
+            },
+        )
+    }
+
+    fn subscribe(stream: &Self::ChatStream) -> Pollable {
+        stream.subscribe()
+    }
+}
+
+#[cfg(not(feature = "library"))]
+type DurableMistralComponent = DurableLLM<CachedLLM<MistralComponent>>;
+
+#[cfg(not(feature = "library"))]
+golem_llm::export_llm!(DurableMistralComponent with_types_in golem_llm);
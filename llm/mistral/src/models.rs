@@ -0,0 +1,301 @@
+use golem_llm::golem::llm::llm::ModelCapabilities;
+use golem_llm::models::KnownModel;
+use golem_llm::pricing::{KnownPrice, Price, Pricing};
+
+/// Context windows and capabilities of the Mistral models, for the aliases and versioned
+/// identifiers starting with them. The output is only limited by the context window.
+pub const KNOWN_MODELS: &[KnownModel] = &[
+    KnownModel {
+        id: "mistral-large",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "mistral-medium",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "mistral-small",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "magistral-medium",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "magistral-small",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "pixtral-large",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "pixtral-12b",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "codestral",
+        context_window: 256_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "devstral-medium",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "devstral-small",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "ministral-8b",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "ministral-3b",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+    KnownModel {
+        id: "open-mistral-nemo",
+        context_window: 128_000,
+        max_output_tokens: None,
+        capabilities: ModelCapabilities {
+            tools: true,
+            vision: false,
+            streaming: true,
+            reasoning: false,
+            json_schema: true,
+        },
+    },
+];
+
+/// Prices of the Mistral models in US dollars per million tokens. Mistral does not report cached tokens.
+pub const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[
+        KnownPrice {
+            id: "mistral-large",
+            price: Price {
+                input: 2.0,
+                output: 6.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "mistral-medium",
+            price: Price {
+                input: 0.4,
+                output: 2.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "mistral-small",
+            price: Price {
+                input: 0.1,
+                output: 0.3,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "magistral-medium",
+            price: Price {
+                input: 2.0,
+                output: 5.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "magistral-small",
+            price: Price {
+                input: 0.5,
+                output: 1.5,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "pixtral-large",
+            price: Price {
+                input: 2.0,
+                output: 6.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "pixtral-12b",
+            price: Price {
+                input: 0.15,
+                output: 0.15,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "codestral",
+            price: Price {
+                input: 0.3,
+                output: 0.9,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "devstral-medium",
+            price: Price {
+                input: 0.4,
+                output: 2.0,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "devstral-small",
+            price: Price {
+                input: 0.1,
+                output: 0.3,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "ministral-8b",
+            price: Price {
+                input: 0.1,
+                output: 0.1,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "ministral-3b",
+            price: Price {
+                input: 0.04,
+                output: 0.04,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+        KnownPrice {
+            id: "open-mistral-nemo",
+            price: Price {
+                input: 0.15,
+                output: 0.15,
+                cache_read: None,
+                cache_write: None,
+                reasoning: None,
+            },
+        },
+    ],
+};
@@ -0,0 +1,272 @@
+use crate::fixture::{MockError, MockErrorCode, MockFinishReason, MockToolCall, MockUsage, Rule};
+use golem_llm::golem::llm::llm::{
+    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ModelCapabilities, ModelInfo,
+    ReasoningContent, Response, ResponseMetadata, StreamDelta, StreamEvent, ToolCall,
+    ToolCallDelta, Usage,
+};
+use golem_llm::tokens::estimate_tokens;
+
+/// Name of the message in which the mock's retry prompt passes the partially streamed response
+pub const PARTIAL_RESPONSE_NAME: &str = "golem-llm-mock-partial-response";
+
+/// The part of a streamed response received before the stream was interrupted
+#[derive(Default)]
+pub struct PartialResponse {
+    text_length: usize,
+    has_reasoning: bool,
+}
+
+/// Removes the partial response added by the mock's retry prompt from the events
+pub fn take_partial_response(events: &mut Vec<Event>) -> PartialResponse {
+    let Some(Event::Message(message)) = events.last() else {
+        return PartialResponse::default();
+    };
+    if message.name.as_deref() != Some(PARTIAL_RESPONSE_NAME) {
+        return PartialResponse::default();
+    }
+
+    let mut partial = PartialResponse::default();
+    for content in &message.content {
+        match content {
+            ContentPart::Text(text) => partial.text_length += text.chars().count(),
+            ContentPart::Reasoning(_) => partial.has_reasoning = true,
+            _ => {}
+        }
+    }
+    events.pop();
+    partial
+}
+
+pub fn rule_to_response(
+    index: usize,
+    rule: &Rule,
+    events: &[Event],
+    config: &Config,
+) -> Result<Response, Error> {
+    if let Some(error) = &rule.error {
+        return Err(convert_error(error));
+    }
+
+    let mut content = Vec::new();
+    if let Some(reasoning) = &rule.reasoning {
+        content.push(reasoning_to_content_part(reasoning));
+    }
+    let text = rule.text_chunks().concat();
+    if !text.is_empty() {
+        content.push(ContentPart::Text(text));
+    }
+
+    Ok(Response {
+        id: response_id(index),
+        content,
+        tool_calls: rule
+            .tool_calls
+            .iter()
+            .enumerate()
+            .map(|(index, tool_call)| convert_tool_call(index, tool_call))
+            .collect(),
+        metadata: rule_to_metadata(index, rule, events, config),
+        citations: vec![],
+    })
+}
+
+/// Splits the reply of a rule into the events returned by the consecutive polls of a stream,
+/// skipping the part already received before an interruption
+pub fn rule_to_stream_polls(
+    index: usize,
+    rule: &Rule,
+    events: &[Event],
+    config: &Config,
+    partial: &PartialResponse,
+) -> Vec<Vec<Result<StreamEvent, Error>>> {
+    if let Some(error) = &rule.error {
+        return vec![vec![Err(convert_error(error))]];
+    }
+
+    let mut polls = Vec::new();
+    if let Some(reasoning) = rule.reasoning.as_ref().filter(|_| !partial.has_reasoning) {
+        polls.push(vec![Ok(content_delta(reasoning_to_content_part(
+            reasoning,
+        )))]);
+    }
+
+    let mut skipped = partial.text_length;
+    for chunk in rule.text_chunks() {
+        let length = chunk.chars().count();
+        if skipped >= length {
+            skipped -= length;
+            continue;
+        }
+        let chunk = chunk.chars().skip(skipped).collect::<String>();
+        skipped = 0;
+        polls.push(vec![Ok(content_delta(ContentPart::Text(chunk)))]);
+    }
+
+    let mut last_poll = Vec::new();
+    if !rule.tool_calls.is_empty() {
+        last_poll.push(Ok(StreamEvent::Delta(StreamDelta {
+            content: None,
+            tool_calls: None,
+            tool_call_deltas: Some(
+                rule.tool_calls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, tool_call)| {
+                        let tool_call = convert_tool_call(index, tool_call);
+                        ToolCallDelta {
+                            index: index as u32,
+                            id: Some(tool_call.id),
+                            name: Some(tool_call.name),
+                            arguments_fragment: tool_call.arguments_json,
+                        }
+                    })
+                    .collect(),
+            ),
+            citations: None,
+        })));
+    }
+    match &rule.stream_error {
+        Some(error) => last_poll.push(Err(convert_error(error))),
+        None => last_poll.push(Ok(StreamEvent::Finish(rule_to_metadata(
+            index, rule, events, config,
+        )))),
+    }
+    polls.push(last_poll);
+    polls
+}
+
+pub fn model_to_model_info(model: String) -> ModelInfo {
+    ModelInfo {
+        id: model,
+        context_window: None,
+        max_output_tokens: None,
+        capabilities: Some(ModelCapabilities {
+            tools: true,
+            vision: true,
+            streaming: true,
+            reasoning: true,
+            json_schema: true,
+        }),
+    }
+}
+
+pub fn convert_error(error: &MockError) -> Error {
+    Error {
+        code: convert_error_code(error.code),
+        message: error.message.clone(),
+        provider_error_json: None,
+        retry_after_ms: error.retry_after_ms,
+    }
+}
+
+fn convert_error_code(code: MockErrorCode) -> ErrorCode {
+    match code {
+        MockErrorCode::InvalidRequest => ErrorCode::InvalidRequest,
+        MockErrorCode::AuthenticationFailed => ErrorCode::AuthenticationFailed,
+        MockErrorCode::RateLimitExceeded => ErrorCode::RateLimitExceeded,
+        MockErrorCode::InternalError => ErrorCode::InternalError,
+        MockErrorCode::Unsupported => ErrorCode::Unsupported,
+        MockErrorCode::Unknown => ErrorCode::Unknown,
+        MockErrorCode::ContextLengthExceeded => ErrorCode::ContextLengthExceeded,
+        MockErrorCode::ContentFiltered => ErrorCode::ContentFiltered,
+        MockErrorCode::Overloaded => ErrorCode::Overloaded,
+        MockErrorCode::QuotaExceeded => ErrorCode::QuotaExceeded,
+        MockErrorCode::ModelNotFound => ErrorCode::ModelNotFound,
+        MockErrorCode::Timeout => ErrorCode::Timeout,
+    }
+}
+
+/// Responses are identified by the index of their rule, so replies are deterministic
+fn response_id(index: usize) -> String {
+    format!("mock-{index}")
+}
+
+fn convert_tool_call(index: usize, tool_call: &MockToolCall) -> ToolCall {
+    ToolCall {
+        id: tool_call
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("{}-{index}", tool_call.name)),
+        name: tool_call.name.clone(),
+        arguments_json: if tool_call.arguments.is_null() {
+            "{}".to_string()
+        } else {
+            tool_call.arguments.to_string()
+        },
+    }
+}
+
+fn rule_to_metadata(
+    index: usize,
+    rule: &Rule,
+    events: &[Event],
+    config: &Config,
+) -> ResponseMetadata {
+    let finish_reason = match rule.finish_reason {
+        Some(finish_reason) => convert_finish_reason(finish_reason),
+        None if !rule.tool_calls.is_empty() => FinishReason::ToolCalls,
+        None => FinishReason::Stop,
+    };
+    let usage = match &rule.usage {
+        Some(usage) => convert_usage(usage),
+        None => Usage {
+            input_tokens: Some(estimate_tokens(events, config)),
+            output_tokens: None,
+            total_tokens: None,
+            reasoning_tokens: None,
+            cache_read_tokens: None,
+            cache_write_tokens: None,
+        },
+    };
+
+    ResponseMetadata {
+        finish_reason: Some(finish_reason),
+        usage: Some(usage),
+        provider_id: Some(response_id(index)),
+        timestamp: None,
+        provider_metadata_json: None,
+        cost: None,
+    }
+}
+
+fn convert_finish_reason(finish_reason: MockFinishReason) -> FinishReason {
+    match finish_reason {
+        MockFinishReason::Stop => FinishReason::Stop,
+        MockFinishReason::Length => FinishReason::Length,
+        MockFinishReason::ToolCalls => FinishReason::ToolCalls,
+        MockFinishReason::ContentFilter => FinishReason::ContentFilter,
+        MockFinishReason::Error => FinishReason::Error,
+        MockFinishReason::Other => FinishReason::Other,
+    }
+}
+
+fn convert_usage(usage: &MockUsage) -> Usage {
+    Usage {
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        total_tokens: usage
+            .input_tokens
+            .zip(usage.output_tokens)
+            .map(|(input, output)| input + output),
+        reasoning_tokens: usage.reasoning_tokens,
+        cache_read_tokens: usage.cache_read_tokens,
+        cache_write_tokens: usage.cache_write_tokens,
+    }
+}
+
+fn reasoning_to_content_part(reasoning: &str) -> ContentPart {
+    ContentPart::Reasoning(ReasoningContent {
+        text: reasoning.to_string(),
+        signature: None,
+        redacted_data: None,
+    })
+}
+
+fn content_delta(content: ContentPart) -> StreamEvent {
+    StreamEvent::Delta(StreamDelta {
+        content: Some(vec![content]),
+        tool_calls: None,
+        tool_call_deltas: None,
+        citations: None,
+    })
+}
@@ -0,0 +1,394 @@
+use golem_llm::config::get_config_key_or_none;
+use golem_llm::golem::llm::llm::{Config, ContentPart, Error, ErrorCode, Event, Role};
+use serde::Deserialize;
+
+/// The fixture itself, as inline JSON
+const FIXTURE_ENV_VAR_NAME: &str = "GOLEM_LLM_MOCK_FIXTURE";
+/// Path of a JSON file containing the fixture, used if there is no inline fixture
+const FIXTURE_PATH_ENV_VAR_NAME: &str = "GOLEM_LLM_MOCK_FIXTURE_PATH";
+
+/// The scripted replies of the mock. Each request is answered by the first rule matching it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Fixture {
+    /// Models listed in addition to the ones named in the rules
+    #[serde(default)]
+    pub models: Vec<String>,
+    pub rules: Vec<Rule>,
+}
+
+/// A scripted reply, and the requests it answers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Rule {
+    #[serde(default, rename = "match")]
+    pub matcher: Matcher,
+    #[serde(default)]
+    pub text: String,
+    /// The text split into stream deltas, by default it is split after each space
+    pub chunks: Option<Vec<String>>,
+    pub reasoning: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<MockToolCall>,
+    pub finish_reason: Option<MockFinishReason>,
+    pub usage: Option<MockUsage>,
+    /// Fails the request instead of replying
+    pub error: Option<MockError>,
+    /// Fails the stream after the text deltas instead of finishing it
+    pub stream_error: Option<MockError>,
+}
+
+/// Conditions of a request, a rule without conditions matches every request
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Matcher {
+    pub model: Option<String>,
+    /// Text contained in the last user message
+    pub contains: Option<String>,
+    /// Whether the last event is a tool result
+    pub after_tool_results: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MockToolCall {
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MockFinishReason {
+    Stop,
+    Length,
+    ToolCalls,
+    ContentFilter,
+    Error,
+    Other,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MockUsage {
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub reasoning_tokens: Option<u32>,
+    pub cache_read_tokens: Option<u32>,
+    pub cache_write_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MockError {
+    pub code: MockErrorCode,
+    pub message: String,
+    pub retry_after_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MockErrorCode {
+    InvalidRequest,
+    AuthenticationFailed,
+    RateLimitExceeded,
+    InternalError,
+    Unsupported,
+    Unknown,
+    ContextLengthExceeded,
+    ContentFiltered,
+    Overloaded,
+    QuotaExceeded,
+    ModelNotFound,
+    Timeout,
+}
+
+impl Fixture {
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from_lookup(get_config_key_or_none)
+    }
+
+    fn load_from_lookup(lookup: impl Fn(&'static str) -> Option<String>) -> Result<Self, Error> {
+        let json = match lookup(FIXTURE_ENV_VAR_NAME) {
+            Some(json) => json,
+            None => {
+                let path = lookup(FIXTURE_PATH_ENV_VAR_NAME).ok_or_else(|| {
+                    fixture_error(format!(
+                        "Missing config key: {FIXTURE_ENV_VAR_NAME} or {FIXTURE_PATH_ENV_VAR_NAME}"
+                    ))
+                })?;
+                std::fs::read_to_string(&path).map_err(|err| {
+                    fixture_error(format!("Failed to read mock fixture {path}: {err}"))
+                })?
+            }
+        };
+        serde_json::from_str(&json)
+            .map_err(|err| fixture_error(format!("Failed to parse mock fixture: {err}")))
+    }
+
+    /// Finds the first rule matching the request, together with its index
+    pub fn find_rule(&self, events: &[Event], config: &Config) -> Result<(usize, &Rule), Error> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matcher.matches(events, config))
+            .ok_or_else(|| Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "No mock rule matches the request for model {} with last user message {:?}",
+                    config.model,
+                    last_user_text(events)
+                ),
+                provider_error_json: None,
+                retry_after_ms: None,
+            })
+    }
+
+    /// The listed models, and the ones named in the rules
+    pub fn all_models(&self) -> Vec<String> {
+        let mut models: Vec<String> = Vec::new();
+        for model in self.models.iter().chain(
+            self.rules
+                .iter()
+                .filter_map(|rule| rule.matcher.model.as_ref()),
+        ) {
+            if !models.contains(model) {
+                models.push(model.clone());
+            }
+        }
+        models
+    }
+}
+
+impl Matcher {
+    fn matches(&self, events: &[Event], config: &Config) -> bool {
+        self.model
+            .as_ref()
+            .is_none_or(|model| *model == config.model)
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|text| last_user_text(events).contains(text.as_str()))
+            && self.after_tool_results.is_none_or(|after_tool_results| {
+                matches!(events.last(), Some(Event::ToolResults(_))) == after_tool_results
+            })
+    }
+}
+
+impl Rule {
+    /// The text deltas the reply is streamed in
+    pub fn text_chunks(&self) -> Vec<String> {
+        match &self.chunks {
+            Some(chunks) => chunks.clone(),
+            None => self.text.split_inclusive(' ').map(str::to_string).collect(),
+        }
+    }
+}
+
+fn last_user_text(events: &[Event]) -> String {
+    events
+        .iter()
+        .rev()
+        .find_map(|event| match event {
+            Event::Message(message) if message.role == Role::User => Some(
+                message
+                    .content
+                    .iter()
+                    .filter_map(|content| match content {
+                        ContentPart::Text(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn fixture_error(message: String) -> Error {
+    Error {
+        code: ErrorCode::InternalError,
+        message,
+        provider_error_json: None,
+        retry_after_ms: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use golem_llm::golem::llm::llm::Message;
+    use std::collections::HashMap;
+
+    const FIXTURE: &str = r#"{
+        "models": ["mock-model"],
+        "rules": [
+            {"match": {"after-tool-results": true}, "text": "after tools"},
+            {"match": {"model": "mock-vision-model", "contains": "cat"}, "text": "vision cat"},
+            {"match": {"contains": "cat"}, "text": "cat"},
+            {"match": {"model": "mock-model"}, "text": "fallback"}
+        ]
+    }"#;
+
+    fn load(values: &[(&'static str, String)]) -> Result<Fixture, Error> {
+        let values = values.iter().cloned().collect::<HashMap<_, _>>();
+        Fixture::load_from_lookup(|key| values.get(key).cloned())
+    }
+
+    fn config(model: &str) -> Config {
+        Config {
+            model: model.to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        }
+    }
+
+    fn message(text: &str) -> Event {
+        Event::Message(Message {
+            role: Role::User,
+            name: None,
+            content: vec![ContentPart::Text(text.to_string())],
+            cache_hint: None,
+        })
+    }
+
+    fn matching_text(fixture: &Fixture, events: &[Event], model: &str) -> Option<String> {
+        fixture
+            .find_rule(events, &config(model))
+            .ok()
+            .map(|(_, rule)| rule.text.clone())
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let fixture = load(&[(FIXTURE_ENV_VAR_NAME, FIXTURE.to_string())]).unwrap();
+
+        assert_eq!(
+            matching_text(&fixture, &[message("a cat")], "mock-vision-model"),
+            Some("vision cat".to_string())
+        );
+        assert_eq!(
+            matching_text(&fixture, &[message("a cat")], "mock-model"),
+            Some("cat".to_string())
+        );
+        assert_eq!(
+            matching_text(&fixture, &[message("a dog")], "mock-model"),
+            Some("fallback".to_string())
+        );
+        assert_eq!(
+            matching_text(
+                &fixture,
+                &[message("a cat"), Event::ToolResults(vec![])],
+                "mock-vision-model"
+            ),
+            Some("after tools".to_string())
+        );
+        assert_eq!(
+            fixture
+                .find_rule(&[message("a dog")], &config("mock-model"))
+                .unwrap()
+                .0,
+            3
+        );
+    }
+
+    #[test]
+    fn test_no_matching_rule() {
+        let fixture = load(&[(FIXTURE_ENV_VAR_NAME, FIXTURE.to_string())]).unwrap();
+
+        let error = fixture
+            .find_rule(&[message("a dog")], &config("other-model"))
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidRequest);
+        assert!(error.message.contains("other-model"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_all_models() {
+        let fixture = load(&[(FIXTURE_ENV_VAR_NAME, FIXTURE.to_string())]).unwrap();
+
+        assert_eq!(
+            fixture.all_models(),
+            vec!["mock-model", "mock-vision-model"]
+        );
+    }
+
+    #[test]
+    fn test_inline_fixture_takes_precedence_over_path() {
+        let fixture = load(&[
+            (FIXTURE_ENV_VAR_NAME, FIXTURE.to_string()),
+            (
+                FIXTURE_PATH_ENV_VAR_NAME,
+                "/does/not/exist.json".to_string(),
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(fixture.rules.len(), 4);
+    }
+
+    #[test]
+    fn test_fixture_file() {
+        let path = std::env::temp_dir().join("golem-llm-mock-fixture-test.json");
+        std::fs::write(&path, FIXTURE).unwrap();
+        let fixture = load(&[(
+            FIXTURE_PATH_ENV_VAR_NAME,
+            path.to_string_lossy().to_string(),
+        )]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(fixture.unwrap().rules.len(), 4);
+    }
+
+    #[test]
+    fn test_malformed_fixture_file() {
+        let path = std::env::temp_dir().join("golem-llm-mock-malformed-fixture-test.json");
+        std::fs::write(&path, r#"{"rules": [{"text": "unterminated"#).unwrap();
+        let error = load(&[(
+            FIXTURE_PATH_ENV_VAR_NAME,
+            path.to_string_lossy().to_string(),
+        )])
+        .unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.code, ErrorCode::InternalError);
+        assert!(
+            error.message.starts_with("Failed to parse mock fixture"),
+            "{}",
+            error.message
+        );
+    }
+
+    #[test]
+    fn test_missing_fixture() {
+        let error = load(&[]).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InternalError);
+
+        let error = load(&[(
+            FIXTURE_PATH_ENV_VAR_NAME,
+            "/does/not/exist.json".to_string(),
+        )])
+        .unwrap_err();
+        assert!(
+            error.message.starts_with("Failed to read mock fixture"),
+            "{}",
+            error.message
+        );
+
+        let error = load(&[(FIXTURE_ENV_VAR_NAME, r#"{"models": []}"#.to_string())]).unwrap_err();
+        assert!(
+            error.message.starts_with("Failed to parse mock fixture"),
+            "{}",
+            error.message
+        );
+    }
+}
@@ -0,0 +1,186 @@
+mod conversions;
+mod fixture;
+
+use crate::conversions::{
+    model_to_model_info, rule_to_response, rule_to_stream_polls, take_partial_response,
+    PARTIAL_RESPONSE_NAME,
+};
+use crate::fixture::Fixture;
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
+use golem_llm::chat_stream::ToolCallAssembler;
+use golem_llm::durability::{DurableLLM, ExtendedGuest};
+use golem_llm::error::unsupported;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{
+    ChatStream, Config, Error, Event, Guest, GuestChatStream, Message, ModelInfo, Response, Role,
+    StreamDelta, StreamEvent,
+};
+use golem_llm::pricing::{add_cost, Pricing};
+use golem_llm::tokens::estimate_tokens;
+use golem_rust::bindings::wasi::clocks::monotonic_clock;
+use golem_rust::wasm_rpc::Pollable;
+use log::trace;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// Stream of a scripted reply, returning the events of one poll at a time
+pub struct MockChatStream {
+    polls: RefCell<VecDeque<Vec<Result<StreamEvent, Error>>>>,
+    tool_calls: ToolCallAssembler,
+}
+
+impl MockChatStream {
+    pub fn new(polls: Vec<Vec<Result<StreamEvent, Error>>>) -> Self {
+        MockChatStream {
+            polls: RefCell::new(polls.into()),
+            tool_calls: ToolCallAssembler::new(),
+        }
+    }
+
+    pub fn failed(error: Error) -> Self {
+        Self::new(vec![vec![Err(error)]])
+    }
+
+    pub fn subscribe(&self) -> Pollable {
+        // Every poll is ready immediately
+        monotonic_clock::subscribe_duration(0)
+    }
+}
+
+impl GuestChatStream for MockChatStream {
+    fn poll_next(&self) -> Option<Vec<Result<StreamEvent, Error>>> {
+        let events = self.polls.borrow_mut().pop_front();
+        match events {
+            Some(events) => Some(self.tool_calls.process(events)),
+            // The tool calls of a script ending without a finish event are still emitted
+            None => Some(self.tool_calls.complete()),
+        }
+    }
+
+    fn get_next(&self) -> Vec<Result<StreamEvent, Error>> {
+        self.poll_next().unwrap_or_default()
+    }
+}
+
+/// Only the prices configured in `GOLEM_LLM_PRICES` apply to the mock models
+const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[],
+};
+
+pub struct MockComponent;
+
+impl MockComponent {
+    fn stream_polls(
+        mut events: Vec<Event>,
+        config: Config,
+    ) -> Result<Vec<Vec<Result<StreamEvent, Error>>>, Error> {
+        let fixture = Fixture::load()?;
+        let partial = take_partial_response(&mut events);
+        let (index, rule) = fixture.find_rule(&events, &config)?;
+        trace!("Streaming the reply of mock rule {index}");
+        let polls = rule_to_stream_polls(index, rule, &events, &config, &partial);
+        Ok(match PRICING.model_pricing(&config.model) {
+            Some(pricing) => polls
+                .into_iter()
+                .map(|poll| pricing.add_stream_costs(poll))
+                .collect(),
+            None => polls,
+        })
+    }
+}
+
+impl Guest for MockComponent {
+    type ChatStream = MockChatStream;
+    type ChatSession = ChatSession<Self>;
+
+    fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+        let fixture = Fixture::load()?;
+        let (index, rule) = fixture.find_rule(&events, &config)?;
+        trace!("Replying with mock rule {index}");
+        let mut response = rule_to_response(index, rule, &events, &config)?;
+        add_cost(&mut response.metadata, &config.model, &PRICING);
+        Ok(response)
+    }
+
+    fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+        ChatStream::new(Self::unwrapped_stream(events, config))
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(model_to_model_info(model))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let fixture = Fixture::load()?;
+        Ok(fixture
+            .all_models()
+            .into_iter()
+            .map(model_to_model_info)
+            .collect())
+    }
+}
+
+impl BatchGuest for MockComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+}
+
+impl ExtendedGuest for MockComponent {
+    const PROVIDER_NAME: &'static str = "mock";
+
+    fn unwrapped_stream(events: Vec<Event>, config: Config) -> MockChatStream {
+        match Self::stream_polls(events, config) {
+            Ok(polls) => MockChatStream::new(polls),
+            Err(err) => MockChatStream::failed(err),
+        }
+    }
+
+    /// Instead of asking to continue, the retry prompt passes the partial response in a message
+    /// the mock recognizes, so the scripted stream resumes exactly where it was interrupted
+    fn retry_prompt(
+        original_events: &[Result<Event, Error>],
+        partial_result: &[StreamDelta],
+    ) -> Vec<Event> {
+        let mut extended_events = original_events
+            .iter()
+            .filter_map(|event| event.as_ref().ok().cloned())
+            .collect::<Vec<_>>();
+        extended_events.push(Event::Message(Message {
+            role: Role::Assistant,
+            name: Some(PARTIAL_RESPONSE_NAME.to_string()),
+            content: partial_result
+                .iter()
+                .flat_map(|delta| delta.content.iter().flatten().cloned())
+                .collect(),
+            cache_hint: None,
+        }));
+        extended_events
+    }
+
+    fn subscribe(stream: &Self::ChatStream) -> Pollable {
+        stream.subscribe()
+    }
+}
+
+type DurableMockComponent = DurableLLM<CachedLLM<MockComponent>>;
+
+golem_llm::export_llm!(DurableMockComponent with_types_in golem_llm);
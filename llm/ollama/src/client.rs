@@ -2,9 +2,10 @@ use std::{fmt::Debug, fs, path::Path};
 
 use base64::{engine::general_purpose, Engine};
 use golem_llm::{
-    error::{error_code_from_status, from_event_source_error},
+    error::{error_code_from_response, from_event_source_error, retry_after_ms},
     event_source::EventSource,
     golem::llm::llm::ErrorCode,
+    retry::{send_idempotent_with_retry, send_with_retry},
 };
 use log::trace;
 use reqwest::{
@@ -49,13 +50,13 @@ impl OllamaApi {
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
         let url = format!("{}/api/chat", self.base_url);
-        let response: Response = self
-            .client
-            .request(Method::POST, url)
-            .headers(headers)
-            .json(&modified_params)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, &url)
+                .headers(headers.clone())
+                .json(&modified_params)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         handle_response::<CompletionsResponse>(response)
     }
@@ -73,6 +74,7 @@ impl OllamaApi {
             code: ErrorCode::InternalError,
             message: format!("Failed to serialize request body: {e}"),
             provider_error_json: None,
+            retry_after_ms: None,
         })?;
 
         let mut headers = HeaderMap::new();
@@ -80,16 +82,97 @@ impl OllamaApi {
         headers.insert("Accept", HeaderValue::from_static("application/x-ndjson"));
 
         let url = format!("{}/api/chat", self.base_url);
-        let response = self
-            .client
-            .request(Method::POST, url)
-            .headers(headers)
-            .body(json_body)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, &url)
+                .headers(headers.clone())
+                .body(json_body.clone())
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
         EventSource::new(response)
             .map_err(|err| from_event_source_error("Failed to create EventSource stream", err))
     }
+
+    pub fn show_model(&self, model: &str) -> Result<ShowModelResponse, Error> {
+        trace!("Sending show request to Ollama API for model {model}");
+
+        let url = format!("{}/api/show", self.base_url);
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::POST, &url)
+                .json(&ShowModelRequest {
+                    model: model.to_string(),
+                })
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        handle_response::<ShowModelResponse>(response)
+    }
+
+    pub fn list_local_models(&self) -> Result<ListModelsResponse, Error> {
+        trace!("Listing local models of Ollama API");
+
+        let url = format!("{}/api/tags", self.base_url);
+        let response: Response =
+            send_idempotent_with_retry(|| self.client.request(Method::GET, &url))
+                .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        handle_response::<ListModelsResponse>(response)
+    }
+
+    pub fn list_running_models(&self) -> Result<ListRunningModelsResponse, Error> {
+        trace!("Listing running models of Ollama API");
+
+        let url = format!("{}/api/ps", self.base_url);
+        let response: Response =
+            send_idempotent_with_retry(|| self.client.request(Method::GET, &url))
+                .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        handle_response::<ListRunningModelsResponse>(response)
+    }
+
+    /// Starts pulling a model, streaming the progress as NDJSON
+    pub fn pull_model_stream(&self, model: &str) -> Result<EventSource, Error> {
+        trace!("Sending pull request to Ollama API for model {model}");
+
+        let url = format!("{}/api/pull", self.base_url);
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, &url)
+                .header("Accept", HeaderValue::from_static("application/x-ndjson"))
+                .json(&PullModelRequest {
+                    model: model.to_string(),
+                    stream: true,
+                })
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        EventSource::new(response)
+            .map_err(|err| from_event_source_error("Failed to create EventSource stream", err))
+    }
+
+    pub fn delete_model(&self, model: &str) -> Result<(), Error> {
+        trace!("Sending delete request to Ollama API for model {model}");
+
+        let url = format!("{}/api/delete", self.base_url);
+        // Repeating a delete whose response was lost would fail with `404`, so only the requests
+        // the server did not process are retried
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::DELETE, &url)
+                .json(&DeleteModelRequest {
+                    model: model.to_string(),
+                })
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        // A successful delete has an empty body
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            handle_response::<serde_json::Value>(response).map(|_| ())
+        }
+    }
 }
 
 /// GenerateOptions is Options for generating completions
@@ -162,12 +245,17 @@ pub struct CompletionsRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
 
+    /// Either `"json"` or a JSON schema the response has to conform to
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub format: Option<String>,
+    pub format: Option<serde_json::Value>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<OllamaModelOptions>,
 
+    /// Whether thinking models should think before responding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub think: Option<bool>,
+
     /// If false the response will be returned as a single response object, rather than a stream of objects.
     /// For `chat_completion` this will be set to false.
     /// For `chat_completion_stream` this will be set to true.
@@ -183,6 +271,8 @@ pub struct MessageRequest {
     pub role: MessageRole,
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub images: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools_calls: Option<Vec<Tool>>,
@@ -201,6 +291,111 @@ pub struct FunctionTool {
     pub parameters: serde_json::Value,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShowModelRequest {
+    pub model: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShowModelResponse {
+    #[serde(default)]
+    pub model_info: serde_json::Map<String, serde_json::Value>,
+    /// Capabilities like `completion`, `tools`, `vision` or `thinking`, reported by
+    /// Ollama 0.6.4 and newer
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    pub modelfile: Option<String>,
+    pub parameters: Option<String>,
+    pub template: Option<String>,
+    #[serde(default)]
+    pub details: ModelDetails,
+    pub modified_at: Option<String>,
+}
+
+impl ShowModelResponse {
+    /// The context length the model was trained with, stored under an architecture
+    /// specific key like `llama.context_length`
+    pub fn context_length(&self) -> Option<u32> {
+        let architecture = self.model_info.get("general.architecture")?.as_str()?;
+        self.model_info
+            .get(&format!("{architecture}.context_length"))?
+            .as_u64()
+            .map(|context_length| context_length as u32)
+    }
+
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ListModelsResponse {
+    pub models: Vec<LocalModel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalModel {
+    pub name: String,
+    pub model: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub digest: String,
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub details: ModelDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ModelDetails {
+    pub format: Option<String>,
+    pub family: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ListRunningModelsResponse {
+    pub models: Vec<RunningModel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunningModel {
+    pub name: String,
+    pub model: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub size_vram: u64,
+    #[serde(default)]
+    pub digest: String,
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub details: ModelDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PullModelRequest {
+    pub model: String,
+    pub stream: bool,
+}
+
+/// A line of the pull progress stream, which reports failures in `error`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PullModelProgress {
+    #[serde(default)]
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteModelRequest {
+    pub model: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CompletionsResponse {
     pub model: String,
@@ -243,6 +438,8 @@ pub struct MessageResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub images: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
@@ -285,26 +482,32 @@ pub fn handle_response<T: DeserializeOwned + Debug>(response: Response) -> Resul
                     code: ErrorCode::InternalError,
                     message: format!("Failed to parse response body: {err}"),
                     provider_error_json: Some(raw_body),
+                    retry_after_ms: None,
                 }),
             }
         }
         _ => {
+            let retry_after_ms = retry_after_ms(response.headers());
             let raw_error_body = response
                 .text()
                 .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
             trace!("Received {status} response from OpenRouter API: {raw_error_body:?}");
 
+            // Ollama's errors are plain messages, so the code is based on the whole body
+            let code = error_code_from_response(status, &raw_error_body);
             let error_body: OllamaRequestError =
                 serde_json::from_str(&raw_error_body).map_err(|err| Error {
-                    code: ErrorCode::InternalError,
+                    code,
                     message: format!("Failed to parse error response body: {err}"),
                     provider_error_json: Some(raw_error_body),
+                    retry_after_ms: None,
                 })?;
 
             Err(Error {
-                code: error_code_from_status(status),
+                code,
                 message: error_body.status.unwrap_or_default(),
                 provider_error_json: error_body.error_message,
+                retry_after_ms,
             })
         }
     }
@@ -331,5 +534,6 @@ pub fn from_reqwest_error(context: &str, err: reqwest::Error) -> Error {
         code: ErrorCode::InternalError,
         message: format!("{context}: {err}"),
         provider_error_json: None,
+        retry_after_ms: None,
     }
 }
@@ -1,12 +1,14 @@
 use crate::client::MessageRole::Assistant;
 use crate::client::{
     image_to_base64, CompletionsRequest, CompletionsResponse, FunctionTool, MessageRequest,
-    MessageRole, OllamaModelOptions, Tool,
+    MessageRole, OllamaModelOptions, ShowModelResponse, Tool,
 };
 use base64::{engine::general_purpose, Engine};
+use golem_llm::error::unsupported;
 use golem_llm::golem::llm::llm::{
-    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageReference, Message, Response,
-    ResponseMetadata, Role, ToolCall as GolemToolCall, ToolResult, Usage,
+    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageReference, Message,
+    ModelCapabilities, ModelInfo, ReasoningContent, Response, ResponseFormat, ResponseMetadata,
+    Role, ToolCall as GolemToolCall, ToolChoice, ToolResult, Usage,
 };
 use log::trace;
 use std::collections::HashMap;
@@ -26,8 +28,8 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
 
     for event in events {
         match event {
-            Event::Message(message) => request_messages.push(message_to_request(message)),
-            Event::Response(response) => request_messages.push(response_to_request(response)),
+            Event::Message(message) => request_messages.push(message_to_request(message)?),
+            Event::Response(response) => request_messages.push(response_to_request(response)?),
             Event::ToolResults(tool_results) => {
                 request_messages.extend(tool_results.into_iter().map(tool_result_to_request))
             }
@@ -36,10 +38,14 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
 
     let mut tools = Vec::new();
     for tool in config.tools.unwrap_or_default() {
+        if tool.builtin.is_some() {
+            return Err(unsupported("builtin tools"));
+        }
         let param = serde_json::from_str(&tool.parameters_schema).map_err(|err| Error {
             code: ErrorCode::InternalError,
             message: format!("Failed to parse tool parameters for {}: {err}", tool.name),
             provider_error_json: None,
+            retry_after_ms: None,
         })?;
         tools.push(Tool {
             tool_type: String::from("function"),
@@ -50,6 +56,21 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
             },
         });
     }
+    // Ollama has no tool choice, calling the tools can only be prevented by not sending them
+    match config.tool_choice {
+        None | Some(ToolChoice::Auto) => {}
+        Some(ToolChoice::None) => tools.clear(),
+        Some(ToolChoice::Required | ToolChoice::Specific(_)) => {
+            return Err(unsupported("forcing tool calls"))
+        }
+    }
+
+    let format = match config.response_format {
+        Some(response_format) => response_format_to_format(response_format)?,
+        None => options.get("format").map(|format| {
+            serde_json::from_str(format).unwrap_or(serde_json::Value::String(format.to_string()))
+        }),
+    };
 
     let ollama_options = OllamaModelOptions {
         min_p: parse_option(&options, "min_p"),
@@ -82,14 +103,38 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
         model: Some(config.model.clone()),
         messages: Some(request_messages),
         tools: Some(tools),
-        format: options.get("format").map(|v| v.to_string()),
+        format,
         options: Some(ollama_options),
+        think: config
+            .reasoning
+            .map(|_| true)
+            .or_else(|| parse_option(&options, "think")),
         keep_alive: options.get("keep_alive").map(|v| v.to_string()),
         stream: Some(false),
     })
 }
 
-fn message_to_request(message: Message) -> MessageRequest {
+fn response_format_to_format(
+    response_format: ResponseFormat,
+) -> Result<Option<serde_json::Value>, Error> {
+    match response_format {
+        ResponseFormat::Text => Ok(None),
+        ResponseFormat::JsonObject => Ok(Some(serde_json::Value::String("json".to_string()))),
+        ResponseFormat::JsonSchema(json_schema) => serde_json::from_str(&json_schema.schema)
+            .map(Some)
+            .map_err(|err| Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "Failed to parse response format schema {}: {err}",
+                    json_schema.name
+                ),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }),
+    }
+}
+
+fn message_to_request(message: Message) -> Result<MessageRequest, Error> {
     let message_role = match message.role {
         Role::Assistant => MessageRole::Assistant,
         Role::System => MessageRole::System,
@@ -98,6 +143,7 @@ fn message_to_request(message: Message) -> MessageRequest {
     };
 
     let mut message_content = String::new();
+    let mut thinking = String::new();
     let mut attached_image = Vec::new();
 
     for content_part in message.content {
@@ -123,23 +169,30 @@ fn message_to_request(message: Message) -> MessageRequest {
                     attached_image.push(base64_data);
                 }
             },
+            ContentPart::Reasoning(reasoning) => thinking.push_str(&reasoning.text),
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
         }
     }
 
-    MessageRequest {
+    Ok(MessageRequest {
         content: message_content,
         role: message_role,
+        thinking: (!thinking.is_empty()).then_some(thinking),
         images: if attached_image.is_empty() {
             None
         } else {
             Some(attached_image)
         },
         tools_calls: None,
-    }
+    })
 }
 
-fn response_to_request(response: Response) -> MessageRequest {
+fn response_to_request(response: Response) -> Result<MessageRequest, Error> {
     let mut message_content = String::new();
+    let mut thinking = String::new();
     let mut attached_image = Vec::new();
 
     for content_part in response.content {
@@ -165,6 +218,11 @@ fn response_to_request(response: Response) -> MessageRequest {
                     attached_image.push(base64_data);
                 }
             },
+            ContentPart::Reasoning(reasoning) => thinking.push_str(&reasoning.text),
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
         }
     }
 
@@ -181,16 +239,17 @@ fn response_to_request(response: Response) -> MessageRequest {
         })
         .collect::<Vec<_>>();
 
-    MessageRequest {
+    Ok(MessageRequest {
         content: message_content,
         role: Assistant,
+        thinking: (!thinking.is_empty()).then_some(thinking),
         images: if attached_image.is_empty() {
             None
         } else {
             Some(attached_image)
         },
         tools_calls: (!tool_calls.is_empty()).then_some(tool_calls),
-    }
+    })
 }
 
 fn tool_result_to_request(tool_result: ToolResult) -> MessageRequest {
@@ -216,11 +275,20 @@ fn tool_result_to_request(tool_result: ToolResult) -> MessageRequest {
                 error.error_message,
             ),
         },
+        thinking: None,
         images: None,
         tools_calls: None,
     }
 }
 
+pub fn reasoning_to_content_part(text: String) -> ContentPart {
+    ContentPart::Reasoning(ReasoningContent {
+        text,
+        signature: None,
+        redacted_data: None,
+    })
+}
+
 fn parse_option<T: std::str::FromStr>(options: &HashMap<String, String>, key: &str) -> Option<T> {
     options.get(key).and_then(|v| v.parse::<T>().ok())
 }
@@ -230,6 +298,9 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
         let mut content = Vec::<ContentPart>::new();
         let mut tool_calls = Vec::<GolemToolCall>::new();
 
+        if let Some(ref thinking) = message.thinking {
+            content.push(reasoning_to_content_part(thinking.clone()));
+        }
         if let Some(ref message_content) = message.content {
             content.push(ContentPart::Text(message_content.clone()));
         }
@@ -256,6 +327,9 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
             input_tokens,
             output_tokens,
             total_tokens: Some(input_tokens.unwrap_or(0) + output_tokens.unwrap_or(0)),
+            reasoning_tokens: None,
+            cache_read_tokens: None,
+            cache_write_tokens: None,
         };
 
         let timestamp = response.created_at.clone();
@@ -266,6 +340,7 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
             provider_id: Some("ollama".to_string()),
             timestamp: Some(timestamp.clone()),
             provider_metadata_json: Some(get_provider_metadata(&response)),
+            cost: None,
         };
 
         Ok(Response {
@@ -273,12 +348,14 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
             content,
             tool_calls,
             metadata,
+            citations: Vec::new(),
         })
     } else {
         Err(Error {
             code: ErrorCode::InternalError,
             message: String::from("No messages in response"),
             provider_error_json: None,
+            retry_after_ms: None,
         })
     }
 }
@@ -299,3 +376,21 @@ pub fn get_provider_metadata(response: &CompletionsResponse) -> String {
         response.eval_count.unwrap_or(0)
     )
 }
+
+pub fn show_response_to_model_info(model: String, response: ShowModelResponse) -> ModelInfo {
+    // Older Ollama versions do not report the capabilities
+    let capabilities = (!response.capabilities.is_empty()).then(|| ModelCapabilities {
+        tools: response.has_capability("tools"),
+        vision: response.has_capability("vision"),
+        streaming: true,
+        reasoning: response.has_capability("thinking"),
+        json_schema: response.has_capability("completion"),
+    });
+
+    ModelInfo {
+        id: model,
+        context_window: response.context_length(),
+        max_output_tokens: None,
+        capabilities,
+    }
+}
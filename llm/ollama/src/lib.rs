@@ -1,27 +1,41 @@
 use std::cell::{Ref, RefCell, RefMut};
 
 use client::{CompletionsRequest, OllamaApi};
-use conversions::{events_to_request, process_response};
+use conversions::{
+    events_to_request, process_response, reasoning_to_content_part, show_response_to_model_info,
+};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::error::unsupported;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
 use golem_llm::golem::llm::llm::ErrorCode;
 use golem_llm::{
+    chat_session::ChatSession,
     chat_stream::{LlmChatStream, LlmChatStreamState},
-    durability::{DurableLLM, ExtendedGuest},
+    durability::ExtendedGuest,
     event_source::EventSource,
     golem::llm::llm::{
-        ChatStream, Config, ContentPart, Error, Event, FinishReason, Guest, Message, Response,
-        ResponseMetadata, Role, StreamDelta, StreamEvent, ToolCall, Usage,
+        ChatStream, Config, ContentPart, Error, Event, FinishReason, Guest, Message, ModelInfo,
+        Response, ResponseMetadata, Role, StreamDelta, StreamEvent, ToolCallDelta, Usage,
     },
+    pricing::{add_cost, Pricing},
+    tokens::estimate_tokens,
 };
 use golem_rust::wasm_rpc::Pollable;
 use log::trace;
 
 mod client;
 mod conversions;
+#[cfg(not(feature = "library"))]
+mod model_management;
 
-struct OllamaChatStream {
+pub struct OllamaChatStream {
     stream: RefCell<Option<EventSource>>,
     failure: Option<Error>,
     finished: RefCell<bool>,
+    next_tool_call: RefCell<u32>,
 }
 
 impl OllamaChatStream {
@@ -30,6 +44,7 @@ impl OllamaChatStream {
             stream: RefCell::new(Some(stream)),
             failure: None,
             finished: RefCell::new(false),
+            next_tool_call: RefCell::new(0),
         })
     }
 
@@ -38,6 +53,7 @@ impl OllamaChatStream {
             stream: RefCell::new(None),
             failure: Some(error),
             finished: RefCell::new(false),
+            next_tool_call: RefCell::new(0),
         })
     }
 }
@@ -68,6 +84,7 @@ impl LlmChatStreamState for OllamaChatStream {
                 code: ErrorCode::InternalError,
                 message: message.into(),
                 provider_error_json: None,
+                retry_after_ms: None,
             }
         }
 
@@ -89,6 +106,9 @@ impl LlmChatStreamState for OllamaChatStream {
                 input_tokens: Some(input_tokens),
                 output_tokens: Some(input_tokens),
                 total_tokens: Some(input_tokens + output_tokens),
+                reasoning_tokens: None,
+                cache_read_tokens: None,
+                cache_write_tokens: None,
             };
 
             let total_duration = json
@@ -127,13 +147,19 @@ impl LlmChatStreamState for OllamaChatStream {
                 provider_id: Some("ollama".to_string()),
                 timestamp,
                 provider_metadata_json: Some(provider_metadata),
+                cost: None,
             })));
         }
 
         if let Some(message) = json.get("message") {
             let mut content = Vec::new();
-            let mut tool_calls = Vec::new();
+            let mut tool_call_deltas = Vec::new();
 
+            if let Some(thinking) = message.get("thinking").and_then(|c| c.as_str()) {
+                if !thinking.is_empty() {
+                    content.push(reasoning_to_content_part(thinking.to_string()));
+                }
+            }
             if let Some(text) = message.get("content").and_then(|c| c.as_str()) {
                 if !text.is_empty() {
                     content.push(ContentPart::Text(text.to_string()));
@@ -141,6 +167,8 @@ impl LlmChatStreamState for OllamaChatStream {
             }
 
             if let Some(calls) = message.get("tool_calls").and_then(|tc| tc.as_array()) {
+                // Ollama sends every tool call complete, in a single fragment
+                let mut next_tool_call = self.next_tool_call.borrow_mut();
                 for call in calls {
                     if let Some(function) = call.get("function") {
                         let name = function
@@ -159,11 +187,13 @@ impl LlmChatStreamState for OllamaChatStream {
                                 .map(|s| s.to_string())
                                 .unwrap_or_default()
                         );
-                        tool_calls.push(ToolCall {
-                            id,
-                            name,
-                            arguments_json: args_json.to_string(),
+                        tool_call_deltas.push(ToolCallDelta {
+                            index: *next_tool_call,
+                            id: Some(id),
+                            name: Some(name),
+                            arguments_fragment: args_json.to_string(),
                         });
+                        *next_tool_call += 1;
                     }
                 }
             }
@@ -174,23 +204,34 @@ impl LlmChatStreamState for OllamaChatStream {
                 } else {
                     Some(content)
                 },
-                tool_calls: if tool_calls.is_empty() {
+                tool_calls: None,
+                tool_call_deltas: if tool_call_deltas.is_empty() {
                     None
                 } else {
-                    Some(tool_calls)
+                    Some(tool_call_deltas)
                 },
+                citations: None,
             })));
         }
         Ok(None)
     }
 }
 
-struct OllamaComponent;
+/// The models run locally, so only the prices configured in `GOLEM_LLM_PRICES` apply
+const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[],
+};
+
+pub struct OllamaComponent;
 
 impl OllamaComponent {
     fn request(client: &OllamaApi, request: CompletionsRequest) -> Result<Response, Error> {
+        let model = request.model.clone().unwrap_or_default();
         let response = client.send_chat(request)?;
-        process_response(response)
+        let mut response = process_response(response)?;
+        add_cost(&mut response.metadata, &model, &PRICING);
+        Ok(response)
     }
 
     fn streaming_request(
@@ -198,8 +239,9 @@ impl OllamaComponent {
         mut request: CompletionsRequest,
     ) -> LlmChatStream<OllamaChatStream> {
         request.stream = Some(true);
+        let model = request.model.clone().unwrap_or_default();
         match client.send_chat_stream(request) {
-            Ok(stream) => OllamaChatStream::new(stream),
+            Ok(stream) => OllamaChatStream::new(stream).with_pricing(&model, &PRICING),
             Err(err) => OllamaChatStream::failed(err),
         }
     }
@@ -207,6 +249,7 @@ impl OllamaComponent {
 
 impl Guest for OllamaComponent {
     type ChatStream = LlmChatStream<OllamaChatStream>;
+    type ChatSession = ChatSession<Self>;
 
     fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
         let client = OllamaApi::new(config.model.clone());
@@ -217,9 +260,57 @@ impl Guest for OllamaComponent {
     fn stream(events: Vec<Event>, config: Config) -> ChatStream {
         ChatStream::new(Self::unwrapped_stream(events, config))
     }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        let client = OllamaApi::new(model.clone());
+        let response = client.show_model(&model)?;
+        Ok(show_response_to_model_info(model, response))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let client = OllamaApi::new(String::new());
+        let response = client.list_local_models()?;
+
+        let mut models = Vec::new();
+        for local_model in response.models {
+            let show_response = client.show_model(&local_model.model)?;
+            // Skipping embedding models, which cannot be used for chat
+            if show_response.capabilities.is_empty() || show_response.has_capability("completion") {
+                models.push(show_response_to_model_info(
+                    local_model.model,
+                    show_response,
+                ));
+            }
+        }
+        Ok(models)
+    }
+}
+
+impl BatchGuest for OllamaComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
 }
 
 impl ExtendedGuest for OllamaComponent {
+    const PROVIDER_NAME: &'static str = "ollama";
+
     fn unwrapped_stream(events: Vec<Event>, config: Config) -> LlmChatStream<OllamaChatStream> {
         let client = OllamaApi::new(config.model.clone());
         match events_to_request(events, config) {
@@ -243,6 +334,7 @@ impl ExtendedGuest for OllamaComponent {
                  Do not include the part of the response that was already seen."
                     .to_string(),
             )],
+            cache_hint: None,
         }));
 
         extended_messages.push(Event::Message(Message {
@@ -251,6 +343,7 @@ impl ExtendedGuest for OllamaComponent {
             content: vec![ContentPart::Text(
                 "Here is the original question:".to_string(),
             )],
+            cache_hint: None,
         }));
 
         extended_messages.extend(original_events.iter().map(|e| e.as_ref().unwrap().clone()));
@@ -258,7 +351,13 @@ impl ExtendedGuest for OllamaComponent {
         let mut partial_result_as_content = Vec::new();
         for delta in partial_result {
             if let Some(contents) = &delta.content {
-                partial_result_as_content.extend_from_slice(contents);
+                // Reasoning is not part of the answer the model has to continue
+                partial_result_as_content.extend(
+                    contents
+                        .iter()
+                        .filter(|content| !matches!(content, ContentPart::Reasoning(_)))
+                        .cloned(),
+                );
             }
             if let Some(tool_calls) = &delta.tool_calls {
                 for tool_call in tool_calls {
@@ -279,6 +378,7 @@ impl ExtendedGuest for OllamaComponent {
             .into_iter()
             .chain(partial_result_as_content)
             .collect(),
+            cache_hint: None,
         }));
 
         extended_messages
@@ -289,6 +389,8 @@ impl ExtendedGuest for OllamaComponent {
     }
 }
 
-type DurableOllamaComponent = DurableLLM<OllamaComponent>;
+#[cfg(not(feature = "library"))]
+type DurableOllamaComponent = DurableLLM<CachedLLM<OllamaComponent>>;
 
+#[cfg(not(feature = "library"))]
 golem_llm::export_llm!(DurableOllamaComponent with_types_in golem_llm);
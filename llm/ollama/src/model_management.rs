@@ -0,0 +1,606 @@
+use crate::client::{self, OllamaApi, PullModelProgress};
+use crate::OllamaComponent;
+use exports::golem::llm::model_management::{
+    Error, ErrorCode, GuestPullStream, LocalModel, ModelDescription, ModelDetails, PullEvent,
+    PullProgress, RunningModel,
+};
+use golem_llm::event_source::{Event, EventSource, MessageEvent};
+use golem_rust::wasm_rpc::Pollable;
+use log::trace;
+use std::cell::RefCell;
+use std::task::Poll;
+
+wit_bindgen::generate!({
+    path: "../wit",
+    world: "model-management-library",
+    generate_all,
+    generate_unused_types: true,
+    additional_derives: [
+        PartialEq,
+        golem_rust::FromValueAndType,
+        golem_rust::IntoValue,
+    ],
+});
+
+impl From<golem_llm::golem::llm::llm::Error> for Error {
+    fn from(error: golem_llm::golem::llm::llm::Error) -> Self {
+        use golem_llm::golem::llm::llm::ErrorCode as LlmErrorCode;
+
+        let code = match error.code {
+            LlmErrorCode::InvalidRequest => ErrorCode::InvalidRequest,
+            LlmErrorCode::ModelNotFound => ErrorCode::ModelNotFound,
+            LlmErrorCode::Timeout => ErrorCode::Timeout,
+            _ => ErrorCode::InternalError,
+        };
+        Error {
+            code,
+            message: error.message,
+            provider_error_json: error.provider_error_json,
+        }
+    }
+}
+
+impl From<client::ModelDetails> for ModelDetails {
+    fn from(details: client::ModelDetails) -> Self {
+        ModelDetails {
+            format: details.format,
+            family: details.family,
+            parameter_size: details.parameter_size,
+            quantization_level: details.quantization_level,
+        }
+    }
+}
+
+pub struct OllamaPullStream {
+    stream: RefCell<Option<EventSource>>,
+    failure: Option<Error>,
+    finished: RefCell<bool>,
+}
+
+impl OllamaPullStream {
+    pub fn new(stream: EventSource) -> Self {
+        OllamaPullStream {
+            stream: RefCell::new(Some(stream)),
+            failure: None,
+            finished: RefCell::new(false),
+        }
+    }
+
+    pub fn failed(error: Error) -> Self {
+        OllamaPullStream {
+            stream: RefCell::new(None),
+            failure: Some(error),
+            finished: RefCell::new(false),
+        }
+    }
+
+    fn subscribe(&self) -> Pollable {
+        if let Some(stream) = self.stream.borrow().as_ref() {
+            stream.subscribe()
+        } else {
+            golem_rust::bindings::wasi::clocks::monotonic_clock::subscribe_duration(0)
+        }
+    }
+
+    fn decode_message(&self, raw: &str) -> Result<PullEvent, Error> {
+        trace!("Received raw pull progress: {raw}");
+        let progress: PullModelProgress = serde_json::from_str(raw).map_err(|err| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to parse pull progress: {err}"),
+            provider_error_json: Some(raw.to_string()),
+        })?;
+
+        // Failures after the pull has started, like an unknown model, are reported in the stream
+        if let Some(message) = progress.error {
+            let code = if message.contains("file does not exist") {
+                ErrorCode::ModelNotFound
+            } else {
+                ErrorCode::InternalError
+            };
+            return Err(Error {
+                code,
+                message,
+                provider_error_json: Some(raw.to_string()),
+            });
+        }
+
+        if progress.status == "success" {
+            Ok(PullEvent::Finish)
+        } else {
+            Ok(PullEvent::Progress(PullProgress {
+                status: progress.status,
+                digest: progress.digest,
+                total: progress.total,
+                completed: progress.completed,
+            }))
+        }
+    }
+}
+
+impl GuestPullStream for OllamaPullStream {
+    fn poll_next(&self) -> Option<Vec<Result<PullEvent, Error>>> {
+        if *self.finished.borrow() {
+            return Some(vec![]);
+        }
+
+        let mut stream = self.stream.borrow_mut();
+        if let Some(stream) = stream.as_mut() {
+            match stream.poll_next() {
+                Poll::Ready(None)
+                | Poll::Ready(Some(Err(golem_llm::event_source::error::Error::StreamEnded))) => {
+                    *self.finished.borrow_mut() = true;
+                    Some(vec![])
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    *self.finished.borrow_mut() = true;
+                    Some(vec![Err(Error {
+                        code: ErrorCode::InternalError,
+                        message: error.to_string(),
+                        provider_error_json: None,
+                    })])
+                }
+                Poll::Ready(Some(Ok(Event::Open))) => None,
+                Poll::Ready(Some(Ok(Event::Message(MessageEvent { data, .. })))) => {
+                    let event = self.decode_message(&data);
+                    if matches!(event, Ok(PullEvent::Finish) | Err(_)) {
+                        *self.finished.borrow_mut() = true;
+                    }
+                    Some(vec![event])
+                }
+                Poll::Pending => None,
+            }
+        } else if let Some(error) = self.failure.clone() {
+            *self.finished.borrow_mut() = true;
+            Some(vec![Err(error)])
+        } else {
+            None
+        }
+    }
+
+    fn get_next(&self) -> Vec<Result<PullEvent, Error>> {
+        let pollable = self.subscribe();
+        loop {
+            pollable.block();
+            if let Some(events) = self.poll_next() {
+                return events;
+            }
+        }
+    }
+}
+
+fn list_local_models() -> Result<Vec<LocalModel>, Error> {
+    let client = OllamaApi::new(String::new());
+    let response = client.list_local_models()?;
+    Ok(response
+        .models
+        .into_iter()
+        .map(|model| LocalModel {
+            name: model.name,
+            size: model.size,
+            digest: model.digest,
+            modified_at: model.modified_at,
+            details: model.details.into(),
+        })
+        .collect())
+}
+
+fn pull_model(name: &str) -> OllamaPullStream {
+    let client = OllamaApi::new(String::new());
+    match client.pull_model_stream(name) {
+        Ok(stream) => OllamaPullStream::new(stream),
+        Err(err) => OllamaPullStream::failed(err.into()),
+    }
+}
+
+fn show_model(name: String) -> Result<ModelDescription, Error> {
+    let client = OllamaApi::new(String::new());
+    let response = client.show_model(&name)?;
+    let model_info_json = (!response.model_info.is_empty())
+        .then(|| serde_json::to_string(&response.model_info).ok())
+        .flatten();
+    Ok(ModelDescription {
+        name,
+        details: response.details.into(),
+        capabilities: response.capabilities,
+        modelfile: response.modelfile,
+        parameters: response.parameters,
+        template: response.template,
+        model_info_json,
+        modified_at: response.modified_at,
+    })
+}
+
+fn delete_model(name: &str) -> Result<(), Error> {
+    let client = OllamaApi::new(String::new());
+    client.delete_model(name).map_err(Error::from)
+}
+
+fn list_running_models() -> Result<Vec<RunningModel>, Error> {
+    let client = OllamaApi::new(String::new());
+    let response = client.list_running_models()?;
+    Ok(response
+        .models
+        .into_iter()
+        .map(|model| RunningModel {
+            name: model.name,
+            size: model.size,
+            size_vram: model.size_vram,
+            digest: model.digest,
+            expires_at: model.expires_at,
+            details: model.details.into(),
+        })
+        .collect())
+}
+
+/// When the durability feature flag is off, the model management functions call Ollama directly
+#[cfg(not(feature = "durability"))]
+mod passthrough_impl {
+    use super::exports::golem::llm::model_management::{
+        Error, Guest, LocalModel, ModelDescription, PullStream, RunningModel,
+    };
+    use super::OllamaPullStream;
+    use crate::OllamaComponent;
+    use golem_llm::init_logging;
+
+    impl Guest for OllamaComponent {
+        type PullStream = OllamaPullStream;
+
+        fn list_local_models() -> Result<Vec<LocalModel>, Error> {
+            init_logging();
+            super::list_local_models()
+        }
+
+        fn pull_model(name: String) -> PullStream {
+            init_logging();
+            PullStream::new(super::pull_model(&name))
+        }
+
+        fn show_model(name: String) -> Result<ModelDescription, Error> {
+            init_logging();
+            super::show_model(name)
+        }
+
+        fn delete_model(name: String) -> Result<(), Error> {
+            init_logging();
+            super::delete_model(&name)
+        }
+
+        fn list_running_models() -> Result<Vec<RunningModel>, Error> {
+            init_logging();
+            super::list_running_models()
+        }
+    }
+}
+
+/// When the durability feature flag is on, the model management functions are wrapped with custom
+/// durability the same way `DurableLLM` wraps the LLM functions: the requests and their results
+/// are saved in the oplog, and each poll of a pull stream is persisted like the polls of a chat
+/// stream.
+#[cfg(feature = "durability")]
+mod durable_impl {
+    use super::exports::golem::llm::model_management::{
+        Error, Guest, GuestPullStream, LocalModel, ModelDescription, PullEvent, PullStream,
+        RunningModel,
+    };
+    use super::OllamaPullStream;
+    use crate::OllamaComponent;
+    use golem_llm::init_logging;
+    use golem_rust::bindings::golem::durability::durability::{
+        DurableFunctionType, LazyInitializedPollable,
+    };
+    use golem_rust::durability::Durability;
+    use golem_rust::wasm_rpc::Pollable;
+    use golem_rust::{with_persistence_level, FromValueAndType, IntoValue, PersistenceLevel};
+    use std::cell::RefCell;
+    use std::fmt::{Display, Formatter};
+
+    impl Guest for OllamaComponent {
+        type PullStream = DurablePullStream;
+
+        fn list_local_models() -> Result<Vec<LocalModel>, Error> {
+            init_logging();
+
+            let durability = Durability::<Vec<LocalModel>, Error>::new(
+                "golem_llm",
+                "list_local_models",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    super::list_local_models()
+                });
+                durability.persist_serializable(NoInput, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn pull_model(name: String) -> PullStream {
+            init_logging();
+
+            let durability = Durability::<NoOutput, UnusedError>::new(
+                "golem_llm",
+                "pull_model",
+                DurableFunctionType::WriteRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    PullStream::new(DurablePullStream::live(super::pull_model(&name)))
+                });
+                let _ = durability.persist_infallible(NameInput { name }, NoOutput);
+                result
+            } else {
+                let _: NoOutput = durability.replay_infallible();
+                PullStream::new(DurablePullStream::replay(name))
+            }
+        }
+
+        fn show_model(name: String) -> Result<ModelDescription, Error> {
+            init_logging();
+
+            let durability = Durability::<ModelDescription, Error>::new(
+                "golem_llm",
+                "show_model",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    super::show_model(name.clone())
+                });
+                durability.persist_serializable(NameInput { name }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn delete_model(name: String) -> Result<(), Error> {
+            init_logging();
+
+            let durability = Durability::<NoOutput, Error>::new(
+                "golem_llm",
+                "delete_model",
+                DurableFunctionType::WriteRemote,
+            );
+            let result = if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    super::delete_model(&name).map(|()| NoOutput)
+                });
+                durability.persist_serializable(NameInput { name }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            };
+            result.map(|NoOutput| ())
+        }
+
+        fn list_running_models() -> Result<Vec<RunningModel>, Error> {
+            init_logging();
+
+            let durability = Durability::<Vec<RunningModel>, Error>::new(
+                "golem_llm",
+                "list_running_models",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    super::list_running_models()
+                });
+                durability.persist_serializable(NoInput, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+    }
+
+    /// Represents the durable pull stream's state
+    ///
+    /// In live mode it directly calls the underlying pull stream which is implemented on top of
+    /// an SSE parser using the wasi-http response body stream.
+    ///
+    /// In replay mode it tracks whether the replayed events finished the pull, and the created
+    /// pollables to be able to reattach them to the new live stream when the switch to live mode
+    /// happens.
+    ///
+    /// When reaching the end of the replay mode, if the replayed pull was not finished yet, the
+    /// pull is started again. Ollama keeps the layers downloaded so far, so the new pull continues
+    /// where the interrupted one stopped.
+    enum DurablePullStreamState {
+        Live {
+            stream: OllamaPullStream,
+            pollables: Vec<LazyInitializedPollable>,
+        },
+        Replay {
+            name: String,
+            pollables: Vec<LazyInitializedPollable>,
+            finished: bool,
+        },
+    }
+
+    pub struct DurablePullStream {
+        state: RefCell<Option<DurablePullStreamState>>,
+        subscription: RefCell<Option<Pollable>>,
+    }
+
+    impl DurablePullStream {
+        fn live(stream: OllamaPullStream) -> Self {
+            Self {
+                state: RefCell::new(Some(DurablePullStreamState::Live {
+                    stream,
+                    pollables: Vec::new(),
+                })),
+                subscription: RefCell::new(None),
+            }
+        }
+
+        fn replay(name: String) -> Self {
+            Self {
+                state: RefCell::new(Some(DurablePullStreamState::Replay {
+                    name,
+                    pollables: Vec::new(),
+                    finished: false,
+                })),
+                subscription: RefCell::new(None),
+            }
+        }
+
+        fn subscribe(&self) -> Pollable {
+            let mut state = self.state.borrow_mut();
+            match &mut *state {
+                Some(DurablePullStreamState::Live { stream, .. }) => stream.subscribe(),
+                Some(DurablePullStreamState::Replay { pollables, .. }) => {
+                    let lazy_pollable = LazyInitializedPollable::new();
+                    let pollable = lazy_pollable.subscribe();
+                    pollables.push(lazy_pollable);
+                    pollable
+                }
+                None => {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    impl Drop for DurablePullStream {
+        fn drop(&mut self) {
+            let _ = self.subscription.take();
+
+            match self.state.take() {
+                Some(DurablePullStreamState::Live {
+                    mut pollables,
+                    stream,
+                }) => {
+                    with_persistence_level(PersistenceLevel::PersistNothing, move || {
+                        pollables.clear();
+                        drop(stream);
+                    });
+                }
+                Some(DurablePullStreamState::Replay { mut pollables, .. }) => {
+                    pollables.clear();
+                }
+                None => {}
+            }
+        }
+    }
+
+    impl GuestPullStream for DurablePullStream {
+        fn poll_next(&self) -> Option<Vec<Result<PullEvent, Error>>> {
+            let durability = Durability::<Option<Vec<Result<PullEvent, Error>>>, UnusedError>::new(
+                "golem_llm",
+                "poll_next",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let mut state = self.state.borrow_mut();
+                let (result, new_live_stream) = match &*state {
+                    Some(DurablePullStreamState::Live { stream, .. }) => {
+                        let result =
+                            with_persistence_level(PersistenceLevel::PersistNothing, || {
+                                stream.poll_next()
+                            });
+                        durability.persist_infallible(NoInput, result.clone());
+                        (result, None)
+                    }
+                    Some(DurablePullStreamState::Replay {
+                        name,
+                        pollables,
+                        finished,
+                    }) => {
+                        if *finished {
+                            (None, None)
+                        } else {
+                            let (stream, first_live_result) =
+                                with_persistence_level(PersistenceLevel::PersistNothing, || {
+                                    let stream = super::pull_model(name);
+                                    for lazy_initialized_pollable in pollables {
+                                        lazy_initialized_pollable.set(stream.subscribe());
+                                    }
+
+                                    let next = stream.poll_next();
+                                    (stream, next)
+                                });
+                            durability.persist_infallible(NoInput, first_live_result.clone());
+                            (first_live_result, Some(stream))
+                        }
+                    }
+                    None => {
+                        unreachable!()
+                    }
+                };
+
+                if let Some(stream) = new_live_stream {
+                    let pollables = match state.take() {
+                        Some(DurablePullStreamState::Live { pollables, .. }) => pollables,
+                        Some(DurablePullStreamState::Replay { pollables, .. }) => pollables,
+                        None => {
+                            unreachable!()
+                        }
+                    };
+                    *state = Some(DurablePullStreamState::Live { stream, pollables });
+                }
+
+                result
+            } else {
+                let result: Option<Vec<Result<PullEvent, Error>>> = durability.replay_infallible();
+                let mut state = self.state.borrow_mut();
+                match &mut *state {
+                    Some(DurablePullStreamState::Live { .. }) => {
+                        unreachable!("Durable pull stream cannot be in live mode during replay")
+                    }
+                    Some(DurablePullStreamState::Replay { finished, .. }) => {
+                        if let Some(result) = &result {
+                            if result
+                                .iter()
+                                .any(|event| matches!(event, Ok(PullEvent::Finish) | Err(_)))
+                            {
+                                *finished = true;
+                            }
+                        }
+                    }
+                    None => {
+                        unreachable!()
+                    }
+                }
+                result
+            }
+        }
+
+        fn get_next(&self) -> Vec<Result<PullEvent, Error>> {
+            let mut subscription = self.subscription.borrow_mut();
+            if subscription.is_none() {
+                *subscription = Some(self.subscribe());
+            }
+            let subscription = subscription.as_mut().unwrap();
+            loop {
+                subscription.block();
+                if let Some(events) = self.poll_next() {
+                    return events;
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct NameInput {
+        name: String,
+    }
+
+    #[derive(Debug, IntoValue)]
+    struct NoInput;
+
+    #[derive(Debug, Clone, FromValueAndType, IntoValue)]
+    struct NoOutput;
+
+    #[derive(Debug, FromValueAndType, IntoValue)]
+    struct UnusedError;
+
+    impl Display for UnusedError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "UnusedError")
+        }
+    }
+}
+
+export!(OllamaComponent with_types_in crate::model_management);
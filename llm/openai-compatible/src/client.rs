@@ -0,0 +1,486 @@
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
+use log::trace;
+use reqwest::header::HeaderValue;
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// The Chat Completions API client for servers implementing the OpenAI wire format.
+///
+/// Based on https://platform.openai.com/docs/api-reference/chat/create
+pub struct CompletionsApi {
+    base_url: String,
+    api_key: Option<String>,
+    api_key_header: Option<String>,
+    extra_headers: HashMap<String, String>,
+    client: Client,
+}
+
+impl CompletionsApi {
+    /// Creates a client for the server at `base_url` (including the version prefix, for example
+    /// `http://localhost:8000/v1`).
+    ///
+    /// The API key is sent as a bearer token in the `Authorization` header, unless
+    /// `api_key_header` is set, in which case it is sent as-is in that header.
+    pub fn new(
+        base_url: String,
+        api_key: Option<String>,
+        api_key_header: Option<String>,
+        extra_headers: HashMap<String, String>,
+    ) -> Self {
+        let client = Client::builder()
+            .build()
+            .expect("Failed to initialize HTTP client");
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            api_key_header,
+            extra_headers,
+            client,
+        }
+    }
+
+    pub fn send_messages(&self, request: CompletionsRequest) -> Result<CompletionsResponse, Error> {
+        trace!("Sending request to OpenAI-compatible API: {request:?}");
+
+        let response: Response = send_with_retry(|| {
+            self.create_request(Method::POST, "chat/completions")
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn stream_send_messages(&self, request: CompletionsRequest) -> Result<EventSource, Error> {
+        trace!("Sending request to OpenAI-compatible API: {request:?}");
+
+        let response: Response = send_with_retry(|| {
+            self.create_request(Method::POST, "chat/completions")
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        trace!("Initializing SSE stream");
+
+        EventSource::new(response)
+            .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
+    }
+
+    pub fn list_models(&self) -> Result<ModelsResponse, Error> {
+        trace!("Listing models of OpenAI-compatible API");
+
+        let response: Response =
+            send_idempotent_with_retry(|| self.create_request(Method::GET, "models"))
+                .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    fn create_request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut builder = self
+            .client
+            .request(method, format!("{}/{path}", self.base_url));
+
+        if let Some(api_key) = &self.api_key {
+            builder = match &self.api_key_header {
+                Some(header) => builder.header(header, api_key),
+                None => builder.bearer_auth(api_key),
+            };
+        }
+
+        for (name, value) in &self.extra_headers {
+            builder = builder.header(name, value);
+        }
+
+        builder
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelsResponse {
+    pub data: Vec<Model>,
+}
+
+/// A model of the models endpoint. Its limits are not part of the OpenAI format, but some
+/// servers report them in their own fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    /// Reported by vLLM
+    pub max_model_len: Option<u32>,
+    /// Reported by OpenRouter and LM Studio
+    pub context_length: Option<u32>,
+    /// Reported by Groq
+    pub context_window: Option<u32>,
+    /// Reported by Groq
+    pub max_completion_tokens: Option<u32>,
+}
+
+impl Model {
+    pub fn context_window(&self) -> Option<u32> {
+        self.max_model_len
+            .or(self.context_length)
+            .or(self.context_window)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionsRequest {
+    pub messages: Vec<Message>,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<Effort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Server specific parameters (for example `top_k` or `min_p`) passed through from the
+    /// provider options
+    #[serde(flatten)]
+    pub extra_parameters: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Effort {
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "high")]
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseFormat {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "json_object")]
+    JsonObject,
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchema },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOptions {
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Tool {
+    #[serde(rename = "function")]
+    Function { function: Function },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Function {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "role")]
+pub enum Message {
+    #[serde(rename = "system")]
+    System {
+        content: Content,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    #[serde(rename = "user")]
+    User {
+        content: Content,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    #[serde(rename = "assistant")]
+    Assistant {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        content: Option<Content>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tool_calls: Option<Vec<ToolCall>>,
+    },
+    #[serde(rename = "tool")]
+    Tool {
+        content: String,
+        tool_call_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    TextInput(String),
+    List(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    String(String), // none, auto or required
+    Function(ToolChoiceFunction),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolChoiceFunction {
+    #[serde(rename = "function")]
+    Function { function: FunctionName },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionName {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ContentPart {
+    #[serde(rename = "text")]
+    TextInput { text: String },
+    #[serde(rename = "image_url")]
+    ImageInput { image_url: ImageUrl },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub enum Detail {
+    #[serde(rename = "auto")]
+    #[default]
+    Auto,
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "high")]
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Detail>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolCall {
+    #[serde(rename = "function")]
+    Function {
+        function: FunctionCall,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    #[serde(default)]
+    pub arguments: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionsResponse {
+    #[serde(default)]
+    pub id: String,
+    pub choices: Vec<Choice>,
+    pub created: Option<u64>,
+    pub model: Option<String>,
+    pub system_fingerprint: Option<String>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Choice {
+    pub finish_reason: Option<FinishReason>,
+    pub message: ResponseMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FinishReason {
+    #[serde(rename = "tool_calls")]
+    ToolCalls,
+    #[serde(rename = "function_call")]
+    FunctionCall,
+    #[serde(rename = "stop")]
+    Stop,
+    #[serde(rename = "length")]
+    Length,
+    #[serde(rename = "content_filter")]
+    ContentFilter,
+    #[serde(rename = "error")]
+    Error,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseMessage {
+    pub content: Option<String>,
+    /// Reasoning text, as returned by vLLM, llama.cpp and DeepSeek compatible servers
+    pub reasoning_content: Option<String>,
+    /// Reasoning text, as returned by Groq, LM Studio and OpenRouter compatible servers
+    pub reasoning: Option<String>,
+    pub refusal: Option<String>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub message: String,
+}
+
+/// Error bodies are either wrapped in an `error` field (OpenAI, llama.cpp) or returned
+/// directly (vLLM)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ErrorResponseBody {
+    Wrapped { error: ErrorResponse },
+    Plain(ErrorResponse),
+}
+
+impl ErrorResponseBody {
+    pub fn message(self) -> String {
+        match self {
+            ErrorResponseBody::Wrapped { error } => error.message,
+            ErrorResponseBody::Plain(error) => error.message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub completion_tokens: Option<u32>,
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    pub prompt_tokens: Option<u32>,
+    pub total_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionTokensDetails {
+    pub reasoning_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTokensDetails {
+    pub cached_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub id: String,
+    pub created: Option<u64>,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub choices: Vec<ChoiceChunk>,
+    pub usage: Option<Usage>,
+    pub system_fingerprint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceChunk {
+    #[serde(default)]
+    pub delta: ChoiceDelta,
+    pub finish_reason: Option<FinishReason>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChoiceDelta {
+    pub content: Option<String>,
+    pub reasoning_content: Option<String>,
+    pub reasoning: Option<String>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub role: Option<String>,
+}
+
+fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T, Error> {
+    let status = response.status();
+    if status.is_success() {
+        let raw_body = response
+            .text()
+            .map_err(|err| from_reqwest_error("Failed to receive response body", err))?;
+
+        let body = serde_json::from_str::<T>(&raw_body).map_err(|err| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to decode response body: {err}"),
+            provider_error_json: Some(raw_body),
+            retry_after_ms: None,
+        })?;
+
+        trace!("Received response from OpenAI-compatible API: {body:?}");
+
+        Ok(body)
+    } else {
+        let retry_after_ms = retry_after_ms(response.headers());
+        let raw_error_body = response
+            .text()
+            .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
+
+        trace!("Received {status} response from OpenAI-compatible API: {raw_error_body:?}");
+
+        let message = serde_json::from_str::<ErrorResponseBody>(&raw_error_body)
+            .map(|error_body| error_body.message())
+            .unwrap_or_else(|_| format!("Request failed with {status}"));
+
+        Err(Error {
+            code: error_code_from_response(status, &raw_error_body),
+            message,
+            provider_error_json: Some(raw_error_body),
+            retry_after_ms,
+        })
+    }
+}
@@ -0,0 +1,396 @@
+use crate::client::{
+    CompletionsRequest, CompletionsResponse, Detail, Effort, FunctionName, Model,
+    ToolChoiceFunction,
+};
+use base64::{engine::general_purpose, Engine as _};
+use golem_llm::error::unsupported;
+use golem_llm::golem::llm::llm::{
+    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageDetail, ImageReference,
+    ModelInfo, ReasoningContent, ReasoningEffort, Response, ResponseFormat, ResponseMetadata, Role,
+    ToolCall, ToolChoice, ToolDefinition, ToolResult, Usage,
+};
+use std::collections::HashMap;
+
+pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<CompletionsRequest, Error> {
+    let mut options = config
+        .provider_options
+        .map(|options| {
+            options
+                .into_iter()
+                .map(|kv| (kv.key, kv.value))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut completion_messages = Vec::new();
+    for event in events {
+        match event {
+            Event::Message(message) => match message.role {
+                Role::User => completion_messages.push(crate::client::Message::User {
+                    name: message.name,
+                    content: convert_content_parts(message.content)?,
+                }),
+                Role::Assistant => completion_messages.push(crate::client::Message::Assistant {
+                    name: message.name,
+                    content: Some(convert_content_parts(message.content)?),
+                    tool_calls: None,
+                }),
+                Role::System => completion_messages.push(crate::client::Message::System {
+                    name: message.name,
+                    content: convert_content_parts(message.content)?,
+                }),
+                Role::Tool => completion_messages.push(crate::client::Message::Tool {
+                    name: message.name,
+                    content: convert_content_parts_to_string(message.content)?,
+                    tool_call_id: "unknown".to_string(),
+                }),
+            },
+            Event::Response(response) => {
+                completion_messages.push(crate::client::Message::Assistant {
+                    name: None,
+                    content: {
+                        (!response.content.is_empty())
+                            .then(|| convert_content_parts(response.content))
+                            .transpose()?
+                    },
+                    tool_calls: (!response.tool_calls.is_empty()).then(|| {
+                        response
+                            .tool_calls
+                            .into_iter()
+                            .map(tool_call_to_client_tool_call)
+                            .collect()
+                    }),
+                });
+            }
+            Event::ToolResults(tool_results) => {
+                completion_messages.extend(tool_results.into_iter().map(tool_result_to_message))
+            }
+        }
+    }
+
+    let mut tools = Vec::new();
+    for tool in config.tools.unwrap_or_default() {
+        tools.push(tool_definition_to_tool(tool)?)
+    }
+
+    let response_format = config
+        .response_format
+        .map(convert_response_format)
+        .transpose()?;
+
+    let frequency_penalty = options
+        .remove("frequency_penalty")
+        .and_then(|fp_s| fp_s.parse::<f32>().ok());
+    let presence_penalty = options
+        .remove("presence_penalty")
+        .and_then(|pp_s| pp_s.parse::<f32>().ok());
+    let seed = options
+        .remove("seed")
+        .and_then(|seed_s| seed_s.parse::<u32>().ok());
+    let top_p = options
+        .remove("top_p")
+        .and_then(|top_p_s| top_p_s.parse::<f32>().ok());
+    let user = options.remove("user");
+
+    // Every other provider option is passed to the server as a request body parameter
+    let extra_parameters = options
+        .into_iter()
+        .map(|(key, value)| {
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            (key, value)
+        })
+        .collect();
+
+    Ok(CompletionsRequest {
+        messages: completion_messages,
+        model: config.model,
+        frequency_penalty,
+        max_tokens: config.max_tokens,
+        parallel_tool_calls: config.parallel_tool_calls,
+        presence_penalty,
+        reasoning_effort: config
+            .reasoning
+            .and_then(|reasoning| reasoning.effort)
+            .map(convert_reasoning_effort),
+        response_format,
+        seed,
+        stop: config.stop_sequences,
+        stream: Some(false),
+        stream_options: None,
+        temperature: config.temperature,
+        tool_choice: config.tool_choice.map(convert_tool_choice),
+        tools,
+        top_p,
+        user,
+        extra_parameters,
+    })
+}
+
+pub fn process_response(response: CompletionsResponse) -> Result<Response, Error> {
+    let choice = response.choices.first();
+    if let Some(choice) = choice {
+        let mut contents = Vec::new();
+        let mut tool_calls = Vec::new();
+
+        if let Some(reasoning) = choice
+            .message
+            .reasoning_content
+            .as_ref()
+            .or(choice.message.reasoning.as_ref())
+        {
+            contents.push(reasoning_to_content_part(reasoning.clone()));
+        }
+        if let Some(content) = &choice.message.content {
+            contents.push(ContentPart::Text(content.clone()));
+        }
+        if let Some(refusal) = &choice.message.refusal {
+            contents.push(ContentPart::Text(refusal.clone()));
+        }
+
+        let empty = Vec::new();
+        for tool_call in choice.message.tool_calls.as_ref().unwrap_or(&empty) {
+            tool_calls.push(convert_tool_call(tool_call));
+        }
+
+        let metadata = ResponseMetadata {
+            finish_reason: choice.finish_reason.as_ref().map(convert_finish_reason),
+            usage: response.usage.as_ref().map(convert_usage),
+            provider_id: None,
+            timestamp: response.created.map(|created| created.to_string()),
+            provider_metadata_json: None,
+            cost: None,
+        };
+
+        Ok(Response {
+            id: response.id,
+            content: contents,
+            tool_calls,
+            metadata,
+            citations: Vec::new(),
+        })
+    } else {
+        Err(Error {
+            code: ErrorCode::InternalError,
+            message: "No choices in response".to_string(),
+            provider_error_json: None,
+            retry_after_ms: None,
+        })
+    }
+}
+
+pub fn tool_call_to_client_tool_call(tool_call: ToolCall) -> crate::client::ToolCall {
+    crate::client::ToolCall::Function {
+        function: crate::client::FunctionCall {
+            arguments: tool_call.arguments_json,
+            name: Some(tool_call.name),
+        },
+        id: Some(tool_call.id.clone()),
+        index: None,
+    }
+}
+
+pub fn tool_result_to_message(tool_result: ToolResult) -> crate::client::Message {
+    match tool_result {
+        ToolResult::Success(success) => crate::client::Message::Tool {
+            name: None,
+            content: success.result_json,
+            tool_call_id: success.id,
+        },
+        ToolResult::Error(error) => crate::client::Message::Tool {
+            name: None,
+            content: error.error_message,
+            tool_call_id: error.id,
+        },
+    }
+}
+
+pub fn convert_tool_call(tool_call: &crate::client::ToolCall) -> ToolCall {
+    match tool_call {
+        crate::client::ToolCall::Function { function, id, .. } => ToolCall {
+            id: id.clone().unwrap_or_default(),
+            name: function.name.clone().unwrap_or_default(),
+            arguments_json: function.arguments.clone(),
+        },
+    }
+}
+
+fn convert_content_parts(contents: Vec<ContentPart>) -> Result<crate::client::Content, Error> {
+    let mut result = Vec::new();
+    for content in contents {
+        match content {
+            ContentPart::Text(text) => result.push(crate::client::ContentPart::TextInput { text }),
+            ContentPart::Image(image_reference) => match image_reference {
+                ImageReference::Url(image_url) => {
+                    result.push(crate::client::ContentPart::ImageInput {
+                        image_url: crate::client::ImageUrl {
+                            url: image_url.url,
+                            detail: image_url.detail.map(|d| d.into()),
+                        },
+                    })
+                }
+                ImageReference::Inline(image_source) => {
+                    let base64_data = general_purpose::STANDARD.encode(&image_source.data);
+                    let media_type = &image_source.mime_type;
+                    result.push(crate::client::ContentPart::ImageInput {
+                        image_url: crate::client::ImageUrl {
+                            url: format!("data:{media_type};base64,{base64_data}"),
+                            detail: image_source.detail.map(|d| d.into()),
+                        },
+                    });
+                }
+            },
+            // Reasoning is not sent back to the model
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
+        }
+    }
+    Ok(crate::client::Content::List(result))
+}
+
+fn convert_content_parts_to_string(contents: Vec<ContentPart>) -> Result<String, Error> {
+    let mut result = String::new();
+    for content in contents {
+        match content {
+            ContentPart::Text(text) => result.push_str(&text),
+            ContentPart::Image(_) => {}
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
+        }
+    }
+    Ok(result)
+}
+
+impl From<ImageDetail> for Detail {
+    fn from(value: ImageDetail) -> Self {
+        match value {
+            ImageDetail::Auto => Self::Auto,
+            ImageDetail::Low => Self::Low,
+            ImageDetail::High => Self::High,
+        }
+    }
+}
+
+pub fn convert_finish_reason(value: &crate::client::FinishReason) -> FinishReason {
+    match value {
+        crate::client::FinishReason::Stop => FinishReason::Stop,
+        crate::client::FinishReason::Length => FinishReason::Length,
+        crate::client::FinishReason::ContentFilter => FinishReason::ContentFilter,
+        crate::client::FinishReason::ToolCalls => FinishReason::ToolCalls,
+        crate::client::FinishReason::FunctionCall => FinishReason::ToolCalls,
+        crate::client::FinishReason::Error => FinishReason::Error,
+        crate::client::FinishReason::Other => FinishReason::Other,
+    }
+}
+
+pub fn convert_usage(value: &crate::client::Usage) -> Usage {
+    Usage {
+        input_tokens: value.prompt_tokens,
+        output_tokens: value.completion_tokens,
+        total_tokens: value.total_tokens,
+        reasoning_tokens: value
+            .completion_tokens_details
+            .as_ref()
+            .and_then(|details| details.reasoning_tokens),
+        cache_read_tokens: value
+            .prompt_tokens_details
+            .as_ref()
+            .and_then(|details| details.cached_tokens),
+        cache_write_tokens: None,
+    }
+}
+
+pub fn reasoning_to_content_part(text: String) -> ContentPart {
+    ContentPart::Reasoning(ReasoningContent {
+        text,
+        signature: None,
+        redacted_data: None,
+    })
+}
+
+fn convert_reasoning_effort(effort: ReasoningEffort) -> Effort {
+    match effort {
+        ReasoningEffort::Low => Effort::Low,
+        ReasoningEffort::Medium => Effort::Medium,
+        ReasoningEffort::High => Effort::High,
+    }
+}
+
+fn tool_definition_to_tool(tool: ToolDefinition) -> Result<crate::client::Tool, Error> {
+    if tool.builtin.is_some() {
+        return Err(unsupported("builtin tools"));
+    }
+    match serde_json::from_str(&tool.parameters_schema) {
+        Ok(value) => Ok(crate::client::Tool::Function {
+            function: crate::client::Function {
+                name: tool.name,
+                description: tool.description,
+                parameters: value,
+            },
+        }),
+        Err(error) => Err(Error {
+            code: ErrorCode::InternalError,
+            message: format!("Failed to parse tool parameters for {}: {error}", tool.name),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+    }
+}
+
+fn convert_tool_choice(tool_choice: ToolChoice) -> crate::client::ToolChoice {
+    match tool_choice {
+        ToolChoice::Auto => crate::client::ToolChoice::String("auto".to_string()),
+        ToolChoice::None => crate::client::ToolChoice::String("none".to_string()),
+        ToolChoice::Required => crate::client::ToolChoice::String("required".to_string()),
+        ToolChoice::Specific(name) => {
+            crate::client::ToolChoice::Function(ToolChoiceFunction::Function {
+                function: FunctionName { name },
+            })
+        }
+    }
+}
+
+fn convert_response_format(
+    response_format: ResponseFormat,
+) -> Result<crate::client::ResponseFormat, Error> {
+    match response_format {
+        ResponseFormat::Text => Ok(crate::client::ResponseFormat::Text),
+        ResponseFormat::JsonObject => Ok(crate::client::ResponseFormat::JsonObject),
+        ResponseFormat::JsonSchema(json_schema) => {
+            match serde_json::from_str(&json_schema.schema) {
+                Ok(schema) => Ok(crate::client::ResponseFormat::JsonSchema {
+                    json_schema: crate::client::JsonSchema {
+                        name: json_schema.name,
+                        schema,
+                        strict: json_schema.strict,
+                    },
+                }),
+                Err(error) => Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Failed to parse response format schema {}: {error}",
+                        json_schema.name
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                }),
+            }
+        }
+    }
+}
+
+/// Converts a model of the models endpoint. The capabilities are unknown, as neither the OpenAI
+/// format nor the common servers report them.
+pub fn model_to_model_info(model: Model) -> ModelInfo {
+    ModelInfo {
+        context_window: model.context_window(),
+        max_output_tokens: model.max_completion_tokens,
+        capabilities: None,
+        id: model.id,
+    }
+}
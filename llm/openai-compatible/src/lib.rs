@@ -0,0 +1,432 @@
+mod client;
+mod conversions;
+
+use crate::client::{
+    ChatCompletionChunk, CompletionsApi, CompletionsRequest, ErrorResponseBody, FunctionCall,
+    StreamOptions, Usage,
+};
+use crate::conversions::{
+    convert_finish_reason, convert_usage, events_to_request, model_to_model_info, process_response,
+    reasoning_to_content_part,
+};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
+use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
+use golem_llm::config::{get_config_key, get_config_key_or_none, with_config_key};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::unsupported;
+use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{
+    ChatStream, Config, ContentPart, Error, ErrorCode, Event, FinishReason, Guest, Message,
+    ModelInfo, Response, ResponseMetadata, Role, StreamDelta, StreamEvent, ToolCallDelta,
+};
+use golem_llm::pricing::{add_cost, Pricing};
+use golem_llm::tokens::estimate_tokens;
+use golem_rust::wasm_rpc::Pollable;
+use log::trace;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+
+pub struct OpenAICompatibleChatStream {
+    stream: RefCell<Option<EventSource>>,
+    failure: Option<Error>,
+    finished: RefCell<bool>,
+    finish_reason: RefCell<Option<FinishReason>>,
+    unindexed_tool_calls: RefCell<u32>,
+}
+
+impl OpenAICompatibleChatStream {
+    pub fn new(stream: EventSource) -> LlmChatStream<Self> {
+        LlmChatStream::new(OpenAICompatibleChatStream {
+            stream: RefCell::new(Some(stream)),
+            failure: None,
+            finished: RefCell::new(false),
+            finish_reason: RefCell::new(None),
+            unindexed_tool_calls: RefCell::new(0),
+        })
+    }
+
+    pub fn failed(error: Error) -> LlmChatStream<Self> {
+        LlmChatStream::new(OpenAICompatibleChatStream {
+            stream: RefCell::new(None),
+            failure: Some(error),
+            finished: RefCell::new(false),
+            finish_reason: RefCell::new(None),
+            unindexed_tool_calls: RefCell::new(0),
+        })
+    }
+
+    fn finish(&self, chunk: &ChatCompletionChunk, usage: &Usage) -> StreamEvent {
+        StreamEvent::Finish(ResponseMetadata {
+            finish_reason: *self.finish_reason.borrow(),
+            usage: Some(convert_usage(usage)),
+            provider_id: None,
+            timestamp: chunk.created.map(|created| created.to_string()),
+            provider_metadata_json: None,
+            cost: None,
+        })
+    }
+}
+
+impl LlmChatStreamState for OpenAICompatibleChatStream {
+    fn failure(&self) -> &Option<Error> {
+        &self.failure
+    }
+
+    fn is_finished(&self) -> bool {
+        *self.finished.borrow()
+    }
+
+    fn set_finished(&self) {
+        *self.finished.borrow_mut() = true;
+    }
+
+    fn stream(&self) -> Ref<'_, Option<EventSource>> {
+        self.stream.borrow()
+    }
+
+    fn stream_mut(&self) -> RefMut<'_, Option<EventSource>> {
+        self.stream.borrow_mut()
+    }
+
+    fn decode_message(&self, raw: &str) -> Result<Option<StreamEvent>, Error> {
+        fn decode_internal_error<S: Into<String>>(message: S) -> Error {
+            Error {
+                code: ErrorCode::InternalError,
+                message: message.into(),
+                provider_error_json: None,
+                retry_after_ms: None,
+            }
+        }
+
+        trace!("Received raw stream event: {raw}");
+        if raw.starts_with(": ") {
+            return Ok(None); // comment
+        }
+
+        let json: serde_json::Value = serde_json::from_str(raw).map_err(|err| {
+            decode_internal_error(format!("Failed to deserialize stream event: {err}"))
+        })?;
+
+        if json.get("error").is_some_and(|error| !error.is_null()) {
+            let message = serde_json::from_value::<ErrorResponseBody>(json)
+                .map(|error_body| error_body.message())
+                .unwrap_or_else(|_| "Received error event from server".to_string());
+            return Err(Error {
+                code: ErrorCode::InternalError,
+                message,
+                provider_error_json: Some(raw.to_string()),
+                retry_after_ms: None,
+            });
+        }
+
+        let chunk: ChatCompletionChunk = serde_json::from_value(json)
+            .map_err(|err| decode_internal_error(format!("Failed to parse stream event: {err}")))?;
+
+        let Some(choice) = chunk.choices.first() else {
+            // The final chunk only carries the usage when `include_usage` is requested
+            return Ok(chunk.usage.as_ref().map(|usage| self.finish(&chunk, usage)));
+        };
+
+        if let Some(finish_reason) = &choice.finish_reason {
+            *self.finish_reason.borrow_mut() = Some(convert_finish_reason(finish_reason));
+        }
+
+        let content = choice
+            .delta
+            .reasoning_content
+            .clone()
+            .or(choice.delta.reasoning.clone())
+            .filter(|reasoning| !reasoning.is_empty())
+            .into_iter()
+            .map(reasoning_to_content_part)
+            .chain(
+                choice
+                    .delta
+                    .content
+                    .clone()
+                    .filter(|content| !content.is_empty())
+                    .map(ContentPart::Text),
+            )
+            .collect::<Vec<_>>();
+
+        let mut tool_call_deltas = Vec::new();
+        for tool_call in choice.delta.tool_calls.clone().unwrap_or_default() {
+            match tool_call {
+                client::ToolCall::Function {
+                    id,
+                    function:
+                        FunctionCall {
+                            name: Some(name),
+                            arguments,
+                        },
+                    index: None,
+                } => {
+                    // Full tool call, sent as a single fragment. These are numbered separately,
+                    // as servers not indexing the tool calls do not stream them either.
+                    let mut unindexed_tool_calls = self.unindexed_tool_calls.borrow_mut();
+                    tool_call_deltas.push(ToolCallDelta {
+                        index: *unindexed_tool_calls,
+                        id,
+                        name: Some(name),
+                        arguments_fragment: arguments,
+                    });
+                    *unindexed_tool_calls += 1;
+                }
+                client::ToolCall::Function {
+                    id,
+                    function: FunctionCall { name, arguments },
+                    index: Some(index),
+                } => {
+                    // Beginning or continuation of a streamed tool call. Some servers repeat
+                    // the id and name in every fragment, or send them empty.
+                    tool_call_deltas.push(ToolCallDelta {
+                        index,
+                        id: id.filter(|id| !id.is_empty()),
+                        name: name.filter(|name| !name.is_empty()),
+                        arguments_fragment: arguments,
+                    });
+                }
+                _ => {
+                    return Err(decode_internal_error(format!(
+                        "Unexpected tool call format: {tool_call:?}"
+                    )));
+                }
+            }
+        }
+
+        if !content.is_empty() || !tool_call_deltas.is_empty() {
+            Ok(Some(StreamEvent::Delta(StreamDelta {
+                content: (!content.is_empty()).then_some(content),
+                tool_calls: None,
+                tool_call_deltas: (!tool_call_deltas.is_empty()).then_some(tool_call_deltas),
+                citations: None,
+            })))
+        } else {
+            Ok(chunk.usage.as_ref().map(|usage| self.finish(&chunk, usage)))
+        }
+    }
+}
+
+/// The models are not known in advance, so only the prices configured in `GOLEM_LLM_PRICES` apply
+const PRICING: Pricing = Pricing {
+    input_includes_cached: true,
+    known_prices: &[],
+};
+
+pub struct OpenAICompatibleComponent;
+
+impl OpenAICompatibleComponent {
+    const BASE_URL_ENV_VAR_NAME: &'static str = "GOLEM_OPENAI_COMPATIBLE_BASE_URL";
+    const API_KEY_ENV_VAR_NAME: &'static str = "OPENAI_COMPATIBLE_API_KEY";
+    const API_KEY_HEADER_ENV_VAR_NAME: &'static str = "GOLEM_OPENAI_COMPATIBLE_API_KEY_HEADER";
+    const EXTRA_HEADERS_ENV_VAR_NAME: &'static str = "GOLEM_OPENAI_COMPATIBLE_EXTRA_HEADERS";
+
+    fn create_client(base_url: String) -> Result<CompletionsApi, Error> {
+        let extra_headers = match get_config_key_or_none(Self::EXTRA_HEADERS_ENV_VAR_NAME) {
+            Some(extra_headers) => serde_json::from_str::<HashMap<String, String>>(&extra_headers)
+                .map_err(|err| Error {
+                    code: ErrorCode::InternalError,
+                    message: format!(
+                        "Invalid config key {}, expected a JSON object of header names and values: {err}",
+                        Self::EXTRA_HEADERS_ENV_VAR_NAME
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                })?,
+            None => HashMap::new(),
+        };
+
+        Ok(CompletionsApi::new(
+            base_url,
+            get_config_key_or_none(Self::API_KEY_ENV_VAR_NAME),
+            get_config_key_or_none(Self::API_KEY_HEADER_ENV_VAR_NAME),
+            extra_headers,
+        ))
+    }
+
+    fn request(client: CompletionsApi, request: CompletionsRequest) -> Result<Response, Error> {
+        let model = request.model.clone();
+        let response = client.send_messages(request)?;
+        let mut response = process_response(response)?;
+        add_cost(&mut response.metadata, &model, &PRICING);
+        Ok(response)
+    }
+
+    fn streaming_request(
+        client: CompletionsApi,
+        mut request: CompletionsRequest,
+    ) -> LlmChatStream<OpenAICompatibleChatStream> {
+        request.stream = Some(true);
+        request.stream_options = Some(StreamOptions {
+            include_usage: true,
+        });
+        let model = request.model.clone();
+        match client.stream_send_messages(request) {
+            Ok(stream) => OpenAICompatibleChatStream::new(stream).with_pricing(&model, &PRICING),
+            Err(err) => OpenAICompatibleChatStream::failed(err),
+        }
+    }
+}
+
+impl Guest for OpenAICompatibleComponent {
+    type ChatStream = LlmChatStream<OpenAICompatibleChatStream>;
+    type ChatSession = ChatSession<Self>;
+
+    fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+        let base_url = get_config_key(Self::BASE_URL_ENV_VAR_NAME)?;
+        let client = Self::create_client(base_url)?;
+        let request = events_to_request(events, config)?;
+        Self::request(client, request)
+    }
+
+    fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+        ChatStream::new(Self::unwrapped_stream(events, config))
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        let base_url = get_config_key(Self::BASE_URL_ENV_VAR_NAME)?;
+        let client = Self::create_client(base_url)?;
+        let response = client.list_models()?;
+        let known_model = response.data.into_iter().find(|known| known.id == model);
+
+        Ok(match known_model {
+            Some(known_model) => model_to_model_info(known_model),
+            None => ModelInfo {
+                id: model,
+                context_window: None,
+                max_output_tokens: None,
+                capabilities: None,
+            },
+        })
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let base_url = get_config_key(Self::BASE_URL_ENV_VAR_NAME)?;
+        let client = Self::create_client(base_url)?;
+        let response = client.list_models()?;
+        Ok(response.data.into_iter().map(model_to_model_info).collect())
+    }
+}
+
+impl BatchGuest for OpenAICompatibleComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+}
+
+impl ExtendedGuest for OpenAICompatibleComponent {
+    const PROVIDER_NAME: &'static str = "openai-compatible";
+
+    fn unwrapped_stream(
+        events: Vec<Event>,
+        config: Config,
+    ) -> LlmChatStream<OpenAICompatibleChatStream> {
+        with_config_key(
+            Self::BASE_URL_ENV_VAR_NAME,
+            OpenAICompatibleChatStream::failed,
+            |base_url| match Self::create_client(base_url) {
+                Ok(client) => match events_to_request(events, config) {
+                    Ok(request) => Self::streaming_request(client, request),
+                    Err(err) => OpenAICompatibleChatStream::failed(err),
+                },
+                Err(err) => OpenAICompatibleChatStream::failed(err),
+            },
+        )
+    }
+
+    fn retry_prompt(
+        original_events: &[Result<Event, Error>],
+        partial_result: &[StreamDelta],
+    ) -> Vec<Event> {
+        let mut extended_events = Vec::new();
+        extended_events.push(Event::Message(Message {
+            role: Role::System,
+            name: None,
+            content: vec![
+                ContentPart::Text(
+                    "You were asked the same question previously, but the response was interrupted before completion. \
+                     Please continue your response from where you left off. \
+                     Do not include the part of the response that was already seen.".to_string()),
+            ],
+            cache_hint: None,
+        }));
+        extended_events.push(Event::Message(Message {
+            role: Role::User,
+            name: None,
+            content: vec![ContentPart::Text(
+                "Here is the original question:".to_string(),
+            )],
+            cache_hint: None,
+        }));
+        extended_events.extend(
+            original_events
+                .iter()
+                .filter_map(|event| event.as_ref().ok().cloned()),
+        );
+
+        let mut partial_result_as_content = Vec::new();
+        for delta in partial_result {
+            if let Some(contents) = &delta.content {
+                // Reasoning is not part of the answer the model has to continue
+                partial_result_as_content.extend(
+                    contents
+                        .iter()
+                        .filter(|content| !matches!(content, ContentPart::Reasoning(_)))
+                        .cloned(),
+                );
+            }
+            if let Some(tool_calls) = &delta.tool_calls {
+                for tool_call in tool_calls {
+                    partial_result_as_content.push(ContentPart::Text(format!(
+                        "<tool-call id=\"{}\" name=\"{}\" arguments=\"{}\"/>",
+                        tool_call.id, tool_call.name, tool_call.arguments_json,
+                    )));
+                }
+            }
+        }
+
+        extended_events.push(Event::Message(Message {
+            role: Role::User,
+            name: None,
+            content: vec![ContentPart::Text(
+                "Here is the partial response that was successfully received:".to_string(),
+            )]
+            .into_iter()
+            .chain(partial_result_as_content)
+            .collect(),
+            cache_hint: None,
+        }));
+        extended_events
+    }
+
+    fn subscribe(stream: &Self::ChatStream) -> Pollable {
+        stream.subscribe()
+    }
+}
+
+#[cfg(not(feature = "library"))]
+type DurableOpenAICompatibleComponent = DurableLLM<CachedLLM<OpenAICompatibleComponent>>;
+
+#[cfg(not(feature = "library"))]
+golem_llm::export_llm!(DurableOpenAICompatibleComponent with_types_in golem_llm);
@@ -1,6 +1,9 @@
-use golem_llm::error::{error_code_from_status, from_event_source_error, from_reqwest_error};
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
 use golem_llm::event_source::EventSource;
 use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
 use log::trace;
 use reqwest::header::HeaderValue;
 use reqwest::{Client, Method, Response};
@@ -10,6 +13,12 @@ use std::fmt::Debug;
 
 const BASE_URL: &str = "https://api.openai.com";
 
+/// Endpoint the requests of batches are sent to
+pub const BATCH_ENDPOINT: &str = "/v1/responses";
+
+/// Boundary of the multipart body used to upload batch input files
+const MULTIPART_BOUNDARY: &str = "golem-llm-batch-input-5f3a9c0e";
+
 /// The OpenAI API client for creating model responses.
 ///
 /// Based on https://platform.openai.com/docs/api-reference/responses/create
@@ -35,13 +44,13 @@ impl ResponsesApi {
     ) -> Result<CreateModelResponseResponse, Error> {
         trace!("Sending request to OpenAI API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/v1/responses"))
-            .bearer_auth(&self.openai_api_key)
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/responses"))
+                .bearer_auth(&self.openai_api_key)
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         parse_response(response)
     }
@@ -52,23 +61,160 @@ impl ResponsesApi {
     ) -> Result<EventSource, Error> {
         trace!("Sending request to OpenAI API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/v1/responses"))
-            .bearer_auth(&self.openai_api_key)
-            .header(
-                reqwest::header::ACCEPT,
-                HeaderValue::from_static("text/event-stream"),
-            )
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/responses"))
+                .bearer_auth(&self.openai_api_key)
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         trace!("Initializing SSE stream");
 
         EventSource::new(response)
             .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
     }
+
+    pub fn count_input_tokens(
+        &self,
+        request: CountInputTokensRequest,
+    ) -> Result<CountInputTokensResponse, Error> {
+        trace!("Sending request to OpenAI API: {request:?}");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(
+                    Method::POST,
+                    format!("{BASE_URL}/v1/responses/input_tokens"),
+                )
+                .bearer_auth(&self.openai_api_key)
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    /// Uploads the JSONL input file of a batch
+    ///
+    /// Based on https://platform.openai.com/docs/api-reference/files/create
+    pub fn upload_batch_input(&self, lines: &[BatchRequestLine]) -> Result<FileObject, Error> {
+        trace!("Uploading batch input file with {} requests", lines.len());
+
+        let mut content = String::new();
+        for line in lines {
+            content.push_str(&serde_json::to_string(line).map_err(|err| Error {
+                code: ErrorCode::InternalError,
+                message: format!("Failed to serialize batch request: {err}"),
+                provider_error_json: None,
+                retry_after_ms: None,
+            })?);
+            content.push('\n');
+        }
+        // multipart is not supported by the golem reqwest fork, so the body is built manually
+        let body = format!(
+            "--{MULTIPART_BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"purpose\"\r\n\r\n\
+             batch\r\n\
+             --{MULTIPART_BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+             Content-Type: application/jsonl\r\n\r\n\
+             {content}\r\n\
+             --{MULTIPART_BOUNDARY}--\r\n"
+        );
+
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/files"))
+                .bearer_auth(&self.openai_api_key)
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={MULTIPART_BOUNDARY}"),
+                )
+                .body(body.clone())
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    /// Based on https://platform.openai.com/docs/api-reference/batch/create
+    pub fn create_batch(&self, request: CreateBatchRequest) -> Result<Batch, Error> {
+        trace!("Sending request to OpenAI API: {request:?}");
+
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/v1/batches"))
+                .bearer_auth(&self.openai_api_key)
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn retrieve_batch(&self, batch_id: &str) -> Result<Batch, Error> {
+        trace!("Retrieving batch {batch_id} from OpenAI API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, format!("{BASE_URL}/v1/batches/{batch_id}"))
+                .bearer_auth(&self.openai_api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    pub fn cancel_batch(&self, batch_id: &str) -> Result<Batch, Error> {
+        trace!("Cancelling batch {batch_id} with OpenAI API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(
+                    Method::POST,
+                    format!("{BASE_URL}/v1/batches/{batch_id}/cancel"),
+                )
+                .bearer_auth(&self.openai_api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+
+    /// Downloads an output or error file of a batch, parsing its JSONL lines
+    pub fn batch_output(&self, file_id: &str) -> Result<Vec<BatchResponseLine>, Error> {
+        trace!("Downloading batch output file {file_id} from OpenAI API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(
+                    Method::GET,
+                    format!("{BASE_URL}/v1/files/{file_id}/content"),
+                )
+                .bearer_auth(&self.openai_api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_jsonl_response(response)
+    }
+
+    pub fn list_models(&self) -> Result<ListModelsResponse, Error> {
+        trace!("Listing models of OpenAI API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, format!("{BASE_URL}/v1/models"))
+                .bearer_auth(&self.openai_api_key)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,12 +228,75 @@ pub struct CreateModelResponseRequest {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tools: Vec<Tool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_choice: Option<String>,
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<Reasoning>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
     pub stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+    /// Whether OpenAI stores the response, so a later request can continue from it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+    /// The stored response the conversation continues from, whose input and output are not
+    /// resent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_response_id: Option<String>,
+}
+
+/// Request of the input token counting endpoint, accepting only the input-related
+/// fields of `CreateModelResponseRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountInputTokensRequest {
+    pub input: Input,
+    pub model: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<Reasoning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_response_id: Option<String>,
+}
+
+impl From<CreateModelResponseRequest> for CountInputTokensRequest {
+    fn from(request: CreateModelResponseRequest) -> Self {
+        Self {
+            input: request.input,
+            model: request.model,
+            tools: request.tools,
+            tool_choice: request.tool_choice,
+            text: request.text,
+            reasoning: request.reasoning,
+            previous_response_id: request.previous_response_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountInputTokensResponse {
+    pub input_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListModelsResponse {
+    pub data: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub owned_by: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +329,66 @@ pub enum OutputItem {
         id: String,
         status: Status,
     },
+    #[serde(rename = "reasoning")]
+    Reasoning {
+        id: String,
+        summary: Vec<ReasoningSummary>,
+        encrypted_content: Option<String>,
+    },
+    #[serde(rename = "web_search_call")]
+    WebSearchCall {
+        id: String,
+        action: Option<WebSearchAction>,
+    },
+    #[serde(rename = "code_interpreter_call")]
+    CodeInterpreterCall {
+        id: String,
+        code: Option<String>,
+        outputs: Option<Vec<CodeInterpreterOutput>>,
+    },
+    #[serde(rename = "file_search_call")]
+    FileSearchCall {
+        id: String,
+        #[serde(default)]
+        queries: Vec<String>,
+        results: Option<Vec<FileSearchResult>>,
+    },
+}
+
+/// The action of a web search call, only the search action has a query and sources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSearchAction {
+    pub query: Option<String>,
+    pub url: Option<String>,
+    pub sources: Option<Vec<WebSearchSource>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSearchSource {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CodeInterpreterOutput {
+    #[serde(rename = "logs")]
+    Logs { logs: String },
+    #[serde(rename = "image")]
+    Image { url: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchResult {
+    pub file_id: String,
+    pub filename: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ReasoningSummary {
+    #[serde(rename = "summary_text")]
+    Text { text: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,11 +402,42 @@ pub enum OpenOutputItem {
 #[serde(tag = "type")]
 pub enum OutputMessageContent {
     #[serde(rename = "output_text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(default)]
+        annotations: Vec<Annotation>,
+    },
     #[serde(rename = "refusal")]
     Refusal { refusal: String },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Annotation {
+    #[serde(rename = "url_citation")]
+    UrlCitation {
+        url: String,
+        title: Option<String>,
+        start_index: u32,
+        end_index: u32,
+    },
+    #[serde(rename = "file_citation")]
+    FileCitation {
+        file_id: String,
+        filename: Option<String>,
+        index: u32,
+    },
+    #[serde(rename = "container_file_citation")]
+    ContainerFileCitation {
+        file_id: String,
+        filename: Option<String>,
+        start_index: u32,
+        end_index: u32,
+    },
+    #[serde(other)]
+    Other,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorObject {
     pub code: String,
@@ -195,6 +495,21 @@ pub enum InputItem {
         /// A JSON string of the output of the function tool call.
         output: String,
     },
+    #[serde(rename = "reasoning")]
+    Reasoning {
+        /// The unique identifier of the reasoning content.
+        id: String,
+        /// Reasoning text contents.
+        summary: Vec<ReasoningSummary>,
+        /// The encrypted content of the reasoning item.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        encrypted_content: Option<String>,
+    },
+    #[serde(rename = "item_reference")]
+    ItemReference {
+        /// The ID of an item of a previous response, such as a call of a hosted tool.
+        id: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -217,6 +532,23 @@ pub enum InnerInputItem {
         #[serde(default)]
         detail: Detail,
     },
+    #[serde(rename = "input_file")]
+    FileInput {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file_url: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file_data: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+    },
+    #[serde(rename = "input_audio")]
+    AudioInput { input_audio: InputAudio },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputAudio {
+    pub data: String,
+    pub format: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -230,6 +562,20 @@ pub enum Detail {
     High,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    String(String), // none, auto or required
+    Function(ToolChoiceFunction),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ToolChoiceFunction {
+    #[serde(rename = "function")]
+    Function { name: String },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Tool {
@@ -242,6 +588,71 @@ pub enum Tool {
         parameters: Option<serde_json::Value>,
         strict: bool,
     },
+    #[serde(rename = "web_search")]
+    WebSearch {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        filters: Option<WebSearchFilters>,
+    },
+    #[serde(rename = "code_interpreter")]
+    CodeInterpreter { container: CodeInterpreterContainer },
+    #[serde(rename = "file_search")]
+    FileSearch {
+        vector_store_ids: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_num_results: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSearchFilters {
+    pub allowed_domains: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CodeInterpreterContainer {
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reasoning {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<Effort>,
+    /// The raw reasoning is not returned, only its summary if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Effort {
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "high")]
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextConfig {
+    pub format: TextFormat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TextFormat {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "json_object")]
+    JsonObject,
+    #[serde(rename = "json_schema")]
+    JsonSchema {
+        name: String,
+        schema: serde_json::Value,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        strict: Option<bool>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -271,12 +682,113 @@ pub struct ResponseOutputTextDelta {
     pub output_index: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseOutputTextAnnotationAdded {
+    pub annotation: Annotation,
+    pub content_index: u32,
+    pub item_id: String,
+    pub output_index: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseReasoningSummaryTextDelta {
+    pub delta: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub summary_index: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseOutputItemAdded {
+    pub item: OutputItem,
+    pub output_index: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseOutputItemDone {
     pub item: OutputItem,
     pub output_index: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseFunctionCallArgumentsDelta {
+    pub delta: String,
+    pub item_id: String,
+    pub output_index: u32,
+}
+
+/// A line of a batch input file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequestLine {
+    pub custom_id: String,
+    pub method: String,
+    pub url: String,
+    pub body: CreateModelResponseRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileObject {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateBatchRequest {
+    pub input_file_id: String,
+    pub endpoint: String,
+    pub completion_window: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    pub id: String,
+    pub status: BatchStatus,
+    pub created_at: u64,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub request_counts: Option<BatchRequestCounts>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum BatchStatus {
+    #[serde(rename = "validating")]
+    Validating,
+    #[serde(rename = "failed")]
+    Failed,
+    #[serde(rename = "in_progress")]
+    InProgress,
+    #[serde(rename = "finalizing")]
+    Finalizing,
+    #[serde(rename = "completed")]
+    Completed,
+    #[serde(rename = "expired")]
+    Expired,
+    #[serde(rename = "cancelling")]
+    Cancelling,
+    #[serde(rename = "cancelled")]
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequestCounts {
+    pub total: u32,
+    pub completed: u32,
+    pub failed: u32,
+}
+
+/// A line of a batch output or error file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponseLine {
+    pub custom_id: String,
+    pub response: Option<BatchResponse>,
+    pub error: Option<ErrorObject>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponse {
+    pub status_code: u16,
+    pub body: serde_json::Value,
+}
+
 fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T, Error> {
     let status = response.status();
     if status.is_success() {
@@ -288,22 +800,56 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
             code: ErrorCode::InvalidRequest,
             message: format!("Failed to decode response body: {}", err),
             provider_error_json: Some(body_text),
+            retry_after_ms: None,
         })?;
 
         trace!("Received response from OpenAI API: {body:?}");
 
         Ok(body)
     } else {
-        let body = response
+        Err(parse_error_response(response))
+    }
+}
+
+/// Parses a response body consisting of one JSON value per line
+fn parse_jsonl_response<T: DeserializeOwned + Debug>(response: Response) -> Result<Vec<T>, Error> {
+    let status = response.status();
+    if status.is_success() {
+        let body_text = response
             .text()
-            .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
+            .map_err(|err| from_reqwest_error("Failed to receive response body", err))?;
 
-        trace!("Received {status} response from OpenAI API: {body:?}");
+        body_text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<T>(line).map_err(|err| Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!("Failed to decode response line: {}", err),
+                    provider_error_json: Some(line.to_string()),
+                    retry_after_ms: None,
+                })
+            })
+            .collect()
+    } else {
+        Err(parse_error_response(response))
+    }
+}
 
-        Err(Error {
-            code: error_code_from_status(status),
-            message: format!("Request failed with {status}"),
-            provider_error_json: Some(body),
-        })
+fn parse_error_response(response: Response) -> Error {
+    let status = response.status();
+    let retry_after_ms = retry_after_ms(response.headers());
+    let body = match response.text() {
+        Ok(body) => body,
+        Err(err) => return from_reqwest_error("Failed to receive error response body", err),
+    };
+
+    trace!("Received {status} response from OpenAI API: {body:?}");
+
+    Error {
+        code: error_code_from_response(status, &body),
+        message: format!("Request failed with {status}"),
+        provider_error_json: Some(body),
+        retry_after_ms,
     }
 }
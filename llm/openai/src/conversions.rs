@@ -1,12 +1,19 @@
 use crate::client::{
-    CreateModelResponseRequest, CreateModelResponseResponse, Detail, InnerInput, InnerInputItem,
-    Input, InputItem, OpenOutputItem, OutputItem, OutputMessageContent, Tool,
+    Annotation, Batch, BatchRequestLine, BatchResponseLine, BatchStatus as ClientBatchStatus,
+    CodeInterpreterContainer, CodeInterpreterOutput, CreateModelResponseRequest,
+    CreateModelResponseResponse, Detail, Effort, InnerInput, InnerInputItem, Input, InputAudio,
+    InputItem, OpenOutputItem, OutputItem, OutputMessageContent, Reasoning, ReasoningSummary,
+    TextConfig, TextFormat, Tool, ToolChoiceFunction, WebSearchFilters, BATCH_ENDPOINT,
 };
 use base64::{engine::general_purpose, Engine as _};
-use golem_llm::error::error_code_from_status;
+use golem_llm::citations::text_length;
+use golem_llm::error::{error_code_from_response, error_code_from_status, unsupported};
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, BatchStatus};
 use golem_llm::golem::llm::llm::{
-    Config, ContentPart, Error, ErrorCode, Event, ImageDetail, ImageReference, Message, Response,
-    ResponseMetadata, Role, ToolCall, ToolDefinition, ToolResult, Usage,
+    BuiltinTool, BuiltinToolCall, BuiltinToolKind, Citation, CitationSource, Config, ContentPart,
+    Error, ErrorCode, Event, ImageDetail, ImageReference, MediaSource, Message, ReasoningConfig,
+    ReasoningContent, ReasoningEffort, Response, ResponseFormat, ResponseMetadata, Role,
+    SearchResult, ToolCall, ToolChoice, ToolDefinition, ToolResult, Usage,
 };
 use log::trace;
 use reqwest::StatusCode;
@@ -17,6 +24,7 @@ pub fn create_request(
     items: Vec<InputItem>,
     config: Config,
     tools: Vec<Tool>,
+    text: Option<TextConfig>,
 ) -> CreateModelResponseRequest {
     let options = config
         .provider_options
@@ -28,13 +36,32 @@ pub fn create_request(
         })
         .unwrap_or_default();
 
+    // The results of hosted tools are only returned when asked for
+    let include = tools
+        .iter()
+        .filter_map(|tool| match tool {
+            Tool::Function { .. } => None,
+            Tool::WebSearch { .. } => Some("web_search_call.action.sources"),
+            Tool::CodeInterpreter { .. } => Some("code_interpreter_call.outputs"),
+            Tool::FileSearch { .. } => Some("file_search_call.results"),
+        })
+        .map(|include| include.to_string())
+        .collect();
+
+    // Stateful conversations store every response, so it can be continued from
+    let store = store_option(&options).or_else(|| is_stateful(&options).then_some(true));
+
     CreateModelResponseRequest {
         input: Input::List(items),
         model: config.model,
         temperature: config.temperature,
         max_output_tokens: config.max_tokens,
         tools,
-        tool_choice: config.tool_choice,
+        tool_choice: config.tool_choice.map(convert_tool_choice),
+        parallel_tool_calls: config.parallel_tool_calls,
+        text,
+        reasoning: config.reasoning.map(convert_reasoning_config),
+        include,
         stream: false,
         top_p: options
             .get("top_p")
@@ -42,21 +69,84 @@ pub fn create_request(
         user: options
             .get("user")
             .and_then(|user_s| user_s.parse::<String>().ok()),
+        store,
+        previous_response_id: options.get("previous_response_id").cloned(),
     }
 }
 
-pub fn events_to_input_items(events: Vec<Event>) -> Vec<InputItem> {
+fn store_option(options: &HashMap<String, String>) -> Option<bool> {
+    options
+        .get("store")
+        .and_then(|store_s| store_s.parse::<bool>().ok())
+}
+
+fn is_stateful(options: &HashMap<String, String>) -> bool {
+    options
+        .get("stateful")
+        .and_then(|stateful_s| stateful_s.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+/// With the `stateful` provider option, the conversation up to the last response is already
+/// stored by OpenAI, so only the events after it are sent, continuing from the stored response.
+/// Returns the events to send and the id of the response to continue from, if any.
+pub fn continue_stored_conversation(
+    events: Vec<Event>,
+    config: &Config,
+) -> (Vec<Event>, Option<String>) {
+    let options = config
+        .provider_options
+        .iter()
+        .flatten()
+        .map(|kv| (kv.key.clone(), kv.value.clone()))
+        .collect::<HashMap<_, _>>();
+    // An explicit `previous_response_id` means the caller only passes the new events, and with
+    // `store` turned off the earlier responses of the conversation were not stored either
+    if !is_stateful(&options)
+        || options.contains_key("previous_response_id")
+        || store_option(&options) == Some(false)
+    {
+        return (events, None);
+    }
+
+    let last_response = events
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(index, event)| match event {
+            Event::Response(response) => Some((
+                index,
+                response
+                    .metadata
+                    .provider_id
+                    .clone()
+                    .unwrap_or_else(|| response.id.clone()),
+            )),
+            _ => None,
+        });
+    match last_response {
+        // Responses of other providers, for example of a router fallback, are not stored by OpenAI
+        Some((index, response_id)) if response_id.starts_with("resp_") => {
+            let mut events = events;
+            let new_events = events.split_off(index + 1);
+            (new_events, Some(response_id))
+        }
+        _ => (events, None),
+    }
+}
+
+pub fn events_to_input_items(events: Vec<Event>) -> Result<Vec<InputItem>, Error> {
     let mut items = Vec::new();
     for event in events {
         match event {
-            Event::Message(message) => items.push(llm_message_to_openai_input_item(message)),
-            Event::Response(response) => items.extend(response_to_openai_input_items(response)),
+            Event::Message(message) => items.push(llm_message_to_openai_input_item(message)?),
+            Event::Response(response) => items.extend(response_to_openai_input_items(response)?),
             Event::ToolResults(tool_results) => {
                 items.extend(tool_results.into_iter().map(tool_result_to_input_item))
             }
         }
     }
-    items
+    Ok(items)
 }
 
 pub fn tool_call_to_input_item(tool_call: ToolCall) -> InputItem {
@@ -89,6 +179,10 @@ pub fn tool_defs_to_tools(
 ) -> Result<Vec<Tool>, Error> {
     let mut tools = Vec::new();
     for tool_def in tool_definitions.unwrap_or_default() {
+        if let Some(builtin) = tool_def.builtin {
+            tools.push(builtin_tool_to_tool(builtin));
+            continue;
+        }
         match serde_json::from_str(&tool_def.parameters_schema) {
             Ok(value) => {
                 let tool = Tool::Function {
@@ -107,6 +201,7 @@ pub fn tool_defs_to_tools(
                         tool_def.name
                     ),
                     provider_error_json: None,
+                    retry_after_ms: None,
                 })?;
             }
         }
@@ -114,6 +209,51 @@ pub fn tool_defs_to_tools(
     Ok(tools)
 }
 
+/// The web search has no limit on the number of searches, `max-uses` is ignored
+fn builtin_tool_to_tool(builtin: BuiltinTool) -> Tool {
+    match builtin {
+        BuiltinTool::WebSearch(options) => Tool::WebSearch {
+            filters: options
+                .allowed_domains
+                .map(|allowed_domains| WebSearchFilters { allowed_domains }),
+        },
+        BuiltinTool::CodeInterpreter => Tool::CodeInterpreter {
+            container: CodeInterpreterContainer::Auto,
+        },
+        BuiltinTool::FileSearch(options) => Tool::FileSearch {
+            vector_store_ids: options.vector_store_ids,
+            max_num_results: options.max_results,
+        },
+    }
+}
+
+pub fn response_format_to_text_config(
+    response_format: Option<ResponseFormat>,
+) -> Result<Option<TextConfig>, Error> {
+    let format = match response_format {
+        None => return Ok(None),
+        Some(ResponseFormat::Text) => TextFormat::Text,
+        Some(ResponseFormat::JsonObject) => TextFormat::JsonObject,
+        Some(ResponseFormat::JsonSchema(json_schema)) => {
+            let schema = serde_json::from_str(&json_schema.schema).map_err(|error| Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "Failed to parse response format schema {}: {error}",
+                    json_schema.name
+                ),
+                provider_error_json: None,
+                retry_after_ms: None,
+            })?;
+            TextFormat::JsonSchema {
+                name: json_schema.name,
+                schema,
+                strict: json_schema.strict,
+            }
+        }
+    };
+    Ok(Some(TextConfig { format }))
+}
+
 pub fn to_openai_role_name(role: &Role) -> &'static str {
     match role {
         Role::User => "user",
@@ -123,7 +263,34 @@ pub fn to_openai_role_name(role: &Role) -> &'static str {
     }
 }
 
-pub fn content_part_to_inner_input_item(role: &Role, content_part: ContentPart) -> InnerInputItem {
+fn convert_tool_choice(tool_choice: ToolChoice) -> crate::client::ToolChoice {
+    match tool_choice {
+        ToolChoice::Auto => crate::client::ToolChoice::String("auto".to_string()),
+        ToolChoice::None => crate::client::ToolChoice::String("none".to_string()),
+        ToolChoice::Required => crate::client::ToolChoice::String("required".to_string()),
+        ToolChoice::Specific(name) => {
+            crate::client::ToolChoice::Function(ToolChoiceFunction::Function { name })
+        }
+    }
+}
+
+fn convert_reasoning_config(reasoning: ReasoningConfig) -> Reasoning {
+    Reasoning {
+        effort: reasoning.effort.map(|effort| match effort {
+            ReasoningEffort::Low => Effort::Low,
+            ReasoningEffort::Medium => Effort::Medium,
+            ReasoningEffort::High => Effort::High,
+        }),
+        summary: Some("auto".to_string()),
+    }
+}
+
+/// Reasoning and builtin tool call parts are not message contents, they are sent as separate
+/// input items
+pub fn content_part_to_inner_input_item(
+    role: &Role,
+    content_part: ContentPart,
+) -> Result<Option<InnerInputItem>, Error> {
     fn convert_image_detail(detail: Option<ImageDetail>) -> Detail {
         match detail {
             Some(ImageDetail::Auto) => Detail::Auto,
@@ -133,59 +300,229 @@ pub fn content_part_to_inner_input_item(role: &Role, content_part: ContentPart)
         }
     }
 
-    match content_part {
+    Ok(match content_part {
         ContentPart::Text(msg) => match role {
-            Role::Assistant => InnerInputItem::TextOutput { text: msg },
-            _ => InnerInputItem::TextInput { text: msg },
+            Role::Assistant => Some(InnerInputItem::TextOutput { text: msg }),
+            _ => Some(InnerInputItem::TextInput { text: msg }),
         },
         ContentPart::Image(image_reference) => match image_reference {
-            ImageReference::Url(image_url) => InnerInputItem::ImageInput {
+            ImageReference::Url(image_url) => Some(InnerInputItem::ImageInput {
                 image_url: image_url.url,
                 detail: convert_image_detail(image_url.detail),
-            },
+            }),
             ImageReference::Inline(image_source) => {
                 let base64_data = general_purpose::STANDARD.encode(&image_source.data);
                 let mime_type = &image_source.mime_type; // This is already a string
                 let data_url = format!("data:{mime_type};base64,{base64_data}");
 
-                InnerInputItem::ImageInput {
+                Some(InnerInputItem::ImageInput {
                     image_url: data_url,
                     detail: convert_image_detail(image_source.detail),
-                }
+                })
             }
         },
+        ContentPart::Reasoning(_) | ContentPart::BuiltinToolCall(_) => None,
+        ContentPart::Document(document) => Some(match document.source {
+            MediaSource::Url(url) => InnerInputItem::FileInput {
+                file_url: Some(url),
+                file_data: None,
+                filename: document.title,
+            },
+            MediaSource::Inline(data) => InnerInputItem::FileInput {
+                file_url: None,
+                file_data: Some(format!(
+                    "data:{};base64,{}",
+                    document.mime_type,
+                    general_purpose::STANDARD.encode(data)
+                )),
+                // OpenAI requires a file name for inline files
+                filename: Some(document.title.unwrap_or_else(|| "document".to_string())),
+            },
+        }),
+        ContentPart::Audio(audio) => match audio.source {
+            MediaSource::Inline(data) => Some(InnerInputItem::AudioInput {
+                input_audio: InputAudio {
+                    data: general_purpose::STANDARD.encode(data),
+                    format: audio_format(&audio.mime_type)?,
+                },
+            }),
+            MediaSource::Url(_) => return Err(unsupported("audio input by URL")),
+        },
+    })
+}
+
+/// Gets the audio format OpenAI expects from a MIME type
+fn audio_format(mime_type: &str) -> Result<String, Error> {
+    match mime_type {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => Ok("wav".to_string()),
+        "audio/mpeg" | "audio/mp3" => Ok("mp3".to_string()),
+        _ => Err(unsupported(format!("audio format {mime_type}"))),
     }
 }
 
-pub fn llm_message_to_openai_input_item(message: Message) -> InputItem {
-    let role = message.role;
-    InputItem::InputMessage {
-        role: to_openai_role_name(&role).to_string(),
-        content: InnerInput::List(
-            message
-                .content
+/// Converts reasoning parts to reasoning input items. The reasoning item's ID is stored as the
+/// signature, streamed reasoning summaries are merged into the item carrying the ID.
+fn reasoning_to_input_items(content_parts: Vec<ContentPart>) -> Vec<InputItem> {
+    let mut items = Vec::new();
+    let mut summary = String::new();
+    for content_part in content_parts {
+        if let ContentPart::Reasoning(reasoning) = content_part {
+            summary.push_str(&reasoning.text);
+            if let Some(id) = reasoning.signature {
+                let summary = std::mem::take(&mut summary);
+                items.push(InputItem::Reasoning {
+                    id,
+                    summary: if summary.is_empty() {
+                        vec![]
+                    } else {
+                        vec![ReasoningSummary::Text { text: summary }]
+                    },
+                    encrypted_content: reasoning.redacted_data,
+                });
+            }
+        }
+    }
+    items
+}
+
+pub fn reasoning_item_to_content_part(
+    id: String,
+    summary: Vec<ReasoningSummary>,
+    encrypted_content: Option<String>,
+) -> ContentPart {
+    ContentPart::Reasoning(ReasoningContent {
+        text: summary
+            .into_iter()
+            .map(|summary| match summary {
+                ReasoningSummary::Text { text } => text,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        signature: Some(id),
+        redacted_data: encrypted_content,
+    })
+}
+
+/// Converts the call of a hosted tool to a content part, keeping the whole item as provider data
+pub fn builtin_tool_call_to_content_part(output_item: OutputItem) -> Option<ContentPart> {
+    let provider_data_json = serde_json::to_string(&output_item).ok();
+    let call = match output_item {
+        OutputItem::WebSearchCall { id, action } => {
+            let action = action.unwrap_or(crate::client::WebSearchAction {
+                query: None,
+                url: None,
+                sources: None,
+            });
+            BuiltinToolCall {
+                id,
+                tool: BuiltinToolKind::WebSearch,
+                input: action.query.or(action.url),
+                output: None,
+                results: action
+                    .sources
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|source| SearchResult {
+                        source: CitationSource::Url(source.url),
+                        title: None,
+                        text: None,
+                    })
+                    .collect(),
+                provider_data_json,
+            }
+        }
+        OutputItem::CodeInterpreterCall { id, code, outputs } => {
+            let logs = outputs
+                .unwrap_or_default()
                 .into_iter()
-                .map(|part| content_part_to_inner_input_item(&role, part))
+                .filter_map(|output| match output {
+                    CodeInterpreterOutput::Logs { logs } => Some(logs),
+                    CodeInterpreterOutput::Image { .. } => None,
+                })
+                .collect::<Vec<_>>();
+            BuiltinToolCall {
+                id,
+                tool: BuiltinToolKind::CodeInterpreter,
+                input: code,
+                output: (!logs.is_empty()).then(|| logs.join("\n")),
+                results: vec![],
+                provider_data_json,
+            }
+        }
+        OutputItem::FileSearchCall {
+            id,
+            queries,
+            results,
+        } => BuiltinToolCall {
+            id,
+            tool: BuiltinToolKind::FileSearch,
+            input: (!queries.is_empty()).then(|| queries.join("\n")),
+            output: None,
+            results: results
+                .unwrap_or_default()
+                .into_iter()
+                .map(|result| SearchResult {
+                    source: CitationSource::File(result.file_id),
+                    title: result.filename,
+                    text: result.text,
+                })
                 .collect(),
-        ),
+            provider_data_json,
+        },
+        OutputItem::Message { .. } | OutputItem::ToolCall { .. } | OutputItem::Reasoning { .. } => {
+            return None
+        }
+    };
+    Some(ContentPart::BuiltinToolCall(call))
+}
+
+pub fn llm_message_to_openai_input_item(message: Message) -> Result<InputItem, Error> {
+    let role = message.role;
+    Ok(InputItem::InputMessage {
+        role: to_openai_role_name(&role).to_string(),
+        content: InnerInput::List(content_parts_to_inner_input_items(&role, message.content)?),
+    })
+}
+
+fn content_parts_to_inner_input_items(
+    role: &Role,
+    content_parts: Vec<ContentPart>,
+) -> Result<Vec<InnerInputItem>, Error> {
+    let mut items = Vec::new();
+    for part in content_parts {
+        items.extend(content_part_to_inner_input_item(role, part)?);
     }
+    Ok(items)
 }
 
-pub fn response_to_openai_input_items(message: Response) -> Vec<InputItem> {
+pub fn response_to_openai_input_items(message: Response) -> Result<Vec<InputItem>, Error> {
     let mut items = Vec::new();
 
     let role = Role::Assistant;
 
-    if !message.content.is_empty() {
+    let (reasoning, content): (Vec<_>, Vec<_>) = message
+        .content
+        .into_iter()
+        .partition(|part| matches!(part, ContentPart::Reasoning(_)));
+
+    items.extend(reasoning_to_input_items(reasoning));
+
+    // Calls of hosted tools are referenced by their ID, the provider stores their results
+    items.extend(content.iter().filter_map(|part| match part {
+        ContentPart::BuiltinToolCall(call) => Some(InputItem::ItemReference {
+            id: call.id.clone(),
+        }),
+        _ => None,
+    }));
+    let content = content
+        .into_iter()
+        .filter(|part| !matches!(part, ContentPart::BuiltinToolCall(_)))
+        .collect::<Vec<_>>();
+
+    if !content.is_empty() {
         items.push(InputItem::InputMessage {
             role: to_openai_role_name(&role).to_string(),
-            content: InnerInput::List(
-                message
-                    .content
-                    .into_iter()
-                    .map(|part| content_part_to_inner_input_item(&role, part))
-                    .collect(),
-            ),
+            content: InnerInput::List(content_parts_to_inner_input_items(&role, content)?),
         })
     }
 
@@ -193,7 +530,7 @@ pub fn response_to_openai_input_items(message: Response) -> Vec<InputItem> {
         items.extend(message.tool_calls.into_iter().map(tool_call_to_input_item))
     }
 
-    items
+    Ok(items)
 }
 
 pub fn parse_error_code(code: String) -> ErrorCode {
@@ -202,8 +539,10 @@ pub fn parse_error_code(code: String) -> ErrorCode {
         .and_then(|code| StatusCode::from_u16(code).ok())
     {
         error_code_from_status(code)
+    } else if code == "rate_limit_exceeded" {
+        ErrorCode::RateLimitExceeded
     } else {
-        ErrorCode::InternalError
+        error_code_from_response(StatusCode::INTERNAL_SERVER_ERROR, &code)
     }
 }
 
@@ -213,10 +552,12 @@ pub fn process_model_response(response: CreateModelResponseResponse) -> Result<R
             code: parse_error_code(error.code),
             message: error.message,
             provider_error_json: None,
+            retry_after_ms: None,
         })
     } else {
         let mut contents = Vec::new();
         let mut tool_calls = Vec::new();
+        let mut citations = Vec::new();
 
         let metadata = create_response_metadata(&response);
 
@@ -226,7 +567,12 @@ pub fn process_model_response(response: CreateModelResponseResponse) -> Result<R
                     OutputItem::Message { content, .. } => {
                         for content in content {
                             match content {
-                                OutputMessageContent::Text { text, .. } => {
+                                OutputMessageContent::Text { text, annotations } => {
+                                    // Annotation offsets are relative to their own text part
+                                    let offset = text_length(&contents);
+                                    citations.extend(annotations.into_iter().filter_map(
+                                        |annotation| convert_annotation(annotation, offset),
+                                    ));
                                     contents.push(ContentPart::Text(text));
                                 }
                                 OutputMessageContent::Refusal { refusal, .. } => {
@@ -248,6 +594,20 @@ pub fn process_model_response(response: CreateModelResponseResponse) -> Result<R
                         };
                         tool_calls.push(tool_call);
                     }
+                    OutputItem::Reasoning {
+                        id,
+                        summary,
+                        encrypted_content,
+                    } => {
+                        contents.push(reasoning_item_to_content_part(
+                            id,
+                            summary,
+                            encrypted_content,
+                        ));
+                    }
+                    output_item => {
+                        contents.extend(builtin_tool_call_to_content_part(output_item));
+                    }
                 },
                 OpenOutputItem::Other(value) => {
                     trace!("Ignoring unknown output item: {value:?}");
@@ -260,10 +620,54 @@ pub fn process_model_response(response: CreateModelResponseResponse) -> Result<R
             content: contents,
             tool_calls,
             metadata,
+            citations,
         })
     }
 }
 
+/// Converts a text annotation to a citation, shifting its offsets by the given number of
+/// characters preceding the annotated text part
+pub fn convert_annotation(annotation: Annotation, offset: u32) -> Option<Citation> {
+    match annotation {
+        Annotation::UrlCitation {
+            url,
+            title,
+            start_index,
+            end_index,
+        } => Some(Citation {
+            source: CitationSource::Url(url),
+            title,
+            cited_text: None,
+            start_index: Some(offset + start_index),
+            end_index: Some(offset + end_index),
+        }),
+        Annotation::FileCitation {
+            file_id,
+            filename,
+            index,
+        } => Some(Citation {
+            source: CitationSource::File(file_id),
+            title: filename,
+            cited_text: None,
+            start_index: Some(offset + index),
+            end_index: Some(offset + index),
+        }),
+        Annotation::ContainerFileCitation {
+            file_id,
+            filename,
+            start_index,
+            end_index,
+        } => Some(Citation {
+            source: CitationSource::File(file_id),
+            title: filename,
+            cited_text: None,
+            start_index: Some(offset + start_index),
+            end_index: Some(offset + end_index),
+        }),
+        Annotation::Other => None,
+    }
+}
+
 pub fn create_response_metadata(response: &CreateModelResponseResponse) -> ResponseMetadata {
     ResponseMetadata {
         finish_reason: None,
@@ -271,9 +675,233 @@ pub fn create_response_metadata(response: &CreateModelResponseResponse) -> Respo
             input_tokens: Some(usage.input_tokens),
             output_tokens: Some(usage.output_tokens),
             total_tokens: Some(usage.total_tokens),
+            reasoning_tokens: Some(usage.output_tokens_details.reasoning_tokens),
+            cache_read_tokens: Some(usage.input_tokens_details.cached_tokens),
+            cache_write_tokens: None,
         }),
         provider_id: Some(response.id.clone()),
         timestamp: Some(response.created_at.to_string()),
         provider_metadata_json: response.metadata.as_ref().map(|m| m.to_string()),
+        cost: None,
+    }
+}
+
+pub fn batch_item_to_request_line(item: BatchItem) -> Result<BatchRequestLine, Error> {
+    let items = events_to_input_items(item.events)?;
+    let tools = tool_defs_to_tools(item.config.tools.clone())?;
+    let text = response_format_to_text_config(item.config.response_format.clone())?;
+    Ok(BatchRequestLine {
+        custom_id: item.custom_id,
+        method: "POST".to_string(),
+        url: BATCH_ENDPOINT.to_string(),
+        body: create_request(items, item.config, tools, text),
+    })
+}
+
+pub fn batch_to_batch_info(batch: Batch) -> BatchInfo {
+    let (total_count, succeeded_count, failed_count) = batch
+        .request_counts
+        .map(|counts| (counts.total, counts.completed, counts.failed))
+        .unwrap_or_default();
+    BatchInfo {
+        id: batch.id,
+        status: match batch.status {
+            ClientBatchStatus::Validating => BatchStatus::Pending,
+            ClientBatchStatus::InProgress | ClientBatchStatus::Finalizing => BatchStatus::Running,
+            ClientBatchStatus::Completed => BatchStatus::Completed,
+            ClientBatchStatus::Failed => BatchStatus::Failed,
+            ClientBatchStatus::Expired => BatchStatus::Expired,
+            ClientBatchStatus::Cancelling => BatchStatus::Cancelling,
+            ClientBatchStatus::Cancelled => BatchStatus::Cancelled,
+        },
+        total_count,
+        succeeded_count,
+        failed_count,
+        created_at: Some(batch.created_at.to_string()),
+    }
+}
+
+/// Converts a line of a batch output or error file, where failed requests either have an error,
+/// or a response with an error status and the error in its body
+pub fn batch_response_line_to_result(line: BatchResponseLine) -> BatchResult {
+    let result = match (line.response, line.error) {
+        (_, Some(error)) => Err(Error {
+            code: parse_error_code(error.code),
+            message: error.message,
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+        (Some(response), None) if (200..300).contains(&response.status_code) => {
+            serde_json::from_value::<CreateModelResponseResponse>(response.body)
+                .map_err(|err| Error {
+                    code: ErrorCode::InternalError,
+                    message: format!("Failed to decode batch response: {err}"),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                })
+                .and_then(process_model_response)
+        }
+        (Some(response), None) => {
+            let status = StatusCode::from_u16(response.status_code)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let body = response.body.to_string();
+            Err(Error {
+                code: error_code_from_response(status, &body),
+                message: response.body["error"]["message"]
+                    .as_str()
+                    .map(|message| message.to_string())
+                    .unwrap_or_else(|| format!("Request failed with {status}")),
+                provider_error_json: Some(body),
+                retry_after_ms: None,
+            })
+        }
+        (None, None) => Err(Error {
+            code: ErrorCode::InternalError,
+            message: "Batch result has neither a response nor an error".to_string(),
+            provider_error_json: None,
+            retry_after_ms: None,
+        }),
+    };
+    BatchResult {
+        custom_id: line.custom_id,
+        result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use golem_llm::golem::llm::llm::Kv;
+
+    fn config(options: &[(&str, &str)]) -> Config {
+        Config {
+            model: "gpt-4o-mini".to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: Some(
+                options
+                    .iter()
+                    .map(|(key, value)| Kv {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn message(text: &str) -> Event {
+        Event::Message(Message {
+            role: Role::User,
+            name: None,
+            content: vec![ContentPart::Text(text.to_string())],
+            cache_hint: None,
+        })
+    }
+
+    fn response(id: &str) -> Event {
+        Event::Response(Response {
+            id: id.to_string(),
+            content: vec![ContentPart::Text("answer".to_string())],
+            tool_calls: vec![],
+            citations: vec![],
+            metadata: ResponseMetadata {
+                finish_reason: None,
+                usage: None,
+                provider_id: Some(id.to_string()),
+                timestamp: None,
+                provider_metadata_json: None,
+                cost: None,
+            },
+        })
+    }
+
+    #[test]
+    fn test_continue_stored_conversation_after_last_response() {
+        let events = vec![
+            message("first"),
+            response("resp_1"),
+            message("second"),
+            response("resp_2"),
+            message("third"),
+        ];
+
+        let (events, previous_response_id) =
+            continue_stored_conversation(events, &config(&[("stateful", "true")]));
+
+        assert_eq!(events, vec![message("third")]);
+        assert_eq!(previous_response_id, Some("resp_2".to_string()));
+    }
+
+    #[test]
+    fn test_continue_stored_conversation_without_response() {
+        let events = vec![message("first")];
+
+        let (sent, previous_response_id) =
+            continue_stored_conversation(events.clone(), &config(&[("stateful", "true")]));
+
+        assert_eq!(sent, events);
+        assert_eq!(previous_response_id, None);
+    }
+
+    #[test]
+    fn test_continue_stored_conversation_after_other_provider() {
+        let events = vec![message("first"), response("msg_1"), message("second")];
+
+        let (sent, previous_response_id) =
+            continue_stored_conversation(events.clone(), &config(&[("stateful", "true")]));
+
+        assert_eq!(sent, events);
+        assert_eq!(previous_response_id, None);
+    }
+
+    #[test]
+    fn test_continue_stored_conversation_not_stateful() {
+        let events = vec![message("first"), response("resp_1"), message("second")];
+
+        for options in [
+            vec![],
+            vec![("stateful", "false")],
+            vec![("stateful", "true"), ("store", "false")],
+            vec![("stateful", "true"), ("previous_response_id", "resp_0")],
+        ] {
+            let (sent, previous_response_id) =
+                continue_stored_conversation(events.clone(), &config(&options));
+
+            assert_eq!(sent, events, "{options:?}");
+            assert_eq!(previous_response_id, None, "{options:?}");
+        }
+    }
+
+    #[test]
+    fn test_create_request_store() {
+        for (options, store, previous_response_id) in [
+            (vec![], None, None),
+            (vec![("stateful", "true")], Some(true), None),
+            (
+                vec![("stateful", "true"), ("store", "false")],
+                Some(false),
+                None,
+            ),
+            (
+                vec![("store", "false"), ("previous_response_id", "resp_1")],
+                Some(false),
+                Some("resp_1".to_string()),
+            ),
+        ] {
+            let request = create_request(vec![], config(&options), vec![], None);
+
+            assert_eq!(request.store, store, "{options:?}");
+            assert_eq!(
+                request.previous_response_id, previous_response_id,
+                "{options:?}"
+            );
+        }
     }
 }
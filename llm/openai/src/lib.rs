@@ -1,30 +1,47 @@
 use crate::client::{
-    CreateModelResponseResponse, InputItem, OutputItem, ResponseOutputItemDone,
-    ResponseOutputTextDelta, ResponsesApi,
+    CreateBatchRequest, CreateModelResponseResponse, InputItem, OutputItem,
+    ResponseFunctionCallArgumentsDelta, ResponseOutputItemAdded, ResponseOutputItemDone,
+    ResponseOutputTextAnnotationAdded, ResponseOutputTextDelta, ResponseReasoningSummaryTextDelta,
+    ResponsesApi, BATCH_ENDPOINT,
 };
 use crate::conversions::{
+    batch_item_to_request_line, batch_response_line_to_result, batch_to_batch_info,
+    builtin_tool_call_to_content_part, continue_stored_conversation, convert_annotation,
     create_request, create_response_metadata, events_to_input_items, parse_error_code,
-    process_model_response, tool_defs_to_tools,
+    process_model_response, reasoning_item_to_content_part, response_format_to_text_config,
+    tool_defs_to_tools,
 };
+use crate::models::{KNOWN_MODELS, PRICING};
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
 use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
 use golem_llm::config::{get_config_key, with_config_key};
-use golem_llm::durability::{DurableLLM, ExtendedGuest};
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
 use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
 use golem_llm::golem::llm::llm::{
-    ChatStream, Config, ContentPart, Error, ErrorCode, Event, Guest, Response, StreamDelta,
-    StreamEvent, ToolCall,
+    ChatStream, Config, ContentPart, Error, ErrorCode, Event, Guest, ModelInfo, ReasoningContent,
+    Response, StreamDelta, StreamEvent, ToolCallDelta,
 };
+use golem_llm::models::known_model_info;
+use golem_llm::pricing::add_cost;
 use golem_rust::wasm_rpc::Pollable;
 use log::trace;
 use std::cell::{Ref, RefCell, RefMut};
 
 mod client;
 mod conversions;
+mod models;
 
-struct OpenAIChatStream {
+pub struct OpenAIChatStream {
     stream: RefCell<Option<EventSource>>,
     failure: Option<Error>,
     finished: RefCell<bool>,
+    text_length: RefCell<u32>,
+    text_part_start: RefCell<u32>,
 }
 
 impl OpenAIChatStream {
@@ -33,6 +50,8 @@ impl OpenAIChatStream {
             stream: RefCell::new(Some(stream)),
             failure: None,
             finished: RefCell::new(false),
+            text_length: RefCell::new(0),
+            text_part_start: RefCell::new(0),
         })
     }
 
@@ -41,6 +60,8 @@ impl OpenAIChatStream {
             stream: RefCell::new(None),
             failure: Some(error),
             finished: RefCell::new(false),
+            text_length: RefCell::new(0),
+            text_part_start: RefCell::new(0),
         })
     }
 }
@@ -72,6 +93,7 @@ impl LlmChatStreamState for OpenAIChatStream {
                 code: ErrorCode::InternalError,
                 message: message.into(),
                 provider_error_json: None,
+                retry_after_ms: None,
             }
         }
 
@@ -107,12 +129,14 @@ impl LlmChatStreamState for OpenAIChatStream {
                         code: parse_error_code(error.code),
                         message: error.message,
                         provider_error_json: None,
+                        retry_after_ms: None,
                     })
                 } else {
                     Err(Error {
                         code: ErrorCode::Unknown,
                         message: "Unknown error".to_string(),
                         provider_error_json: None,
+                        retry_after_ms: None,
                     })
                 }
             }
@@ -141,33 +165,129 @@ impl LlmChatStreamState for OpenAIChatStream {
                     serde_json::from_value::<ResponseOutputTextDelta>(json).map_err(|err| {
                         decode_internal_error(format!("Failed to deserialize stream event: {err}"))
                     })?;
+                *self.text_length.borrow_mut() += decoded.delta.chars().count() as u32;
                 Ok(Some(StreamEvent::Delta(StreamDelta {
                     content: Some(vec![ContentPart::Text(decoded.delta)]),
                     tool_calls: None,
+                    tool_call_deltas: None,
+                    citations: None,
                 })))
             }
-            Some("response.output_item.done") => {
+            Some("response.content_part.added") => {
+                // Annotation offsets are relative to the text part they belong to
+                *self.text_part_start.borrow_mut() = *self.text_length.borrow();
+                Ok(None)
+            }
+            Some("response.output_text.annotation.added") => {
+                let decoded = serde_json::from_value::<ResponseOutputTextAnnotationAdded>(json)
+                    .map_err(|err| {
+                        decode_internal_error(format!("Failed to deserialize stream event: {err}"))
+                    })?;
+                let offset = *self.text_part_start.borrow();
+                Ok(
+                    convert_annotation(decoded.annotation, offset).map(|citation| {
+                        StreamEvent::Delta(StreamDelta {
+                            content: None,
+                            tool_calls: None,
+                            tool_call_deltas: None,
+                            citations: Some(vec![citation]),
+                        })
+                    }),
+                )
+            }
+            Some("response.reasoning_summary_text.delta") => {
+                let decoded = serde_json::from_value::<ResponseReasoningSummaryTextDelta>(json)
+                    .map_err(|err| {
+                        decode_internal_error(format!("Failed to deserialize stream event: {err}"))
+                    })?;
+                Ok(Some(StreamEvent::Delta(StreamDelta {
+                    content: Some(vec![ContentPart::Reasoning(ReasoningContent {
+                        text: decoded.delta,
+                        signature: None,
+                        redacted_data: None,
+                    })]),
+                    tool_calls: None,
+                    tool_call_deltas: None,
+                    citations: None,
+                })))
+            }
+            Some("response.output_item.added") => {
                 let decoded =
-                    serde_json::from_value::<ResponseOutputItemDone>(json).map_err(|err| {
+                    serde_json::from_value::<ResponseOutputItemAdded>(json).map_err(|err| {
                         decode_internal_error(format!("Failed to deserialize stream event: {err}"))
                     })?;
-                if let OutputItem::ToolCall {
-                    arguments,
-                    call_id,
-                    name,
-                    ..
-                } = decoded.item
-                {
-                    Ok(Some(StreamEvent::Delta(StreamDelta {
+                match decoded.item {
+                    OutputItem::ToolCall {
+                        arguments,
+                        call_id,
+                        name,
+                        ..
+                    } => Ok(Some(StreamEvent::Delta(StreamDelta {
                         content: None,
-                        tool_calls: Some(vec![ToolCall {
-                            id: call_id,
-                            name,
-                            arguments_json: arguments,
+                        tool_calls: None,
+                        tool_call_deltas: Some(vec![ToolCallDelta {
+                            index: decoded.output_index,
+                            id: Some(call_id),
+                            name: Some(name),
+                            arguments_fragment: arguments,
                         }]),
-                    })))
-                } else {
-                    Ok(None)
+                        citations: None,
+                    }))),
+                    _ => Ok(None),
+                }
+            }
+            Some("response.function_call_arguments.delta") => {
+                let decoded = serde_json::from_value::<ResponseFunctionCallArgumentsDelta>(json)
+                    .map_err(|err| {
+                        decode_internal_error(format!("Failed to deserialize stream event: {err}"))
+                    })?;
+                Ok(Some(StreamEvent::Delta(StreamDelta {
+                    content: None,
+                    tool_calls: None,
+                    tool_call_deltas: Some(vec![ToolCallDelta {
+                        index: decoded.output_index,
+                        id: None,
+                        name: None,
+                        arguments_fragment: decoded.delta,
+                    }]),
+                    citations: None,
+                })))
+            }
+            Some("response.output_item.done") => {
+                let decoded =
+                    serde_json::from_value::<ResponseOutputItemDone>(json).map_err(|err| {
+                        decode_internal_error(format!("Failed to deserialize stream event: {err}"))
+                    })?;
+                match decoded.item {
+                    // The arguments have already been streamed by the argument deltas
+                    OutputItem::ToolCall { .. } => Ok(None),
+                    // The summary has already been streamed, only the ID is needed to send it back
+                    OutputItem::Reasoning {
+                        id,
+                        encrypted_content,
+                        ..
+                    } => Ok(Some(StreamEvent::Delta(StreamDelta {
+                        content: Some(vec![reasoning_item_to_content_part(
+                            id,
+                            vec![],
+                            encrypted_content,
+                        )]),
+                        tool_calls: None,
+                        tool_call_deltas: None,
+                        citations: None,
+                    }))),
+                    OutputItem::Message { .. } => Ok(None),
+                    // Calls of hosted tools are sent once completed, with their results
+                    output_item => Ok(builtin_tool_call_to_content_part(output_item).map(
+                        |content_part| {
+                            StreamEvent::Delta(StreamDelta {
+                                content: Some(vec![content_part]),
+                                tool_calls: None,
+                                tool_call_deltas: None,
+                                citations: None,
+                            })
+                        },
+                    )),
                 }
             }
             Some(_) => Ok(None),
@@ -178,7 +298,7 @@ impl LlmChatStreamState for OpenAIChatStream {
     }
 }
 
-struct OpenAIComponent;
+pub struct OpenAIComponent;
 
 impl OpenAIComponent {
     const ENV_VAR_NAME: &'static str = "OPENAI_API_KEY";
@@ -186,25 +306,40 @@ impl OpenAIComponent {
     fn request(
         client: ResponsesApi,
         items: Vec<InputItem>,
+        previous_response_id: Option<String>,
         config: Config,
     ) -> Result<Response, Error> {
         let tools = tool_defs_to_tools(config.tools.clone())?;
-        let request = create_request(items, config, tools);
+        let text = response_format_to_text_config(config.response_format.clone())?;
+        let model = config.model.clone();
+        let mut request = create_request(items, config, tools, text);
+        if previous_response_id.is_some() {
+            request.previous_response_id = previous_response_id;
+        }
         let response = client.create_model_response(request)?;
-        process_model_response(response)
+        let mut response = process_model_response(response)?;
+        add_cost(&mut response.metadata, &model, &PRICING);
+        Ok(response)
     }
 
     fn streaming_request(
         client: ResponsesApi,
         items: Vec<InputItem>,
+        previous_response_id: Option<String>,
         config: Config,
     ) -> LlmChatStream<OpenAIChatStream> {
-        match tool_defs_to_tools(config.tools.clone()) {
-            Ok(tools) => {
-                let mut request = create_request(items, config, tools);
+        match tool_defs_to_tools(config.tools.clone()).and_then(|tools| {
+            response_format_to_text_config(config.response_format.clone()).map(|text| (tools, text))
+        }) {
+            Ok((tools, text)) => {
+                let model = config.model.clone();
+                let mut request = create_request(items, config, tools, text);
                 request.stream = true;
+                if previous_response_id.is_some() {
+                    request.previous_response_id = previous_response_id;
+                }
                 match client.stream_model_response(request) {
-                    Ok(stream) => OpenAIChatStream::new(stream),
+                    Ok(stream) => OpenAIChatStream::new(stream).with_pricing(&model, &PRICING),
                     Err(error) => OpenAIChatStream::failed(error),
                 }
             }
@@ -215,28 +350,113 @@ impl OpenAIComponent {
 
 impl Guest for OpenAIComponent {
     type ChatStream = LlmChatStream<OpenAIChatStream>;
+    type ChatSession = ChatSession<Self>;
 
     fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
         let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
         let client = ResponsesApi::new(openai_api_key);
-        let items = events_to_input_items(events);
-        Self::request(client, items, config)
+        let (events, previous_response_id) = continue_stored_conversation(events, &config);
+        let items = events_to_input_items(events)?;
+        Self::request(client, items, previous_response_id, config)
     }
 
     fn stream(events: Vec<Event>, config: Config) -> ChatStream {
         ChatStream::new(Self::unwrapped_stream(events, config))
     }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ResponsesApi::new(openai_api_key);
+        let items = events_to_input_items(events)?;
+        let tools = tool_defs_to_tools(config.tools.clone())?;
+        let text = response_format_to_text_config(config.response_format.clone())?;
+        let request = create_request(items, config, tools, text);
+        let response = client.count_input_tokens(request.into())?;
+        Ok(response.input_tokens)
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        Ok(known_model_info(&model, KNOWN_MODELS))
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ResponsesApi::new(openai_api_key);
+        let response = client.list_models()?;
+        Ok(response
+            .data
+            .into_iter()
+            .map(|model| known_model_info(&model.id, KNOWN_MODELS))
+            .collect())
+    }
+}
+
+impl BatchGuest for OpenAIComponent {
+    fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+        let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ResponsesApi::new(openai_api_key);
+        let lines = items
+            .into_iter()
+            .map(batch_item_to_request_line)
+            .collect::<Result<Vec<_>, _>>()?;
+        let input_file = client.upload_batch_input(&lines)?;
+        let batch = client.create_batch(CreateBatchRequest {
+            input_file_id: input_file.id,
+            endpoint: BATCH_ENDPOINT.to_string(),
+            completion_window: "24h".to_string(),
+        })?;
+        Ok(batch.id)
+    }
+
+    fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+        let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ResponsesApi::new(openai_api_key);
+        client.retrieve_batch(&id).map(batch_to_batch_info)
+    }
+
+    /// The successful requests are in the batch's output file, the failed ones in its error file
+    fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+        let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ResponsesApi::new(openai_api_key);
+        let batch = client.retrieve_batch(&id)?;
+        let mut results = Vec::new();
+        for file_id in [batch.output_file_id, batch.error_file_id]
+            .into_iter()
+            .flatten()
+        {
+            results.extend(
+                client
+                    .batch_output(&file_id)?
+                    .into_iter()
+                    .map(batch_response_line_to_result),
+            );
+        }
+        Ok(results)
+    }
+
+    fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+        let openai_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = ResponsesApi::new(openai_api_key);
+        client.cancel_batch(&id).map(batch_to_batch_info)
+    }
 }
 
 impl ExtendedGuest for OpenAIComponent {
+    const PROVIDER_NAME: &'static str = "openai";
+
     fn unwrapped_stream(events: Vec<Event>, config: Config) -> Self::ChatStream {
         with_config_key(
             Self::ENV_VAR_NAME,
             OpenAIChatStream::failed,
             |openai_api_key| {
                 let client = ResponsesApi::new(openai_api_key);
-                let items = events_to_input_items(events);
-                Self::streaming_request(client, items, config)
+                let (events, previous_response_id) = continue_stored_conversation(events, &config);
+                match events_to_input_items(events) {
+                    Ok(items) => {
+                        Self::streaming_request(client, items, previous_response_id, config)
+                    }
+                    Err(error) => OpenAIChatStream::failed(error),
+                }
             },
         )
     }
@@ -246,6 +466,8 @@ impl ExtendedGuest for OpenAIComponent {
     }
 }
 
-type DurableOpenAIComponent = DurableLLM<OpenAIComponent>;
+#[cfg(not(feature = "library"))]
+type DurableOpenAIComponent = DurableLLM<CachedLLM<OpenAIComponent>>;
 
+#[cfg(not(feature = "library"))]
 golem_llm::export_llm!(DurableOpenAIComponent with_types_in golem_llm);
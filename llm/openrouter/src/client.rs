@@ -1,6 +1,9 @@
-use golem_llm::error::{error_code_from_status, from_event_source_error, from_reqwest_error};
+use golem_llm::error::{
+    error_code_from_response, from_event_source_error, from_reqwest_error, retry_after_ms,
+};
 use golem_llm::event_source::EventSource;
 use golem_llm::golem::llm::llm::{Error, ErrorCode};
+use golem_llm::retry::{send_idempotent_with_retry, send_with_retry};
 use log::trace;
 use reqwest::header::HeaderValue;
 use reqwest::{Client, Method, Response, StatusCode};
@@ -27,13 +30,13 @@ impl CompletionsApi {
     pub fn send_messages(&self, request: CompletionsRequest) -> Result<CompletionsResponse, Error> {
         trace!("Sending request to OpenRouter API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/api/v1/chat/completions"))
-            .bearer_auth(self.api_key.clone())
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/api/v1/chat/completions"))
+                .bearer_auth(self.api_key.clone())
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         parse_response(response)
     }
@@ -41,23 +44,63 @@ impl CompletionsApi {
     pub fn stream_send_messages(&self, request: CompletionsRequest) -> Result<EventSource, Error> {
         trace!("Sending request to OpenRouter API: {request:?}");
 
-        let response: Response = self
-            .client
-            .request(Method::POST, format!("{BASE_URL}/api/v1/chat/completions"))
-            .bearer_auth(self.api_key.clone())
-            .header(
-                reqwest::header::ACCEPT,
-                HeaderValue::from_static("text/event-stream"),
-            )
-            .json(&request)
-            .send()
-            .map_err(|err| from_reqwest_error("Request failed", err))?;
+        let response: Response = send_with_retry(|| {
+            self.client
+                .request(Method::POST, format!("{BASE_URL}/api/v1/chat/completions"))
+                .bearer_auth(self.api_key.clone())
+                .header(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_static("text/event-stream"),
+                )
+                .json(&request)
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
 
         trace!("Initializing SSE stream");
 
         EventSource::new(response)
             .map_err(|err| from_event_source_error("Failed to create SSE stream", err))
     }
+
+    pub fn list_models(&self) -> Result<ModelsResponse, Error> {
+        trace!("Listing models of OpenRouter API");
+
+        let response: Response = send_idempotent_with_retry(|| {
+            self.client
+                .request(Method::GET, format!("{BASE_URL}/api/v1/models"))
+                .bearer_auth(self.api_key.clone())
+        })
+        .map_err(|err| from_reqwest_error("Request failed", err))?;
+
+        parse_response(response)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelsResponse {
+    pub data: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub context_length: Option<u32>,
+    pub top_provider: Option<TopProvider>,
+    pub architecture: Option<Architecture>,
+    #[serde(default)]
+    pub supported_parameters: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Architecture {
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopProvider {
+    pub context_length: Option<u32>,
+    pub max_completion_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,12 +110,18 @@ pub struct CompletionsRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub frequency_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<Reasoning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub repetition_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub seed: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
@@ -92,6 +141,51 @@ pub struct CompletionsRequest {
     pub min_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_a: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageOptions>,
+}
+
+/// Asks for the cost of the request in the usage of the response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageOptions {
+    pub include: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reasoning {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<Effort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Effort {
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "high")]
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseFormat {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "json_object")]
+    JsonObject,
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchema },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,7 +246,7 @@ pub enum Content {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ToolChoice {
-    String(String), // none or auto
+    String(String), // none, auto or required
     Function(ToolChoiceFunction),
 }
 
@@ -249,8 +343,28 @@ pub enum FinishReason {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseMessage {
     pub content: Option<String>,
+    pub reasoning: Option<String>,
     pub role: String,
     pub tool_calls: Option<Vec<ToolCall>>,
+    pub annotations: Option<Vec<Annotation>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Annotation {
+    #[serde(rename = "url_citation")]
+    UrlCitation { url_citation: UrlCitation },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlCitation {
+    pub url: String,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub start_index: Option<u32>,
+    pub end_index: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -268,8 +382,22 @@ pub struct ErrorResponseBody {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
     pub completion_tokens: u32,
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
     pub prompt_tokens: u32,
     pub total_tokens: u32,
+    /// Cost of the request in credits, equal to US dollars
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionTokensDetails {
+    pub reasoning_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTokensDetails {
+    pub cached_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -293,8 +421,10 @@ pub struct ChoiceChunk {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChoiceDelta {
     pub content: Option<String>,
+    pub reasoning: Option<String>,
     pub tool_calls: Option<Vec<ToolCall>>,
     pub role: Option<String>,
+    pub annotations: Option<Vec<Annotation>>,
 }
 
 fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T, Error> {
@@ -314,6 +444,7 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
                     code: ErrorCode::InternalError,
                     message: format!("Failed to parse response body: {err}"),
                     provider_error_json: Some(raw_body),
+                    retry_after_ms: None,
                 })?;
 
             let status = TryInto::<u16>::try_into(error_body.error.code)
@@ -321,15 +452,17 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
                 .and_then(|code| StatusCode::from_u16(code).ok())
                 .unwrap_or(status);
             Err(Error {
-                code: error_code_from_status(status),
+                code: error_code_from_response(status, &error_body.error.message),
                 message: error_body.error.message,
                 provider_error_json: error_body
                     .error
                     .metadata
                     .map(|value| serde_json::to_string(&value).unwrap()),
+                retry_after_ms: None,
             })
         }
     } else {
+        let retry_after_ms = retry_after_ms(response.headers());
         let raw_error_body = response
             .text()
             .map_err(|err| from_reqwest_error("Failed to receive error response body", err))?;
@@ -340,15 +473,17 @@ fn parse_response<T: DeserializeOwned + Debug>(response: Response) -> Result<T,
                 code: ErrorCode::InternalError,
                 message: format!("Failed to parse error response body: {err}"),
                 provider_error_json: Some(raw_error_body),
+                retry_after_ms: None,
             })?;
 
         Err(Error {
-            code: error_code_from_status(status),
+            code: error_code_from_response(status, &error_body.error.message),
             message: error_body.error.message,
             provider_error_json: error_body
                 .error
                 .metadata
                 .map(|value| serde_json::to_string(&value).unwrap()),
+            retry_after_ms,
         })
     }
 }
@@ -1,10 +1,14 @@
 use crate::client::{
-    CompletionsRequest, CompletionsResponse, Detail, FunctionName, ToolChoiceFunction,
+    Annotation, CompletionsRequest, CompletionsResponse, Detail, Effort, FunctionName, Model,
+    Reasoning, ToolChoiceFunction, UsageOptions,
 };
 use base64::{engine::general_purpose, Engine as _};
+use golem_llm::error::unsupported;
 use golem_llm::golem::llm::llm::{
-    Config, ContentPart, Error, ErrorCode, Event, FinishReason, ImageDetail, ImageReference,
-    Response, ResponseMetadata, Role, ToolCall, ToolDefinition, ToolResult, Usage,
+    Citation, CitationSource, Config, ContentPart, Cost, Error, ErrorCode, Event, FinishReason,
+    ImageDetail, ImageReference, ModelCapabilities, ModelInfo, ReasoningConfig, ReasoningContent,
+    ReasoningEffort, Response, ResponseFormat, ResponseMetadata, Role, ToolCall, ToolChoice,
+    ToolDefinition, ToolResult, Usage,
 };
 use std::collections::HashMap;
 
@@ -25,20 +29,20 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
             Event::Message(message) => match message.role {
                 Role::User => completion_messages.push(crate::client::Message::User {
                     name: message.name,
-                    content: convert_content_parts(message.content),
+                    content: convert_content_parts(message.content)?,
                 }),
                 Role::Assistant => completion_messages.push(crate::client::Message::Assistant {
                     name: message.name,
-                    content: Some(convert_content_parts(message.content)),
+                    content: Some(convert_content_parts(message.content)?),
                     tool_calls: None,
                 }),
                 Role::System => completion_messages.push(crate::client::Message::System {
                     name: message.name,
-                    content: convert_content_parts(message.content),
+                    content: convert_content_parts(message.content)?,
                 }),
                 Role::Tool => completion_messages.push(crate::client::Message::Tool {
                     name: message.name,
-                    content: convert_content_parts_to_string(message.content),
+                    content: convert_content_parts_to_string(message.content)?,
                     tool_call_id: "unknown".to_string(),
                 }),
             },
@@ -48,6 +52,7 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
                     content: {
                         (!response.content.is_empty())
                             .then(|| convert_content_parts(response.content))
+                            .transpose()?
                     },
                     tool_calls: (!response.tool_calls.is_empty()).then(|| {
                         response
@@ -69,6 +74,11 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
         tools.push(tool_definition_to_tool(tool)?)
     }
 
+    let response_format = config
+        .response_format
+        .map(convert_response_format)
+        .transpose()?;
+
     Ok(CompletionsRequest {
         messages: completion_messages,
         model: config.model,
@@ -76,12 +86,15 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
             .get("frequency_penalty")
             .and_then(|fp_s| fp_s.parse::<f32>().ok()),
         max_tokens: config.max_tokens,
+        parallel_tool_calls: config.parallel_tool_calls,
         presence_penalty: options
             .get("presence_penalty")
             .and_then(|pp_s| pp_s.parse::<f32>().ok()),
+        reasoning: config.reasoning.map(convert_reasoning_config),
         repetition_penalty: options
             .get("repetition_penalty")
             .and_then(|rp_s| rp_s.parse::<f32>().ok()),
+        response_format,
         seed: options
             .get("seed")
             .and_then(|seed_s| seed_s.parse::<u32>().ok()),
@@ -102,6 +115,7 @@ pub fn events_to_request(events: Vec<Event>, config: Config) -> Result<Completio
         top_a: options
             .get("top_a")
             .and_then(|top_a_s| top_a_s.parse::<f32>().ok()),
+        usage: Some(UsageOptions { include: true }),
     })
 }
 
@@ -111,6 +125,9 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
         let mut contents = Vec::new();
         let mut tool_calls = Vec::new();
 
+        if let Some(reasoning) = &choice.message.reasoning {
+            contents.push(reasoning_to_content_part(reasoning.clone()));
+        }
         if let Some(content) = &choice.message.content {
             contents.push(ContentPart::Text(content.clone()));
         }
@@ -126,6 +143,7 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
             provider_id: None,
             timestamp: Some(response.created.to_string()),
             provider_metadata_json: None,
+            cost: response.usage.as_ref().and_then(convert_cost),
         };
 
         Ok(Response {
@@ -133,16 +151,40 @@ pub fn process_response(response: CompletionsResponse) -> Result<Response, Error
             content: contents,
             tool_calls,
             metadata,
+            citations: choice
+                .message
+                .annotations
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(convert_annotation)
+                .collect(),
         })
     } else {
         Err(Error {
             code: ErrorCode::InternalError,
             message: "No choices in response".to_string(),
             provider_error_json: None,
+            retry_after_ms: None,
         })
     }
 }
 
+/// Converts an annotation, such as the sources of the web search plugin, to a citation.
+/// The offsets are relative to the message's text, which is the only text part of the response.
+pub fn convert_annotation(annotation: Annotation) -> Option<Citation> {
+    match annotation {
+        Annotation::UrlCitation { url_citation } => Some(Citation {
+            source: CitationSource::Url(url_citation.url),
+            title: url_citation.title,
+            cited_text: url_citation.content,
+            start_index: url_citation.start_index,
+            end_index: url_citation.end_index,
+        }),
+        Annotation::Other => None,
+    }
+}
+
 pub fn tool_call_to_client_tool_call(tool_call: ToolCall) -> crate::client::ToolCall {
     crate::client::ToolCall::Function {
         function: crate::client::FunctionCall {
@@ -179,7 +221,7 @@ pub fn convert_tool_call(tool_call: &crate::client::ToolCall) -> ToolCall {
     }
 }
 
-fn convert_content_parts(contents: Vec<ContentPart>) -> crate::client::Content {
+fn convert_content_parts(contents: Vec<ContentPart>) -> Result<crate::client::Content, Error> {
     let mut result = Vec::new();
     for content in contents {
         match content {
@@ -204,20 +246,31 @@ fn convert_content_parts(contents: Vec<ContentPart>) -> crate::client::Content {
                     });
                 }
             },
+            // Reasoning is not sent back to the model
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
         }
     }
-    crate::client::Content::List(result)
+    Ok(crate::client::Content::List(result))
 }
 
-fn convert_content_parts_to_string(contents: Vec<ContentPart>) -> String {
+fn convert_content_parts_to_string(contents: Vec<ContentPart>) -> Result<String, Error> {
     let mut result = String::new();
     for content in contents {
         match content {
             ContentPart::Text(text) => result.push_str(&text),
             ContentPart::Image(_) => {} // Correctly ignores any image content
+            ContentPart::Reasoning(_) => {}
+            ContentPart::Document(_) => return Err(unsupported("document input")),
+            ContentPart::Audio(_) => return Err(unsupported("audio input")),
+            // Calls of provider-hosted tools are only understood by the provider making them
+            ContentPart::BuiltinToolCall(_) => {}
         }
     }
-    result
+    Ok(result)
 }
 
 impl From<ImageDetail> for Detail {
@@ -245,10 +298,52 @@ pub fn convert_usage(value: &crate::client::Usage) -> Usage {
         input_tokens: Some(value.prompt_tokens),
         output_tokens: Some(value.completion_tokens),
         total_tokens: Some(value.total_tokens),
+        reasoning_tokens: value
+            .completion_tokens_details
+            .as_ref()
+            .and_then(|details| details.reasoning_tokens),
+        cache_read_tokens: value
+            .prompt_tokens_details
+            .as_ref()
+            .and_then(|details| details.cached_tokens),
+        cache_write_tokens: None,
+    }
+}
+
+/// OpenRouter reports the total cost of the request, without breaking it down
+pub fn convert_cost(value: &crate::client::Usage) -> Option<Cost> {
+    value.cost.map(|cost| Cost {
+        input: None,
+        output: None,
+        cached: None,
+        reasoning: None,
+        total: (cost * 1_000_000.0).round() as u64,
+    })
+}
+
+pub fn reasoning_to_content_part(text: String) -> ContentPart {
+    ContentPart::Reasoning(ReasoningContent {
+        text,
+        signature: None,
+        redacted_data: None,
+    })
+}
+
+fn convert_reasoning_config(reasoning: ReasoningConfig) -> Reasoning {
+    Reasoning {
+        effort: reasoning.effort.map(|effort| match effort {
+            ReasoningEffort::Low => Effort::Low,
+            ReasoningEffort::Medium => Effort::Medium,
+            ReasoningEffort::High => Effort::High,
+        }),
+        max_tokens: reasoning.budget_tokens,
     }
 }
 
 fn tool_definition_to_tool(tool: ToolDefinition) -> Result<crate::client::Tool, Error> {
+    if tool.builtin.is_some() {
+        return Err(unsupported("builtin tools"));
+    }
     match serde_json::from_str(&tool.parameters_schema) {
         Ok(value) => Ok(crate::client::Tool::Function {
             function: crate::client::Function {
@@ -261,15 +356,80 @@ fn tool_definition_to_tool(tool: ToolDefinition) -> Result<crate::client::Tool,
             code: ErrorCode::InternalError,
             message: format!("Failed to parse tool parameters for {}: {error}", tool.name),
             provider_error_json: None,
+            retry_after_ms: None,
         }),
     }
 }
 
-fn convert_tool_choice(tool_choice: String) -> crate::client::ToolChoice {
-    match tool_choice.as_str() {
-        "auto" | "none" => crate::client::ToolChoice::String(tool_choice),
-        _ => crate::client::ToolChoice::Function(ToolChoiceFunction::Function {
-            function: FunctionName { name: tool_choice },
+fn convert_tool_choice(tool_choice: ToolChoice) -> crate::client::ToolChoice {
+    match tool_choice {
+        ToolChoice::Auto => crate::client::ToolChoice::String("auto".to_string()),
+        ToolChoice::None => crate::client::ToolChoice::String("none".to_string()),
+        ToolChoice::Required => crate::client::ToolChoice::String("required".to_string()),
+        ToolChoice::Specific(name) => {
+            crate::client::ToolChoice::Function(ToolChoiceFunction::Function {
+                function: FunctionName { name },
+            })
+        }
+    }
+}
+
+fn convert_response_format(
+    response_format: ResponseFormat,
+) -> Result<crate::client::ResponseFormat, Error> {
+    match response_format {
+        ResponseFormat::Text => Ok(crate::client::ResponseFormat::Text),
+        ResponseFormat::JsonObject => Ok(crate::client::ResponseFormat::JsonObject),
+        ResponseFormat::JsonSchema(json_schema) => {
+            match serde_json::from_str(&json_schema.schema) {
+                Ok(schema) => Ok(crate::client::ResponseFormat::JsonSchema {
+                    json_schema: crate::client::JsonSchema {
+                        name: json_schema.name,
+                        schema,
+                        strict: json_schema.strict,
+                    },
+                }),
+                Err(error) => Err(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Failed to parse response format schema {}: {error}",
+                        json_schema.name
+                    ),
+                    provider_error_json: None,
+                    retry_after_ms: None,
+                }),
+            }
+        }
+    }
+}
+
+pub fn model_to_model_info(model: Model) -> ModelInfo {
+    let supports = |parameter: &str| {
+        model
+            .supported_parameters
+            .iter()
+            .any(|supported| supported == parameter)
+    };
+    let capabilities = ModelCapabilities {
+        tools: supports("tools"),
+        vision: model.architecture.as_ref().is_some_and(|architecture| {
+            architecture
+                .input_modalities
+                .iter()
+                .any(|modality| modality == "image")
         }),
+        streaming: true,
+        reasoning: supports("reasoning"),
+        json_schema: supports("structured_outputs"),
+    };
+    let top_provider = model.top_provider.as_ref();
+
+    ModelInfo {
+        context_window: top_provider
+            .and_then(|top_provider| top_provider.context_length)
+            .or(model.context_length),
+        max_output_tokens: top_provider.and_then(|top_provider| top_provider.max_completion_tokens),
+        capabilities: Some(capabilities),
+        id: model.id,
     }
 }
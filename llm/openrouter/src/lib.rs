@@ -3,36 +3,36 @@ mod conversions;
 
 use crate::client::{ChatCompletionChunk, CompletionsApi, CompletionsRequest, FunctionCall};
 use crate::conversions::{
-    convert_finish_reason, convert_usage, events_to_request, process_response,
+    convert_annotation, convert_cost, convert_finish_reason, convert_usage, events_to_request,
+    model_to_model_info, process_response, reasoning_to_content_part,
 };
+#[cfg(not(feature = "library"))]
+use golem_llm::cache::CachedLLM;
+use golem_llm::chat_session::ChatSession;
 use golem_llm::chat_stream::{LlmChatStream, LlmChatStreamState};
 use golem_llm::config::{get_config_key, with_config_key};
-use golem_llm::durability::{DurableLLM, ExtendedGuest};
-use golem_llm::error::error_code_from_status;
+#[cfg(not(feature = "library"))]
+use golem_llm::durability::DurableLLM;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::error::{error_code_from_response, unsupported};
 use golem_llm::event_source::EventSource;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
 use golem_llm::golem::llm::llm::{
     ChatStream, Config, ContentPart, Error, ErrorCode, Event, FinishReason, Guest, Message,
-    Response, ResponseMetadata, Role, StreamDelta, StreamEvent, ToolCall,
+    ModelInfo, Response, ResponseMetadata, Role, StreamDelta, StreamEvent, ToolCallDelta,
 };
+use golem_llm::tokens::estimate_tokens;
 use golem_rust::wasm_rpc::Pollable;
 use log::trace;
 use reqwest::StatusCode;
 use std::cell::{Ref, RefCell, RefMut};
-use std::collections::{HashMap, HashSet};
 
-#[derive(Default)]
-struct JsonFragment {
-    id: String,
-    name: String,
-    json: String,
-}
-
-struct OpenRouterChatStream {
+pub struct OpenRouterChatStream {
     stream: RefCell<Option<EventSource>>,
     failure: Option<Error>,
     finished: RefCell<bool>,
     finish_reason: RefCell<Option<FinishReason>>,
-    json_fragments: RefCell<HashMap<u32, JsonFragment>>,
+    unindexed_tool_calls: RefCell<u32>,
 }
 
 impl OpenRouterChatStream {
@@ -42,7 +42,7 @@ impl OpenRouterChatStream {
             failure: None,
             finished: RefCell::new(false),
             finish_reason: RefCell::new(None),
-            json_fragments: RefCell::new(HashMap::new()),
+            unindexed_tool_calls: RefCell::new(0),
         })
     }
 
@@ -52,7 +52,7 @@ impl OpenRouterChatStream {
             failure: Some(error),
             finished: RefCell::new(false),
             finish_reason: RefCell::new(None),
-            json_fragments: RefCell::new(HashMap::new()),
+            unindexed_tool_calls: RefCell::new(0),
         })
     }
 }
@@ -84,6 +84,7 @@ impl LlmChatStreamState for OpenRouterChatStream {
                 code: ErrorCode::InternalError,
                 message: message.into(),
                 provider_error_json: None,
+                retry_after_ms: None,
             }
         }
 
@@ -113,6 +114,7 @@ impl LlmChatStreamState for OpenRouterChatStream {
                             provider_id: None,
                             timestamp: Some(message.created.to_string()),
                             provider_metadata_json: None,
+                            cost: convert_cost(&usage),
                         })))
                     } else if let Some(choice) = message.choices.into_iter().next() {
                         if let Some(finish_reason) = choice.finish_reason {
@@ -121,27 +123,30 @@ impl LlmChatStreamState for OpenRouterChatStream {
                         }
                         if let Some(error) = choice.error {
                             Err(Error {
-                                code: error_code_from_status(
+                                code: error_code_from_response(
                                     TryInto::<u16>::try_into(error.code)
                                         .ok()
                                         .and_then(|code| StatusCode::from_u16(code).ok())
                                         .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                                    &error.message,
                                 ),
                                 message: error.message,
                                 provider_error_json: error
                                     .metadata
                                     .map(|value| serde_json::to_string(&value).unwrap()),
+                                retry_after_ms: None,
                             })
                         } else {
                             let content = choice
                                 .delta
-                                .content
-                                .map(|text| vec![ContentPart::Text(text)]);
-
-                            let mut seen_indices = HashSet::new();
-                            let mut tool_calls = Vec::new();
-                            let mut json_fragments = self.json_fragments.borrow_mut();
-
+                                .reasoning
+                                .into_iter()
+                                .map(reasoning_to_content_part)
+                                .chain(choice.delta.content.into_iter().map(ContentPart::Text))
+                                .collect::<Vec<_>>();
+                            let content = (!content.is_empty()).then_some(content);
+
+                            let mut tool_call_deltas = Vec::new();
                             for tool_call in choice.delta.tool_calls.unwrap_or_default() {
                                 match tool_call {
                                     client::ToolCall::Function {
@@ -153,42 +158,29 @@ impl LlmChatStreamState for OpenRouterChatStream {
                                             },
                                         index: None,
                                     } => {
-                                        // Full tool call
-                                        tool_calls.push(ToolCall {
-                                            id,
-                                            name,
-                                            arguments_json: arguments,
+                                        // Full tool call, sent as a single fragment
+                                        let mut unindexed_tool_calls =
+                                            self.unindexed_tool_calls.borrow_mut();
+                                        tool_call_deltas.push(ToolCallDelta {
+                                            index: *unindexed_tool_calls,
+                                            id: Some(id),
+                                            name: Some(name),
+                                            arguments_fragment: arguments,
                                         });
+                                        *unindexed_tool_calls += 1;
                                     }
                                     client::ToolCall::Function {
-                                        id: Some(id),
-                                        function:
-                                            FunctionCall {
-                                                name: Some(name),
-                                                arguments,
-                                            },
+                                        id,
+                                        function: FunctionCall { name, arguments },
                                         index: Some(index),
                                     } => {
-                                        // Beginning of a streamed tool call
-                                        json_fragments.insert(
+                                        // Beginning or continuation of a streamed tool call
+                                        tool_call_deltas.push(ToolCallDelta {
                                             index,
-                                            JsonFragment {
-                                                id,
-                                                name,
-                                                json: arguments,
-                                            },
-                                        );
-                                        seen_indices.insert(index);
-                                    }
-                                    client::ToolCall::Function {
-                                        id: _,
-                                        function: FunctionCall { name: _, arguments },
-                                        index: Some(index),
-                                    } => {
-                                        // Fragment
-                                        let fragment = json_fragments.entry(index).or_default();
-                                        fragment.json.push_str(&arguments);
-                                        seen_indices.insert(index);
+                                            id,
+                                            name,
+                                            arguments_fragment: arguments,
+                                        });
                                     }
                                     _ => {
                                         return Err(decode_internal_error(format!(
@@ -198,27 +190,17 @@ impl LlmChatStreamState for OpenRouterChatStream {
                                 }
                             }
 
-                            let indices =
-                                json_fragments.keys().copied().collect::<Vec<_>>().clone();
-                            for index in indices {
-                                if !seen_indices.contains(&index) {
-                                    // Emitting finished tool call
-                                    let fragment = json_fragments.remove(&index).unwrap();
-                                    tool_calls.push(ToolCall {
-                                        id: fragment.id,
-                                        name: fragment.name,
-                                        arguments_json: fragment.json,
-                                    });
-                                }
-                            }
-
                             Ok(Some(StreamEvent::Delta(StreamDelta {
                                 content,
-                                tool_calls: if tool_calls.is_empty() {
-                                    None
-                                } else {
-                                    Some(tool_calls)
-                                },
+                                tool_calls: None,
+                                tool_call_deltas: (!tool_call_deltas.is_empty())
+                                    .then_some(tool_call_deltas),
+                                citations: choice.delta.annotations.map(|annotations| {
+                                    annotations
+                                        .into_iter()
+                                        .filter_map(convert_annotation)
+                                        .collect()
+                                }),
                             })))
                         }
                     } else {
@@ -234,7 +216,7 @@ impl LlmChatStreamState for OpenRouterChatStream {
     }
 }
 
-struct OpenRouterComponent;
+pub struct OpenRouterComponent;
 
 impl OpenRouterComponent {
     const ENV_VAR_NAME: &'static str = "OPENROUTER_API_KEY";
@@ -258,6 +240,7 @@ impl OpenRouterComponent {
 
 impl Guest for OpenRouterComponent {
     type ChatStream = LlmChatStream<OpenRouterChatStream>;
+    type ChatSession = ChatSession<Self>;
 
     fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
         let openrouter_api_key = get_config_key(Self::ENV_VAR_NAME)?;
@@ -269,9 +252,57 @@ impl Guest for OpenRouterComponent {
     fn stream(events: Vec<Event>, config: Config) -> ChatStream {
         ChatStream::new(Self::unwrapped_stream(events, config))
     }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        Ok(estimate_tokens(&events, &config))
+    }
+
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        let openrouter_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = CompletionsApi::new(openrouter_api_key);
+        let response = client.list_models()?;
+        let known_model = response.data.into_iter().find(|known| known.id == model);
+
+        Ok(match known_model {
+            Some(known_model) => model_to_model_info(known_model),
+            None => ModelInfo {
+                id: model,
+                context_window: None,
+                max_output_tokens: None,
+                capabilities: None,
+            },
+        })
+    }
+
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let openrouter_api_key = get_config_key(Self::ENV_VAR_NAME)?;
+        let client = CompletionsApi::new(openrouter_api_key);
+        let response = client.list_models()?;
+        Ok(response.data.into_iter().map(model_to_model_info).collect())
+    }
+}
+
+impl BatchGuest for OpenRouterComponent {
+    fn submit_batch(_items: Vec<BatchItem>) -> Result<String, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn poll_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn get_batch_results(_id: String) -> Result<Vec<BatchResult>, Error> {
+        Err(unsupported("batches"))
+    }
+
+    fn cancel_batch(_id: String) -> Result<BatchInfo, Error> {
+        Err(unsupported("batches"))
+    }
 }
 
 impl ExtendedGuest for OpenRouterComponent {
+    const PROVIDER_NAME: &'static str = "openrouter";
+
     fn unwrapped_stream(events: Vec<Event>, config: Config) -> LlmChatStream<OpenRouterChatStream> {
         with_config_key(
             Self::ENV_VAR_NAME,
@@ -301,6 +332,7 @@ impl ExtendedGuest for OpenRouterComponent {
                      Please continue your response from where you left off. \
                      Do not include the part of the response that was already seen.".to_string()),
             ],
+            cache_hint: None,
         }));
         extended_events.push(Event::Message(Message {
             role: Role::User,
@@ -308,6 +340,7 @@ impl ExtendedGuest for OpenRouterComponent {
             content: vec![ContentPart::Text(
                 "Here is the original question:".to_string(),
             )],
+            cache_hint: None,
         }));
         extended_events.extend(
             original_events
@@ -318,7 +351,13 @@ impl ExtendedGuest for OpenRouterComponent {
         let mut partial_result_as_content = Vec::new();
         for delta in partial_result {
             if let Some(contents) = &delta.content {
-                partial_result_as_content.extend_from_slice(contents);
+                // Reasoning is not part of the answer the model has to continue
+                partial_result_as_content.extend(
+                    contents
+                        .iter()
+                        .filter(|content| !matches!(content, ContentPart::Reasoning(_)))
+                        .cloned(),
+                );
             }
             if let Some(tool_calls) = &delta.tool_calls {
                 for tool_call in tool_calls {
@@ -339,6 +378,7 @@ impl ExtendedGuest for OpenRouterComponent {
             .into_iter()
             .chain(partial_result_as_content)
             .collect(),
+            cache_hint: None,
         }));
         extended_events
     }
@@ -348,6 +388,8 @@ impl ExtendedGuest for OpenRouterComponent {
     }
 }
 
-type DurableOpenRouterComponent = DurableLLM<OpenRouterComponent>;
+#[cfg(not(feature = "library"))]
+type DurableOpenRouterComponent = DurableLLM<CachedLLM<OpenRouterComponent>>;
 
+#[cfg(not(feature = "library"))]
 golem_llm::export_llm!(DurableOpenRouterComponent with_types_in golem_llm);
@@ -0,0 +1,123 @@
+use crate::config::Provider;
+use golem_llm::cache::CachedLLM;
+#[cfg(feature = "durability")]
+use golem_llm::durability::DurableChatStream;
+use golem_llm::durability::ExtendedGuest;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{
+    Config, Error, Event, Guest, GuestChatStream, ModelInfo, Response,
+};
+
+/// Runs `$body` with `$backend` being a type alias of the given provider's implementation, wrapped
+/// with the response cache
+macro_rules! with_backend {
+    ($provider:expr, $backend:ident => $body:expr) => {
+        match $provider {
+            Provider::Anthropic => {
+                type $backend = CachedLLM<golem_llm_anthropic::AnthropicComponent>;
+                $body
+            }
+            Provider::AzureOpenAI => {
+                type $backend = CachedLLM<golem_llm_azure_openai::AzureOpenAIComponent>;
+                $body
+            }
+            Provider::Gemini => {
+                type $backend = CachedLLM<golem_llm_gemini::GeminiComponent>;
+                $body
+            }
+            Provider::Grok => {
+                type $backend = CachedLLM<golem_llm_grok::GrokComponent>;
+                $body
+            }
+            Provider::Mistral => {
+                type $backend = CachedLLM<golem_llm_mistral::MistralComponent>;
+                $body
+            }
+            Provider::Ollama => {
+                type $backend = CachedLLM<golem_llm_ollama::OllamaComponent>;
+                $body
+            }
+            Provider::OpenAI => {
+                type $backend = CachedLLM<golem_llm_openai::OpenAIComponent>;
+                $body
+            }
+            Provider::OpenAICompatible => {
+                type $backend = CachedLLM<golem_llm_openai_compatible::OpenAICompatibleComponent>;
+                $body
+            }
+            Provider::OpenRouter => {
+                type $backend = CachedLLM<golem_llm_openrouter::OpenRouterComponent>;
+                $body
+            }
+        }
+    };
+}
+
+pub fn send(provider: Provider, events: Vec<Event>, config: Config) -> Result<Response, Error> {
+    with_backend!(provider, B => B::send(events, config))
+}
+
+pub fn count_tokens(provider: Provider, events: Vec<Event>, config: Config) -> Result<u32, Error> {
+    with_backend!(provider, B => B::count_tokens(events, config))
+}
+
+pub fn get_model_info(provider: Provider, model: String) -> Result<ModelInfo, Error> {
+    with_backend!(provider, B => B::get_model_info(model))
+}
+
+pub fn list_models(provider: Provider) -> Result<Vec<ModelInfo>, Error> {
+    with_backend!(provider, B => B::list_models())
+}
+
+pub fn submit_batch(provider: Provider, items: Vec<BatchItem>) -> Result<String, Error> {
+    with_backend!(provider, B => B::submit_batch(items))
+}
+
+pub fn poll_batch(provider: Provider, id: String) -> Result<BatchInfo, Error> {
+    with_backend!(provider, B => B::poll_batch(id))
+}
+
+pub fn get_batch_results(provider: Provider, id: String) -> Result<Vec<BatchResult>, Error> {
+    with_backend!(provider, B => B::get_batch_results(id))
+}
+
+pub fn cancel_batch(provider: Provider, id: String) -> Result<BatchInfo, Error> {
+    with_backend!(provider, B => B::cancel_batch(id))
+}
+
+/// Opens a stream on the given provider. Also returns the error if the stream failed to start,
+/// so the router can decide to fail over to the next backend.
+pub fn open_stream(
+    provider: Provider,
+    events: Vec<Event>,
+    config: Config,
+) -> (Box<dyn GuestChatStream>, Option<Error>) {
+    with_backend!(provider, B => {
+        let stream = B::unwrapped_stream(events, config);
+        let failure = stream.failure().clone();
+        (live_stream::<B>(stream), failure)
+    })
+}
+
+/// Recreates a stream on the provider which answered it originally, when replaying the worker
+#[cfg(feature = "durability")]
+pub fn replay_stream(
+    provider: Provider,
+    events: Vec<Event>,
+    config: Config,
+) -> Box<dyn GuestChatStream> {
+    with_backend!(provider, B => Box::new(DurableChatStream::<B>::replay(
+        events.into_iter().map(Ok).collect(),
+        config,
+    )))
+}
+
+#[cfg(feature = "durability")]
+fn live_stream<B: ExtendedGuest>(stream: B::ChatStream) -> Box<dyn GuestChatStream> {
+    Box::new(DurableChatStream::<B>::live(stream))
+}
+
+#[cfg(not(feature = "durability"))]
+fn live_stream<B: ExtendedGuest>(stream: B::ChatStream) -> Box<dyn GuestChatStream> {
+    Box::new(stream)
+}
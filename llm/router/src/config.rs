@@ -0,0 +1,137 @@
+use golem_llm::config::get_config_key;
+use golem_llm::golem::llm::llm::{Config, Error, ErrorCode};
+use serde::Deserialize;
+
+/// The LLM implementations the router can dispatch calls to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Provider {
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    #[serde(rename = "azure-openai")]
+    AzureOpenAI,
+    #[serde(rename = "gemini")]
+    Gemini,
+    #[serde(rename = "grok")]
+    Grok,
+    #[serde(rename = "mistral")]
+    Mistral,
+    #[serde(rename = "ollama")]
+    Ollama,
+    #[serde(rename = "openai")]
+    OpenAI,
+    #[serde(rename = "openai-compatible")]
+    OpenAICompatible,
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+}
+
+impl Provider {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::Anthropic => "anthropic",
+            Provider::AzureOpenAI => "azure-openai",
+            Provider::Gemini => "gemini",
+            Provider::Grok => "grok",
+            Provider::Mistral => "mistral",
+            Provider::Ollama => "ollama",
+            Provider::OpenAI => "openai",
+            Provider::OpenAICompatible => "openai-compatible",
+            Provider::OpenRouter => "openrouter",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// One of the providers the router sends calls to
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendConfig {
+    pub provider: Provider,
+    /// Model to use with this backend instead of the one in the call's config
+    pub model: Option<String>,
+    /// Relative share of the calls this backend gets first. A weight of 0 makes the backend
+    /// only used for failover.
+    pub weight: Option<u32>,
+}
+
+impl BackendConfig {
+    /// Adapts the call's config to this backend
+    pub fn apply(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        if let Some(model) = &self.model {
+            config.model = model.clone();
+        }
+        config
+    }
+}
+
+/// Configuration of the router, read as JSON from the `GOLEM_LLM_ROUTER_CONFIG` environment
+/// variable. For example:
+///
+/// ```json
+/// {"backends": [{"provider": "openai", "model": "gpt-4o", "weight": 3}, {"provider": "anthropic", "model": "claude-sonnet-4-0"}]}
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouterConfig {
+    pub backends: Vec<BackendConfig>,
+}
+
+impl RouterConfig {
+    const ENV_VAR_NAME: &'static str = "GOLEM_LLM_ROUTER_CONFIG";
+
+    pub fn load() -> Result<Self, Error> {
+        let json = get_config_key(Self::ENV_VAR_NAME)?;
+        let config: RouterConfig = serde_json::from_str(&json).map_err(|err| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Invalid {}: {err}", Self::ENV_VAR_NAME),
+            provider_error_json: None,
+            retry_after_ms: None,
+        })?;
+        if config.backends.is_empty() {
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: format!("No backends configured in {}", Self::ENV_VAR_NAME),
+                provider_error_json: None,
+                retry_after_ms: None,
+            })
+        } else {
+            Ok(config)
+        }
+    }
+
+    /// Gets the indices of the backends in the order they should be tried.
+    ///
+    /// Without weights the backends are tried in the configured order. If any backend has a weight,
+    /// the first one is chosen randomly in proportion to the weights (defaulting to 1), and the rest
+    /// follow in the configured order.
+    pub fn routing_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.backends.len()).collect();
+        if self.backends.iter().any(|backend| backend.weight.is_some()) {
+            let weights: Vec<u64> = self
+                .backends
+                .iter()
+                .map(|backend| backend.weight.unwrap_or(1) as u64)
+                .collect();
+            let total: u64 = weights.iter().sum();
+            if total > 0 {
+                let mut point = wasi::random::random::get_random_u64() % total;
+                let first = weights
+                    .iter()
+                    .position(|weight| {
+                        if point < *weight {
+                            true
+                        } else {
+                            point -= weight;
+                            false
+                        }
+                    })
+                    .unwrap_or(0);
+                order.remove(first);
+                order.insert(0, first);
+            }
+        }
+        order
+    }
+}
@@ -0,0 +1,324 @@
+use golem_llm::chat_session::ChatSession;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult, Guest as BatchGuest};
+use golem_llm::golem::llm::llm::{ChatStream, Config, Error, Event, Guest, ModelInfo, Response};
+use golem_llm::init_logging;
+
+/// The router with durability of the calls it makes to its backends
+pub struct DurableRouter;
+
+#[cfg(not(feature = "durability"))]
+mod passthrough_impl {
+    use super::*;
+    use crate::config::RouterConfig;
+    use crate::{LlmRouterComponent, RouterChatStream};
+
+    impl Guest for DurableRouter {
+        type ChatStream = RouterChatStream;
+        type ChatSession = ChatSession<Self>;
+
+        fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+            init_logging();
+            LlmRouterComponent::send(events, config).map(|(_, response)| response)
+        }
+
+        fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+            init_logging();
+            let stream = match RouterConfig::load() {
+                Ok(router_config) => LlmRouterComponent::stream(&router_config, events, config).1,
+                Err(error) => RouterChatStream::failed(error),
+            };
+            ChatStream::new(stream)
+        }
+
+        fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+            init_logging();
+            LlmRouterComponent::count_tokens(events, config)
+        }
+
+        fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+            init_logging();
+            LlmRouterComponent::get_model_info(model)
+        }
+
+        fn list_models() -> Result<Vec<ModelInfo>, Error> {
+            init_logging();
+            LlmRouterComponent::list_models()
+        }
+    }
+
+    impl BatchGuest for DurableRouter {
+        fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+            init_logging();
+            LlmRouterComponent::submit_batch(items)
+        }
+
+        fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+            LlmRouterComponent::poll_batch(id)
+        }
+
+        fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+            init_logging();
+            LlmRouterComponent::get_batch_results(id)
+        }
+
+        fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+            LlmRouterComponent::cancel_batch(id)
+        }
+    }
+}
+
+/// Besides persisting the results like `DurableLLM` does, the durable router records which backend
+/// answered each call. Streams are reopened on the same backend when replaying, so the routing
+/// order being random, or the configuration changing, does not affect the replayed worker.
+#[cfg(feature = "durability")]
+mod durable_impl {
+    use super::*;
+    use crate::backends::replay_stream;
+    use crate::config::{Provider, RouterConfig};
+    use crate::{LlmRouterComponent, RoutedBackend, RouterChatStream};
+    use golem_llm::golem::llm::llm::ErrorCode;
+    use golem_rust::bindings::golem::durability::durability::DurableFunctionType;
+    use golem_rust::durability::Durability;
+    use golem_rust::{with_persistence_level, FromValueAndType, IntoValue, PersistenceLevel};
+
+    impl Guest for DurableRouter {
+        type ChatStream = RouterChatStream;
+        type ChatSession = ChatSession<Self>;
+
+        fn send(events: Vec<Event>, config: Config) -> Result<Response, Error> {
+            init_logging();
+
+            let durability = Durability::<RoutedResponse, Error>::new(
+                "golem_llm_router",
+                "send",
+                DurableFunctionType::WriteRemote,
+            );
+            let result = if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::send(events.clone(), config.clone())
+                })
+                .map(|(backend, response)| RoutedResponse { backend, response });
+                durability.persist_serializable(SendInput { events, config }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            };
+            result.map(|routed| routed.response)
+        }
+
+        fn stream(events: Vec<Event>, config: Config) -> ChatStream {
+            init_logging();
+
+            // The configuration is only loaded in live mode, the replayed stream is reopened on
+            // the recorded backend even if the configuration changed or became invalid since
+            let durability = Durability::<RoutedBackend, Error>::new(
+                "golem_llm_router",
+                "stream",
+                DurableFunctionType::WriteRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    RouterConfig::load().map(|router_config| {
+                        LlmRouterComponent::stream(&router_config, events.clone(), config.clone())
+                    })
+                });
+                let (backend, stream) = match result {
+                    Ok((backend, stream)) => (Ok(backend), stream),
+                    Err(error) => (Err(error.clone()), RouterChatStream::failed(error)),
+                };
+                durability.persist_serializable(SendInput { events, config }, backend);
+                ChatStream::new(stream)
+            } else {
+                let backend: RoutedBackend = match durability.replay_serializable() {
+                    Ok(backend) => backend,
+                    Err(error) => return ChatStream::new(RouterChatStream::failed(error)),
+                };
+                let stream = match Provider::from_name(&backend.provider) {
+                    Some(provider) => RouterChatStream::Routed(replay_stream(
+                        provider,
+                        events,
+                        Config {
+                            model: backend.model,
+                            ..config
+                        },
+                    )),
+                    None => RouterChatStream::failed(Error {
+                        code: ErrorCode::InternalError,
+                        message: format!("Unknown provider in the oplog: {}", backend.provider),
+                        provider_error_json: None,
+                        retry_after_ms: None,
+                    }),
+                };
+                ChatStream::new(stream)
+            }
+        }
+
+        fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+            init_logging();
+
+            let durability = Durability::<u32, Error>::new(
+                "golem_llm_router",
+                "count_tokens",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::count_tokens(events.clone(), config.clone())
+                });
+                durability.persist_serializable(SendInput { events, config }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+            init_logging();
+
+            let durability = Durability::<ModelInfo, Error>::new(
+                "golem_llm_router",
+                "get_model_info",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::get_model_info(model.clone())
+                });
+                durability.persist_serializable(ModelInfoInput { model }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn list_models() -> Result<Vec<ModelInfo>, Error> {
+            init_logging();
+
+            let durability = Durability::<Vec<ModelInfo>, Error>::new(
+                "golem_llm_router",
+                "list_models",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::list_models()
+                });
+                durability.persist_serializable(NoInput, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+    }
+
+    /// The chosen backend is part of the batch ID, so persisting the results is enough
+    impl BatchGuest for DurableRouter {
+        fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+            init_logging();
+
+            let durability = Durability::<String, Error>::new(
+                "golem_llm_router",
+                "submit_batch",
+                DurableFunctionType::WriteRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::submit_batch(items.clone())
+                });
+                durability.persist_serializable(SubmitBatchInput { items }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+
+            let durability = Durability::<BatchInfo, Error>::new(
+                "golem_llm_router",
+                "poll_batch",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::poll_batch(id.clone())
+                });
+                durability.persist_serializable(BatchIdInput { id }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+            init_logging();
+
+            let durability = Durability::<Vec<BatchResult>, Error>::new(
+                "golem_llm_router",
+                "get_batch_results",
+                DurableFunctionType::ReadRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::get_batch_results(id.clone())
+                });
+                durability.persist_serializable(BatchIdInput { id }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+
+        fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+            init_logging();
+
+            let durability = Durability::<BatchInfo, Error>::new(
+                "golem_llm_router",
+                "cancel_batch",
+                DurableFunctionType::WriteRemote,
+            );
+            if durability.is_live() {
+                let result = with_persistence_level(PersistenceLevel::PersistNothing, || {
+                    LlmRouterComponent::cancel_batch(id.clone())
+                });
+                durability.persist_serializable(BatchIdInput { id }, result.clone());
+                result
+            } else {
+                durability.replay_serializable()
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, FromValueAndType, IntoValue)]
+    struct RoutedResponse {
+        backend: RoutedBackend,
+        response: Response,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct SendInput {
+        events: Vec<Event>,
+        config: Config,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct ModelInfoInput {
+        model: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct SubmitBatchInput {
+        items: Vec<BatchItem>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue)]
+    struct BatchIdInput {
+        id: String,
+    }
+
+    #[derive(Debug, IntoValue)]
+    struct NoInput;
+}
@@ -0,0 +1,258 @@
+mod backends;
+mod config;
+mod durability;
+
+use crate::config::{BackendConfig, Provider, RouterConfig};
+use crate::durability::DurableRouter;
+use golem_llm::golem::llm::batch::{BatchInfo, BatchItem, BatchResult};
+use golem_llm::golem::llm::llm::{
+    Config, Error, ErrorCode, Event, GuestChatStream, ModelInfo, Response, StreamEvent,
+};
+use golem_rust::{FromValueAndType, IntoValue};
+use log::warn;
+use std::cell::Cell;
+use std::collections::HashSet;
+
+/// The backend a call was routed to, recorded by the durable router so replay does not depend
+/// on the random choice made by the original call
+#[derive(Debug, Clone, PartialEq, FromValueAndType, IntoValue)]
+pub struct RoutedBackend {
+    provider: String,
+    model: String,
+}
+
+impl RoutedBackend {
+    fn new(backend: &BackendConfig, config: &Config) -> Self {
+        Self {
+            provider: backend.provider.name().to_string(),
+            model: config.model.clone(),
+        }
+    }
+}
+
+/// Chat stream of the router, either the stream of the chosen backend or a failure to report
+/// before any backend could be called
+pub enum RouterChatStream {
+    Routed(Box<dyn GuestChatStream>),
+    Failed { error: Error, finished: Cell<bool> },
+}
+
+impl RouterChatStream {
+    pub fn failed(error: Error) -> Self {
+        RouterChatStream::Failed {
+            error,
+            finished: Cell::new(false),
+        }
+    }
+}
+
+impl GuestChatStream for RouterChatStream {
+    fn poll_next(&self) -> Option<Vec<Result<StreamEvent, Error>>> {
+        match self {
+            RouterChatStream::Routed(stream) => stream.poll_next(),
+            RouterChatStream::Failed { error, finished } => {
+                if finished.replace(true) {
+                    Some(vec![])
+                } else {
+                    Some(vec![Err(error.clone())])
+                }
+            }
+        }
+    }
+
+    fn get_next(&self) -> Vec<Result<StreamEvent, Error>> {
+        match self {
+            RouterChatStream::Routed(stream) => stream.get_next(),
+            RouterChatStream::Failed { .. } => self.poll_next().unwrap_or_default(),
+        }
+    }
+}
+
+/// Errors which may not happen with another backend, so the router tries the next one
+fn is_failover_error(error: &Error) -> bool {
+    matches!(
+        error.code,
+        ErrorCode::RateLimitExceeded
+            | ErrorCode::InternalError
+            | ErrorCode::Overloaded
+            | ErrorCode::QuotaExceeded
+            | ErrorCode::Timeout
+    )
+}
+
+/// Routes the LLM calls to the backends configured in `GOLEM_LLM_ROUTER_CONFIG`.
+///
+/// Calls fail over to the next backend on rate limiting, overloaded providers, exhausted quotas,
+/// timeouts and internal errors. Streams only fail
+/// over if they fail before receiving any events, as the already streamed content cannot be
+/// taken back from the caller.
+struct LlmRouterComponent;
+
+impl LlmRouterComponent {
+    /// Calls the backends in routing order until one of them succeeds or fails with an error which
+    /// is not worth failing over on
+    fn route<T>(
+        router_config: &RouterConfig,
+        config: &Config,
+        mut call: impl FnMut(Provider, Config) -> Result<T, Error>,
+    ) -> Result<(RoutedBackend, T), Error> {
+        let mut last_error = None;
+        for index in router_config.routing_order() {
+            let backend = &router_config.backends[index];
+            let backend_config = backend.apply(config);
+            let routed_backend = RoutedBackend::new(backend, &backend_config);
+            match call(backend.provider, backend_config) {
+                Ok(result) => return Ok((routed_backend, result)),
+                Err(error) if is_failover_error(&error) => {
+                    warn!(
+                        "Backend {} with model {} failed, trying the next one: {}",
+                        routed_backend.provider, routed_backend.model, error.message
+                    );
+                    last_error = Some(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Err(last_error.expect("Router configuration has no backends"))
+    }
+
+    fn send(events: Vec<Event>, config: Config) -> Result<(RoutedBackend, Response), Error> {
+        let router_config = RouterConfig::load()?;
+        Self::route(&router_config, &config, |provider, config| {
+            backends::send(provider, events.clone(), config)
+        })
+    }
+
+    fn stream(
+        router_config: &RouterConfig,
+        events: Vec<Event>,
+        config: Config,
+    ) -> (RoutedBackend, RouterChatStream) {
+        let mut last_failed = None;
+        for index in router_config.routing_order() {
+            let backend = &router_config.backends[index];
+            let backend_config = backend.apply(&config);
+            let routed_backend = RoutedBackend::new(backend, &backend_config);
+            let (stream, failure) =
+                backends::open_stream(backend.provider, events.clone(), backend_config);
+            match failure {
+                Some(error) if is_failover_error(&error) => {
+                    warn!(
+                        "Backend {} with model {} failed to stream, trying the next one: {}",
+                        routed_backend.provider, routed_backend.model, error.message
+                    );
+                    last_failed = Some((routed_backend, RouterChatStream::Routed(stream)));
+                }
+                _ => return (routed_backend, RouterChatStream::Routed(stream)),
+            }
+        }
+        last_failed.expect("Router configuration has no backends")
+    }
+
+    fn count_tokens(events: Vec<Event>, config: Config) -> Result<u32, Error> {
+        let router_config = RouterConfig::load()?;
+        Self::route(&router_config, &config, |provider, config| {
+            backends::count_tokens(provider, events.clone(), config)
+        })
+        .map(|(_, count)| count)
+    }
+
+    /// Asks every configured provider about the model until one of them knows it, as the model
+    /// is not necessarily served by the first backend
+    fn get_model_info(model: String) -> Result<ModelInfo, Error> {
+        let router_config = RouterConfig::load()?;
+        let mut first_error = None;
+        for backend in &router_config.backends {
+            match backends::get_model_info(backend.provider, model.clone()) {
+                Ok(model_info) => return Ok(model_info),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        Err(first_error.expect("Router configuration has no backends"))
+    }
+
+    /// Lists the models of every configured provider, skipping the ones which are not available
+    fn list_models() -> Result<Vec<ModelInfo>, Error> {
+        let router_config = RouterConfig::load()?;
+        let mut models = Vec::new();
+        let mut listed_providers = HashSet::new();
+        let mut last_error = None;
+        for backend in &router_config.backends {
+            if listed_providers.insert(backend.provider) {
+                match backends::list_models(backend.provider) {
+                    Ok(provider_models) => models.extend(provider_models),
+                    Err(error) if is_failover_error(&error) => last_error = Some(error),
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+        match last_error {
+            Some(error) if models.is_empty() => Err(error),
+            _ => Ok(models),
+        }
+    }
+
+    /// Submits the batch to the first backend in routing order accepting it, also failing over
+    /// on backends not supporting batches. The batch ID is prefixed with the backend's provider
+    /// so the other batch functions know where to send their calls.
+    fn submit_batch(items: Vec<BatchItem>) -> Result<String, Error> {
+        let router_config = RouterConfig::load()?;
+        let mut last_error = None;
+        for index in router_config.routing_order() {
+            let backend = &router_config.backends[index];
+            let backend_items = items
+                .iter()
+                .map(|item| BatchItem {
+                    config: backend.apply(&item.config),
+                    ..item.clone()
+                })
+                .collect();
+            match backends::submit_batch(backend.provider, backend_items) {
+                Ok(id) => return Ok(format!("{}:{id}", backend.provider.name())),
+                Err(error) if is_failover_error(&error) || error.code == ErrorCode::Unsupported => {
+                    warn!(
+                        "Backend {} failed to submit the batch, trying the next one: {}",
+                        backend.provider.name(),
+                        error.message
+                    );
+                    last_error = Some(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Err(last_error.expect("Router configuration has no backends"))
+    }
+
+    fn poll_batch(id: String) -> Result<BatchInfo, Error> {
+        let (provider, provider_id) = Self::batch_provider(&id)?;
+        backends::poll_batch(provider, provider_id).map(|info| BatchInfo { id, ..info })
+    }
+
+    fn get_batch_results(id: String) -> Result<Vec<BatchResult>, Error> {
+        let (provider, provider_id) = Self::batch_provider(&id)?;
+        backends::get_batch_results(provider, provider_id)
+    }
+
+    fn cancel_batch(id: String) -> Result<BatchInfo, Error> {
+        let (provider, provider_id) = Self::batch_provider(&id)?;
+        backends::cancel_batch(provider, provider_id).map(|info| BatchInfo { id, ..info })
+    }
+
+    /// Splits a batch ID returned by `submit_batch` to the provider and the provider's batch ID
+    fn batch_provider(id: &str) -> Result<(Provider, String), Error> {
+        id.split_once(':')
+            .and_then(|(provider, provider_id)| {
+                Provider::from_name(provider).map(|provider| (provider, provider_id.to_string()))
+            })
+            .ok_or_else(|| Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("Not a batch ID of the router: {id}"),
+                provider_error_json: None,
+                retry_after_ms: None,
+            })
+    }
+}
+
+golem_llm::export_llm!(DurableRouter with_types_in golem_llm);
@@ -2,8 +2,11 @@
 mod bindings;
 
 use crate::bindings::exports::test::llm_exports::test_llm_api::*;
+use crate::bindings::golem::llm::batch;
 use crate::bindings::golem::llm::llm;
 use crate::bindings::golem::llm::llm::StreamEvent;
+#[cfg(feature = "ollama")]
+use crate::bindings::golem::llm::model_management;
 use crate::bindings::test::helper_client::test_helper_client::TestHelperApi;
 use golem_rust::atomically;
 
@@ -23,6 +26,18 @@ const MODEL: &'static str = "grok-3-beta";
 const MODEL: &'static str = "openrouter/auto";
 #[cfg(feature = "ollama")]
 const MODEL: &'static str = "qwen3:1.7b";
+#[cfg(feature = "openai-compatible")]
+const MODEL: &'static str = "qwen3:1.7b";
+#[cfg(feature = "gemini")]
+const MODEL: &'static str = "gemini-2.5-flash";
+#[cfg(feature = "router")]
+const MODEL: &'static str = "qwen3:1.7b";
+#[cfg(feature = "mock")]
+const MODEL: &'static str = "mock-model";
+#[cfg(feature = "mistral")]
+const MODEL: &'static str = "mistral-small-latest";
+#[cfg(feature = "azure-openai")]
+const MODEL: &'static str = "gpt-4o-mini";
 
 #[cfg(feature = "openai")]
 const IMAGE_MODEL: &'static str = "gpt-4o-mini";
@@ -36,6 +51,22 @@ const IMAGE_MODEL: &'static str = "grok-2-vision-latest";
 const IMAGE_MODEL: &'static str = "openrouter/auto";
 #[cfg(feature = "ollama")]
 const IMAGE_MODEL: &'static str = "gemma3:4b";
+#[cfg(feature = "openai-compatible")]
+const IMAGE_MODEL: &'static str = "gemma3:4b";
+#[cfg(feature = "gemini")]
+const IMAGE_MODEL: &'static str = "gemini-2.5-flash";
+#[cfg(feature = "router")]
+const IMAGE_MODEL: &'static str = "gemma3:4b";
+#[cfg(feature = "mock")]
+const IMAGE_MODEL: &'static str = "mock-vision-model";
+#[cfg(feature = "mistral")]
+const IMAGE_MODEL: &'static str = "pixtral-12b-latest";
+#[cfg(feature = "azure-openai")]
+const IMAGE_MODEL: &'static str = "gpt-4o-mini";
+
+/// A small model pulled and deleted by test22, distinct from the models used by the other tests
+#[cfg(feature = "ollama")]
+const PULLED_MODEL: &'static str = "smollm2:135m";
 
 impl Guest for Component {
     /// test1 demonstrates a simple, non-streaming text question-answer interaction with the LLM.
@@ -47,6 +78,9 @@ impl Guest for Component {
             stop_sequences: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -59,6 +93,7 @@ impl Guest for Component {
                     "What is the usual weather on the Vršič pass in the beginning of May?"
                         .to_string(),
                 )],
+                cache_hint: None,
             })],
             &config,
         );
@@ -82,6 +117,14 @@ impl Guest for Component {
                                     inline_data.mime_type
                                 ),
                             },
+                            llm::ContentPart::Reasoning(reasoning) =>
+                                format!("[REASONING: {}]", reasoning.text),
+                            llm::ContentPart::Document(document) =>
+                                format!("[DOCUMENT: {}]", document.mime_type),
+                            llm::ContentPart::Audio(audio) =>
+                                format!("[AUDIO: {}]", audio.mime_type),
+                            llm::ContentPart::BuiltinToolCall(call) =>
+                                format!("[BUILTIN TOOL CALL: {:?}]", call.tool),
                         })
                         .collect::<Vec<_>>()
                         .join(", "),
@@ -124,8 +167,13 @@ impl Guest for Component {
                         "additionalProperties": false
                     }"#
                 .to_string(),
+                cache_hint: None,
+                builtin: None,
             }]),
-            tool_choice: Some("auto".to_string()),
+            tool_choice: Some(llm::ToolChoice::Auto),
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -141,6 +189,7 @@ impl Guest for Component {
             role: llm::Role::User,
             name: Some("vigoo".to_string()),
             content: input.clone(),
+            cache_hint: None,
         }));
 
         println!("Sending request to LLM...");
@@ -202,6 +251,9 @@ impl Guest for Component {
             stop_sequences: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -214,6 +266,7 @@ impl Guest for Component {
                     "What is the usual weather on the Vršič pass in the beginning of May?"
                         .to_string(),
                 )],
+                cache_hint: None,
             })],
             &config,
         );
@@ -251,7 +304,8 @@ impl Guest for Component {
         result
     }
 
-    /// test4 shows how streaming works together with using tools
+    /// test4 shows how streaming works together with using tools, the tool call arguments arriving
+    /// in fragments before the completed tool calls
     fn test4() -> String {
         let config = llm::Config {
             model: MODEL.to_string(),
@@ -275,8 +329,13 @@ impl Guest for Component {
                         "additionalProperties": false
                     }"#
                 .to_string(),
+                cache_hint: None,
+                builtin: None,
             }]),
-            tool_choice: Some("auto".to_string()),
+            tool_choice: Some(llm::ToolChoice::Auto),
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -293,6 +352,7 @@ impl Guest for Component {
                 role: llm::Role::User,
                 name: Some("vigoo".to_string()),
                 content: input,
+                cache_hint: None,
             })],
             &config,
         );
@@ -341,6 +401,9 @@ impl Guest for Component {
             stop_sequences: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -358,6 +421,7 @@ impl Guest for Component {
                             detail: Some(llm::ImageDetail::High),
                         })),
                     ],
+                    cache_hint: None,
                 }),
                 llm::Event::Message(llm::Message {
                     role: llm::Role::System,
@@ -365,6 +429,7 @@ impl Guest for Component {
                     content: vec![llm::ContentPart::Text(
                         "Produce the output in both English and Hungarian".to_string(),
                     )],
+                    cache_hint: None,
                 }),
             ],
             &config,
@@ -389,6 +454,14 @@ impl Guest for Component {
                                     inline_data.mime_type
                                 ),
                             },
+                            llm::ContentPart::Reasoning(reasoning) =>
+                                format!("[REASONING: {}]", reasoning.text),
+                            llm::ContentPart::Document(document) =>
+                                format!("[DOCUMENT: {}]", document.mime_type),
+                            llm::ContentPart::Audio(audio) =>
+                                format!("[AUDIO: {}]", audio.mime_type),
+                            llm::ContentPart::BuiltinToolCall(call) =>
+                                format!("[BUILTIN TOOL CALL: {:?}]", call.tool),
                         })
                         .collect::<Vec<_>>()
                         .join(", "),
@@ -416,6 +489,9 @@ impl Guest for Component {
             stop_sequences: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -428,6 +504,7 @@ impl Guest for Component {
                     "What is the usual weather on the Vršič pass in the beginning of May?"
                         .to_string(),
                 )],
+                cache_hint: None,
             })],
             &config,
         );
@@ -470,6 +547,21 @@ impl Guest for Component {
                                         ));
                                     }
                                 },
+                                llm::ContentPart::Reasoning(reasoning) => {
+                                    result.push_str(&format!("REASONING: {}\n", reasoning.text));
+                                }
+                                llm::ContentPart::Document(document) => {
+                                    result.push_str(&format!("DOCUMENT: {}\n", document.mime_type));
+                                }
+                                llm::ContentPart::Audio(audio) => {
+                                    result.push_str(&format!("AUDIO: {}\n", audio.mime_type));
+                                }
+                                llm::ContentPart::BuiltinToolCall(call) => {
+                                    result.push_str(&format!(
+                                        "BUILTIN TOOL CALL: {:?} {:?}\n",
+                                        call.tool, call.input
+                                    ));
+                                }
                             }
                         }
                     }
@@ -516,6 +608,9 @@ impl Guest for Component {
             stop_sequences: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -547,6 +642,7 @@ impl Guest for Component {
                         detail: None,
                     })),
                 ],
+                cache_hint: None,
             })],
             &config,
         );
@@ -570,6 +666,14 @@ impl Guest for Component {
                                     inline_data.mime_type
                                 ),
                             },
+                            llm::ContentPart::Reasoning(reasoning) =>
+                                format!("[REASONING: {}]", reasoning.text),
+                            llm::ContentPart::Document(document) =>
+                                format!("[DOCUMENT: {}]", document.mime_type),
+                            llm::ContentPart::Audio(audio) =>
+                                format!("[AUDIO: {}]", audio.mime_type),
+                            llm::ContentPart::BuiltinToolCall(call) =>
+                                format!("[BUILTIN TOOL CALL: {:?}]", call.tool),
                         })
                         .collect::<Vec<_>>()
                         .join(", "),
@@ -594,6 +698,9 @@ impl Guest for Component {
             stop_sequences: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
             provider_options: None,
         };
 
@@ -603,6 +710,7 @@ impl Guest for Component {
             content: vec![llm::ContentPart::Text(
                 "Do you know what a haiku is?".to_string(),
             )],
+            cache_hint: None,
         })];
 
         let stream = llm::stream(&events, &config);
@@ -622,6 +730,7 @@ impl Guest for Component {
             role: llm::Role::Assistant,
             name: Some("assistant".to_string()),
             content: vec![llm::ContentPart::Text(result)],
+            cache_hint: None,
         }));
 
         events.push(llm::Event::Message(llm::Message {
@@ -630,6 +739,7 @@ impl Guest for Component {
             content: vec![llm::ContentPart::Text(
                 "Can you write one for me?".to_string(),
             )],
+            cache_hint: None,
         }));
 
         println!("Message: {events:?}");
@@ -664,6 +774,920 @@ impl Guest for Component {
 
         result
     }
+
+    /// test9 demonstrates requesting a structured JSON response conforming to a schema
+    fn test9() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: Some(llm::ResponseFormat::JsonSchema(llm::JsonSchemaFormat {
+                name: "mountain_pass".to_string(),
+                schema: r#"{
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "country": { "type": "string" },
+                            "elevation_m": { "type": "integer" }
+                        },
+                        "required": ["name", "country", "elevation_m"],
+                        "additionalProperties": false
+                    }"#
+                .to_string(),
+                strict: Some(true),
+            })),
+            reasoning: None,
+            provider_options: None,
+        };
+
+        println!("Sending request to LLM...");
+        let response = llm::send(
+            &[llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: Some("vigoo".to_string()),
+                content: vec![llm::ContentPart::Text(
+                    "Describe the Vršič pass as a JSON object".to_string(),
+                )],
+                cache_hint: None,
+            })],
+            &config,
+        );
+        println!("Response: {:?}", response);
+
+        match response {
+            Ok(response) => {
+                let json = response
+                    .content
+                    .into_iter()
+                    .filter_map(|content| match content {
+                        llm::ContentPart::Text(txt) => Some(txt),
+                        llm::ContentPart::Image(_)
+                        | llm::ContentPart::Reasoning(_)
+                        | llm::ContentPart::Document(_)
+                        | llm::ContentPart::Audio(_)
+                        | llm::ContentPart::BuiltinToolCall(_) => None,
+                    })
+                    .collect::<String>();
+
+                match serde_json::from_str::<serde_json::Value>(&json) {
+                    Ok(value) => format!("{value:#}"),
+                    Err(error) => {
+                        format!("ERROR: the response is not valid JSON: {error} ({json})")
+                    }
+                }
+            }
+            Err(error) => {
+                format!(
+                    "ERROR: {:?} {} ({})",
+                    error.code,
+                    error.message,
+                    error.provider_error_json.unwrap_or_default()
+                )
+            }
+        }
+    }
+
+    /// test10 demonstrates a multi-turn conversation using a chat session, surviving a crash
+    /// between the turns, and forking the session to ask a different follow-up question
+    fn test10() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        let session = llm::ChatSession::new(&config);
+        session.add_message(&llm::Message {
+            role: llm::Role::User,
+            name: Some("vigoo".to_string()),
+            content: vec![llm::ContentPart::Text(
+                "Do you know what a haiku is?".to_string(),
+            )],
+            cache_hint: None,
+        });
+
+        println!("Sending first message in the chat session...");
+        let mut result = match session.send() {
+            Ok(response) => format!("FIRST: {}\n", utils::response_text(response)),
+            Err(error) => return utils::format_error(error),
+        };
+
+        let name = std::env::var("GOLEM_WORKER_NAME").unwrap();
+        atomically(|| {
+            let client = TestHelperApi::new(&name);
+            let answer = client.blocking_inc_and_get();
+            if answer == 1 {
+                panic!("Simulating crash")
+            }
+        });
+
+        let fork = session.fork();
+
+        session.add_message(&llm::Message {
+            role: llm::Role::User,
+            name: Some("vigoo".to_string()),
+            content: vec![llm::ContentPart::Text(
+                "Can you write one for me?".to_string(),
+            )],
+            cache_hint: None,
+        });
+        fork.add_message(&llm::Message {
+            role: llm::Role::User,
+            name: Some("vigoo".to_string()),
+            content: vec![llm::ContentPart::Text(
+                "Where does this form of poetry come from?".to_string(),
+            )],
+            cache_hint: None,
+        });
+
+        println!("Sending the follow-up messages...");
+        match session.send() {
+            Ok(response) => {
+                result.push_str(&format!("SESSION: {}\n", utils::response_text(response)))
+            }
+            Err(error) => return utils::format_error(error),
+        }
+        match fork.send() {
+            Ok(response) => result.push_str(&format!("FORK: {}\n", utils::response_text(response))),
+            Err(error) => return utils::format_error(error),
+        }
+
+        result.push_str(&format!(
+            "HISTORY: {} events in the session, {} events in the fork\n",
+            session.history().len(),
+            fork.history().len()
+        ));
+
+        result
+    }
+
+    /// test11 demonstrates counting the tokens of a conversation and comparing it with the
+    /// model's context window
+    fn test11() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        let events = vec![
+            llm::Event::Message(llm::Message {
+                role: llm::Role::System,
+                name: None,
+                content: vec![llm::ContentPart::Text(
+                    "You are a helpful assistant answering questions about mountain passes."
+                        .to_string(),
+                )],
+                cache_hint: None,
+            }),
+            llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: Some("vigoo".to_string()),
+                content: vec![llm::ContentPart::Text(
+                    "What is the highest paved mountain pass in Europe?".to_string(),
+                )],
+                cache_hint: None,
+            }),
+        ];
+
+        println!("Getting model info...");
+        let model_info = match llm::get_model_info(MODEL) {
+            Ok(model_info) => model_info,
+            Err(error) => return utils::format_error(error),
+        };
+        println!("Model info: {model_info:?}");
+
+        println!("Counting tokens...");
+        let tokens = match llm::count_tokens(&events, &config) {
+            Ok(tokens) => tokens,
+            Err(error) => return utils::format_error(error),
+        };
+        println!("Tokens: {tokens}");
+
+        match model_info.context_window {
+            Some(context_window) => format!(
+                "{tokens} tokens of the {context_window} tokens context window of {} (max output tokens: {:?})",
+                model_info.id, model_info.max_output_tokens
+            ),
+            None => format!(
+                "{tokens} tokens, the context window of {} is unknown",
+                model_info.id
+            ),
+        }
+    }
+
+    /// test12 demonstrates listing the available models with their capabilities
+    fn test12() -> String {
+        println!("Listing models...");
+        let models = match llm::list_models() {
+            Ok(models) => models,
+            Err(error) => return utils::format_error(error),
+        };
+        println!("Models: {models:?}");
+
+        let mut result = String::new();
+        for model in &models {
+            result.push_str(&format!(
+                "{} (context window: {:?}, max output tokens: {:?})\n",
+                model.id, model.context_window, model.max_output_tokens
+            ));
+            if let Some(capabilities) = &model.capabilities {
+                result.push_str(&format!(
+                    "  tools: {}, vision: {}, streaming: {}, reasoning: {}, json-schema: {}\n",
+                    capabilities.tools,
+                    capabilities.vision,
+                    capabilities.streaming,
+                    capabilities.reasoning,
+                    capabilities.json_schema
+                ));
+            }
+        }
+
+        if !models.iter().any(|model| model.id.starts_with(MODEL)) {
+            result.push_str(&format!("ERROR: {MODEL} is not in the list of models\n"));
+        }
+
+        result
+    }
+
+    /// test13 demonstrates the detailed error codes by sending a request to a model that does not exist
+    fn test13() -> String {
+        let config = llm::Config {
+            model: "golem-llm-no-such-model".to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        println!("Sending request to a model that does not exist...");
+        let response = llm::send(
+            &[llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![llm::ContentPart::Text("Hello!".to_string())],
+                cache_hint: None,
+            })],
+            &config,
+        );
+
+        match response {
+            Ok(response) => format!(
+                "ERROR: unexpected response: {}",
+                utils::response_text(response)
+            ),
+            Err(error) if error.code == llm::ErrorCode::ModelNotFound => format!(
+                "Failed as expected with {:?}: {} (retry after: {:?} ms)",
+                error.code, error.message, error.retry_after_ms
+            ),
+            Err(error) => utils::format_error(error),
+        }
+    }
+
+    /// test14 marks a long system prompt as a cache breakpoint and sends it twice, so the second
+    /// call can read it from the provider's prompt cache
+    fn test14() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        // Providers only cache prompts above a minimum length, typically 1024 tokens
+        let rules = (1..=100)
+            .map(|n| format!("Rule {n}: always answer politely, briefly and in plain English."))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let system = llm::Message {
+            role: llm::Role::System,
+            name: None,
+            content: vec![llm::ContentPart::Text(format!(
+                "You are a helpful assistant following these rules:\n{rules}"
+            ))],
+            cache_hint: Some(llm::CacheHint::Ephemeral),
+        };
+
+        let mut result = String::new();
+        for question in [
+            "What is the capital of France?",
+            "What is the capital of Italy?",
+        ] {
+            println!("Sending request to LLM: {question}");
+            let response = llm::send(
+                &[
+                    llm::Event::Message(system.clone()),
+                    llm::Event::Message(llm::Message {
+                        role: llm::Role::User,
+                        name: None,
+                        content: vec![llm::ContentPart::Text(question.to_string())],
+                        cache_hint: None,
+                    }),
+                ],
+                &config,
+            );
+            match response {
+                Ok(response) => {
+                    let usage = response.metadata.usage.clone();
+                    result.push_str(&format!(
+                        "{}\nCache read tokens: {:?}, cache write tokens: {:?}\n",
+                        utils::response_text(response),
+                        usage.as_ref().and_then(|usage| usage.cache_read_tokens),
+                        usage.as_ref().and_then(|usage| usage.cache_write_tokens),
+                    ));
+                }
+                Err(error) => return utils::format_error(error),
+            }
+        }
+        result
+    }
+
+    /// test15 demonstrates how to send a PDF document to the LLM
+    fn test15() -> String {
+        let config = llm::Config {
+            model: IMAGE_MODEL.to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        println!("Sending request to LLM with a PDF document...");
+        let response = llm::send(
+            &[llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![
+                    llm::ContentPart::Document(llm::Document {
+                        source: llm::MediaSource::Url(
+                            "https://www.w3.org/WAI/ER/tests/xhtml/testfiles/resources/pdf/dummy.pdf"
+                                .to_string(),
+                        ),
+                        mime_type: "application/pdf".to_string(),
+                        title: Some("Dummy PDF".to_string()),
+                    }),
+                    llm::ContentPart::Text("What is written in this document?".to_string()),
+                ],
+                cache_hint: None,
+            })],
+            &config,
+        );
+
+        match response {
+            Ok(response) => utils::response_text(response),
+            Err(error) => utils::format_error(error),
+        }
+    }
+
+    /// test16 demonstrates getting the citations of an answer based on a document
+    fn test16() -> String {
+        let config = llm::Config {
+            model: IMAGE_MODEL.to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        let document = "The Vršič pass is a high mountain pass across the Julian Alps in Slovenia. \
+            At 1,611 metres above sea level, it is the highest pass in Slovenia. \
+            The road over the pass was built by Russian prisoners of war during the First World War. \
+            It has 50 hairpin turns, 24 on the Kranjska Gora side and 26 on the Trenta side.";
+
+        println!("Sending request to LLM with a text document...");
+        let response = llm::send(
+            &[llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![
+                    llm::ContentPart::Document(llm::Document {
+                        source: llm::MediaSource::Inline(document.as_bytes().to_vec()),
+                        mime_type: "text/plain".to_string(),
+                        title: Some("Vršič pass".to_string()),
+                    }),
+                    llm::ContentPart::Text(
+                        "Who built the road over the pass, and how many hairpin turns does it have?"
+                            .to_string(),
+                    ),
+                ],
+                cache_hint: None,
+            })],
+            &config,
+        );
+
+        match response {
+            Ok(response) => {
+                let citations = response
+                    .citations
+                    .iter()
+                    .map(|citation| {
+                        format!(
+                            "CITATION: {:?} {:?} [{:?}..{:?}] {:?}",
+                            citation.source,
+                            citation.title,
+                            citation.start_index,
+                            citation.end_index,
+                            citation.cited_text
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("{}\n{citations}", utils::response_text(response))
+            }
+            Err(error) => utils::format_error(error),
+        }
+    }
+
+    /// test17 lets the provider search the web with its builtin web search tool, printing the
+    /// builtin tool calls made before the answer
+    fn test17() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: None,
+            max_tokens: None,
+            stop_sequences: None,
+            tools: Some(vec![llm::ToolDefinition {
+                name: "web-search".to_string(),
+                description: None,
+                parameters_schema: "{}".to_string(),
+                cache_hint: None,
+                builtin: Some(llm::BuiltinTool::WebSearch(llm::WebSearchOptions {
+                    max_uses: Some(3),
+                    allowed_domains: None,
+                })),
+            }]),
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        println!("Sending request to LLM with web search enabled...");
+        let response = llm::send(
+            &[llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![llm::ContentPart::Text(
+                    "What is the latest released version of the Rust programming language?"
+                        .to_string(),
+                )],
+                cache_hint: None,
+            })],
+            &config,
+        );
+
+        match response {
+            Ok(response) => {
+                let calls = response
+                    .content
+                    .iter()
+                    .filter_map(|content| match content {
+                        llm::ContentPart::BuiltinToolCall(call) => Some(format!(
+                            "BUILTIN TOOL CALL: {:?} {:?} ({} results)",
+                            call.tool,
+                            call.input,
+                            call.results.len()
+                        )),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("{calls}\n{}", utils::response_text(response))
+            }
+            Err(error) => utils::format_error(error),
+        }
+    }
+
+    /// test18 submits a batch of questions, polls it until it is processed, and prints the
+    /// answers. Batches may take a long time to be processed, up to the provider's time limit.
+    fn test18() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: Some(200),
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        let items = [
+            ("capital", "What is the capital of Slovenia?"),
+            ("river", "What is the longest river in Europe?"),
+        ]
+        .into_iter()
+        .map(|(custom_id, question)| batch::BatchItem {
+            custom_id: custom_id.to_string(),
+            events: vec![llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![llm::ContentPart::Text(question.to_string())],
+                cache_hint: None,
+            })],
+            config: config.clone(),
+        })
+        .collect::<Vec<_>>();
+
+        println!("Submitting batch...");
+        let id = match batch::submit_batch(&items) {
+            Ok(id) => id,
+            Err(error) => return utils::format_error(error),
+        };
+
+        let info = loop {
+            match batch::poll_batch(&id) {
+                Ok(info) => match info.status {
+                    batch::BatchStatus::Pending
+                    | batch::BatchStatus::Running
+                    | batch::BatchStatus::Cancelling => {
+                        println!(
+                            "Batch {id} is {:?}, {}/{} items processed",
+                            info.status,
+                            info.succeeded_count + info.failed_count,
+                            info.total_count
+                        );
+                        std::thread::sleep(std::time::Duration::from_secs(60));
+                    }
+                    _ => break info,
+                },
+                Err(error) => return utils::format_error(error),
+            }
+        };
+
+        match batch::get_batch_results(&id) {
+            Ok(results) => {
+                let mut output = format!("Batch {id} finished as {:?}\n", info.status);
+                for result in results {
+                    let answer = match result.result {
+                        Ok(response) => utils::response_text(response),
+                        Err(error) => utils::format_error(error),
+                    };
+                    output.push_str(&format!("{}: {answer}\n", result.custom_id));
+                }
+                output
+            }
+            Err(error) => utils::format_error(error),
+        }
+    }
+
+    /// test19 sends the same question twice and then once more bypassing the cache, to demonstrate
+    /// the response cache enabled by setting `GOLEM_LLM_CACHE_TTL_SECONDS` for the worker
+    fn test19() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+        let bypass_config = llm::Config {
+            provider_options: Some(vec![llm::Kv {
+                key: "golem_llm_cache_bypass".to_string(),
+                value: "true".to_string(),
+            }]),
+            ..config.clone()
+        };
+        let events = vec![llm::Event::Message(llm::Message {
+            role: llm::Role::User,
+            name: None,
+            content: vec![llm::ContentPart::Text(
+                "What is the highest paved mountain pass in Europe?".to_string(),
+            )],
+            cache_hint: None,
+        })];
+
+        let mut ids = vec![];
+        let mut result = String::new();
+        for (label, config) in [
+            ("first", &config),
+            ("second", &config),
+            ("bypassing the cache", &bypass_config),
+        ] {
+            println!("Sending request to LLM ({label})...");
+            match llm::send(&events, config) {
+                Ok(response) => {
+                    ids.push(response.id.clone());
+                    result.push_str(&format!(
+                        "Response {label} ({}): {}\n",
+                        response.id,
+                        utils::response_text(response)
+                    ));
+                }
+                Err(error) => return utils::format_error(error),
+            }
+        }
+
+        result.push_str(&format!(
+            "Second response cached: {}, bypassing response cached: {}\n",
+            ids[1] == ids[0],
+            ids[2] == ids[0]
+        ));
+        result
+    }
+
+    /// test20 asks the same question with and without streaming, showing the cost calculated from
+    /// the usage of the response and of the stream's finish event
+    fn test20() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+        let events = vec![llm::Event::Message(llm::Message {
+            role: llm::Role::User,
+            name: None,
+            content: vec![llm::ContentPart::Text(
+                "What is the usual weather on the Vršič pass in the beginning of May?".to_string(),
+            )],
+            cache_hint: None,
+        })];
+
+        println!("Sending request to LLM...");
+        let mut result = match llm::send(&events, &config) {
+            Ok(response) => format!(
+                "Response usage: {:?}\nResponse cost: {:?}\n",
+                response.metadata.usage, response.metadata.cost
+            ),
+            Err(error) => return utils::format_error(error),
+        };
+
+        println!("Starting streaming request to LLM...");
+        let stream = llm::stream(&events, &config);
+        loop {
+            let events = stream.get_next();
+            if events.is_empty() {
+                break;
+            }
+
+            for event in events {
+                match event {
+                    Ok(StreamEvent::Delta(_)) => {}
+                    Ok(StreamEvent::Finish(finish)) => {
+                        result.push_str(&format!(
+                            "Stream usage: {:?}\nStream cost: {:?}\n",
+                            finish.usage, finish.cost
+                        ));
+                    }
+                    Err(error) => return utils::format_error(error),
+                }
+            }
+        }
+
+        result
+    }
+
+    /// test21 forces the model to call a specific tool, even though the question is better
+    /// answered by the other one, and allows only a single tool call in the response
+    fn test21() -> String {
+        let tool = |name: &str, description: &str| llm::ToolDefinition {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            parameters_schema: r#"{
+                    "type": "object",
+                    "properties": {
+                        "maximum": {
+                            "type": "number",
+                            "description": "Upper bound for the test value"
+                        }
+                    },
+                    "required": [
+                        "maximum"
+                    ],
+                    "additionalProperties": false
+                }"#
+            .to_string(),
+            cache_hint: None,
+            builtin: None,
+        };
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: Some(vec![
+                tool("get-weather", "Gets the current weather of a city"),
+                tool("test-tool", "Test tool for generating test values"),
+            ]),
+            tool_choice: Some(llm::ToolChoice::Specific("test-tool".to_string())),
+            parallel_tool_calls: Some(false),
+            response_format: None,
+            reasoning: None,
+            provider_options: None,
+        };
+
+        println!("Sending request to LLM...");
+        let response = llm::send(
+            &[llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![llm::ContentPart::Text(
+                    "What is the weather like in Ljubljana today?".to_string(),
+                )],
+                cache_hint: None,
+            })],
+            &config,
+        );
+        match response {
+            Ok(response) => response
+                .tool_calls
+                .iter()
+                .map(|call| format!("Tool call: {} {}\n", call.name, call.arguments_json))
+                .collect::<String>(),
+            Err(error) => utils::format_error(error),
+        }
+    }
+
+    /// test22 lists the models available on the Ollama server, then pulls a small model while
+    /// reporting the progress of the download, shows its details and deletes it again
+    #[cfg(feature = "ollama")]
+    fn test22() -> String {
+        let format_error = |error: model_management::Error| {
+            format!(
+                "ERROR: {:?} {} ({})",
+                error.code,
+                error.message,
+                error.provider_error_json.unwrap_or_default()
+            )
+        };
+
+        println!("Listing local models...");
+        let mut result = match model_management::list_local_models() {
+            Ok(models) => format!(
+                "Local models: {}\n",
+                models
+                    .iter()
+                    .map(|model| model.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Err(error) => return format_error(error),
+        };
+
+        println!("Pulling {PULLED_MODEL}...");
+        let stream = model_management::pull_model(PULLED_MODEL);
+        let mut last_status = None;
+        loop {
+            let events = stream.get_next();
+            if events.is_empty() {
+                break;
+            }
+
+            for event in events {
+                match event {
+                    Ok(model_management::PullEvent::Progress(progress)) => {
+                        if last_status.as_ref() != Some(&progress.status) {
+                            println!("Pull status: {}", progress.status);
+                            last_status = Some(progress.status);
+                        }
+                    }
+                    Ok(model_management::PullEvent::Finish) => {
+                        result.push_str(&format!("Pulled {PULLED_MODEL}\n"));
+                    }
+                    Err(error) => return format_error(error),
+                }
+            }
+        }
+
+        println!("Showing {PULLED_MODEL}...");
+        match model_management::show_model(PULLED_MODEL) {
+            Ok(description) => result.push_str(&format!(
+                "Details: {:?}\nCapabilities: {}\n",
+                description.details,
+                description.capabilities.join(", ")
+            )),
+            Err(error) => return format_error(error),
+        }
+
+        println!("Deleting {PULLED_MODEL}...");
+        match model_management::delete_model(PULLED_MODEL) {
+            Ok(()) => result.push_str(&format!("Deleted {PULLED_MODEL}\n")),
+            Err(error) => return format_error(error),
+        }
+
+        result
+    }
+
+    /// test22 demonstrates model management, which is only available with Ollama
+    #[cfg(not(feature = "ollama"))]
+    fn test22() -> String {
+        "Model management is only supported by Ollama".to_string()
+    }
+
+    /// test23 continues a conversation stored by the provider: with the `stateful` provider option
+    /// OpenAI only receives the question asked after its previous response, while the other
+    /// providers ignore the option and receive the whole conversation
+    fn test23() -> String {
+        let config = llm::Config {
+            model: MODEL.to_string(),
+            temperature: Some(0.2),
+            max_tokens: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+            reasoning: None,
+            provider_options: Some(vec![llm::Kv {
+                key: "stateful".to_string(),
+                value: "true".to_string(),
+            }]),
+        };
+        let question = |text: &str| {
+            llm::Event::Message(llm::Message {
+                role: llm::Role::User,
+                name: None,
+                content: vec![llm::ContentPart::Text(text.to_string())],
+                cache_hint: None,
+            })
+        };
+
+        let mut events = vec![question(
+            "Name a mountain hut close to the summit of Triglav, without explanation.",
+        )];
+        let mut result = String::new();
+        for follow_up in [
+            Some("How high above sea level is it? Answer with the number only."),
+            None,
+        ] {
+            println!("Sending request to LLM...");
+            let response = match llm::send(&events, &config) {
+                Ok(response) => response,
+                Err(error) => return utils::format_error(error),
+            };
+            result.push_str(&format!(
+                "Response {:?} (input tokens: {:?}): {}\n",
+                response.metadata.provider_id,
+                response
+                    .metadata
+                    .usage
+                    .as_ref()
+                    .and_then(|usage| usage.input_tokens),
+                utils::response_text(response.clone())
+            ));
+
+            events.push(llm::Event::Response(response));
+            if let Some(follow_up) = follow_up {
+                events.push(question(follow_up));
+            }
+        }
+
+        result
+    }
 }
 
 bindings::export!(Component with_types_in bindings);
@@ -35,6 +35,21 @@ pub fn consume_next_event(stream: &llm::ChatStream) -> Option<String> {
                                 ));
                             }
                         },
+                        llm::ContentPart::Reasoning(reasoning) => {
+                            result.push_str(&format!("REASONING: {}\n", reasoning.text));
+                        }
+                        llm::ContentPart::Document(document) => {
+                            result.push_str(&format!("DOCUMENT: {}\n", document.mime_type));
+                        }
+                        llm::ContentPart::Audio(audio) => {
+                            result.push_str(&format!("AUDIO: {}\n", audio.mime_type));
+                        }
+                        llm::ContentPart::BuiltinToolCall(call) => {
+                            result.push_str(&format!(
+                                "BUILTIN TOOL CALL: {:?} {:?}\n",
+                                call.tool, call.input
+                            ));
+                        }
                     }
                 }
             }
@@ -52,3 +67,28 @@ pub fn consume_next_event(stream: &llm::ChatStream) -> Option<String> {
 
     Some(result)
 }
+
+pub fn response_text(response: llm::Response) -> String {
+    response
+        .content
+        .into_iter()
+        .filter_map(|content| match content {
+            llm::ContentPart::Text(txt) => Some(txt),
+            llm::ContentPart::Image(_)
+            | llm::ContentPart::Reasoning(_)
+            | llm::ContentPart::Document(_)
+            | llm::ContentPart::Audio(_)
+            | llm::ContentPart::BuiltinToolCall(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_error(error: llm::Error) -> String {
+    format!(
+        "ERROR: {:?} {} ({})",
+        error.code,
+        error.message,
+        error.provider_error_json.unwrap_or_default()
+    )
+}